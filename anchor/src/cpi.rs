@@ -0,0 +1,1461 @@
+//! CPI helpers for the Vault, built on the native instruction builders so the wire format has a
+//! single source of truth.
+//!
+//! `ctx.remaining_accounts` are passed through as strategy extra accounts (or, for EstimateValue,
+//! as the trailing accounts described there). Authorities must sign directly: multisig
+//! authorities aren't supported through these helpers.
+//!
+//! Views (PreviewVault & co.) return what the Vault reports via return data.
+use crate::{
+    AcknowledgeLoss, Deposit, DeregisterVault, EstimatePositionValue, EstimateValue,
+    InitializeVault, InitializeVaultWithAccounts, Preview, PreviewPosition, RegisterVault,
+    SetPaused, SetWithdrawalFee, Withdraw, WithdrawInKind, ZapDeposit,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program::{get_return_data, invoke_signed},
+};
+use vault::{inspect::VaultPreview, instruction::VaultInstruction};
+
+/// Options for [initialize_vault] & [initialize_vault_with_accounts], mirroring
+/// VaultInstruction#InitializeVault.
+pub struct InitializeVaultArgs {
+    pub hodl: bool,
+    pub strategy_program_deposit_instruction_id: u8,
+    pub strategy_program_withdraw_instruction_id: u8,
+    pub strategy_program_estimate_instruction_id: u8,
+    pub block_deposits_on_loss: bool,
+}
+
+pub fn initialize_vault<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, InitializeVault<'info>>,
+    args: InitializeVaultArgs,
+) -> Result<()> {
+    let a = &ctx.accounts;
+    let ix = VaultInstruction::initialize_vault(
+        &vault::id(),
+        a.payer.key,
+        a.vault_storage.key,
+        &a.x_token_mint.key(),
+        &a.vault_token_mint.key(),
+        &a.token_program.key(),
+        a.strategy_program.key,
+        args.hodl,
+        args.strategy_program_deposit_instruction_id,
+        args.strategy_program_withdraw_instruction_id,
+        args.strategy_program_estimate_instruction_id,
+        args.block_deposits_on_loss,
+    )?;
+    invoke(ix, ctx)
+}
+
+pub fn initialize_vault_with_accounts<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, InitializeVaultWithAccounts<'info>>,
+    args: InitializeVaultArgs,
+) -> Result<()> {
+    let a = &ctx.accounts;
+    let ix = VaultInstruction::initialize_vault_with_accounts(
+        &vault::id(),
+        a.governance.key,
+        a.vault_storage.key,
+        &a.vault_token_account.key(),
+        a.vault_token_account_owner.key,
+        &[],
+        &a.llx_token_mint.key(),
+        a.llx_token_mint_authority.key,
+        &[],
+        &a.x_token_mint.key(),
+        &a.token_program.key(),
+        a.strategy_program.key,
+        args.hodl,
+        args.strategy_program_deposit_instruction_id,
+        args.strategy_program_withdraw_instruction_id,
+        args.strategy_program_estimate_instruction_id,
+        args.block_deposits_on_loss,
+    )?;
+    invoke(ix, ctx)
+}
+
+pub fn deposit<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Deposit<'info>>,
+    amount: u64,
+    min_shares: u64,
+) -> Result<()> {
+    let a = &ctx.accounts;
+    let ix = VaultInstruction::deposit(
+        &vault::id(),
+        &a.token_program.key(),
+        &a.client_x_token_account.key(),
+        &a.client_llx_token_account.key(),
+        transfer_metas(
+            a.source_authority.key,
+            &a.vault_storage.key(),
+            a.strategy_program.key,
+            &a.llx_token_mint.key(),
+            a.vault_authority.key,
+            &a.vault_token_account.key(),
+            &ctx.remaining_accounts,
+        ),
+        amount,
+        0,
+        min_shares,
+    )?;
+    invoke(ix, ctx)
+}
+
+pub fn withdraw<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Withdraw<'info>>,
+    amount: u64,
+) -> Result<()> {
+    let a = &ctx.accounts;
+    let ix = VaultInstruction::withdraw(
+        &vault::id(),
+        &a.token_program.key(),
+        &a.client_llx_token_account.key(),
+        &a.client_x_token_account.key(),
+        transfer_metas(
+            a.source_authority.key,
+            &a.vault_storage.key(),
+            a.strategy_program.key,
+            &a.llx_token_mint.key(),
+            a.vault_authority.key,
+            &a.vault_token_account.key(),
+            &ctx.remaining_accounts,
+        ),
+        amount,
+        0,
+    )?;
+    invoke(ix, ctx)
+}
+
+/// Mints exactly `shares` llX tokens for the X tokens they're worth.
+pub fn mint<'info>(ctx: CpiContext<'_, '_, '_, 'info, Deposit<'info>>, shares: u64) -> Result<()> {
+    let a = &ctx.accounts;
+    let ix = VaultInstruction::mint(
+        &vault::id(),
+        &a.token_program.key(),
+        &a.client_x_token_account.key(),
+        &a.client_llx_token_account.key(),
+        transfer_metas(
+            a.source_authority.key,
+            &a.vault_storage.key(),
+            a.strategy_program.key,
+            &a.llx_token_mint.key(),
+            a.vault_authority.key,
+            &a.vault_token_account.key(),
+            &ctx.remaining_accounts,
+        ),
+        shares,
+        0,
+    )?;
+    invoke(ix, ctx)
+}
+
+/// Withdraws at least `amount` X tokens for the llX tokens they're worth.
+pub fn withdraw_assets<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Withdraw<'info>>,
+    amount: u64,
+) -> Result<()> {
+    let a = &ctx.accounts;
+    let ix = VaultInstruction::withdraw_assets(
+        &vault::id(),
+        &a.token_program.key(),
+        &a.client_llx_token_account.key(),
+        &a.client_x_token_account.key(),
+        transfer_metas(
+            a.source_authority.key,
+            &a.vault_storage.key(),
+            a.strategy_program.key,
+            &a.llx_token_mint.key(),
+            a.vault_authority.key,
+            &a.vault_token_account.key(),
+            &ctx.remaining_accounts,
+        ),
+        amount,
+        0,
+    )?;
+    invoke(ix, ctx)
+}
+
+pub fn withdraw_in_kind<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, WithdrawInKind<'info>>,
+    amount: u64,
+) -> Result<()> {
+    let a = &ctx.accounts;
+    let mut ix = VaultInstruction::withdraw_in_kind(
+        &vault::id(),
+        &a.token_program.key(),
+        &a.client_llx_token_account.key(),
+        &a.client_lx_token_account.key(),
+        a.source_authority.key,
+        &[],
+        &a.vault_storage.key(),
+        &a.vault_storage,
+        amount,
+    )?;
+    ix.accounts = withdraw_in_kind_metas(a);
+    invoke(ix, ctx)
+}
+
+pub fn zap_deposit<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, ZapDeposit<'info>>,
+    amount_in: u64,
+    minimum_x_out: u64,
+) -> Result<()> {
+    let a = &ctx.accounts;
+    let d = &a.deposit;
+    let swap_account_metas = vec![
+        AccountMeta::new_readonly(*a.swap_program.key, false),
+        AccountMeta::new_readonly(*a.swap.key, false),
+        AccountMeta::new_readonly(*a.swap_authority.key, false),
+        AccountMeta::new(*a.swap_source.key, false),
+        AccountMeta::new(*a.swap_destination.key, false),
+        AccountMeta::new(*a.pool_mint.key, false),
+        AccountMeta::new(*a.pool_fee_account.key, false),
+    ];
+    let ix = VaultInstruction::zap_deposit(
+        &vault::id(),
+        &d.token_program.key(),
+        &a.client_input_token_account.key(),
+        &d.client_x_token_account.key(),
+        &d.client_llx_token_account.key(),
+        swap_account_metas,
+        transfer_metas(
+            d.source_authority.key,
+            &d.vault_storage.key(),
+            d.strategy_program.key,
+            &d.llx_token_mint.key(),
+            d.vault_authority.key,
+            &d.vault_token_account.key(),
+            &ctx.remaining_accounts,
+        ),
+        amount_in,
+        minimum_x_out,
+    )?;
+    invoke(ix, ctx)
+}
+
+/// Estimates the Vault's value; the result is written to `shared_memory`.
+pub fn estimate_value<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, EstimateValue<'info>>,
+) -> Result<()> {
+    let a = &ctx.accounts;
+    let mut metas = vec![
+        AccountMeta::new(a.vault_storage.key(), false),
+        AccountMeta::new_readonly(a.vault_token_account.key(), false),
+    ];
+    metas.extend(remaining_metas(&ctx.remaining_accounts));
+    let ix =
+        VaultInstruction::estimate_value(&vault::id(), a.program.key, a.shared_memory.key, metas)?;
+    invoke(ix, ctx)
+}
+
+/// Estimates the X value of the llX held by the outer Vault & returns it. This Vault's own value
+/// is estimated & recorded as by [estimate_value].
+pub fn estimate_position_value<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, EstimatePositionValue<'info>>,
+) -> Result<u64> {
+    let a = &ctx.accounts;
+    let mut metas = vec![
+        AccountMeta::new_readonly(a.position_token_account.key(), false),
+        AccountMeta::new_readonly(a.outer_vault_storage.key(), false),
+        AccountMeta::new_readonly(a.llx_token_mint.key(), false),
+        AccountMeta::new(a.vault_storage.key(), false),
+        AccountMeta::new_readonly(a.vault_token_account.key(), false),
+    ];
+    metas.extend(remaining_metas(&ctx.remaining_accounts));
+    let ix = VaultInstruction::estimate_position_value(&vault::id(), a.shared_memory.key, metas)?;
+    invoke(ix, ctx)?;
+    returned_u64()
+}
+
+pub fn acknowledge_loss<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, AcknowledgeLoss<'info>>,
+) -> Result<()> {
+    let ix = VaultInstruction::acknowledge_loss(
+        &vault::id(),
+        ctx.accounts.governance.key,
+        &ctx.accounts.vault_storage.key(),
+    )?;
+    invoke(ix, ctx)
+}
+
+pub fn set_withdrawal_fee<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, SetWithdrawalFee<'info>>,
+    fee_bps: u16,
+) -> Result<()> {
+    let ix = VaultInstruction::set_withdrawal_fee(
+        &vault::id(),
+        ctx.accounts.governance.key,
+        &ctx.accounts.vault_storage.key(),
+        fee_bps,
+    )?;
+    invoke(ix, ctx)
+}
+
+pub fn set_paused<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, SetPaused<'info>>,
+    paused: bool,
+) -> Result<()> {
+    let ix = VaultInstruction::set_paused(
+        &vault::id(),
+        ctx.accounts.governance.key,
+        &ctx.accounts.vault_storage.key(),
+        paused,
+    )?;
+    invoke(ix, ctx)
+}
+
+/// Registers the Vault for its X mint; see VaultInstruction#RegisterVault.
+pub fn register_vault<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, RegisterVault<'info>>,
+) -> Result<()> {
+    let a = &ctx.accounts;
+    let ix = VaultInstruction::register_vault(
+        &vault::id(),
+        a.governance.key,
+        &a.vault_storage.key(),
+        &a.vault_storage.x_token_mint,
+    )?;
+    invoke(ix, ctx)
+}
+
+/// Retires the Vault's registry entry & removes it from its mint's Vault registry.
+pub fn deregister_vault<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, DeregisterVault<'info>>,
+) -> Result<()> {
+    let a = &ctx.accounts;
+    let ix = VaultInstruction::deregister_vault(
+        &vault::id(),
+        a.governance.key,
+        &a.vault_storage.key(),
+        &a.vault_storage.x_token_mint,
+    )?;
+    invoke(ix, ctx)
+}
+
+/// Reports the Vault's value & price per share.
+pub fn preview_vault<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Preview<'info>>,
+) -> Result<VaultPreview> {
+    let mut ix = VaultInstruction::preview_vault(
+        &vault::id(),
+        &ctx.accounts.vault_storage.key(),
+        &ctx.accounts.vault_storage,
+        None,
+    )?;
+    ix.accounts = preview_metas(&ctx.accounts);
+    invoke(ix, ctx)?;
+    VaultPreview::unpack(&vault_return_data()?).map_err(Into::into)
+}
+
+/// Reports the Vault's value & price per share, and the X redeemable for the client's llX.
+pub fn preview_vault_position<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, PreviewPosition<'info>>,
+) -> Result<VaultPreview> {
+    let a = &ctx.accounts;
+    let mut ix = VaultInstruction::preview_vault(
+        &vault::id(),
+        &a.preview.vault_storage.key(),
+        &a.preview.vault_storage,
+        Some(&a.client_llx_token_account.key()),
+    )?;
+    ix.accounts = preview_position_metas(a);
+    invoke(ix, ctx)?;
+    VaultPreview::unpack(&vault_return_data()?).map_err(Into::into)
+}
+
+/// Reports the llX tokens Deposit would mint for `amount` X tokens.
+pub fn preview_deposit<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Preview<'info>>,
+    amount: u64,
+) -> Result<u64> {
+    let ix = VaultInstruction::preview_deposit(
+        &vault::id(),
+        &ctx.accounts.vault_storage.key(),
+        &ctx.accounts.vault_storage,
+        amount,
+    )?;
+    view(ix, ctx)
+}
+
+/// Reports the X tokens Withdraw would pay out for `shares` llX tokens.
+pub fn preview_withdraw<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Preview<'info>>,
+    shares: u64,
+) -> Result<u64> {
+    let ix = VaultInstruction::preview_withdraw(
+        &vault::id(),
+        &ctx.accounts.vault_storage.key(),
+        &ctx.accounts.vault_storage,
+        shares,
+    )?;
+    view(ix, ctx)
+}
+
+/// Reports the llX tokens `assets` X tokens are worth.
+pub fn convert_to_shares<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Preview<'info>>,
+    assets: u64,
+) -> Result<u64> {
+    let ix = VaultInstruction::convert_to_shares(
+        &vault::id(),
+        &ctx.accounts.vault_storage.key(),
+        &ctx.accounts.vault_storage,
+        assets,
+    )?;
+    view(ix, ctx)
+}
+
+/// Reports the X tokens `shares` llX tokens are worth.
+pub fn convert_to_assets<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Preview<'info>>,
+    shares: u64,
+) -> Result<u64> {
+    let ix = VaultInstruction::convert_to_assets(
+        &vault::id(),
+        &ctx.accounts.vault_storage.key(),
+        &ctx.accounts.vault_storage,
+        shares,
+    )?;
+    view(ix, ctx)
+}
+
+/// Reports the most X tokens Deposit currently accepts.
+pub fn max_deposit<'info>(ctx: CpiContext<'_, '_, '_, 'info, Preview<'info>>) -> Result<u64> {
+    let ix = VaultInstruction::max_deposit(
+        &vault::id(),
+        &ctx.accounts.vault_storage.key(),
+        &ctx.accounts.vault_storage,
+    )?;
+    view(ix, ctx)
+}
+
+/// Reports the most llX tokens Mint currently mints.
+pub fn max_mint<'info>(ctx: CpiContext<'_, '_, '_, 'info, Preview<'info>>) -> Result<u64> {
+    let ix = VaultInstruction::max_mint(
+        &vault::id(),
+        &ctx.accounts.vault_storage.key(),
+        &ctx.accounts.vault_storage,
+    )?;
+    view(ix, ctx)
+}
+
+/// Reports the X tokens Mint would charge for `shares` llX tokens.
+pub fn preview_mint<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Preview<'info>>,
+    shares: u64,
+) -> Result<u64> {
+    let ix = VaultInstruction::preview_mint(
+        &vault::id(),
+        &ctx.accounts.vault_storage.key(),
+        &ctx.accounts.vault_storage,
+        shares,
+    )?;
+    view(ix, ctx)
+}
+
+/// Reports the llX tokens WithdrawAssets would burn for `amount` X tokens.
+pub fn preview_withdraw_assets<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, Preview<'info>>,
+    amount: u64,
+) -> Result<u64> {
+    let ix = VaultInstruction::preview_withdraw_assets(
+        &vault::id(),
+        &ctx.accounts.vault_storage.key(),
+        &ctx.accounts.vault_storage,
+        amount,
+    )?;
+    view(ix, ctx)
+}
+
+/// Reports the most X tokens the holder of the client llX token account can withdraw.
+pub fn max_withdraw<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, PreviewPosition<'info>>,
+) -> Result<u64> {
+    let a = &ctx.accounts;
+    let ix = VaultInstruction::max_withdraw(
+        &vault::id(),
+        &a.preview.vault_storage.key(),
+        &a.preview.vault_storage,
+        &a.client_llx_token_account.key(),
+    )?;
+    position_view(ix, ctx)
+}
+
+/// Reports the most llX tokens the holder of the client llX token account can redeem.
+pub fn max_redeem<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, PreviewPosition<'info>>,
+) -> Result<u64> {
+    let a = &ctx.accounts;
+    let ix = VaultInstruction::max_redeem(
+        &vault::id(),
+        &a.preview.vault_storage.key(),
+        &a.preview.vault_storage,
+        &a.client_llx_token_account.key(),
+    )?;
+    position_view(ix, ctx)
+}
+
+/// Accounts 4+ of Deposit & Withdraw.
+fn transfer_metas(
+    source_authority: &Pubkey,
+    vault_storage: &Pubkey,
+    strategy_program: &Pubkey,
+    llx_token_mint: &Pubkey,
+    vault_authority: &Pubkey,
+    vault_token_account: &Pubkey,
+    remaining_accounts: &[AccountInfo],
+) -> Vec<AccountMeta> {
+    let mut metas = vec![
+        AccountMeta::new_readonly(*source_authority, true),
+        AccountMeta::new(*vault_storage, false),
+        AccountMeta::new_readonly(*strategy_program, false),
+        AccountMeta::new(*llx_token_mint, false),
+        AccountMeta::new_readonly(*vault_authority, false),
+        AccountMeta::new(*vault_token_account, false),
+    ];
+    metas.extend(remaining_metas(remaining_accounts));
+    metas
+}
+
+pub(crate) fn remaining_metas(remaining_accounts: &[AccountInfo]) -> Vec<AccountMeta> {
+    remaining_accounts
+        .iter()
+        .map(|account| AccountMeta {
+            pubkey: *account.key,
+            is_signer: account.is_signer,
+            is_writable: account.is_writable,
+        })
+        .collect()
+}
+
+/// The Preview views' accounts. Taken from the context rather than derived from the Vault storage
+/// account as by the builders, so that Vaults created by InitializeVaultWithAccounts work too.
+fn withdraw_in_kind_metas(accounts: &WithdrawInKind) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(accounts.token_program.key(), false),
+        AccountMeta::new(accounts.client_llx_token_account.key(), false),
+        AccountMeta::new(accounts.client_lx_token_account.key(), false),
+        AccountMeta::new_readonly(accounts.source_authority.key(), true),
+        AccountMeta::new(accounts.vault_storage.key(), false),
+        AccountMeta::new(accounts.llx_token_mint.key(), false),
+        AccountMeta::new_readonly(accounts.vault_authority.key(), false),
+        AccountMeta::new(accounts.vault_token_account.key(), false),
+    ]
+}
+
+fn preview_metas(accounts: &Preview) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(accounts.vault_storage.key(), false),
+        AccountMeta::new_readonly(accounts.llx_token_mint.key(), false),
+        AccountMeta::new_readonly(accounts.vault_token_account.key(), false),
+    ]
+}
+
+fn preview_position_metas(accounts: &PreviewPosition) -> Vec<AccountMeta> {
+    let mut metas = preview_metas(&accounts.preview);
+    metas.push(AccountMeta::new_readonly(
+        accounts.client_llx_token_account.key(),
+        false,
+    ));
+    metas
+}
+
+/// Invokes a view taking [Preview]'s accounts & returns the u64 it reports.
+fn view<'info>(
+    mut ix: Instruction,
+    ctx: CpiContext<'_, '_, '_, 'info, Preview<'info>>,
+) -> Result<u64> {
+    ix.accounts = preview_metas(&ctx.accounts);
+    invoke(ix, ctx)?;
+    returned_u64()
+}
+
+/// Invokes a view taking [PreviewPosition]'s accounts & returns the u64 it reports.
+fn position_view<'info>(
+    mut ix: Instruction,
+    ctx: CpiContext<'_, '_, '_, 'info, PreviewPosition<'info>>,
+) -> Result<u64> {
+    ix.accounts = preview_position_metas(&ctx.accounts);
+    invoke(ix, ctx)?;
+    returned_u64()
+}
+
+/// Decodes the little-endian u64 reported by the Vault's last view.
+fn returned_u64() -> Result<u64> {
+    vault_return_data()?
+        .get(..8)
+        .and_then(|slice| slice.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or_else(|| ProgramError::InvalidAccountData.into())
+}
+
+/// The return data set by the Vault's last view.
+fn vault_return_data() -> Result<Vec<u8>> {
+    match get_return_data() {
+        Some((program_id, data)) if program_id == vault::id() => Ok(data),
+        _ => Err(ProgramError::InvalidAccountData.into()),
+    }
+}
+
+fn invoke<'info, T: ToAccountInfos<'info> + ToAccountMetas>(
+    ix: Instruction,
+    ctx: CpiContext<'_, '_, '_, 'info, T>,
+) -> Result<()> {
+    invoke_signed(&ix, &ctx.to_account_infos(), ctx.signer_seeds).map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::VaultState;
+    use anchor_lang::solana_program::{
+        entrypoint::ProgramResult,
+        program_pack::Pack,
+        program_stubs::{self, SyscallStubs},
+        system_program, sysvar,
+    };
+    use anchor_spl::token::spl_token;
+    use std::cell::RefCell;
+    use vault::{instruction::transfer_account_metas, state, swap::zap_account_metas};
+
+    thread_local! {
+        static INVOKED: RefCell<Option<Instruction>> = const { RefCell::new(None) };
+        static RETURN_DATA: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Records the instruction a CPI invokes instead of running it, reporting [RETURN_DATA] as the
+    /// Vault's return data.
+    struct RecordInvocation;
+
+    impl SyscallStubs for RecordInvocation {
+        fn sol_invoke_signed(
+            &self,
+            instruction: &Instruction,
+            _account_infos: &[AccountInfo],
+            _signers_seeds: &[&[&[u8]]],
+        ) -> ProgramResult {
+            INVOKED.with(|invoked| *invoked.borrow_mut() = Some(instruction.clone()));
+            Ok(())
+        }
+
+        fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+            Some((vault::id(), RETURN_DATA.with(|data| data.borrow().clone())))
+        }
+    }
+
+    /// The instruction `cpi` invokes.
+    fn invoked(cpi: impl FnOnce() -> Result<()>) -> Instruction {
+        invoked_view(&[], cpi).0
+    }
+
+    /// The instruction the view `cpi` invokes & what it returns when the Vault reports
+    /// `return_data`.
+    fn invoked_view<T>(return_data: &[u8], cpi: impl FnOnce() -> Result<T>) -> (Instruction, T) {
+        program_stubs::set_syscall_stubs(Box::new(RecordInvocation));
+        RETURN_DATA.with(|data| *data.borrow_mut() = return_data.to_vec());
+        let returned = cpi().unwrap();
+        let instruction = INVOKED
+            .with(|invoked| invoked.borrow_mut().take())
+            .expect("No instruction invoked");
+        (instruction, returned)
+    }
+
+    /// A leaked account, so that Anchor's account types can borrow it for the whole test.
+    fn account_info(
+        key: Pubkey,
+        owner: Pubkey,
+        data: Vec<u8>,
+        executable: bool,
+    ) -> &'static AccountInfo<'static> {
+        Box::leak(Box::new(AccountInfo::new(
+            Box::leak(Box::new(key)),
+            false,
+            !executable,
+            Box::leak(Box::new(0)),
+            Box::leak(data.into_boxed_slice()),
+            Box::leak(Box::new(owner)),
+            executable,
+            0,
+        )))
+    }
+
+    fn unchecked() -> &'static AccountInfo<'static> {
+        account_info(Pubkey::new_unique(), system_program::ID, vec![], false)
+    }
+
+    fn program(id: Pubkey) -> &'static AccountInfo<'static> {
+        account_info(id, Pubkey::default(), vec![], true)
+    }
+
+    fn mint_account(key: Pubkey) -> &'static AccountInfo<'static> {
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            is_initialized: true,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+        account_info(key, spl_token::ID, data, false)
+    }
+
+    fn token_account(key: Pubkey, mint: Pubkey) -> &'static AccountInfo<'static> {
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint,
+            owner: Pubkey::new_unique(),
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+        account_info(key, spl_token::ID, data, false)
+    }
+
+    fn rent() -> Sysvar<'static, Rent> {
+        let info = account_info(
+            sysvar::rent::ID,
+            sysvar::ID,
+            vec![0; Rent::size_of()],
+            false,
+        );
+        Rent::default().to_account_info(&mut info.clone()).unwrap();
+        Sysvar::from_account_info(info).unwrap()
+    }
+
+    /// The accounts of a strategy Vault.
+    struct TestVault {
+        state: state::Vault,
+        storage: &'static AccountInfo<'static>,
+        strategy_program: &'static AccountInfo<'static>,
+        llx_token_mint: &'static AccountInfo<'static>,
+        vault_authority: &'static AccountInfo<'static>,
+        vault_token_account: &'static AccountInfo<'static>,
+    }
+
+    impl TestVault {
+        /// A Vault whose token account & llX mint are at their derived addresses.
+        fn new() -> Self {
+            let key = Pubkey::new_unique();
+            let (vault_token_account, _) =
+                VaultInstruction::find_vault_token_account(&vault::id(), &key);
+            let (llx_token_mint, _) = VaultInstruction::find_llx_token_mint(&vault::id(), &key);
+            Self::with_accounts(key, vault_token_account, llx_token_mint)
+        }
+
+        /// A Vault initialized with an existing token account & llX mint, as by
+        /// InitializeVaultWithAccounts.
+        fn with_existing_accounts() -> Self {
+            Self::with_accounts(
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+            )
+        }
+
+        fn with_accounts(key: Pubkey, vault_token_account: Pubkey, llx_token_mint: Pubkey) -> Self {
+            let (vault_authority, _) = VaultInstruction::find_vault_authority(&vault::id(), &key);
+            let state = state::Vault {
+                is_initialized: true,
+                hodl: false,
+                llx_token_mint_id: llx_token_mint,
+                x_token_mint: Pubkey::new_unique(),
+                lx_token_mint: Pubkey::new_unique(),
+                vault_token_account,
+                last_estimated_value: 0,
+                strategy_program_id: Pubkey::new_unique(),
+                strategy_program_deposit_instruction_id: 0,
+                strategy_program_withdraw_instruction_id: 1,
+                strategy_program_estimate_instruction_id: 2,
+                strategy_data_account: None,
+                governance: Pubkey::new_unique(),
+                total_debt: 0,
+                total_loss: 0,
+                unacknowledged_loss: 0,
+                block_deposits_on_loss: false,
+                withdrawal_fee_bps: 0,
+                paused: false,
+            };
+            let mut data = vec![0; state::Vault::LEN];
+            state.pack_into_slice(&mut data);
+            TestVault {
+                storage: account_info(key, vault::id(), data, false),
+                strategy_program: program(state.strategy_program_id),
+                llx_token_mint: mint_account(llx_token_mint),
+                vault_authority: account_info(vault_authority, system_program::ID, vec![], false),
+                vault_token_account: token_account(vault_token_account, state.lx_token_mint),
+                state,
+            }
+        }
+
+        fn vault_storage(&self) -> Account<'static, VaultState> {
+            Account::try_from(self.storage).unwrap()
+        }
+
+        /// Deposit's accounts, depositing from `client_x_token_account`.
+        fn deposit(
+            &self,
+            client_x_token_account: &'static AccountInfo<'static>,
+        ) -> Deposit<'static> {
+            Deposit {
+                token_program: Program::try_from(program(spl_token::ID)).unwrap(),
+                client_x_token_account: Account::try_from(client_x_token_account).unwrap(),
+                client_llx_token_account: Account::try_from(token_account(
+                    Pubkey::new_unique(),
+                    self.state.llx_token_mint_id,
+                ))
+                .unwrap(),
+                source_authority: unchecked().clone(),
+                vault_storage: self.vault_storage(),
+                strategy_program: self.strategy_program.clone(),
+                llx_token_mint: Account::try_from(self.llx_token_mint).unwrap(),
+                vault_authority: self.vault_authority.clone(),
+                vault_token_account: Account::try_from(self.vault_token_account).unwrap(),
+            }
+        }
+
+        fn withdraw(&self) -> Withdraw<'static> {
+            Withdraw {
+                token_program: Program::try_from(program(spl_token::ID)).unwrap(),
+                client_llx_token_account: Account::try_from(token_account(
+                    Pubkey::new_unique(),
+                    self.state.llx_token_mint_id,
+                ))
+                .unwrap(),
+                client_x_token_account: Account::try_from(token_account(
+                    Pubkey::new_unique(),
+                    self.state.x_token_mint,
+                ))
+                .unwrap(),
+                source_authority: unchecked().clone(),
+                vault_storage: self.vault_storage(),
+                strategy_program: self.strategy_program.clone(),
+                llx_token_mint: Account::try_from(self.llx_token_mint).unwrap(),
+                vault_authority: self.vault_authority.clone(),
+                vault_token_account: Account::try_from(self.vault_token_account).unwrap(),
+            }
+        }
+
+        fn preview(&self) -> Preview<'static> {
+            Preview {
+                vault_storage: self.vault_storage(),
+                llx_token_mint: Account::try_from(self.llx_token_mint).unwrap(),
+                vault_token_account: Account::try_from(self.vault_token_account).unwrap(),
+            }
+        }
+
+        fn preview_position(&self) -> PreviewPosition<'static> {
+            PreviewPosition {
+                preview: self.preview(),
+                client_llx_token_account: Account::try_from(token_account(
+                    Pubkey::new_unique(),
+                    self.state.llx_token_mint_id,
+                ))
+                .unwrap(),
+            }
+        }
+
+        /// Accounts 4+ of Deposit & Withdraw, signed by `source_authority`.
+        fn transfer_metas(
+            &self,
+            source_authority: &Pubkey,
+            strategy_extra_accounts: Vec<AccountMeta>,
+        ) -> Vec<AccountMeta> {
+            let mut metas = vec![AccountMeta::new_readonly(*source_authority, true)];
+            metas.extend(transfer_account_metas(
+                &vault::id(),
+                self.storage.key,
+                &self.state,
+                strategy_extra_accounts,
+            ));
+            metas
+        }
+    }
+
+    fn initialize_args() -> InitializeVaultArgs {
+        InitializeVaultArgs {
+            hodl: false,
+            strategy_program_deposit_instruction_id: 3,
+            strategy_program_withdraw_instruction_id: 4,
+            strategy_program_estimate_instruction_id: 5,
+            block_deposits_on_loss: true,
+        }
+    }
+
+    #[test]
+    fn test_initialize_vault() {
+        let vault = TestVault::new();
+        let accounts = InitializeVault {
+            payer: unchecked().clone(),
+            vault_storage: vault.storage.clone(),
+            vault_token_account: vault.vault_token_account.clone(),
+            llx_token_mint: vault.llx_token_mint.clone(),
+            x_token_mint: Account::try_from(mint_account(vault.state.x_token_mint)).unwrap(),
+            vault_token_mint: Account::try_from(mint_account(vault.state.lx_token_mint)).unwrap(),
+            vault_authority: vault.vault_authority.clone(),
+            token_program: Program::try_from(program(spl_token::ID)).unwrap(),
+            strategy_program: vault.strategy_program.clone(),
+            system_program: Program::try_from(program(system_program::ID)).unwrap(),
+            rent: rent(),
+        };
+        let expected = VaultInstruction::initialize_vault(
+            &vault::id(),
+            accounts.payer.key,
+            vault.storage.key,
+            &vault.state.x_token_mint,
+            &vault.state.lx_token_mint,
+            &spl_token::ID,
+            &vault.state.strategy_program_id,
+            false,
+            3,
+            4,
+            5,
+            true,
+        )
+        .unwrap();
+        let ctx = CpiContext::new(program(vault::id()).clone(), accounts);
+        assert_eq!(
+            invoked(|| initialize_vault(ctx, initialize_args())),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_initialize_vault_with_accounts() {
+        let vault = TestVault::new();
+        let accounts = InitializeVaultWithAccounts {
+            governance: unchecked().clone(),
+            vault_storage: vault.storage.clone(),
+            vault_token_account: Account::try_from(vault.vault_token_account).unwrap(),
+            llx_token_mint: Account::try_from(vault.llx_token_mint).unwrap(),
+            x_token_mint: Account::try_from(mint_account(vault.state.x_token_mint)).unwrap(),
+            vault_authority: vault.vault_authority.clone(),
+            token_program: Program::try_from(program(spl_token::ID)).unwrap(),
+            strategy_program: vault.strategy_program.clone(),
+            rent: rent(),
+            vault_token_account_owner: unchecked().clone(),
+            llx_token_mint_authority: unchecked().clone(),
+        };
+        let expected = VaultInstruction::initialize_vault_with_accounts(
+            &vault::id(),
+            accounts.governance.key,
+            vault.storage.key,
+            &vault.state.vault_token_account,
+            accounts.vault_token_account_owner.key,
+            &[],
+            &vault.state.llx_token_mint_id,
+            accounts.llx_token_mint_authority.key,
+            &[],
+            &vault.state.x_token_mint,
+            &spl_token::ID,
+            &vault.state.strategy_program_id,
+            false,
+            3,
+            4,
+            5,
+            true,
+        )
+        .unwrap();
+        let ctx = CpiContext::new(program(vault::id()).clone(), accounts);
+        assert_eq!(
+            invoked(|| initialize_vault_with_accounts(ctx, initialize_args())),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_deposit() {
+        let vault = TestVault::new();
+        let client_x_token_account = token_account(Pubkey::new_unique(), vault.state.x_token_mint);
+        let accounts = vault.deposit(client_x_token_account);
+        let extra_account = unchecked();
+        let expected = VaultInstruction::deposit(
+            &vault::id(),
+            &spl_token::ID,
+            client_x_token_account.key,
+            &accounts.client_llx_token_account.key(),
+            vault.transfer_metas(
+                accounts.source_authority.key,
+                vec![AccountMeta::new(*extra_account.key, false)],
+            ),
+            7,
+            0,
+            6,
+        )
+        .unwrap();
+        let ctx = CpiContext::new(program(vault::id()).clone(), accounts)
+            .with_remaining_accounts(vec![extra_account.clone()]);
+        assert_eq!(invoked(|| deposit(ctx, 7, 6)), expected);
+    }
+
+    #[test]
+    fn test_withdraw() {
+        let vault = TestVault::new();
+        let accounts = vault.withdraw();
+        let expected = VaultInstruction::withdraw(
+            &vault::id(),
+            &spl_token::ID,
+            &accounts.client_llx_token_account.key(),
+            &accounts.client_x_token_account.key(),
+            vault.transfer_metas(accounts.source_authority.key, vec![]),
+            7,
+            0,
+        )
+        .unwrap();
+        let ctx = CpiContext::new(program(vault::id()).clone(), accounts);
+        assert_eq!(invoked(|| withdraw(ctx, 7)), expected);
+    }
+
+    #[test]
+    fn test_mint() {
+        let vault = TestVault::new();
+        let client_x_token_account = token_account(Pubkey::new_unique(), vault.state.x_token_mint);
+        let accounts = vault.deposit(client_x_token_account);
+        let expected = VaultInstruction::mint(
+            &vault::id(),
+            &spl_token::ID,
+            client_x_token_account.key,
+            &accounts.client_llx_token_account.key(),
+            vault.transfer_metas(accounts.source_authority.key, vec![]),
+            7,
+            0,
+        )
+        .unwrap();
+        let ctx = CpiContext::new(program(vault::id()).clone(), accounts);
+        assert_eq!(invoked(|| mint(ctx, 7)), expected);
+    }
+
+    #[test]
+    fn test_withdraw_assets() {
+        let vault = TestVault::new();
+        let accounts = vault.withdraw();
+        let extra_account = unchecked();
+        let expected = VaultInstruction::withdraw_assets(
+            &vault::id(),
+            &spl_token::ID,
+            &accounts.client_llx_token_account.key(),
+            &accounts.client_x_token_account.key(),
+            vault.transfer_metas(
+                accounts.source_authority.key,
+                vec![AccountMeta::new(*extra_account.key, false)],
+            ),
+            7,
+            0,
+        )
+        .unwrap();
+        let ctx = CpiContext::new(program(vault::id()).clone(), accounts)
+            .with_remaining_accounts(vec![extra_account.clone()]);
+        assert_eq!(invoked(|| withdraw_assets(ctx, 7)), expected);
+    }
+
+    #[test]
+    fn test_withdraw_in_kind() {
+        for vault in [TestVault::new(), TestVault::with_existing_accounts()] {
+            let accounts = WithdrawInKind {
+                token_program: Program::try_from(program(spl_token::ID)).unwrap(),
+                client_llx_token_account: Account::try_from(token_account(
+                    Pubkey::new_unique(),
+                    vault.state.llx_token_mint_id,
+                ))
+                .unwrap(),
+                client_lx_token_account: Account::try_from(token_account(
+                    Pubkey::new_unique(),
+                    vault.state.lx_token_mint,
+                ))
+                .unwrap(),
+                source_authority: unchecked().clone(),
+                vault_storage: vault.vault_storage(),
+                llx_token_mint: Account::try_from(vault.llx_token_mint).unwrap(),
+                vault_authority: vault.vault_authority.clone(),
+                vault_token_account: Account::try_from(vault.vault_token_account).unwrap(),
+            };
+            let expected = VaultInstruction::withdraw_in_kind(
+                &vault::id(),
+                &spl_token::ID,
+                &accounts.client_llx_token_account.key(),
+                &accounts.client_lx_token_account.key(),
+                accounts.source_authority.key,
+                &[],
+                vault.storage.key,
+                &vault.state,
+                7,
+            )
+            .unwrap();
+            let ctx = CpiContext::new(program(vault::id()).clone(), accounts);
+            let ix = invoked(|| withdraw_in_kind(ctx, 7));
+            assert_eq!(ix, expected);
+            assert_eq!(ix.accounts[5].pubkey, *vault.llx_token_mint.key);
+            assert_eq!(ix.accounts[7].pubkey, *vault.vault_token_account.key);
+        }
+    }
+
+    #[test]
+    fn test_zap_deposit() {
+        let vault = TestVault::new();
+        let client_x_token_account = token_account(Pubkey::new_unique(), vault.state.x_token_mint);
+        let accounts = ZapDeposit {
+            client_input_token_account: Account::try_from(token_account(
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+            ))
+            .unwrap(),
+            swap_program: program(vault::swap::id()).clone(),
+            swap: unchecked().clone(),
+            swap_authority: unchecked().clone(),
+            swap_source: unchecked().clone(),
+            swap_destination: unchecked().clone(),
+            pool_mint: unchecked().clone(),
+            pool_fee_account: unchecked().clone(),
+            deposit: vault.deposit(client_x_token_account),
+        };
+        let expected = VaultInstruction::zap_deposit(
+            &vault::id(),
+            &spl_token::ID,
+            &accounts.client_input_token_account.key(),
+            client_x_token_account.key,
+            &accounts.deposit.client_llx_token_account.key(),
+            zap_account_metas(
+                accounts.swap.key,
+                accounts.swap_authority.key,
+                accounts.swap_source.key,
+                accounts.swap_destination.key,
+                accounts.pool_mint.key,
+                accounts.pool_fee_account.key,
+            ),
+            vault.transfer_metas(accounts.deposit.source_authority.key, vec![]),
+            7,
+            5,
+        )
+        .unwrap();
+        let ctx = CpiContext::new(program(vault::id()).clone(), accounts);
+        assert_eq!(invoked(|| zap_deposit(ctx, 7, 5)), expected);
+    }
+
+    #[test]
+    fn test_estimate_value() {
+        let vault = TestVault::new();
+        let accounts = EstimateValue {
+            program: unchecked().clone(),
+            shared_memory: unchecked().clone(),
+            vault_storage: vault.vault_storage(),
+            vault_token_account: Account::try_from(vault.vault_token_account).unwrap(),
+        };
+        let expected = VaultInstruction::estimate_value(
+            &vault::id(),
+            accounts.program.key,
+            accounts.shared_memory.key,
+            vault::instruction::estimate_value_account_metas(
+                vault.storage.key,
+                &vault.state,
+                vec![],
+            ),
+        )
+        .unwrap();
+        // Strategy Vaults take their strategy program as the first remaining account.
+        let ctx = CpiContext::new(program(vault::id()).clone(), accounts)
+            .with_remaining_accounts(vec![vault.strategy_program.clone()]);
+        assert_eq!(invoked(|| estimate_value(ctx)), expected);
+    }
+
+    /// Borsh-encodes `preview` by hand, as the Vault uses a newer borsh than Anchor.
+    fn encode(preview: &VaultPreview) -> Vec<u8> {
+        let mut data = [
+            preview.total_assets,
+            preview.llx_supply,
+            preview.price_per_share,
+        ]
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect::<Vec<_>>();
+        match preview.redeemable {
+            Some(redeemable) => {
+                data.push(1);
+                data.extend(redeemable.to_le_bytes());
+            }
+            None => data.push(0),
+        }
+        data
+    }
+
+    #[test]
+    fn test_preview_vault() {
+        let vault = TestVault::new();
+        let preview = VaultPreview {
+            total_assets: 200,
+            llx_supply: 100,
+            price_per_share: 2_000_000_000,
+            redeemable: None,
+        };
+        let ctx = CpiContext::new(program(vault::id()).clone(), vault.preview());
+        assert_eq!(
+            invoked_view(&encode(&preview), || preview_vault(ctx)),
+            (
+                VaultInstruction::preview_vault(
+                    &vault::id(),
+                    vault.storage.key,
+                    &vault.state,
+                    None
+                )
+                .unwrap(),
+                preview
+            )
+        );
+
+        let preview = VaultPreview {
+            redeemable: Some(20),
+            ..preview
+        };
+        let accounts = vault.preview_position();
+        let expected = VaultInstruction::preview_vault(
+            &vault::id(),
+            vault.storage.key,
+            &vault.state,
+            Some(&accounts.client_llx_token_account.key()),
+        )
+        .unwrap();
+        let ctx = CpiContext::new(program(vault::id()).clone(), accounts);
+        assert_eq!(
+            invoked_view(&encode(&preview), || { preview_vault_position(ctx) }),
+            (expected, preview)
+        );
+    }
+
+    #[test]
+    fn test_preview_transfers() {
+        let vault = TestVault::new();
+        let ctx = CpiContext::new(program(vault::id()).clone(), vault.preview());
+        assert_eq!(
+            invoked_view(&5u64.to_le_bytes(), || preview_deposit(ctx, 10)),
+            (
+                VaultInstruction::preview_deposit(
+                    &vault::id(),
+                    vault.storage.key,
+                    &vault.state,
+                    10
+                )
+                .unwrap(),
+                5
+            )
+        );
+        let ctx = CpiContext::new(program(vault::id()).clone(), vault.preview());
+        assert_eq!(
+            invoked_view(&20u64.to_le_bytes(), || preview_withdraw(ctx, 10)),
+            (
+                VaultInstruction::preview_withdraw(
+                    &vault::id(),
+                    vault.storage.key,
+                    &vault.state,
+                    10
+                )
+                .unwrap(),
+                20
+            )
+        );
+    }
+
+    #[test]
+    fn test_tokenized_vault_views() {
+        type View =
+            fn(CpiContext<'static, 'static, 'static, 'static, Preview<'static>>) -> Result<u64>;
+        type PositionView = fn(
+            CpiContext<'static, 'static, 'static, 'static, PreviewPosition<'static>>,
+        ) -> Result<u64>;
+        type PositionBuilder = fn(
+            &Pubkey,
+            &Pubkey,
+            &state::Vault,
+            &Pubkey,
+        ) -> std::result::Result<Instruction, ProgramError>;
+
+        let vault = TestVault::new();
+        let storage = vault.storage.key;
+        let views: Vec<(Instruction, View)> = vec![
+            (
+                VaultInstruction::convert_to_shares(&vault::id(), storage, &vault.state, 10)
+                    .unwrap(),
+                |ctx| convert_to_shares(ctx, 10),
+            ),
+            (
+                VaultInstruction::convert_to_assets(&vault::id(), storage, &vault.state, 10)
+                    .unwrap(),
+                |ctx| convert_to_assets(ctx, 10),
+            ),
+            (
+                VaultInstruction::max_deposit(&vault::id(), storage, &vault.state).unwrap(),
+                max_deposit,
+            ),
+            (
+                VaultInstruction::max_mint(&vault::id(), storage, &vault.state).unwrap(),
+                max_mint,
+            ),
+            (
+                VaultInstruction::preview_mint(&vault::id(), storage, &vault.state, 10).unwrap(),
+                |ctx| preview_mint(ctx, 10),
+            ),
+            (
+                VaultInstruction::preview_withdraw_assets(&vault::id(), storage, &vault.state, 10)
+                    .unwrap(),
+                |ctx| preview_withdraw_assets(ctx, 10),
+            ),
+        ];
+        for (expected, view) in views {
+            let ctx = CpiContext::new(program(vault::id()).clone(), vault.preview());
+            assert_eq!(
+                invoked_view(&5u64.to_le_bytes(), || view(ctx)),
+                (expected, 5)
+            );
+        }
+
+        let position_views: Vec<(PositionBuilder, PositionView)> = vec![
+            (VaultInstruction::max_withdraw, max_withdraw),
+            (VaultInstruction::max_redeem, max_redeem),
+        ];
+        for (builder, view) in position_views {
+            let accounts = vault.preview_position();
+            let expected = builder(
+                &vault::id(),
+                storage,
+                &vault.state,
+                &accounts.client_llx_token_account.key(),
+            )
+            .unwrap();
+            let ctx = CpiContext::new(program(vault::id()).clone(), accounts);
+            assert_eq!(
+                invoked_view(&5u64.to_le_bytes(), || view(ctx)),
+                (expected, 5)
+            );
+        }
+    }
+
+    #[test]
+    fn test_estimate_position_value() {
+        let vault = TestVault::new();
+        let outer_vault = TestVault::new();
+        let accounts = EstimatePositionValue {
+            program: program(vault::id()).clone(),
+            shared_memory: unchecked().clone(),
+            position_token_account: Account::try_from(outer_vault.vault_token_account).unwrap(),
+            outer_vault_storage: outer_vault.vault_storage(),
+            llx_token_mint: Account::try_from(vault.llx_token_mint).unwrap(),
+            vault_storage: vault.vault_storage(),
+            vault_token_account: Account::try_from(vault.vault_token_account).unwrap(),
+        };
+        let mut metas = vec![AccountMeta::new_readonly(
+            outer_vault.state.vault_token_account,
+            false,
+        )];
+        metas.extend(vault::instruction::estimate_position_value_account_metas(
+            outer_vault.storage.key,
+            vault.storage.key,
+            &vault.state,
+            vec![],
+        ));
+        let expected = VaultInstruction::estimate_position_value(
+            &vault::id(),
+            accounts.shared_memory.key,
+            metas,
+        )
+        .unwrap();
+        let ctx = CpiContext::new(program(vault::id()).clone(), accounts)
+            .with_remaining_accounts(vec![vault.strategy_program.clone()]);
+        assert_eq!(
+            invoked_view(&90u64.to_le_bytes(), || estimate_position_value(ctx)),
+            (expected, 90)
+        );
+    }
+
+    #[test]
+    fn test_governance() {
+        let vault = TestVault::new();
+        let governance = || unchecked().clone();
+
+        let accounts = AcknowledgeLoss {
+            governance: governance(),
+            vault_storage: vault.vault_storage(),
+        };
+        let expected = VaultInstruction::acknowledge_loss(
+            &vault::id(),
+            accounts.governance.key,
+            vault.storage.key,
+        )
+        .unwrap();
+        let ctx = CpiContext::new(program(vault::id()).clone(), accounts);
+        assert_eq!(invoked(|| acknowledge_loss(ctx)), expected);
+
+        let accounts = SetWithdrawalFee {
+            governance: governance(),
+            vault_storage: vault.vault_storage(),
+        };
+        let expected = VaultInstruction::set_withdrawal_fee(
+            &vault::id(),
+            accounts.governance.key,
+            vault.storage.key,
+            50,
+        )
+        .unwrap();
+        let ctx = CpiContext::new(program(vault::id()).clone(), accounts);
+        assert_eq!(invoked(|| set_withdrawal_fee(ctx, 50)), expected);
+
+        let accounts = SetPaused {
+            governance: governance(),
+            vault_storage: vault.vault_storage(),
+        };
+        let expected = VaultInstruction::set_paused(
+            &vault::id(),
+            accounts.governance.key,
+            vault.storage.key,
+            true,
+        )
+        .unwrap();
+        let ctx = CpiContext::new(program(vault::id()).clone(), accounts);
+        assert_eq!(invoked(|| set_paused(ctx, true)), expected);
+    }
+
+    #[test]
+    fn test_registry() {
+        let vault = TestVault::new();
+        let governance = account_info(vault.state.governance, system_program::ID, vec![], false);
+        let (registered_vault, _) = VaultInstruction::find_registered_vault(
+            &vault::id(),
+            &vault.state.x_token_mint,
+            vault.storage.key,
+        );
+        let (vault_registry, _) =
+            VaultInstruction::find_vault_registry(&vault::id(), &vault.state.x_token_mint);
+        let registered_vault = account_info(registered_vault, vault::id(), vec![], false);
+        let vault_registry = account_info(vault_registry, vault::id(), vec![], false);
+
+        let accounts = RegisterVault {
+            governance: governance.clone(),
+            vault_storage: vault.vault_storage(),
+            registered_vault: registered_vault.clone(),
+            vault_registry: vault_registry.clone(),
+            system_program: Program::try_from(program(system_program::ID)).unwrap(),
+        };
+        let expected = VaultInstruction::register_vault(
+            &vault::id(),
+            governance.key,
+            vault.storage.key,
+            &vault.state.x_token_mint,
+        )
+        .unwrap();
+        let ctx = CpiContext::new(program(vault::id()).clone(), accounts);
+        assert_eq!(invoked(|| register_vault(ctx)), expected);
+
+        let accounts = DeregisterVault {
+            governance: governance.clone(),
+            vault_storage: vault.vault_storage(),
+            registered_vault: registered_vault.clone(),
+            vault_registry: vault_registry.clone(),
+        };
+        let expected = VaultInstruction::deregister_vault(
+            &vault::id(),
+            governance.key,
+            vault.storage.key,
+            &vault.state.x_token_mint,
+        )
+        .unwrap();
+        let ctx = CpiContext::new(program(vault::id()).clone(), accounts);
+        assert_eq!(invoked(|| deregister_vault(ctx)), expected);
+    }
+}
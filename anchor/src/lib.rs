@@ -0,0 +1,324 @@
+//! Anchor facade for the Vault program & the strategy interface.
+//!
+//! The Vault remains a native program: this crate only describes its accounts to Anchor so
+//! Anchor programs can validate them with `#[derive(Accounts)]` & call the Vault through the
+//! helpers in [cpi]. [strategy] does the same for StrategyInstruction & lets Anchor-based
+//! strategies route the interface through their `fallback`.
+//!
+//! Accounts which the Vault records in its storage are checked against it here; PDAs & the
+//! remaining invariants are checked by the Vault itself. Authorities are plain accounts so that
+//! PDAs can sign for them via `CpiContext::new_with_signer`.
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use std::ops::Deref;
+use vault::state;
+
+pub mod cpi;
+pub mod strategy;
+
+/// The Vault program, for use as `Program<'info, Vault>`.
+#[derive(Clone)]
+pub struct Vault;
+
+impl Id for Vault {
+    fn id() -> Pubkey {
+        vault::id()
+    }
+}
+
+/// Vault storage, for use as `Account<'info, VaultState>`. Read-only: only the Vault writes it.
+#[derive(Clone)]
+pub struct VaultState(pub state::Vault);
+
+impl AccountDeserialize for VaultState {
+    fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self> {
+        use anchor_lang::solana_program::program_pack::Pack;
+        Ok(VaultState(state::Vault::unpack(buf)?))
+    }
+}
+
+impl AccountSerialize for VaultState {}
+
+impl Owner for VaultState {
+    fn owner() -> Pubkey {
+        vault::id()
+    }
+}
+
+impl Deref for VaultState {
+    type Target = state::Vault;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Accounts for VaultInstruction#InitializeVault. The storage account must already be allocated
+/// (Vault::LEN bytes) & owned by the Vault. Register the new Vault for discovery separately, via
+/// [cpi::register_vault].
+#[derive(Accounts)]
+pub struct InitializeVault<'info> {
+    /// CHECK: Pays for the new accounts & becomes the Vault's governance; signs the CPI.
+    #[account(mut)]
+    pub payer: AccountInfo<'info>,
+    /// CHECK: Initialized by the Vault.
+    #[account(mut, owner = vault::id())]
+    pub vault_storage: AccountInfo<'info>,
+    /// CHECK: Created by the Vault at its derived address.
+    #[account(mut)]
+    pub vault_token_account: AccountInfo<'info>,
+    /// CHECK: Created by the Vault at its derived address.
+    #[account(mut)]
+    pub llx_token_mint: AccountInfo<'info>,
+    pub x_token_mint: Account<'info, Mint>,
+    /// The strategy's lX mint, or X if hodling.
+    pub vault_token_mint: Account<'info, Mint>,
+    /// CHECK: The Vault authority; checked by the Vault.
+    pub vault_authority: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: Any program implementing the strategy interface, or the Vault if hodling.
+    pub strategy_program: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Accounts for VaultInstruction#Deposit & VaultInstruction#Mint. Strategy extra accounts are
+/// passed as remaining accounts.
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    pub token_program: Program<'info, Token>,
+    #[account(mut, token::mint = vault_storage.x_token_mint)]
+    pub client_x_token_account: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = vault_storage.llx_token_mint_id)]
+    pub client_llx_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Owner or delegate of the client X token account; signs the CPI.
+    pub source_authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub vault_storage: Account<'info, VaultState>,
+    /// CHECK: The Vault's strategy.
+    #[account(address = vault_storage.strategy_program_id)]
+    pub strategy_program: AccountInfo<'info>,
+    #[account(mut, address = vault_storage.llx_token_mint_id)]
+    pub llx_token_mint: Account<'info, Mint>,
+    /// CHECK: The Vault authority; checked by the Vault.
+    pub vault_authority: AccountInfo<'info>,
+    #[account(mut, address = vault_storage.vault_token_account)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+}
+
+/// Accounts for VaultInstruction#Withdraw & VaultInstruction#WithdrawAssets. Strategy extra
+/// accounts are passed as remaining accounts.
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    pub token_program: Program<'info, Token>,
+    #[account(mut, token::mint = vault_storage.llx_token_mint_id)]
+    pub client_llx_token_account: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = vault_storage.x_token_mint)]
+    pub client_x_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Owner or delegate of the client llX token account; signs the CPI.
+    pub source_authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub vault_storage: Account<'info, VaultState>,
+    /// CHECK: The Vault's strategy.
+    #[account(address = vault_storage.strategy_program_id)]
+    pub strategy_program: AccountInfo<'info>,
+    #[account(mut, address = vault_storage.llx_token_mint_id)]
+    pub llx_token_mint: Account<'info, Mint>,
+    /// CHECK: The Vault authority; checked by the Vault.
+    pub vault_authority: AccountInfo<'info>,
+    #[account(mut, address = vault_storage.vault_token_account)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+}
+
+/// Accounts for VaultInstruction#WithdrawInKind.
+#[derive(Accounts)]
+pub struct WithdrawInKind<'info> {
+    pub token_program: Program<'info, Token>,
+    #[account(mut, token::mint = vault_storage.llx_token_mint_id)]
+    pub client_llx_token_account: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = vault_storage.lx_token_mint)]
+    pub client_lx_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Owner or delegate of the client llX token account; signs the CPI.
+    pub source_authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub vault_storage: Account<'info, VaultState>,
+    #[account(mut, address = vault_storage.llx_token_mint_id)]
+    pub llx_token_mint: Account<'info, Mint>,
+    /// CHECK: The Vault authority; checked by the Vault.
+    pub vault_authority: AccountInfo<'info>,
+    #[account(mut, address = vault_storage.vault_token_account)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+}
+
+/// Accounts for VaultInstruction#ZapDeposit.
+#[derive(Accounts)]
+pub struct ZapDeposit<'info> {
+    #[account(mut)]
+    pub client_input_token_account: Account<'info, TokenAccount>,
+    /// CHECK: The SPL Token Swap program.
+    #[account(address = vault::swap::id())]
+    pub swap_program: AccountInfo<'info>,
+    /// CHECK: Checked by the swap program.
+    pub swap: AccountInfo<'info>,
+    /// CHECK: Checked by the swap program.
+    pub swap_authority: AccountInfo<'info>,
+    /// CHECK: The pool's input token account; checked by the swap program.
+    #[account(mut)]
+    pub swap_source: AccountInfo<'info>,
+    /// CHECK: The pool's X token account; checked by the swap program.
+    #[account(mut)]
+    pub swap_destination: AccountInfo<'info>,
+    /// CHECK: Checked by the swap program.
+    #[account(mut)]
+    pub pool_mint: AccountInfo<'info>,
+    /// CHECK: Checked by the swap program.
+    #[account(mut)]
+    pub pool_fee_account: AccountInfo<'info>,
+    /// The swapped X is deposited from `deposit.client_x_token_account`.
+    pub deposit: Deposit<'info>,
+}
+
+/// Accounts for VaultInstruction#EstimateValue. Strategy Vaults take their strategy program
+/// followed by its extra accounts as remaining accounts; HODL Vaults take none.
+#[derive(Accounts)]
+pub struct EstimateValue<'info> {
+    /// CHECK: Unused by the Vault.
+    pub program: AccountInfo<'info>,
+    /// CHECK: Shared memory output.
+    #[account(mut)]
+    pub shared_memory: AccountInfo<'info>,
+    #[account(mut)]
+    pub vault_storage: Account<'info, VaultState>,
+    #[account(address = vault_storage.vault_token_account)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+}
+
+/// Accounts for VaultInstruction#EstimatePositionValue, valuing the llX held by the outer Vault
+/// `outer_vault_storage`. As for EstimateValue, strategy Vaults take their strategy program followed
+/// by its extra accounts as remaining accounts; HODL Vaults take none.
+#[derive(Accounts)]
+pub struct EstimatePositionValue<'info> {
+    /// CHECK: The calling Vault program, i.e. the Vault itself.
+    #[account(address = vault::id())]
+    pub program: AccountInfo<'info>,
+    /// CHECK: Shared memory output.
+    #[account(mut)]
+    pub shared_memory: AccountInfo<'info>,
+    /// The outer Vault's token account, holding the llX position.
+    #[account(address = outer_vault_storage.vault_token_account)]
+    pub position_token_account: Account<'info, TokenAccount>,
+    pub outer_vault_storage: Account<'info, VaultState>,
+    #[account(address = vault_storage.llx_token_mint_id)]
+    pub llx_token_mint: Account<'info, Mint>,
+    #[account(mut)]
+    pub vault_storage: Account<'info, VaultState>,
+    #[account(address = vault_storage.vault_token_account)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+}
+
+/// Accounts for VaultInstruction#PreviewVault without a client llX token account, and for the
+/// other views taking the same accounts (PreviewDeposit, ConvertToShares, MaxDeposit etc.).
+#[derive(Accounts)]
+pub struct Preview<'info> {
+    pub vault_storage: Account<'info, VaultState>,
+    #[account(address = vault_storage.llx_token_mint_id)]
+    pub llx_token_mint: Account<'info, Mint>,
+    #[account(address = vault_storage.vault_token_account)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+}
+
+/// Accounts for VaultInstruction#PreviewVault with the client llX token account whose position
+/// to report, and for MaxWithdraw & MaxRedeem.
+#[derive(Accounts)]
+pub struct PreviewPosition<'info> {
+    pub preview: Preview<'info>,
+    #[account(token::mint = preview.vault_storage.llx_token_mint_id)]
+    pub client_llx_token_account: Account<'info, TokenAccount>,
+}
+
+/// Accounts for VaultInstruction#AcknowledgeLoss.
+#[derive(Accounts)]
+pub struct AcknowledgeLoss<'info> {
+    /// CHECK: The Vault's governance; signs the CPI.
+    pub governance: AccountInfo<'info>,
+    #[account(mut, has_one = governance)]
+    pub vault_storage: Account<'info, VaultState>,
+}
+
+/// Accounts for VaultInstruction#SetWithdrawalFee.
+#[derive(Accounts)]
+pub struct SetWithdrawalFee<'info> {
+    /// CHECK: The Vault's governance; signs the CPI.
+    pub governance: AccountInfo<'info>,
+    #[account(mut, has_one = governance)]
+    pub vault_storage: Account<'info, VaultState>,
+}
+
+/// Accounts for VaultInstruction#SetPaused.
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    /// CHECK: The Vault's governance; signs the CPI.
+    pub governance: AccountInfo<'info>,
+    #[account(mut, has_one = governance)]
+    pub vault_storage: Account<'info, VaultState>,
+}
+
+/// Accounts for VaultInstruction#RegisterVault.
+#[derive(Accounts)]
+pub struct RegisterVault<'info> {
+    /// CHECK: The Vault's governance; pays for the registry entry & space, and signs the CPI.
+    #[account(mut)]
+    pub governance: AccountInfo<'info>,
+    #[account(has_one = governance)]
+    pub vault_storage: Account<'info, VaultState>,
+    /// CHECK: The Vault's registry entry; checked by the Vault.
+    #[account(mut)]
+    pub registered_vault: AccountInfo<'info>,
+    /// CHECK: The X mint's Vault registry; checked by the Vault.
+    #[account(mut)]
+    pub vault_registry: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Accounts for VaultInstruction#DeregisterVault.
+#[derive(Accounts)]
+pub struct DeregisterVault<'info> {
+    /// CHECK: The Vault's governance; receives the registry's freed rent, and signs the CPI.
+    #[account(mut)]
+    pub governance: AccountInfo<'info>,
+    #[account(has_one = governance)]
+    pub vault_storage: Account<'info, VaultState>,
+    /// CHECK: The Vault's registry entry; checked by the Vault.
+    #[account(mut)]
+    pub registered_vault: AccountInfo<'info>,
+    /// CHECK: The X mint's Vault registry; checked by the Vault.
+    #[account(mut)]
+    pub vault_registry: AccountInfo<'info>,
+}
+
+/// Accounts for VaultInstruction#InitializeVaultWithAccounts. The storage account must already be
+/// allocated (Vault::LEN bytes) & owned by the Vault.
+#[derive(Accounts)]
+pub struct InitializeVaultWithAccounts<'info> {
+    /// CHECK: Becomes the Vault's governance; signs the CPI.
+    pub governance: AccountInfo<'info>,
+    /// CHECK: Initialized by the Vault.
+    #[account(mut, owner = vault::id())]
+    pub vault_storage: AccountInfo<'info>,
+    /// The existing lX token account, or X token account if hodling.
+    #[account(mut)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+    #[account(mut)]
+    pub llx_token_mint: Account<'info, Mint>,
+    pub x_token_mint: Account<'info, Mint>,
+    /// CHECK: The Vault authority; checked by the Vault.
+    pub vault_authority: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    /// CHECK: Any program implementing the strategy interface, or the Vault if hodling.
+    pub strategy_program: AccountInfo<'info>,
+    pub rent: Sysvar<'info, Rent>,
+    /// CHECK: Owner of the token account; signs the CPI.
+    pub vault_token_account_owner: AccountInfo<'info>,
+    /// CHECK: The llX mint authority; signs the CPI.
+    pub llx_token_mint_authority: AccountInfo<'info>,
+}
@@ -0,0 +1,156 @@
+//! Anchor bindings for StrategyInstruction.
+//!
+//! Vault-facing strategies don't use Anchor's 8-byte discriminators, so an Anchor-based strategy
+//! routes the interface through its `fallback` instead of `#[program]` methods:
+//!
+//! ```ignore
+//! #[program]
+//! pub mod my_strategy {
+//!     use super::*;
+//!
+//!     pub fn fallback<'info>(
+//!         program_id: &Pubkey,
+//!         accounts: &'info [AccountInfo<'info>],
+//!         data: &[u8],
+//!     ) -> Result<()> {
+//!         vault_anchor::strategy::process_instruction::<MyStrategy>(program_id, accounts, data)
+//!     }
+//! }
+//! ```
+//!
+//! The Accounts structs & CPI helpers below are for programs calling a strategy directly.
+use crate::cpi::remaining_metas;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program::{get_return_data, invoke_signed},
+};
+use anchor_spl::token::{Token, TokenAccount};
+use std::convert::TryInto;
+use strategy_api::strategy::Strategy;
+use strategy_api::strategy_instruction::StrategyInstruction;
+
+/// Accounts for StrategyInstruction#Deposit & StrategyInstruction#Withdraw. Strategy extra
+/// accounts are passed as remaining accounts.
+#[derive(Accounts)]
+pub struct StrategyTransfer<'info> {
+    pub token_program: Program<'info, Token>,
+    /// X token account on deposit, lX token account on withdraw.
+    #[account(mut)]
+    pub source_token_account: Account<'info, TokenAccount>,
+    /// lX token account on deposit, X token account on withdraw.
+    #[account(mut)]
+    pub target_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Owner or delegate of the source token account; signs the CPI.
+    pub source_authority: AccountInfo<'info>,
+    /// CHECK: Any program implementing the strategy interface.
+    #[account(executable)]
+    pub strategy_program: AccountInfo<'info>,
+}
+
+/// Accounts for StrategyInstruction#EstimateValue. Strategy extra accounts are passed as remaining
+/// accounts.
+#[derive(Accounts)]
+pub struct StrategyEstimateValue<'info> {
+    /// CHECK: Passed through to the strategy.
+    pub vault_program: AccountInfo<'info>,
+    /// CHECK: Shared memory output.
+    #[account(mut)]
+    pub shared_memory: AccountInfo<'info>,
+    /// The calling Vault's lX token account, the position being valued.
+    pub position_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Any program implementing the strategy interface.
+    #[account(executable)]
+    pub strategy_program: AccountInfo<'info>,
+}
+
+/// Invokes the strategy's deposit instruction, `instruction_id` as configured on the Vault.
+pub fn deposit<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, StrategyTransfer<'info>>,
+    instruction_id: u8,
+    amount: u64,
+) -> Result<()> {
+    let a = &ctx.accounts;
+    let ix = StrategyInstruction::deposit(
+        instruction_id,
+        a.strategy_program.key,
+        &a.token_program.key(),
+        &a.source_token_account.key(),
+        &a.target_token_account.key(),
+        transfer_metas(a.source_authority.key, &ctx.remaining_accounts),
+        amount,
+        0,
+    )?;
+    invoke(ix, ctx)
+}
+
+/// Invokes the strategy's withdraw instruction, `instruction_id` as configured on the Vault.
+pub fn withdraw<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, StrategyTransfer<'info>>,
+    instruction_id: u8,
+    amount: u64,
+) -> Result<()> {
+    let a = &ctx.accounts;
+    let ix = StrategyInstruction::withdraw(
+        instruction_id,
+        a.strategy_program.key,
+        &a.token_program.key(),
+        &a.source_token_account.key(),
+        &a.target_token_account.key(),
+        transfer_metas(a.source_authority.key, &ctx.remaining_accounts),
+        amount,
+        0,
+    )?;
+    invoke(ix, ctx)
+}
+
+/// Invokes the strategy's estimate instruction & returns the value it reports.
+pub fn estimate_value<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, StrategyEstimateValue<'info>>,
+    instruction_id: u8,
+) -> Result<u64> {
+    let a = &ctx.accounts;
+    let strategy_program = *a.strategy_program.key;
+    let ix = StrategyInstruction::estimate_value(
+        instruction_id,
+        &strategy_program,
+        a.vault_program.key,
+        a.shared_memory.key,
+        &a.position_token_account.key(),
+        remaining_metas(&ctx.remaining_accounts),
+    )?;
+    invoke(ix, ctx)?;
+    match get_return_data() {
+        Some((program_id, data)) if program_id == strategy_program => data
+            .get(..8)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or_else(|| ProgramError::InvalidAccountData.into()),
+        _ => Err(ProgramError::InvalidAccountData.into()),
+    }
+}
+
+/// Dispatches StrategyInstruction to `S`; call from the strategy program's `fallback`.
+pub fn process_instruction<S: Strategy>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> Result<()> {
+    strategy_api::strategy::process_instruction::<S>(program_id, accounts, data).map_err(Into::into)
+}
+
+fn transfer_metas(
+    source_authority: &Pubkey,
+    remaining_accounts: &[AccountInfo],
+) -> Vec<AccountMeta> {
+    let mut metas = vec![AccountMeta::new_readonly(*source_authority, true)];
+    metas.extend(remaining_metas(remaining_accounts));
+    metas
+}
+
+fn invoke<'info, T: ToAccountInfos<'info> + ToAccountMetas>(
+    ix: Instruction,
+    ctx: CpiContext<'_, '_, '_, 'info, T>,
+) -> Result<()> {
+    invoke_signed(&ix, &ctx.to_account_infos(), ctx.signer_seeds).map_err(Into::into)
+}
@@ -0,0 +1,384 @@
+//! Operator CLI for the Vault, built on vault-client.
+//!
+//! Run against a local `solana-test-validator` with `--url http://localhost:8899` (the default).
+//!
+//! Harvesting is `estimate` (alias `harvest`), which marks the strategy position to market,
+//! recording gains & losses.
+use clap::{
+    crate_description, crate_name, crate_version, App, AppSettings, Arg, ArgMatches, SubCommand,
+};
+use solana_clap_utils::{
+    input_parsers::{pubkey_of, pubkeys_of, value_of},
+    input_validators::{is_keypair, is_pubkey, is_url},
+};
+use solana_client::rpc_client::RpcClient;
+use solana_program::instruction::AccountMeta;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::{read_keypair_file, Keypair, Signer},
+    transaction::Transaction,
+};
+use std::error::Error;
+use vault_client::{VaultClient, VaultStrategy};
+
+type CliResult = Result<(), Box<dyn Error>>;
+
+fn main() {
+    let default_keypair = default_keypair_path();
+    let matches = App::new(crate_name!())
+        .about(crate_description!())
+        .version(crate_version!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .short("u")
+                .value_name("URL")
+                .takes_value(true)
+                .global(true)
+                .default_value("http://localhost:8899")
+                .validator(is_url)
+                .help("JSON RPC URL of the cluster"),
+        )
+        .arg(
+            Arg::with_name("keypair")
+                .long("keypair")
+                .short("k")
+                .value_name("PATH")
+                .takes_value(true)
+                .global(true)
+                .default_value(&default_keypair)
+                .validator(is_keypair)
+                .help("Fee payer & token account authority"),
+        )
+        .arg(
+            Arg::with_name("program_id")
+                .long("program-id")
+                .value_name("PUBKEY")
+                .takes_value(true)
+                .global(true)
+                .validator(is_pubkey)
+                .help("Vault program ID [default: the built-in Vault ID]"),
+        )
+        .subcommand(
+            SubCommand::with_name("create-vault")
+                .about("Create & initialize a Vault; governed by the keypair")
+                .arg(pubkey_arg("x_token_mint", "MINT", "The underlying (X) mint").required(true))
+                .arg(
+                    Arg::with_name("strategy_vault")
+                        .long("strategy-vault")
+                        .value_name("VAULT")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .conflicts_with("strategy_program")
+                        .help("Invest in another Vault, by storage account [default: hodl]"),
+                )
+                .arg(
+                    Arg::with_name("strategy_program")
+                        .long("strategy-program")
+                        .value_name("PROGRAM_ID")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .requires_all(&["lx_token_mint", "instruction_ids"])
+                        .help("Invest in a strategy program"),
+                )
+                .arg(
+                    Arg::with_name("lx_token_mint")
+                        .long("lx-mint")
+                        .value_name("MINT")
+                        .takes_value(true)
+                        .validator(is_pubkey)
+                        .help("The strategy program's derivative (lX) mint"),
+                )
+                .arg(
+                    Arg::with_name("instruction_ids")
+                        .long("instruction-ids")
+                        .value_names(&["DEPOSIT", "WITHDRAW", "ESTIMATE"])
+                        .takes_value(true)
+                        .number_of_values(3)
+                        .help("The strategy program's instruction IDs"),
+                )
+                .arg(
+                    Arg::with_name("block_deposits_on_loss")
+                        .long("block-deposits-on-loss")
+                        .help("Block deposits until losses are acknowledged"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("deposit")
+                .about("Deposit X tokens for llX tokens")
+                .arg(pubkey_arg("vault", "VAULT", "Vault storage account").required(true))
+                .arg(amount_arg("X tokens"))
+                .arg(account_arg("from", "The X token account").required(true))
+                .arg(account_arg("to", "The llX token account").required(true))
+                .arg(
+                    Arg::with_name("min_shares")
+                        .long("min-shares")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .default_value("0")
+                        .validator(|s| s.parse::<u64>().map(|_| ()).map_err(|e| e.to_string()))
+                        .help("Fail unless the deposit buys at least this many llX tokens"),
+                )
+                .arg(strategy_accounts_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("withdraw")
+                .about("Redeem llX tokens for X tokens")
+                .arg(pubkey_arg("vault", "VAULT", "Vault storage account").required(true))
+                .arg(amount_arg("llX tokens"))
+                .arg(account_arg("from", "The llX token account").required(true))
+                .arg(account_arg("to", "The X token account").required(true))
+                .arg(strategy_accounts_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("estimate")
+                .visible_alias("harvest")
+                .about("Harvest: estimate a Vault's value in X, recording any gain or loss")
+                .arg(pubkey_arg("vault", "VAULT", "Vault storage account").required(true))
+                .arg(strategy_accounts_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("acknowledge-loss")
+                .about("Acknowledge a Vault's recorded losses, unblocking deposits; governed by the keypair")
+                .arg(pubkey_arg("vault", "VAULT", "Vault storage account").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("set-fees")
+                .about("Set the fee kept from each withdrawal; governed by the keypair")
+                .arg(pubkey_arg("vault", "VAULT", "Vault storage account").required(true))
+                .arg(
+                    Arg::with_name("withdrawal_fee_bps")
+                        .long("withdrawal-fee-bps")
+                        .value_name("BPS")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(|s| s.parse::<u16>().map(|_| ()).map_err(|e| e.to_string()))
+                        .help("Basis points of each withdrawal left in the Vault for its holders"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("pause")
+                .about("Pause deposits; withdrawals stay open; governed by the keypair")
+                .arg(pubkey_arg("vault", "VAULT", "Vault storage account").required(true))
+                .arg(
+                    Arg::with_name("resume")
+                        .long("resume")
+                        .help("Resume deposits instead"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("Display a Vault's storage")
+                .arg(pubkey_arg("vault", "VAULT", "Vault storage account").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("vaults")
+                .about("List the Active Vaults registered for an X mint")
+                .arg(pubkey_arg("x_token_mint", "MINT", "The underlying (X) mint").required(true)),
+        )
+        .subcommand(
+            SubCommand::with_name("retire")
+                .about("Deregister a Vault, e.g. before shutting it down; governed by the keypair")
+                .arg(pubkey_arg("vault", "VAULT", "Vault storage account").required(true)),
+        )
+        .get_matches();
+
+    if let Err(err) = run(&matches) {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run(matches: &ArgMatches) -> CliResult {
+    let rpc = RpcClient::new_with_commitment(
+        matches.value_of("url").unwrap().to_string(),
+        CommitmentConfig::confirmed(),
+    );
+    let client = match pubkey_of(matches, "program_id") {
+        Some(program_id) => VaultClient::with_program_id(rpc, program_id),
+        None => VaultClient::new(rpc),
+    };
+    let payer = read_keypair_file(matches.value_of("keypair").unwrap())?;
+
+    match matches.subcommand() {
+        ("create-vault", Some(args)) => create_vault(&client, &payer, args),
+        ("deposit", Some(args)) => {
+            let transaction = client.deposit(
+                &payer.pubkey(),
+                &pubkey_of(args, "vault").unwrap(),
+                &pubkey_of(args, "from").unwrap(),
+                &pubkey_of(args, "to").unwrap(),
+                &payer.pubkey(),
+                value_of(args, "amount").unwrap(),
+                value_of(args, "min_shares").unwrap(),
+                strategy_accounts(args),
+            )?;
+            send(&client, transaction, &[&payer])
+        }
+        ("withdraw", Some(args)) => {
+            let transaction = client.withdraw(
+                &payer.pubkey(),
+                &pubkey_of(args, "vault").unwrap(),
+                &pubkey_of(args, "from").unwrap(),
+                &pubkey_of(args, "to").unwrap(),
+                &payer.pubkey(),
+                value_of(args, "amount").unwrap(),
+                strategy_accounts(args),
+            )?;
+            send(&client, transaction, &[&payer])
+        }
+        ("estimate", Some(args)) => {
+            let shared_memory = Keypair::new();
+            let transaction = client.estimate_value(
+                &payer.pubkey(),
+                &pubkey_of(args, "vault").unwrap(),
+                &shared_memory.pubkey(),
+                strategy_accounts(args),
+            )?;
+            send(&client, transaction, &[&payer, &shared_memory])?;
+            println!(
+                "Estimated value: {}",
+                client.get_estimate(&shared_memory.pubkey())?
+            );
+            Ok(())
+        }
+        ("acknowledge-loss", Some(args)) => {
+            let transaction =
+                client.acknowledge_loss(&payer.pubkey(), &pubkey_of(args, "vault").unwrap())?;
+            send(&client, transaction, &[&payer])
+        }
+        ("set-fees", Some(args)) => {
+            let transaction = client.set_withdrawal_fee(
+                &payer.pubkey(),
+                &pubkey_of(args, "vault").unwrap(),
+                value_of(args, "withdrawal_fee_bps").unwrap(),
+            )?;
+            send(&client, transaction, &[&payer])
+        }
+        ("pause", Some(args)) => {
+            let transaction = client.set_paused(
+                &payer.pubkey(),
+                &pubkey_of(args, "vault").unwrap(),
+                !args.is_present("resume"),
+            )?;
+            send(&client, transaction, &[&payer])
+        }
+        ("show", Some(args)) => {
+            let vault_storage_account = pubkey_of(args, "vault").unwrap();
+            let vault = client.get_vault(&vault_storage_account)?;
+            println!("Vault: {}", vault_storage_account);
+            println!("{:#?}", vault);
+            Ok(())
+        }
+        ("vaults", Some(args)) => {
+            let x_token_mint = pubkey_of(args, "x_token_mint").unwrap();
+            let vaults = client.get_registered_vaults(&x_token_mint)?;
+            if vaults.is_empty() {
+                println!("No Vaults registered for {}", x_token_mint);
+            }
+            for vault in vaults {
+                println!(
+                    "{} strategy: {}{}",
+                    vault.vault_storage_account,
+                    vault.strategy_program_id,
+                    if vault.hodl { " (hodl)" } else { "" }
+                );
+            }
+            Ok(())
+        }
+        ("retire", Some(args)) => {
+            let transaction =
+                client.deregister_vault(&payer.pubkey(), &pubkey_of(args, "vault").unwrap())?;
+            send(&client, transaction, &[&payer])
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn create_vault(client: &VaultClient, payer: &Keypair, args: &ArgMatches) -> CliResult {
+    let strategy = if let Some(inner_vault) = pubkey_of(args, "strategy_vault") {
+        VaultStrategy::Vault(inner_vault)
+    } else if let Some(program_id) = pubkey_of(args, "strategy_program") {
+        let ids = args
+            .values_of("instruction_ids")
+            .unwrap()
+            .map(str::parse::<u8>)
+            .collect::<Result<Vec<_>, _>>()?;
+        VaultStrategy::Program {
+            program_id,
+            lx_token_mint: pubkey_of(args, "lx_token_mint").unwrap(),
+            deposit_instruction_id: ids[0],
+            withdraw_instruction_id: ids[1],
+            estimate_instruction_id: ids[2],
+        }
+    } else {
+        VaultStrategy::Hodl
+    };
+    let vault_storage_account = Keypair::new();
+    let transaction = client.create_vault(
+        &payer.pubkey(),
+        &vault_storage_account.pubkey(),
+        &pubkey_of(args, "x_token_mint").unwrap(),
+        strategy,
+        args.is_present("block_deposits_on_loss"),
+    )?;
+    send(client, transaction, &[payer, &vault_storage_account])?;
+    println!("Vault: {}", vault_storage_account.pubkey());
+    Ok(())
+}
+
+fn send(client: &VaultClient, mut transaction: Transaction, signers: &[&Keypair]) -> CliResult {
+    let recent_blockhash = transaction.message.recent_blockhash;
+    transaction.try_sign(&signers.to_vec(), recent_blockhash)?;
+    let signature = client
+        .rpc()
+        .send_and_confirm_transaction_with_spinner(&transaction)?;
+    println!("Signature: {}", signature);
+    Ok(())
+}
+
+/// Extra accounts of the innermost strategy program, passed as writable.
+fn strategy_accounts(args: &ArgMatches) -> Vec<AccountMeta> {
+    pubkeys_of(args, "strategy_accounts")
+        .unwrap_or_default()
+        .into_iter()
+        .map(|pubkey| AccountMeta::new(pubkey, false))
+        .collect()
+}
+
+fn pubkey_arg<'a, 'b>(name: &'a str, value_name: &'a str, help: &'a str) -> Arg<'a, 'b> {
+    Arg::with_name(name)
+        .value_name(value_name)
+        .takes_value(true)
+        .validator(is_pubkey)
+        .help(help)
+}
+
+fn account_arg<'a, 'b>(name: &'a str, help: &'a str) -> Arg<'a, 'b> {
+    pubkey_arg(name, "ACCOUNT", help).long(name)
+}
+
+fn amount_arg<'a, 'b>(help: &'a str) -> Arg<'a, 'b> {
+    Arg::with_name("amount")
+        .value_name("AMOUNT")
+        .takes_value(true)
+        .required(true)
+        .validator(|s| s.parse::<u64>().map(|_| ()).map_err(|e| e.to_string()))
+        .help(help)
+}
+
+fn strategy_accounts_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("strategy_accounts")
+        .long("strategy-account")
+        .value_name("ACCOUNT")
+        .takes_value(true)
+        .multiple(true)
+        .validator(is_pubkey)
+        .help("Extra accounts required by a strategy program, in order")
+}
+
+fn default_keypair_path() -> String {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    format!("{}/.config/solana/id.json", home)
+}
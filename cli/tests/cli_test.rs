@@ -0,0 +1,190 @@
+//! Runs the `vault` binary against a local test validator.
+//!
+//! Build the Vault program first, then point the validator at it:
+//!
+//! ```text
+//! $ cargo build-bpf --manifest-path ../Cargo.toml
+//! $ BPF_OUT_DIR=../target/deploy cargo test --features test-bpf
+//! ```
+#![cfg(feature = "test-bpf")]
+
+use {
+    solana_client::rpc_client::RpcClient,
+    solana_program::{
+        instruction::Instruction, program_pack::Pack, pubkey::Pubkey, system_instruction,
+    },
+    solana_sdk::{
+        commitment_config::CommitmentConfig,
+        signature::{write_keypair_file, Keypair, Signer},
+        transaction::Transaction,
+    },
+    solana_test_validator::{TestValidator, TestValidatorGenesis},
+    std::process::Command,
+    vault::instruction::VaultInstruction,
+};
+
+/// Tests create-vault → deposit → estimate → withdraw on a hodl Vault.
+#[test]
+fn test_hodl_vault_cli() {
+    let (test_validator, payer) = TestValidatorGenesis::default()
+        .add_program("vault", vault::id())
+        .start();
+    let rpc =
+        RpcClient::new_with_commitment(test_validator.rpc_url(), CommitmentConfig::confirmed());
+    let keypair_path = keypair_file(&payer);
+
+    let x_token_mint = create_mint(&rpc, &payer);
+    let x_token_account = create_token_account(&rpc, &payer, &x_token_mint);
+    send(
+        &rpc,
+        &payer,
+        &[spl_token::instruction::mint_to(
+            &spl_token::id(),
+            &x_token_mint,
+            &x_token_account,
+            &payer.pubkey(),
+            &[],
+            1000,
+        )
+        .unwrap()],
+        &[],
+    );
+
+    let output = run(
+        &test_validator,
+        &keypair_path,
+        &["create-vault", &x_token_mint.to_string()],
+    );
+    let vault_storage_account: Pubkey = output
+        .lines()
+        .find_map(|line| line.strip_prefix("Vault: "))
+        .expect("create-vault prints the Vault")
+        .parse()
+        .unwrap();
+    let (llx_token_mint, _) =
+        VaultInstruction::find_llx_token_mint(&vault::id(), &vault_storage_account);
+    let llx_token_account = create_token_account(&rpc, &payer, &llx_token_mint);
+
+    let vault = vault_storage_account.to_string();
+    let x = x_token_account.to_string();
+    let llx = llx_token_account.to_string();
+    run(
+        &test_validator,
+        &keypair_path,
+        &["deposit", &vault, "100", "--from", &x, "--to", &llx],
+    );
+    assert_eq!(token_balance(&rpc, &x_token_account), 900);
+    assert_eq!(token_balance(&rpc, &llx_token_account), 100);
+
+    let output = run(&test_validator, &keypair_path, &["estimate", &vault]);
+    assert!(output.contains("Estimated value: 100"), "{}", output);
+
+    run(
+        &test_validator,
+        &keypair_path,
+        &["withdraw", &vault, "100", "--from", &llx, "--to", &x],
+    );
+    assert_eq!(token_balance(&rpc, &x_token_account), 1000);
+    assert_eq!(token_balance(&rpc, &llx_token_account), 0);
+
+    std::fs::remove_file(keypair_path).unwrap();
+}
+
+/// Runs the CLI against the validator, returning its stdout.
+fn run(test_validator: &TestValidator, keypair_path: &str, args: &[&str]) -> String {
+    let url = test_validator.rpc_url();
+    let output = Command::new(env!("CARGO_BIN_EXE_vault"))
+        .args(&["--url", url.as_str(), "--keypair", keypair_path])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "vault {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn keypair_file(keypair: &Keypair) -> String {
+    let path = std::env::temp_dir().join(format!("vault-cli-{}.json", keypair.pubkey()));
+    write_keypair_file(keypair, &path).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+fn send(rpc: &RpcClient, payer: &Keypair, instructions: &[Instruction], signers: &[&Keypair]) {
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+    let transaction = Transaction::new_signed_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+        &all_signers,
+        rpc.get_latest_blockhash().unwrap(),
+    );
+    rpc.send_and_confirm_transaction(&transaction).unwrap();
+}
+
+fn create_mint(rpc: &RpcClient, payer: &Keypair) -> Pubkey {
+    let mint = Keypair::new();
+    let rent = rpc
+        .get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN)
+        .unwrap();
+    send(
+        rpc,
+        payer,
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &mint.pubkey(),
+                rent,
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &payer.pubkey(),
+                None,
+                6,
+            )
+            .unwrap(),
+        ],
+        &[&mint],
+    );
+    mint.pubkey()
+}
+
+fn create_token_account(rpc: &RpcClient, payer: &Keypair, mint: &Pubkey) -> Pubkey {
+    let account = Keypair::new();
+    let rent = rpc
+        .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)
+        .unwrap();
+    send(
+        rpc,
+        payer,
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &account.pubkey(),
+                rent,
+                spl_token::state::Account::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                &account.pubkey(),
+                mint,
+                &payer.pubkey(),
+            )
+            .unwrap(),
+        ],
+        &[&account],
+    );
+    account.pubkey()
+}
+
+fn token_balance(rpc: &RpcClient, account: &Pubkey) -> u64 {
+    let data = rpc.get_account_data(account).unwrap();
+    spl_token::state::Account::unpack(&data).unwrap().amount
+}
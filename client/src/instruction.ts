@@ -1,6 +1,4 @@
-const BufferLayout = require("buffer-layout");
 import { Buffer } from 'buffer';
-const VariantLayout = BufferLayout.getVariantLayout;
 import {
   Connection,
   PublicKey,
@@ -14,106 +12,141 @@ import {
 } from "@solana/web3.js";
 // import BN = require("bn.js");
 import { Token, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+// Generated from instruction.rs & state.rs: `cargo run --example schema --features schema`.
+import vault_schema from "./vault_schema.json";
 
 export const VAULT_PROGRAM_ID = new PublicKey(
   "9VxcdZKmmL6xwJWZorYnD29tZte5M29XAiKv3ZEW2AJd"
 );
 
+// The Vault's token account & llX mint are created by this instruction at addresses derived
+// from the Vault storage account (see findVaultTokenAccount & findLlxTokenMint), owned by the
+// Vault authority. The llX mint uses the same decimals as the underlying X mint.
+//
 // Accounts expected:
-// `[signer]` initializer of the lx token account
-// `[writeable]` Vault storage account (vault ID)
-// `[]` Vault's lX or X token account
-// `[]` The llX mint account
-// `[]` The strategy program
-// `[]` The rent sysvar
-// `[]` (Optional) Strategy instance data account
-const InitializeVault = 0;
-let vault_instruction_layout = {};
-vault_instruction_layout["InitializeVault"] = [
-  0,
-  BufferLayout.struct([
-    BufferLayout.u8("instruction_num"),
-    // TODO(007): Governance address, strategist address, keeper address.
-    // TODO(008): Withdrawal fee.
-    // https://github.com/yearn/yearn-vaults/blob/master/contracts/BaseStrategy.sol#L781
-    BufferLayout.u8("hodl"),
-    BufferLayout.u8("strategy_program_deposit_instruction_id"),
-    BufferLayout.u8("strategy_program_withdraw_instruction_id"),
-    BufferLayout.u8("strategy_program_estimate_instruction_id"),
-  ]),
-];
+// 1. `[signer, writeable]` Payer for the new accounts; becomes the Vault's governance.
+// 2. `[writeable]` Vault storage account (vault ID)
+// 3. `[writeable]` Vault's lX token account or X token account if hodling (uninitialized)
+// 4. `[writeable]` The llX mint account (uninitialized)
+// 5. `[]` The underlying X mint
+// 6. `[]` The mint of the Vault's token account: the strategy's lX mint, or X if hodling.
+// 7. `[]` The Vault authority (program derived address).
+// 8. `[]` SPL Token program
+// 9. `[]` The strategy program
+// 10. `[]` System program
+// 11. `[]` The rent sysvar
+//
+// TODO(007): Governance address, strategist address, keeper address.
+// Withdrawal fees are set afterwards via SetWithdrawalFee.
+// https://github.com/yearn/yearn-vaults/blob/master/contracts/BaseStrategy.sol#L781
+//
+// Instructions are Borsh-encoded from vault_schema.json, so each is named here by its
+// VaultInstruction variant rather than given a layout.
+const InitializeVault = "InitializeVault";
 
 // Deposits a given token into the vault.
 //
 // Note this API is an implementation of the StrategyInstruction#Deposit instruction.
 //
+// llX tokens are minted in proportion to the Vault's assets: the HODL account balance, or the
+// last estimated value for strategy Vaults. Fails while deposits are blocked on a loss, and
+// with ExceededSlippage if the deposit buys no llX or fewer than `min_shares`.
+//
 // Accounts expected:
 // 1. `[]` SPL Token program
-// 2. `[signer]` The source wallet containing X tokens.
-// 3. `[]` The target wallet for llX tokens.
-// 4+ `[]` Source signers
-// 5. `[]` The Vault storage account.
-// 6. `[]` The strategy program.
-// 7. `[]` (Optional) X SPL account owned by Vault if hodling.
-// 8+. `[]` Strategy extra accoounts (see StrategyInstruction#Deposit)
-// TODO(009):: Signer pubkeys for multisignature wallets - need signer_num param.
-const Deposit = 1;
-vault_instruction_layout["Deposit"] = [
-  Deposit,
-  BufferLayout.struct([
-    BufferLayout.u8("instruction_num"),
-    BufferLayout.nu64("amount"),
-  ]),
-];
+// 2. `[writeable]` The source wallet containing X tokens; must hold the Vault's X mint.
+// 3. `[writeable]` The target wallet for llX tokens, which may be owned by anyone.
+// 4. `[]` Source authority; a signer unless it's an SPL Token multisig.
+// 5. `[signer]` `signer_num` multisig signers, if any.
+// 6. `[writeable]` The Vault storage account.
+// 7. `[]` The strategy program.
+// 8. `[writeable]` The llX mint account.
+// 9. `[]` The Vault authority (program derived address).
+// 10. `[writeable]` The Vault's lX token account, or X token account if hodling.
+// 11+. `[]` Strategy extra accoounts (see StrategyInstruction#Deposit)
+const Deposit = "Deposit";
 
 // Withdraws a token from the vault.
 //
 // Note this API is an implementation of the StrategyInstruction#Withdraw instruction.
 //
+// The llX tokens are burned and the client receives the same share of the Vault's holdings, so
+// any recognized losses are shared pro-rata between all llX holders.
+//
 // Accounts expected:
 // 1. `[]` SPL Token program
-// 2. `[signer]` Source Wallet for derivative token (lX).
-// 3. `[]` Target token (X) wallet target.
-// 4+ `[]` Source signers
-// 5. `[]` The Vault storage account.
-// 6. `[]` The strategy program.
-// 7. `[]` (Optional) X SPL account owned by Vault if hodling.
-// 8+. `[]` Strategy extra accoounts (see StrategyInstruction#Withdraw)
-// TODO(009):: Signer pubkeys for multisignature wallets - need signer_num param.
-const Withdraw = 2;
-vault_instruction_layout["Withdraw"] = [
-  Withdraw,
-  BufferLayout.struct([
-    BufferLayout.u8("instruction_num"),
-    BufferLayout.nu64("amount"), // # of derivative tokens.
-  ]),
-];
+// 2. `[writeable]` Source Wallet for derivative token (llX).
+// 3. `[writeable]` Target token (X) wallet target; must hold the Vault's X mint, but may be
+//    owned by anyone.
+// 4. `[]` Source authority; a signer unless it's an SPL Token multisig.
+// 5. `[signer]` `signer_num` multisig signers, if any.
+// 6. `[writeable]` The Vault storage account.
+// 7. `[]` The strategy program.
+// 8. `[writeable]` The llX mint account.
+// 9. `[]` The Vault authority (program derived address).
+// 10. `[writeable]` The Vault's lX token account, or X token account if hodling.
+// 11+. `[]` Strategy extra accoounts (see StrategyInstruction#Withdraw)
+const Withdraw = "Withdraw";
 
 // Estimates the underlying value of the vault in its native asset.
 //
 // This instruction stores its results in a temporary account using the Shared Memory program.
 // https://spl.solana.com/shared-memory
 //
+// If the estimate is below the Vault's recorded debt, the difference is recorded as a loss.
+// This is deliberately also the Vault's harvest; there's no separate Harvest instruction.
+//
+// Accounts expected:
+// 1. `[]` Vault program
+// 2. `[writeable]` Shared memory output
+// 3. `[writeable]` The Vault storage account.
+// 4. `[]` The Vault's lX token account (X token account if hodling).
+// 5. `[]` The strategy program, unless hodling.
+// 6+ `[*]` Strategy extra accounts - any additional accounts required by strategy
+const EstimateValue = "EstimateValue";
+
+// Acknowledges any losses recorded since the last acknowledgement, unblocking deposits.
+//
+// Accounts expected:
+// 1. `[signer]` Vault governance
+// 2. `[writeable]` The Vault storage account.
+const AcknowledgeLoss = "AcknowledgeLoss";
+
+// Redeems llX tokens for the Vault's lX tokens directly, bypassing the strategy.
+//
+// The llX tokens are burned and the client receives the same pro-rata share of the Vault's lX
+// account as Withdraw would redeem, so clients can exit even if the strategy can't withdraw.
+// For HODL Vaults this is equivalent to Withdraw.
+//
 // Accounts expected:
-// 1. `[]` Shared Memory program
-// 1. `[]` Shared memory output
-// 2. `[]` The Vault storage account.
-// 3. `[]` (Optional) X SPL account owned by Vault if hodling.
-// 4+ `[*]` Strategy extra accounts - any additional accounts required by strategy
-const EstimateValue = 3;
-vault_instruction_layout["EstimateValue"] = [
-  EstimateValue,
-  BufferLayout.struct([BufferLayout.u8("instruction_num")]),
-];
-
-// A helper utility which functions similarly to the (unlaunched) Shared Memory program.
+// 1. `[]` SPL Token program
+// 2. `[writeable]` Source Wallet for derivative token (llX).
+// 3. `[writeable]` Target lX wallet; must hold the Vault's lX mint.
+// 4. `[]` Source authority; a signer unless it's an SPL Token multisig.
+// 5. `[signer]` `signer_num` multisig signers, if any.
+// 6. `[writeable]` The Vault storage account.
+// 7. `[writeable]` The llX mint account.
+// 8. `[]` The Vault authority (program derived address).
+// 9. `[writeable]` The Vault's lX token account, or X token account if hodling.
+const WithdrawInKind = "WithdrawInKind";
+
+// Swaps a token other than X into X via an SPL Token Swap pool, then deposits it.
+//
+// The swapped X lands in the client's X account and the amount received is deposited as per
+// Deposit. Fails if fewer than `minimum_x_out` X tokens are received. The source authority
+// must be a signer: the swap program doesn't support multisig authorities.
 //
-// Data is read directly from the account memory.
-const WriteData = 4;
-vault_instruction_layout["WriteData"] = [
-  WriteData,
-  BufferLayout.struct([BufferLayout.u8("instruction_num")]),
-];
+// Accounts expected:
+// 1. `[writeable]` The source wallet containing the input token.
+// 2. `[]` Token Swap program.
+// 3. `[]` Token Swap pool.
+// 4. `[]` Token Swap pool authority.
+// 5. `[writeable]` The pool's input token account.
+// 6. `[writeable]` The pool's X token account.
+// 7. `[writeable]` The pool token mint.
+// 8. `[writeable]` The pool fee account.
+// 9+. Deposit accounts (see Deposit) without multisig signers; the client X account receives X.
+const ZapDeposit = "ZapDeposit";
 
 export async function e2e(connection: Connection, payerAccount: Keypair) {
   await addLamports(connection, payerAccount);
@@ -132,11 +165,6 @@ export async function e2e(connection: Connection, payerAccount: Keypair) {
   const clientTokenAAccountKey = await tokenA.createAccount(
     payerAccount.publicKey
   );
-  // const clientTokenlAAccountKey = await tokenlA.createAccount(payerAccount.publicKey);
-  const vaultTokenAAccountKey = await tokenA.createAccount(
-    payerAccount.publicKey
-  );
-  // const vaultTokenlAAccountKey = await tokenlA.createAccount(payerAccount.publicKey);
   await addLamports(connection, payerAccount, 100000);
   await tokenA.mintTo(clientTokenAAccountKey, payerAccount, [], 1000);
   console.log(`Created accounts and sent 1000 tokens to ${clientTokenAAccountKey}.`);
@@ -144,16 +172,15 @@ export async function e2e(connection: Connection, payerAccount: Keypair) {
   expect(account_info.amount.toString()).toEqual('1000');
   console.log(`Confirmed balance of 1000 tokens.`);
 
-  const tokenlA = await Token.createMint(connection, payerAccount, payerAccount.publicKey, null, 6, TOKEN_PROGRAM_ID);
-  // const vaultTokenlAAccountKey = await tokenlA.createAccount(payerAccount.publicKey);
-  const clientTokenlAAccountKey = await tokenlA.createAccount(payerAccount.publicKey);
   // Setup the HODL vault for tokenA
   await addLamports(connection, payerAccount, 100000);
-  await createHodlVault(connection, payerAccount, vaultTokenAAccountKey,
-    false // debug_crash
-  ).then(
+  await createHodlVault(connection, payerAccount, tokenA.publicKey).then(
     async (vaultStorageAccount: Keypair) => {
       console.log("Created hodl vault");
+      // The Vault creates its HODL account & llX mint; the client only needs an llX account.
+      const vaultTokenAAccountKey = await findVaultTokenAccount(vaultStorageAccount.publicKey);
+      const tokenlA = new Token(connection, await findLlxTokenMint(vaultStorageAccount.publicKey), TOKEN_PROGRAM_ID, payerAccount);
+      const clientTokenlAAccountKey = await tokenlA.createAccount(payerAccount.publicKey);
       await deposit(
         connection,
         payerAccount,
@@ -161,9 +188,9 @@ export async function e2e(connection: Connection, payerAccount: Keypair) {
         vaultStorageAccount.publicKey,
         clientTokenAAccountKey,
         clientTokenlAAccountKey,
+        tokenlA.publicKey,
         vaultTokenAAccountKey,
-        10,
-        false // debug_crash
+        10
       ).then(async (_) => {
         console.log("Deposited into vault account", vaultTokenAAccountKey.toBase58());
         await tokenA.getAccountInfo(vaultTokenAAccountKey).then(account_info => {
@@ -178,9 +205,9 @@ export async function e2e(connection: Connection, payerAccount: Keypair) {
           vaultStorageAccount.publicKey,
           clientTokenlAAccountKey,
           clientTokenAAccountKey,
+          tokenlA.publicKey,
           vaultTokenAAccountKey,
-          10,
-          false // debug_crash
+          10
         ).then(async (_) => {
           console.log("Withdrew {} from vault {}", 10, vaultTokenAAccountKey);
           console.log(`Created accounts and sent 1000 tokens to ${clientTokenAAccountKey}.`);
@@ -197,59 +224,42 @@ export async function e2e(connection: Connection, payerAccount: Keypair) {
 export async function createHodlVault(
   connection: Connection,
   payer_account: Keypair,
-  vault_x_token_account: PublicKey,
-  debug_crash: boolean
+  x_token_mint: PublicKey
 ): Promise<Keypair> {
   let vault_storage_account = new Keypair();
-  const token_vault_derivative = await Token.createMint(
-    connection,
-    payer_account,
-    payer_account.publicKey,
-    null,
-    6,
-    TOKEN_PROGRAM_ID
-  );
-  let transaction = await createHodlVaultTransaction(connection, vault_storage_account.publicKey, payer_account.publicKey, vault_x_token_account, token_vault_derivative.publicKey, debug_crash);
+  let transaction = await createHodlVaultTransaction(connection, vault_storage_account.publicKey, payer_account.publicKey, x_token_mint);
   console.log("Sending instruction to create HODL vault");
   await lagunaSendAndConfirmTransaction(connection, transaction, [payer_account, vault_storage_account]);
-  console.log(
-    "vault_storage_account ",
-    vault_storage_account.publicKey.toBase58(),
-    connection.getAccountInfo(vault_storage_account.publicKey)
-  );
-  console.log(
-    "vault_x_token_account ",
-    vault_x_token_account.toBase58(),
-    connection.getAccountInfo(vault_x_token_account)
-  );
   console.log("Created hodl vault");
   let account_info = await connection.getAccountInfo(vault_storage_account.publicKey);
   console.log("data_account ", vault_storage_account.publicKey.toBase58(), account_info);
   return vault_storage_account;
 }
 
-export function createHodlDepositInstruciton(
+export async function createHodlDepositInstruciton(
   payer_account: PublicKey,
   strategy_program: PublicKey,
   vault_account: PublicKey,
   client_x_token_account: PublicKey,
-  client_lx_token_account: PublicKey,
+  client_llx_token_account: PublicKey,
+  llx_token_mint: PublicKey,
   vault_x_token_account: PublicKey,
-  amount: number,
-  debug_crash: boolean) : TransactionInstruction {
+  amount: number) : Promise<TransactionInstruction> {
+    let pda = await findVaultAuthority(vault_account);
     return depositInstruction(
       VAULT_PROGRAM_ID,
       TOKEN_PROGRAM_ID,
       client_x_token_account,
-      client_lx_token_account,
+      client_llx_token_account,
       [
         { isWritable: false, pubkey: payer_account, isSigner: true },
         { isWritable: true, pubkey: vault_account, isSigner: false },
         { isWritable: false, pubkey: strategy_program, isSigner: false },
+        { isWritable: true, pubkey: llx_token_mint, isSigner: false },
+        { isWritable: false, pubkey: pda, isSigner: false },
         { isWritable: true, pubkey: vault_x_token_account, isSigner: false },
       ],
-      amount,
-      debug_crash
+      amount
     );
 }
 
@@ -259,16 +269,16 @@ export async function deposit(
   strategy_program: PublicKey,
   vault_account: PublicKey,
   client_x_token_account: PublicKey,
-  client_lx_token_account: PublicKey,
+  client_llx_token_account: PublicKey,
+  llx_token_mint: PublicKey,
   vault_x_token_account: PublicKey,
-  amount: number,
-  debug_crash: boolean
+  amount: number
 ): Promise<void> {
   console.log("vault_account ", vault_account.toBase58());
   let transaction = new Transaction();
   console.log("payer_account {}", payer_account.publicKey.toBase58());
   transaction.add(
-    createHodlDepositInstruciton(payer_account.publicKey ,strategy_program ,vault_account ,client_x_token_account ,client_lx_token_account ,vault_x_token_account ,amount, debug_crash)
+    await createHodlDepositInstruciton(payer_account.publicKey ,strategy_program ,vault_account ,client_x_token_account ,client_llx_token_account ,llx_token_mint ,vault_x_token_account ,amount)
   );
   console.log("vault_x_token_account ", vault_x_token_account.toBase58());
   await lagunaSendAndConfirmTransaction(connection, transaction, [payer_account]);
@@ -280,36 +290,35 @@ export async function createHodlWithdrawInstruction(
   payer_account: Keypair,
   strategy_program: PublicKey,
   vault_account: PublicKey,
-  client_lx_token_account: PublicKey,
+  client_llx_token_account: PublicKey,
   client_x_token_account: PublicKey,
+  llx_token_mint: PublicKey,
   vault_x_token_account: PublicKey,
-  amount: number,
-  debug_crash: boolean
+  amount: number
 ): Promise<void> {
   console.log("vault_account ", vault_account.toBase58());
   let transaction = new Transaction();
   console.log("payer_account {}", payer_account.publicKey.toBase58());
   console.log("client_x_token_account {}", client_x_token_account.toBase58());
-  let _, bump_pda = await PublicKey.findProgramAddress([Buffer.from("vault", 'utf-8')], VAULT_PROGRAM_ID);
-  let pda = bump_pda[0];
+  let pda = await findVaultAuthority(vault_account);
   console.log("pda", pda);
 
-  console.log(`client_lx_token_account ${client_lx_token_account}`);
+  console.log(`client_llx_token_account ${client_llx_token_account}`);
   transaction.add(
     withdrawInstruction(
       VAULT_PROGRAM_ID,
       TOKEN_PROGRAM_ID,
-      // Note the source is now the vault - not the client.
-      client_lx_token_account,
+      client_llx_token_account,
       client_x_token_account,
       [
-        { isWritable: false, pubkey: pda, isSigner: false },
-        { isWritable: false, pubkey: vault_account, isSigner: false },
+        { isWritable: false, pubkey: payer_account.publicKey, isSigner: true },
+        { isWritable: true, pubkey: vault_account, isSigner: false },
         { isWritable: false, pubkey: strategy_program, isSigner: false },
+        { isWritable: true, pubkey: llx_token_mint, isSigner: false },
+        { isWritable: false, pubkey: pda, isSigner: false },
         { isWritable: true, pubkey: vault_x_token_account, isSigner: false },
       ],
-      amount,
-      debug_crash
+      amount
     )
   );
   await lagunaSendAndConfirmTransaction(connection, transaction, [payer_account]);
@@ -320,36 +329,35 @@ export async function withdraw(
   payer_account: Keypair,
   strategy_program: PublicKey,
   vault_account: PublicKey,
-  client_lx_token_account: PublicKey,
+  client_llx_token_account: PublicKey,
   client_x_token_account: PublicKey,
+  llx_token_mint: PublicKey,
   vault_x_token_account: PublicKey,
-  amount: number,
-  debug_crash: boolean
+  amount: number
 ): Promise<void> {
   console.log("vault_account ", vault_account.toBase58());
   let transaction = new Transaction();
   console.log("payer_account {}", payer_account.publicKey.toBase58());
   console.log("client_x_token_account {}", client_x_token_account.toBase58());
-  let _, bump_pda = await PublicKey.findProgramAddress([Buffer.from("vault", 'utf-8')], VAULT_PROGRAM_ID);
-  let pda = bump_pda[0];
+  let pda = await findVaultAuthority(vault_account);
   console.log("pda", pda);
 
-  console.log(`client_lx_token_account ${client_lx_token_account}`);
+  console.log(`client_llx_token_account ${client_llx_token_account}`);
   transaction.add(
     withdrawInstruction(
       VAULT_PROGRAM_ID,
       TOKEN_PROGRAM_ID,
-      // Note the source is now the vault - not the client.
-      client_lx_token_account,
+      client_llx_token_account,
       client_x_token_account,
       [
-        { isWritable: false, pubkey: pda, isSigner: false },
-        { isWritable: false, pubkey: vault_account, isSigner: false },
+        { isWritable: false, pubkey: payer_account.publicKey, isSigner: true },
+        { isWritable: true, pubkey: vault_account, isSigner: false },
         { isWritable: false, pubkey: strategy_program, isSigner: false },
+        { isWritable: true, pubkey: llx_token_mint, isSigner: false },
+        { isWritable: false, pubkey: pda, isSigner: false },
         { isWritable: true, pubkey: vault_x_token_account, isSigner: false },
       ],
-      amount,
-      debug_crash
+      amount
     )
   );
   await lagunaSendAndConfirmTransaction(connection, transaction, [payer_account]);
@@ -360,84 +368,102 @@ export async function createHodlVaultTransaction(
   connection: Connection,
   vault_storage_account: PublicKey,
   payer_account_public_key: PublicKey,
-  vault_x_token_account: PublicKey,
-  token_vault_derivative: PublicKey,
-  debug_crash: boolean
+  x_token_mint: PublicKey
 ) {
   console.log("Creating HODL vault");
 
-
-  // const vault_vault_token_account = await token_vault_derivative.createAccount(payer_account_public_key);
   let transaction = new Transaction();
   let instruction = await createAccountInstruction
     (
       connection,
       vault_storage_account,
       payer_account_public_key,
-      1 + 1 + 32 + 32 + 8 + 32 + 1 + 1 + 1 + 36,
+      VAULT_STORAGE_SIZE,
       VAULT_PROGRAM_ID
     );
   transaction.add(instruction);
   console.log("vault_storage_account ", vault_storage_account);
-  // let vault_token_account = createAccount(connection, payer_account_public_key, numBytes, PROGRAM_ID);
-  // let llx_token_mint_id = createAccount(connection, payer_account_public_key, numBytes, PROGRAM_ID);
-  instruction = initializeVaultInstruction(
-    payer_account_public_key, // TODO: Separate owner
+  instruction = await initializeVaultInstruction(
+    payer_account_public_key,
     vault_storage_account,
-    vault_x_token_account,
-    token_vault_derivative,
+    x_token_mint,
+    x_token_mint, // The HODL account holds X directly.
     TOKEN_PROGRAM_ID,
     VAULT_PROGRAM_ID,
     1,
     2,
     3,
     true,
-    debug_crash // debug_crash
+    false // block_deposits_on_loss
   );
   transaction.add(instruction);
 
   return transaction;
 }
 
+// Derives the address of the Vault's authority, which owns its token accounts & llX mint.
+export async function findVaultAuthority(vault_storage_account: PublicKey): Promise<PublicKey> {
+  let [address, _bump_seed] = await PublicKey.findProgramAddress(
+    [Buffer.from("vault", 'utf-8'), vault_storage_account.toBuffer()],
+    VAULT_PROGRAM_ID
+  );
+  return address;
+}
+
+// Derives the address of the Vault's lX token account (X token account if hodling).
+export async function findVaultTokenAccount(vault_storage_account: PublicKey): Promise<PublicKey> {
+  let [address, _bump_seed] = await PublicKey.findProgramAddress(
+    [vault_storage_account.toBuffer(), Buffer.from("token", 'utf-8')],
+    VAULT_PROGRAM_ID
+  );
+  return address;
+}
+
+// Derives the address of the Vault's llX mint.
+export async function findLlxTokenMint(vault_storage_account: PublicKey): Promise<PublicKey> {
+  let [address, _bump_seed] = await PublicKey.findProgramAddress(
+    [vault_storage_account.toBuffer(), Buffer.from("llx_mint", 'utf-8')],
+    VAULT_PROGRAM_ID
+  );
+  return address;
+}
+
 // Note: These instructions mirror instruction.rs.
-function initializeVaultInstruction(
-  vault_token_account_owner: PublicKey,
+async function initializeVaultInstruction(
+  payer: PublicKey,
   vault_storage_account: PublicKey,
-  vault_token_account: PublicKey,
-  llx_token_mint_id: PublicKey,
+  x_token_mint: PublicKey,
+  vault_token_mint: PublicKey,
   token_program: PublicKey,
   strategy_program: PublicKey,
   strategy_program_deposit_instruction_id: number,
   strategy_program_withdraw_instruction_id: number,
   strategy_program_estimate_value_instruction_id: number,
   hodl: boolean,
-  debug_crash: boolean
+  block_deposits_on_loss: boolean
 ) {
-  console.log(
-    "vault_token_account_owner ",
-    vault_token_account_owner.toBase58()
-  );
-  if (debug_crash) {
-    console.log("Crashing initialize vault!")
-  } else {
-    console.log("Not crashing init");
-  }
+  let pda = await findVaultAuthority(vault_storage_account);
+  let vault_token_account = await findVaultTokenAccount(vault_storage_account);
+  let llx_token_mint = await findLlxTokenMint(vault_storage_account);
   console.log("vault_storage_account ", vault_storage_account.toBase58());
   console.log("vault_token_account ", vault_token_account.toBase58());
-  console.log("llx_token_mint_id ", llx_token_mint_id.toBase58());
+  console.log("llx_token_mint ", llx_token_mint.toBase58());
   console.log("token_program ", token_program.toBase58());
   console.log("strategy_program ", strategy_program.toBase58());
   const accounts = [
-    { pubkey: vault_token_account_owner, isSigner: true, isWritable: false },
+    { pubkey: payer, isSigner: true, isWritable: true },
     { pubkey: vault_storage_account, isSigner: false, isWritable: true },
     { pubkey: vault_token_account, isSigner: false, isWritable: true },
-    { pubkey: llx_token_mint_id, isSigner: false, isWritable: true },
+    { pubkey: llx_token_mint, isSigner: false, isWritable: true },
+    { pubkey: x_token_mint, isSigner: false, isWritable: false },
+    { pubkey: vault_token_mint, isSigner: false, isWritable: false },
+    { pubkey: pda, isSigner: false, isWritable: false },
     { pubkey: token_program, isSigner: false, isWritable: false },
     { pubkey: strategy_program, isSigner: false, isWritable: false },
+    { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
     { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
   ];
   let data = {
-    instruction_num: InitializeVault + (debug_crash ? 64 : 0),
     hodl: hodl,
     strategy_program_deposit_instruction_id:
       strategy_program_deposit_instruction_id,
@@ -445,19 +471,12 @@ function initializeVaultInstruction(
       strategy_program_withdraw_instruction_id,
     strategy_program_estimate_instruction_id:
       strategy_program_estimate_value_instruction_id,
-
+    block_deposits_on_loss: block_deposits_on_loss,
   };
   console.log("instruction data ", data);
-  console.log(
-    "instruction layout size {}",
-    vault_instruction_layout["InitializeVault"][1].getSpan(data)
-  );
   return new TransactionInstruction({
     keys: accounts,
-    data: encodeInstructionData(
-      data,
-      vault_instruction_layout["InitializeVault"][1]
-    ),
+    data: encodeInstructionData(InitializeVault, data),
     programId: VAULT_PROGRAM_ID,
   });
 }
@@ -473,27 +492,19 @@ function depositInstruction(
     pubkey: PublicKey;
     isSigner: boolean;
   }[],
-  amount: number,
-  debug_crash: boolean
+  amount: number
 ) {
   console.log("vault_program_id {}", vault_program_id.toBase58());
   console.log("token_program_id {}", token_program_id.toBase58());
   console.log("client_x_token {}", client_x_token.toBase58());
   console.log("vault_x_token_pubkey {}", vault_x_token_pubkey.toBase58());
-  if (debug_crash) {
-    console.log("Crashing deposit!")
-  } else {
-    console.log("Not crashing");
-  }
   let data = {
-    instruction_num: Deposit + (debug_crash ? 64 : 0),
     amount,
+    signer_num: 0,
+    min_shares: 0,
   };
 
-  let instructionData = encodeInstructionData(
-    data,
-    vault_instruction_layout["Deposit"][1]
-  );
+  let instructionData = encodeInstructionData(Deposit, data);
   return createTransferInstruction(
     instructionData,
     vault_program_id,
@@ -514,21 +525,17 @@ function withdrawInstruction(
     pubkey: PublicKey;
     isSigner: boolean;
   }[],
-  amount: number,
-  debug_crash: boolean
+  amount: number
 ) {
   console.log("vault_program_id {}", vault_program_id.toBase58());
   console.log("token_program_id {}", token_program_id.toBase58());
   console.log("source_pubkey {}", source_pubkey.toBase58());
   console.log("target_pubkey {}", target_pubkey.toBase58());
   let data = {
-    instruction_num: Withdraw + (debug_crash ? 64 : 0),
     amount,
+    signer_num: 0,
   };
-  let instructionData = encodeInstructionData(
-    data,
-    vault_instruction_layout["Withdraw"][1]
-  );
+  let instructionData = encodeInstructionData(Withdraw, data);
   return createTransferInstruction(
     instructionData,
     vault_program_id,
@@ -538,6 +545,52 @@ function withdrawInstruction(
     additional_account_metas
   );
 }
+export function acknowledgeLossInstruction(
+  governance: PublicKey,
+  vault_storage_account: PublicKey
+) {
+  let data = {
+  };
+  return new TransactionInstruction({
+    keys: [
+      { pubkey: governance, isSigner: true, isWritable: false },
+      { pubkey: vault_storage_account, isSigner: false, isWritable: true },
+    ],
+    data: encodeInstructionData(AcknowledgeLoss, data),
+    programId: VAULT_PROGRAM_ID,
+  });
+}
+
+export async function withdrawInKindInstruction(
+  client_llx_token_account: PublicKey,
+  client_lx_token_account: PublicKey,
+  source_authority: PublicKey,
+  vault_storage_account: PublicKey,
+  llx_token_mint: PublicKey,
+  vault_token_account: PublicKey,
+  amount: number
+) {
+  let pda = await findVaultAuthority(vault_storage_account);
+  let data = {
+    amount: amount,
+    signer_num: 0,
+  };
+  return new TransactionInstruction({
+    keys: [
+      { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      { pubkey: client_llx_token_account, isSigner: false, isWritable: true },
+      { pubkey: client_lx_token_account, isSigner: false, isWritable: true },
+      { pubkey: source_authority, isSigner: true, isWritable: false },
+      { pubkey: vault_storage_account, isSigner: false, isWritable: true },
+      { pubkey: llx_token_mint, isSigner: false, isWritable: true },
+      { pubkey: pda, isSigner: false, isWritable: false },
+      { pubkey: vault_token_account, isSigner: false, isWritable: true },
+    ],
+    data: encodeInstructionData(WithdrawInKind, data),
+    programId: VAULT_PROGRAM_ID,
+  });
+}
+
 export function createTransferInstruction(
   data: Buffer,
   program_id: PublicKey,
@@ -582,27 +635,101 @@ export async function lagunaSendAndConfirmTransaction(connection: Connection, tr
     });
 }
 
-function encodeInstructionData(
-  instruction: Object,
-  layout: typeof BufferLayout
-) {
-  console.log("Ensuring all data is provided in layout..");
-  console.log("Layout {}", layout);
-  for (let i = 0; i < layout.fields.length; i++) {
-    let field = layout.fields[i];
-    // console.log(`field[${i}] ${field} ${typeof (field)} ${field.property}`);
-    if (!instruction.hasOwnProperty(field.property)) {
-      console.log(`Missing layout field ${field.property}`);
-      throw field;
+// Borsh-encodes the VaultInstruction `variant` with the given fields.
+function encodeInstructionData(variant: string, fields: Object): Buffer {
+  const schema = vault_schema.instruction;
+  return borshEncode(schema.definitions, schema.declaration, { [variant]: fields });
+}
+
+// Encodes `value` as the Borsh type `declaration`, as laid out in a vault_schema.json entry's
+// `definitions`. Structs are given as objects keyed by field name (arrays if unnamed), enums as
+// `{ Variant: fields }`, e.g. `{ Some: key }`, & Pubkeys as PublicKeys.
+function borshEncode(definitions: any, declaration: string, value: any): Buffer {
+  if (value instanceof PublicKey) {
+    return value.toBuffer();
+  }
+  const definition = definitions[declaration];
+  const encodeFields = (fields: any[]) => Buffer.concat(fields.map((field, i) => {
+    const field_value = field.name === undefined ? value[i] : value[field.name];
+    if (field_value === undefined) {
+      throw new Error(`Missing ${declaration} field ${field.name ?? i}`);
+    }
+    return borshEncode(definitions, field.declaration, field_value);
+  }));
+  switch (definition.kind) {
+    case "primitive": {
+      const b = Buffer.alloc(definition.size);
+      if (definition.size == 8) {
+        // Numbers are only exact up to 2^53, which fits in the two 32 bit halves.
+        b.writeUInt32LE(value % 0x100000000, 0);
+        b.writeUInt32LE(Math.floor(value / 0x100000000), 4);
+      } else if (definition.size > 0) {
+        b.writeUIntLE(Number(value), 0, definition.size);
+      }
+      return b;
+    }
+    case "struct":
+      return encodeFields(definition.fields);
+    case "tuple":
+      return encodeFields(definition.elements.map((element: string) => ({ declaration: element })));
+    case "enum": {
+      const name = Object.keys(value)[0];
+      const variant = definition.variants.find((v: any) => v.name == name);
+      if (variant === undefined) {
+        throw new Error(`Unknown ${declaration} variant ${name}`);
+      }
+      const tag = Buffer.alloc(definition.tag_width);
+      tag.writeUIntLE(variant.discriminant, 0, definition.tag_width);
+      return Buffer.concat([tag, borshEncode(definitions, variant.declaration, value[name])]);
+    }
+    case "sequence": {
+      const [min_length, max_length] = definition.length_range;
+      if (value.length < min_length || value.length > max_length) {
+        throw new Error(`${declaration} must have ${min_length}-${max_length} elements`);
+      }
+      const length = Buffer.alloc(definition.length_width);
+      if (definition.length_width > 0) {
+        length.writeUIntLE(value.length, 0, definition.length_width);
+      }
+      let elements: Buffer[] = [length];
+      for (let i = 0; i < value.length; i++) {
+        elements.push(borshEncode(definitions, definition.elements, value[i]));
+      }
+      return Buffer.concat(elements);
     }
   }
+  throw new Error(`Unsupported Borsh type ${declaration}`);
+}
 
-  // const instructionMaxSpan = Math.max(...Object.values(layout.registry).map((r: typeof BufferLayout) => r.span));
-  const b = Buffer.alloc(layout.getSpan(instruction));
-  const span = layout.encode(instruction, b);
-  return b.slice(0, span);
+// The size of the largest value of the fixed-size Borsh type `declaration`.
+function borshMaxSize(definitions: any, declaration: string): number {
+  const definition = definitions[declaration];
+  const sum = (declarations: string[]) =>
+    declarations.reduce((size, d) => size + borshMaxSize(definitions, d), 0);
+  switch (definition.kind) {
+    case "primitive":
+      return definition.size;
+    case "struct":
+      return sum(definition.fields.map((field: any) => field.declaration));
+    case "tuple":
+      return sum(definition.elements);
+    case "enum":
+      return definition.tag_width + Math.max(...definition.variants.map(
+        (variant: any) => borshMaxSize(definitions, variant.declaration)));
+    case "sequence":
+      if (definition.length_width == 0) {
+        return definition.length_range[1] * borshMaxSize(definitions, definition.elements);
+      }
+  }
+  throw new Error(`${declaration} isn't fixed-size`);
 }
 
+// The size of a Vault storage account.
+export const VAULT_STORAGE_SIZE = borshMaxSize(
+  vault_schema.state.definitions,
+  vault_schema.state.declaration
+);
+
 export async function createAccountInstruction(
   connection: Connection,
   dataAccount: PublicKey,
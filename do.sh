@@ -89,6 +89,7 @@ perform_action() {
             yarn --cwd lib/client new-swap
         ;;
     test)
+            cargo build-bpf --manifest-path mock_strategy/Cargo.toml --bpf-out-dir target/deploy
             cargo test-bpf ${@:2}
         ;;
     update)
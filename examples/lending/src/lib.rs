@@ -0,0 +1,194 @@
+//! Reference strategy which lends X tokens to a lending pool.
+//!
+//! Deposits are forwarded to the pool in exchange for the pool's lX tokens, which the calling Vault
+//! holds. Value is estimated from the pool's reserves & lX supply, so any interest paid into the
+//! pool shows up in the Vault's estimate.
+use solana_program::{
+  account_info::{next_account_info, AccountInfo},
+  entrypoint::ProgramResult,
+  instruction::AccountMeta,
+  msg,
+  program::invoke,
+  program_error::ProgramError,
+  program_pack::Pack,
+  pubkey::Pubkey,
+};
+
+use lending_pool::{instruction::LendingPoolInstruction, state::LendingPool};
+use strategy_api::{
+  error::StrategyError,
+  strategy::{EstimateValueAccounts, Strategy, TransferAccounts},
+};
+
+/// Accounts appended to StrategyInstruction#Deposit and StrategyInstruction#Withdraw.
+///
+/// 1. `[]` Lending pool program
+/// 2. `[]` Pool storage account
+/// 3. `[writeable]` Pool reserve token account
+/// 4. `[writeable]` Pool lX mint
+/// 5. `[]` Pool authority
+pub fn transfer_account_metas(
+  pool_storage_account: &Pubkey,
+  reserve_token_account: &Pubkey,
+  lx_token_mint: &Pubkey,
+) -> Vec<AccountMeta> {
+  let (authority, _) = LendingPoolInstruction::find_authority(&lending_pool::id(), pool_storage_account);
+  vec![
+    AccountMeta::new_readonly(lending_pool::id(), false),
+    AccountMeta::new_readonly(*pool_storage_account, false),
+    AccountMeta::new(*reserve_token_account, false),
+    AccountMeta::new(*lx_token_mint, false),
+    AccountMeta::new_readonly(authority, false),
+  ]
+}
+
+/// Accounts appended to StrategyInstruction#EstimateValue, after the Vault's lX token account.
+///
+/// 1. `[]` Pool storage account
+/// 2. `[]` Pool reserve token account
+/// 3. `[]` Pool lX mint
+pub fn estimate_value_account_metas(
+  pool_storage_account: &Pubkey,
+  reserve_token_account: &Pubkey,
+  lx_token_mint: &Pubkey,
+) -> Vec<AccountMeta> {
+  vec![
+    AccountMeta::new_readonly(*pool_storage_account, false),
+    AccountMeta::new_readonly(*reserve_token_account, false),
+    AccountMeta::new_readonly(*lx_token_mint, false),
+  ]
+}
+
+/// Unpacks the pool storage account & checks the reserve and mint belong to it.
+fn unpack_pool(
+  pool_storage_account: &AccountInfo,
+  reserve_token_account: &AccountInfo,
+  lx_token_mint: &AccountInfo,
+) -> Result<LendingPool, ProgramError> {
+  if *pool_storage_account.owner != lending_pool::id() {
+    msg!("Pool {} isn't owned by the lending pool program", pool_storage_account.key);
+    return Err(ProgramError::IncorrectProgramId);
+  }
+  let pool = LendingPool::unpack(&pool_storage_account.data.borrow())?;
+  if pool.reserve_token_account != *reserve_token_account.key
+    || pool.lx_token_mint != *lx_token_mint.key
+  {
+    msg!("Reserve or lX mint doesn't match pool {}", pool_storage_account.key);
+    return Err(StrategyError::InvalidInstruction.into());
+  }
+  Ok(pool)
+}
+
+pub struct LendingStrategy;
+
+impl LendingStrategy {
+  fn transfer(accounts: &TransferAccounts, amount: u64, is_deposit: bool) -> ProgramResult {
+    let account_info_iter = &mut accounts.extra_accounts.iter();
+    let pool_program = next_account_info(account_info_iter)?;
+    let pool_storage_account = next_account_info(account_info_iter)?;
+    let reserve_token_account = next_account_info(account_info_iter)?;
+    let lx_token_mint = next_account_info(account_info_iter)?;
+    let pool_authority = next_account_info(account_info_iter)?;
+    if *pool_program.key != lending_pool::id() {
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    if !accounts.signer_accounts.is_empty() {
+      msg!("The lending pool doesn't support multisig source authorities");
+      return Err(StrategyError::InvalidInstruction.into());
+    }
+    unpack_pool(pool_storage_account, reserve_token_account, lx_token_mint)?;
+
+    let create_instruction = if is_deposit {
+      LendingPoolInstruction::deposit
+    } else {
+      LendingPoolInstruction::withdraw
+    };
+    let instruction = create_instruction(
+      pool_program.key,
+      accounts.token_program.key,
+      accounts.source_token_account.key,
+      accounts.target_token_account.key,
+      accounts.source_authority.key,
+      pool_storage_account.key,
+      reserve_token_account.key,
+      lx_token_mint.key,
+      amount,
+    )?;
+    invoke(
+      &instruction,
+      &[
+        accounts.token_program.clone(),
+        accounts.source_token_account.clone(),
+        accounts.target_token_account.clone(),
+        accounts.source_authority.clone(),
+        pool_storage_account.clone(),
+        reserve_token_account.clone(),
+        lx_token_mint.clone(),
+        pool_authority.clone(),
+        pool_program.clone(),
+      ],
+    )
+  }
+}
+
+impl Strategy for LendingStrategy {
+  fn deposit(_program_id: &Pubkey, accounts: &TransferAccounts, amount: u64) -> ProgramResult {
+    Self::transfer(accounts, amount, true)
+  }
+
+  fn withdraw(_program_id: &Pubkey, accounts: &TransferAccounts, amount: u64) -> ProgramResult {
+    Self::transfer(accounts, amount, false)
+  }
+
+  fn estimate_value(
+    _program_id: &Pubkey,
+    accounts: &EstimateValueAccounts,
+  ) -> Result<u64, ProgramError> {
+    let account_info_iter = &mut accounts.extra_accounts.iter();
+    let pool_storage_account = next_account_info(account_info_iter)?;
+    let reserve_token_account = next_account_info(account_info_iter)?;
+    let lx_token_mint = next_account_info(account_info_iter)?;
+    let pool = unpack_pool(pool_storage_account, reserve_token_account, lx_token_mint)?;
+
+    let lx_token_account = accounts.position_token_account;
+    let position = spl_token::state::Account::unpack(&lx_token_account.data.borrow())?;
+    if position.mint != pool.lx_token_mint {
+      msg!("{} doesn't hold lX tokens", lx_token_account.key);
+      return Err(StrategyError::InvalidInstruction.into());
+    }
+    let reserve = spl_token::state::Account::unpack(&reserve_token_account.data.borrow())?;
+    let lx_mint = spl_token::state::Mint::unpack(&lx_token_mint.data.borrow())?;
+    let value = LendingPool::lx_to_x(position.amount, reserve.amount, lx_mint.supply)
+      .ok_or(ProgramError::InvalidAccountData)?;
+    msg!(
+      "{} lX valued at {} X (reserve {}, supply {})",
+      position.amount,
+      value,
+      reserve.amount,
+      lx_mint.supply
+    );
+    Ok(value)
+  }
+}
+
+#[cfg(not(feature = "no-entrypoint"))]
+pub mod entrypoint {
+  use solana_program::{
+    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, pubkey::Pubkey,
+  };
+
+  entrypoint!(process_instruction);
+  pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+  ) -> ProgramResult {
+    strategy_api::strategy::process_instruction::<super::LendingStrategy>(
+      program_id,
+      accounts,
+      instruction_data,
+    )
+  }
+}
+
+solana_program::declare_id!("9zcN2sFmRfUC3x3f5b5NAScGE8MVjcjzmp3Xt9oHzPbH");
@@ -0,0 +1,543 @@
+#![cfg(feature = "test-bpf")]
+
+use {
+  ::vault::instruction::VaultInstruction,
+  assert_matches::*,
+  lending::{estimate_value_account_metas, transfer_account_metas},
+  lending_pool::{instruction::LendingPoolInstruction, state::LendingPool},
+  solana_program::{
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction, system_program,
+  },
+  solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext},
+  solana_sdk::signature::Keypair,
+  solana_sdk::{
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+  },
+  spl_token::processor::Processor,
+  strategy_api::strategy_instruction::{DEPOSIT, ESTIMATE_VALUE, WITHDRAW},
+};
+
+use std::convert::TryInto;
+
+/// Tests the full Vault -> lending strategy -> lending pool path.
+#[tokio::test]
+async fn test_lending_vault() {
+  let mut program_test = ProgramTest::new(
+    "token_test",
+    spl_token::id(),
+    processor!(Processor::process),
+  );
+  program_test.add_program(
+    "vault_test",
+    ::vault::id(),
+    processor!(::vault::processor::Processor::process),
+  );
+  program_test.add_program(
+    "lending_test",
+    ::lending::id(),
+    processor!(::lending::entrypoint::process_instruction),
+  );
+  program_test.add_program(
+    "lending_pool_test",
+    ::lending_pool::id(),
+    processor!(::lending_pool::processor::Processor::process),
+  );
+  let mut program_test_context = program_test.start_with_context().await;
+
+  // Row 0: X mint, client X account, pool reserve.
+  // Row 1: lX (pool token) mint.
+  let mint_client_vault_accounts =
+    create_tokens_and_accounts(&mut program_test_context, 2, 2).await;
+  let x_mint = &mint_client_vault_accounts[0][0];
+  let client_x_token_account = &mint_client_vault_accounts[0][1];
+  let reserve_token_account = &mint_client_vault_accounts[0][2];
+  let lx_token_mint = &mint_client_vault_accounts[1][0];
+
+  // Create the lending pool & a Vault which lends to it.
+  let pool_storage_account = Keypair::new();
+  let vault_storage_account = Keypair::new();
+  let (vault_lx_token_account, _) =
+    VaultInstruction::find_vault_token_account(&::vault::id(), &vault_storage_account.pubkey());
+  let (llx_token_mint, _) =
+    VaultInstruction::find_llx_token_mint(&::vault::id(), &vault_storage_account.pubkey());
+  let client_llx_token_account = Keypair::new();
+  let mut transaction = Transaction::new_with_payer(
+    &[
+      system_instruction::create_account(
+        &program_test_context.payer.pubkey(),
+        &pool_storage_account.pubkey(),
+        Rent::default().minimum_balance(LendingPool::LEN),
+        LendingPool::LEN as u64,
+        &::lending_pool::id(),
+      ),
+      LendingPoolInstruction::initialize_pool(
+        &::lending_pool::id(),
+        &program_test_context.payer.pubkey(),
+        &pool_storage_account.pubkey(),
+        &reserve_token_account.pubkey(),
+        &lx_token_mint.pubkey(),
+        &spl_token::id(),
+      )
+      .unwrap(),
+      system_instruction::create_account(
+        &program_test_context.payer.pubkey(),
+        &vault_storage_account.pubkey(),
+        Rent::default().minimum_balance(::vault::state::Vault::LEN),
+        ::vault::state::Vault::LEN as u64,
+        &::vault::id(),
+      ),
+      VaultInstruction::initialize_vault(
+        &::vault::id(),
+        &program_test_context.payer.pubkey(),
+        &vault_storage_account.pubkey(),
+        &x_mint.pubkey(),
+        &lx_token_mint.pubkey(),
+        &spl_token::id(),
+        &::lending::id(),
+        false, // hodl
+        DEPOSIT,
+        WITHDRAW,
+        ESTIMATE_VALUE,
+        false, // block deposits on loss
+      )
+      .unwrap(),
+      // The client's llX account can only be created once the Vault has created the llX mint.
+      system_instruction::create_account(
+        &program_test_context.payer.pubkey(),
+        &client_llx_token_account.pubkey(),
+        Rent::default().minimum_balance(spl_token::state::Account::LEN),
+        spl_token::state::Account::LEN as u64,
+        &spl_token::id(),
+      ),
+      spl_token::instruction::initialize_account(
+        &spl_token::id(),
+        &client_llx_token_account.pubkey(),
+        &llx_token_mint,
+        &program_test_context.payer.pubkey(),
+      )
+      .unwrap(),
+    ],
+    Some(&program_test_context.payer.pubkey()),
+  );
+  transaction.sign(
+    &[
+      &program_test_context.payer,
+      &pool_storage_account,
+      &vault_storage_account,
+      &client_llx_token_account,
+    ],
+    program_test_context.last_blockhash,
+  );
+  assert_matches!(
+    program_test_context
+      .banks_client
+      .process_transaction(transaction)
+      .await,
+    Ok(())
+  );
+
+  // Deposit X tokens via the Vault; the strategy lends them to the pool.
+  let (pda, _bump_seed) =
+    VaultInstruction::find_vault_authority(&::vault::id(), &vault_storage_account.pubkey());
+  let mut vault_account_metas = vec![
+    AccountMeta::new_readonly(program_test_context.payer.pubkey(), true), // source authority
+    AccountMeta::new(vault_storage_account.pubkey(), false),
+    AccountMeta::new_readonly(::lending::id(), false),
+    AccountMeta::new(llx_token_mint, false),
+    AccountMeta::new_readonly(pda, false),
+    AccountMeta::new(vault_lx_token_account, false),
+  ];
+  vault_account_metas.extend(transfer_account_metas(
+    &pool_storage_account.pubkey(),
+    &reserve_token_account.pubkey(),
+    &lx_token_mint.pubkey(),
+  ));
+  let mut transaction = Transaction::new_with_payer(
+    &[
+      spl_token::instruction::mint_to(
+        &spl_token::id(),
+        &x_mint.pubkey(),
+        &client_x_token_account.pubkey(),
+        &program_test_context.payer.pubkey(),
+        &[&program_test_context.payer.pubkey()],
+        1000,
+      )
+      .unwrap(),
+      VaultInstruction::deposit(
+        &::vault::id(),
+        &spl_token::id(),
+        &client_x_token_account.pubkey(),
+        &client_llx_token_account.pubkey(),
+        vault_account_metas.clone(),
+        100,
+        0, // signer_num
+        0, // min_shares
+      )
+      .unwrap(),
+    ],
+    Some(&program_test_context.payer.pubkey()),
+  );
+  transaction.sign(
+    &[&program_test_context.payer],
+    program_test_context.last_blockhash,
+  );
+  assert_matches!(
+    program_test_context
+      .banks_client
+      .process_transaction(transaction)
+      .await,
+    Ok(())
+  );
+  check_token_account(&mut program_test_context, &client_x_token_account.pubkey(), &COption::None, 900).await;
+  check_token_account(&mut program_test_context, &reserve_token_account.pubkey(), &COption::None, 100).await;
+  check_token_account(&mut program_test_context, &vault_lx_token_account, &COption::Some(pda), 100).await;
+  check_token_account(&mut program_test_context, &client_llx_token_account.pubkey(), &COption::None, 100).await;
+
+  let mut estimate_account_metas = vec![
+    AccountMeta::new(vault_storage_account.pubkey(), false),
+    AccountMeta::new_readonly(vault_lx_token_account, false),
+    AccountMeta::new_readonly(::lending::id(), false),
+  ];
+  estimate_account_metas.extend(estimate_value_account_metas(
+    &pool_storage_account.pubkey(),
+    &reserve_token_account.pubkey(),
+    &lx_token_mint.pubkey(),
+  ));
+  check_vault_value(&mut program_test_context, estimate_account_metas.clone(), 100).await;
+
+  // Simulate interest accruing to the pool's reserve.
+  let mut transaction = Transaction::new_with_payer(
+    &[spl_token::instruction::mint_to(
+      &spl_token::id(),
+      &x_mint.pubkey(),
+      &reserve_token_account.pubkey(),
+      &program_test_context.payer.pubkey(),
+      &[&program_test_context.payer.pubkey()],
+      50,
+    )
+    .unwrap()],
+    Some(&program_test_context.payer.pubkey()),
+  );
+  transaction.sign(
+    &[&program_test_context.payer],
+    program_test_context.last_blockhash,
+  );
+  assert_matches!(
+    program_test_context
+      .banks_client
+      .process_transaction(transaction)
+      .await,
+    Ok(())
+  );
+  check_vault_value(&mut program_test_context, estimate_account_metas, 150).await;
+
+  // Redeem all llX; the pool pays out principal plus interest.
+  let mut transaction = Transaction::new_with_payer(
+    &[VaultInstruction::withdraw(
+      &::vault::id(),
+      &spl_token::id(),
+      &client_llx_token_account.pubkey(),
+      &client_x_token_account.pubkey(),
+      vault_account_metas,
+      100,
+      0, // signer_num
+    )
+    .unwrap()],
+    Some(&program_test_context.payer.pubkey()),
+  );
+  transaction.sign(
+    &[&program_test_context.payer],
+    program_test_context.last_blockhash,
+  );
+  assert_matches!(
+    program_test_context
+      .banks_client
+      .process_transaction(transaction)
+      .await,
+    Ok(())
+  );
+  check_token_account(&mut program_test_context, &client_x_token_account.pubkey(), &COption::None, 1050).await;
+  check_token_account(&mut program_test_context, &reserve_token_account.pubkey(), &COption::None, 0).await;
+  check_token_account(&mut program_test_context, &vault_lx_token_account, &COption::Some(pda), 0).await;
+  check_token_account(&mut program_test_context, &client_llx_token_account.pubkey(), &COption::None, 0).await;
+}
+
+/// Tests that the lending pool only calls the real SPL Token program.
+#[tokio::test]
+async fn test_lending_pool_token_program() {
+  let mut program_test = ProgramTest::new(
+    "token_test",
+    spl_token::id(),
+    processor!(Processor::process),
+  );
+  program_test.add_program(
+    "lending_pool_test",
+    ::lending_pool::id(),
+    processor!(::lending_pool::processor::Processor::process),
+  );
+  let mut program_test_context = program_test.start_with_context().await;
+
+  let mint_client_vault_accounts =
+    create_tokens_and_accounts(&mut program_test_context, 2, 2).await;
+  let client_x_token_account = mint_client_vault_accounts[0][1].pubkey();
+  let reserve_token_account = mint_client_vault_accounts[0][2].pubkey();
+  let lx_token_mint = mint_client_vault_accounts[1][0].pubkey();
+  let client_lx_token_account = mint_client_vault_accounts[1][1].pubkey();
+  let payer = program_test_context.payer.pubkey();
+
+  let pool_storage_account = Keypair::new();
+  let initialize_pool = |token_program_id| {
+    vec![
+      system_instruction::create_account(
+        &payer,
+        &pool_storage_account.pubkey(),
+        Rent::default().minimum_balance(LendingPool::LEN),
+        LendingPool::LEN as u64,
+        &::lending_pool::id(),
+      ),
+      LendingPoolInstruction::initialize_pool(
+        &::lending_pool::id(),
+        &payer,
+        &pool_storage_account.pubkey(),
+        &reserve_token_account,
+        &lx_token_mint,
+        token_program_id,
+      )
+      .unwrap(),
+    ]
+  };
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &initialize_pool(&system_program::id()),
+      &[&pool_storage_account],
+    )
+    .await,
+    Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+      1,
+      InstructionError::IncorrectProgramId
+    )))
+  );
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &initialize_pool(&spl_token::id()),
+      &[&pool_storage_account],
+    )
+    .await,
+    Ok(())
+  );
+
+  for is_deposit in [true, false] {
+    let (source, target) = if is_deposit {
+      (client_x_token_account, client_lx_token_account)
+    } else {
+      (client_lx_token_account, client_x_token_account)
+    };
+    let transfer =
+      if is_deposit { LendingPoolInstruction::deposit } else { LendingPoolInstruction::withdraw };
+    assert_matches!(
+      process_instructions(
+        &mut program_test_context,
+        &[transfer(
+          &::lending_pool::id(),
+          &system_program::id(),
+          &source,
+          &target,
+          &payer,
+          &pool_storage_account.pubkey(),
+          &reserve_token_account,
+          &lx_token_mint,
+          10,
+        )
+        .unwrap()],
+        &[],
+      )
+      .await,
+      Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+        0,
+        InstructionError::IncorrectProgramId
+      )))
+    );
+  }
+}
+
+/// Processes `instructions` in a transaction paid for & signed by the payer, plus `signers`.
+async fn process_instructions(
+  program_test_context: &mut ProgramTestContext,
+  instructions: &[Instruction],
+  signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+  let mut transaction =
+    Transaction::new_with_payer(instructions, Some(&program_test_context.payer.pubkey()));
+  let mut all_signers = vec![&program_test_context.payer];
+  all_signers.extend(signers);
+  transaction.sign(&all_signers, program_test_context.last_blockhash);
+  program_test_context
+    .banks_client
+    .process_transaction(transaction)
+    .await
+}
+
+/// Checks for expected values on a token account.
+async fn check_token_account(
+  program_test_context: &mut ProgramTestContext,
+  token_account_key: &Pubkey,
+  expected_owner: &COption<Pubkey>,
+  expected_amount: u64,
+) {
+  let token_account = program_test_context
+    .banks_client
+    .get_account(*token_account_key)
+    .await
+    .unwrap()
+    .expect("Account unretrievable");
+  assert_eq!(token_account.owner, spl_token::id());
+  let internal_account = spl_token::state::Account::unpack(&token_account.data).unwrap();
+  if expected_owner.is_some() {
+    assert_eq!(internal_account.owner, expected_owner.unwrap());
+  }
+  assert_eq!(internal_account.amount, expected_amount);
+}
+
+/// Estimates the Vault's value & checks it matches `expected_amount`.
+async fn check_vault_value(
+  program_test_context: &mut ProgramTestContext,
+  additional_account_metas: Vec<AccountMeta>,
+  expected_amount: u64,
+) {
+  let temp_memory_account = Keypair::new();
+  let mut transaction = Transaction::new_with_payer(
+    &[
+      system_instruction::create_account(
+        &program_test_context.payer.pubkey(),
+        &temp_memory_account.pubkey(),
+        Rent::default().minimum_balance(8),
+        8, // sizeof(u64)
+        &::vault::id(),
+      ),
+      VaultInstruction::estimate_value(
+        &::vault::id(),
+        &::vault::id(),
+        &temp_memory_account.pubkey(),
+        additional_account_metas,
+      )
+      .unwrap(),
+    ],
+    Some(&program_test_context.payer.pubkey()),
+  );
+  transaction.sign(
+    &[&program_test_context.payer, &temp_memory_account],
+    program_test_context.last_blockhash,
+  );
+  assert_matches!(
+    program_test_context
+      .banks_client
+      .process_transaction(transaction)
+      .await,
+    Ok(())
+  );
+
+  let temp_memory_account = program_test_context
+    .banks_client
+    .get_account(temp_memory_account.pubkey())
+    .await
+    .unwrap()
+    .expect("Account unretrievable");
+  let value: [u8; 8] = temp_memory_account.data[..8].try_into().unwrap();
+  assert_eq!(u64::from_le_bytes(value), expected_amount);
+}
+
+/// Generates tokens & token-accounts to hold them in the specified numbers.
+///
+/// Returns a Vec matrix in which each row corresponds to a single token, the first value in the
+/// row is the mint account, and the remaining values are token accounts.
+async fn create_tokens_and_accounts(
+  program_test_context: &mut ProgramTestContext,
+  num_tokens: u64,
+  num_accounts: u64,
+) -> Vec<Vec<Keypair>> {
+  let mint_client_vault_accounts = (1..(num_tokens + 1))
+    .map(|_| {
+      (1..(num_accounts + 2))
+        .map(|_| Keypair::new())
+        .collect::<Vec<Keypair>>()
+    })
+    .collect::<Vec<Vec<Keypair>>>();
+
+  // Mint our various tokens & setup accounts.
+  for accounts in mint_client_vault_accounts.iter() {
+    let mint = &accounts[0]; // First account is always mint
+    let mut transaction = Transaction::new_with_payer(
+      &[
+        system_instruction::create_account(
+          &program_test_context.payer.pubkey(),
+          &mint.pubkey(),
+          Rent::default().minimum_balance(spl_token::state::Mint::LEN),
+          spl_token::state::Mint::LEN as u64,
+          &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_mint(
+          &spl_token::id(),
+          &mint.pubkey(),
+          &program_test_context.payer.pubkey(),
+          None, // Freeze authority
+          6,    // decimals
+        )
+        .unwrap(),
+      ],
+      Some(&program_test_context.payer.pubkey()),
+    );
+    transaction.sign(
+      &[&program_test_context.payer, mint],
+      program_test_context.last_blockhash,
+    );
+    assert_matches!(
+      program_test_context
+        .banks_client
+        .process_transaction(transaction)
+        .await,
+      Ok(())
+    );
+
+    for token_account in accounts[1..].iter() {
+      let mut transaction = Transaction::new_with_payer(
+        &[
+          system_instruction::create_account(
+            &program_test_context.payer.pubkey(),
+            &token_account.pubkey(),
+            Rent::default().minimum_balance(spl_token::state::Account::LEN),
+            spl_token::state::Account::LEN as u64,
+            &spl_token::id(),
+          ),
+          spl_token::instruction::initialize_account(
+            &spl_token::id(),
+            &token_account.pubkey(),
+            &mint.pubkey(),
+            &program_test_context.payer.pubkey(),
+          )
+          .unwrap(),
+        ],
+        Some(&program_test_context.payer.pubkey()),
+      );
+      transaction.sign(
+        &[&program_test_context.payer, token_account],
+        program_test_context.last_blockhash,
+      );
+      assert_matches!(
+        program_test_context
+          .banks_client
+          .process_transaction(transaction)
+          .await,
+        Ok(())
+      );
+    }
+  }
+  mint_client_vault_accounts
+}
@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+use solana_program::program_error::ProgramError;
+
+#[derive(Error, Debug, Copy, Clone)]
+pub enum LendingPoolError {
+  #[error("Invalid Instruction")]
+  InvalidInstruction,
+  #[error("Account inconsistency - possible setup failure")]
+  AccountInconsistency,
+  #[error("Math overflow")]
+  Overflow,
+}
+
+impl From<LendingPoolError> for ProgramError {
+  fn from(e: LendingPoolError) -> Self {
+    ProgramError::Custom(e as u32)
+  }
+}
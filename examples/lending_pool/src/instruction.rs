@@ -0,0 +1,192 @@
+use crate::error::LendingPoolError::InvalidInstruction;
+use solana_program::{
+  instruction::{AccountMeta, Instruction},
+  program_error::ProgramError,
+  pubkey::Pubkey,
+};
+
+use std::convert::TryInto;
+use std::mem::size_of;
+
+pub enum LendingPoolInstruction {
+  /// Creates a lending pool.
+  ///
+  /// Accounts expected:
+  /// 1. `[signer]` Reserve token account owner & lX mint owner
+  /// 2. `[writeable]` Pool storage account
+  /// 3. `[writeable]` X token account which will hold the pool's reserve
+  /// 4. `[writeable]` The lX mint account
+  /// 5. `[]` SPL Token program
+  InitializePool,
+
+  /// Deposits X tokens into the pool in exchange for lX tokens.
+  ///
+  /// Accounts expected:
+  /// 1. `[]` SPL Token program
+  /// 2. `[writeable]` The source X token account.
+  /// 3. `[writeable]` The target lX token account.
+  /// 4. `[signer]` Source authority
+  /// 5. `[]` Pool storage account
+  /// 6. `[writeable]` Pool reserve token account
+  /// 7. `[writeable]` lX mint account
+  /// 8. `[]` Pool authority
+  Deposit { amount: u64 },
+
+  /// Burns lX tokens in exchange for their share of the pool's X reserve.
+  ///
+  /// Accounts expected:
+  /// 1. `[]` SPL Token program
+  /// 2. `[writeable]` The source lX token account.
+  /// 3. `[writeable]` The target X token account.
+  /// 4. `[signer]` Source authority
+  /// 5. `[]` Pool storage account
+  /// 6. `[writeable]` Pool reserve token account
+  /// 7. `[writeable]` lX mint account
+  /// 8. `[]` Pool authority
+  Withdraw {
+    amount: u64, // # of lX tokens.
+  },
+}
+
+impl LendingPoolInstruction {
+  pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+    let (tag, rest) = input.split_first().ok_or(InvalidInstruction)?;
+    Ok(match tag {
+      0 => Self::InitializePool,
+      1 | 2 => {
+        let amount = rest
+          .get(..8)
+          .and_then(|slice| slice.try_into().ok())
+          .map(u64::from_le_bytes)
+          .ok_or(InvalidInstruction)?;
+        if *tag == 1 {
+          Self::Deposit { amount }
+        } else {
+          Self::Withdraw { amount }
+        }
+      }
+      _ => return Err(InvalidInstruction.into()),
+    })
+  }
+
+  fn pack(&self) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(size_of::<Self>());
+    match *self {
+      Self::InitializePool => buf.push(0),
+      Self::Deposit { amount } => {
+        buf.push(1);
+        buf.extend_from_slice(&amount.to_le_bytes());
+      }
+      Self::Withdraw { amount } => {
+        buf.push(2);
+        buf.extend_from_slice(&amount.to_le_bytes());
+      }
+    }
+    buf
+  }
+
+  /// Derives the authority which owns a pool's reserve & lX mint.
+  pub fn find_authority(program_id: &Pubkey, pool_storage_account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[pool_storage_account.as_ref()], program_id)
+  }
+
+  pub fn initialize_pool(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    pool_storage_account: &Pubkey,
+    reserve_token_account: &Pubkey,
+    lx_token_mint: &Pubkey,
+    token_program_id: &Pubkey,
+  ) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+      program_id: *program_id,
+      accounts: vec![
+        AccountMeta::new_readonly(*initializer, true),
+        AccountMeta::new(*pool_storage_account, false),
+        AccountMeta::new(*reserve_token_account, false),
+        AccountMeta::new(*lx_token_mint, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+      ],
+      data: Self::InitializePool.pack(),
+    })
+  }
+
+  #[allow(clippy::too_many_arguments)]
+  pub fn deposit(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    target_pubkey: &Pubkey,
+    source_authority: &Pubkey,
+    pool_storage_account: &Pubkey,
+    reserve_token_account: &Pubkey,
+    lx_token_mint: &Pubkey,
+    amount: u64,
+  ) -> Result<Instruction, ProgramError> {
+    Ok(Self::create_transfer(
+      Self::Deposit { amount }.pack(),
+      program_id,
+      token_program_id,
+      source_pubkey,
+      target_pubkey,
+      source_authority,
+      pool_storage_account,
+      reserve_token_account,
+      lx_token_mint,
+    ))
+  }
+
+  #[allow(clippy::too_many_arguments)]
+  pub fn withdraw(
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    target_pubkey: &Pubkey,
+    source_authority: &Pubkey,
+    pool_storage_account: &Pubkey,
+    reserve_token_account: &Pubkey,
+    lx_token_mint: &Pubkey,
+    amount: u64,
+  ) -> Result<Instruction, ProgramError> {
+    Ok(Self::create_transfer(
+      Self::Withdraw { amount }.pack(),
+      program_id,
+      token_program_id,
+      source_pubkey,
+      target_pubkey,
+      source_authority,
+      pool_storage_account,
+      reserve_token_account,
+      lx_token_mint,
+    ))
+  }
+
+  #[allow(clippy::too_many_arguments)]
+  fn create_transfer(
+    data: Vec<u8>,
+    program_id: &Pubkey,
+    token_program_id: &Pubkey,
+    source_pubkey: &Pubkey,
+    target_pubkey: &Pubkey,
+    source_authority: &Pubkey,
+    pool_storage_account: &Pubkey,
+    reserve_token_account: &Pubkey,
+    lx_token_mint: &Pubkey,
+  ) -> Instruction {
+    let (authority, _) = Self::find_authority(program_id, pool_storage_account);
+    Instruction {
+      program_id: *program_id,
+      accounts: vec![
+        AccountMeta::new_readonly(*token_program_id, false),
+        AccountMeta::new(*source_pubkey, false),
+        AccountMeta::new(*target_pubkey, false),
+        AccountMeta::new_readonly(*source_authority, true),
+        AccountMeta::new_readonly(*pool_storage_account, false),
+        AccountMeta::new(*reserve_token_account, false),
+        AccountMeta::new(*lx_token_mint, false),
+        AccountMeta::new_readonly(authority, false),
+      ],
+      data,
+    }
+  }
+}
@@ -0,0 +1,14 @@
+//! A minimal lending protocol used as a local stand-in for a real money market.
+//!
+//! Depositors hand X tokens to the pool's reserve and receive lX pool tokens in exchange. Interest
+//! is simulated by adding X tokens directly to the reserve, which increases the X value of every
+//! lX token.
+
+#[cfg(not(feature = "no-entrypoint"))]
+pub mod entrypoint;
+pub mod error;
+pub mod instruction;
+pub mod processor;
+pub mod state;
+
+solana_program::declare_id!("HQU959WJW9Vadhjxqib4RzJErGbg2jDwipphrLBkV7p6");
@@ -0,0 +1,213 @@
+use solana_program::{
+  account_info::{next_account_info, AccountInfo},
+  entrypoint::ProgramResult,
+  msg,
+  program::{invoke, invoke_signed},
+  program_error::ProgramError,
+  program_pack::{IsInitialized, Pack},
+  pubkey::Pubkey,
+};
+
+use crate::{
+  error::LendingPoolError, instruction::LendingPoolInstruction, state::LendingPool,
+};
+
+pub struct Processor;
+impl Processor {
+  pub fn process(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+  ) -> ProgramResult {
+    match LendingPoolInstruction::unpack(instruction_data)? {
+      LendingPoolInstruction::InitializePool => {
+        msg!("LendingPoolInstruction: InitializePool");
+        Self::process_initialize_pool(program_id, accounts)
+      }
+      LendingPoolInstruction::Deposit { amount } => {
+        msg!("LendingPoolInstruction: Deposit {}", amount);
+        Self::process_transfer(program_id, accounts, amount, true)
+      }
+      LendingPoolInstruction::Withdraw { amount } => {
+        msg!("LendingPoolInstruction: Withdraw {}", amount);
+        Self::process_transfer(program_id, accounts, amount, false)
+      }
+    }
+  }
+
+  fn process_initialize_pool(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let initializer = next_account_info(account_info_iter)?;
+    let storage_account = next_account_info(account_info_iter)?;
+    let reserve_token_account = next_account_info(account_info_iter)?;
+    let lx_token_mint = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    if !initializer.is_signer {
+      return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *token_program.key != spl_token::id() {
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    if storage_account.owner != program_id {
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut pool = LendingPool::unpack_unchecked(&storage_account.data.borrow())?;
+    if pool.is_initialized() {
+      return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let (authority, bump_seed) =
+      LendingPoolInstruction::find_authority(program_id, storage_account.key);
+    pool.is_initialized = true;
+    pool.authority_bump_seed = bump_seed;
+    pool.reserve_token_account = *reserve_token_account.key;
+    pool.lx_token_mint = *lx_token_mint.key;
+    LendingPool::pack(pool, &mut storage_account.data.borrow_mut())?;
+
+    invoke(
+      &spl_token::instruction::set_authority(
+        token_program.key,
+        reserve_token_account.key,
+        Some(&authority),
+        spl_token::instruction::AuthorityType::AccountOwner,
+        initializer.key,
+        &[initializer.key],
+      )?,
+      &[
+        reserve_token_account.clone(),
+        initializer.clone(),
+        token_program.clone(),
+      ],
+    )?;
+    invoke(
+      &spl_token::instruction::set_authority(
+        token_program.key,
+        lx_token_mint.key,
+        Some(&authority),
+        spl_token::instruction::AuthorityType::MintTokens,
+        initializer.key,
+        &[initializer.key],
+      )?,
+      &[
+        lx_token_mint.clone(),
+        initializer.clone(),
+        token_program.clone(),
+      ],
+    )
+  }
+
+  fn process_transfer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    is_deposit: bool,
+  ) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let token_program = next_account_info(account_info_iter)?;
+    let source_token_account = next_account_info(account_info_iter)?;
+    let target_token_account = next_account_info(account_info_iter)?;
+    let source_authority = next_account_info(account_info_iter)?;
+    let storage_account = next_account_info(account_info_iter)?;
+    let reserve_token_account = next_account_info(account_info_iter)?;
+    let lx_token_mint = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+
+    if *token_program.key != spl_token::id() {
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    if storage_account.owner != program_id {
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    let pool = LendingPool::unpack(&storage_account.data.borrow())?;
+    if pool.reserve_token_account != *reserve_token_account.key
+      || pool.lx_token_mint != *lx_token_mint.key
+    {
+      msg!("Reserve or lX mint doesn't match pool {}", storage_account.key);
+      return Err(LendingPoolError::AccountInconsistency.into());
+    }
+    let authority_seeds: &[&[u8]] = &[storage_account.key.as_ref(), &[pool.authority_bump_seed]];
+    if Pubkey::create_program_address(authority_seeds, program_id)? != *authority.key {
+      return Err(LendingPoolError::AccountInconsistency.into());
+    }
+
+    let reserve_amount = spl_token::state::Account::unpack(&reserve_token_account.data.borrow())?.amount;
+    let lx_supply = spl_token::state::Mint::unpack(&lx_token_mint.data.borrow())?.supply;
+
+    if is_deposit {
+      let lx_amount = LendingPool::x_to_lx(amount, reserve_amount, lx_supply)
+        .ok_or(LendingPoolError::Overflow)?;
+      msg!("Depositing {} X for {} lX", amount, lx_amount);
+      invoke(
+        &spl_token::instruction::transfer(
+          token_program.key,
+          source_token_account.key,
+          reserve_token_account.key,
+          source_authority.key,
+          &[],
+          amount,
+        )?,
+        &[
+          source_token_account.clone(),
+          reserve_token_account.clone(),
+          source_authority.clone(),
+          token_program.clone(),
+        ],
+      )?;
+      invoke_signed(
+        &spl_token::instruction::mint_to(
+          token_program.key,
+          lx_token_mint.key,
+          target_token_account.key,
+          authority.key,
+          &[],
+          lx_amount,
+        )?,
+        &[
+          lx_token_mint.clone(),
+          target_token_account.clone(),
+          authority.clone(),
+          token_program.clone(),
+        ],
+        &[authority_seeds],
+      )
+    } else {
+      let x_amount = LendingPool::lx_to_x(amount, reserve_amount, lx_supply)
+        .ok_or(LendingPoolError::Overflow)?;
+      msg!("Withdrawing {} lX for {} X", amount, x_amount);
+      invoke(
+        &spl_token::instruction::burn(
+          token_program.key,
+          source_token_account.key,
+          lx_token_mint.key,
+          source_authority.key,
+          &[],
+          amount,
+        )?,
+        &[
+          source_token_account.clone(),
+          lx_token_mint.clone(),
+          source_authority.clone(),
+          token_program.clone(),
+        ],
+      )?;
+      invoke_signed(
+        &spl_token::instruction::transfer(
+          token_program.key,
+          reserve_token_account.key,
+          target_token_account.key,
+          authority.key,
+          &[],
+          x_amount,
+        )?,
+        &[
+          reserve_token_account.clone(),
+          target_token_account.clone(),
+          authority.clone(),
+          token_program.clone(),
+        ],
+        &[authority_seeds],
+      )
+    }
+  }
+}
@@ -0,0 +1,79 @@
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+  program_error::ProgramError,
+  program_pack::{IsInitialized, Pack, Sealed},
+  pubkey::Pubkey,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LendingPool {
+  pub is_initialized: bool,
+  /// Bump seed of the pool authority, derived from the pool's storage account.
+  pub authority_bump_seed: u8,
+  /// X token account holding all deposits; owned by the pool authority.
+  pub reserve_token_account: Pubkey,
+  /// Mint of the lX pool token; minted by the pool authority.
+  pub lx_token_mint: Pubkey,
+}
+
+impl LendingPool {
+  /// Returns the X value of `lx_amount` pool tokens given the current reserve & lX supply.
+  pub fn lx_to_x(lx_amount: u64, reserve_amount: u64, lx_supply: u64) -> Option<u64> {
+    if lx_supply == 0 {
+      return Some(0);
+    }
+    let x_amount = (lx_amount as u128)
+      .checked_mul(reserve_amount as u128)?
+      .checked_div(lx_supply as u128)?;
+    if x_amount > u64::MAX as u128 {
+      return None;
+    }
+    Some(x_amount as u64)
+  }
+
+  /// Returns the number of pool tokens minted for depositing `x_amount` X tokens.
+  pub fn x_to_lx(x_amount: u64, reserve_amount: u64, lx_supply: u64) -> Option<u64> {
+    if lx_supply == 0 || reserve_amount == 0 {
+      return Some(x_amount);
+    }
+    Self::lx_to_x(x_amount, lx_supply, reserve_amount)
+  }
+}
+
+impl Sealed for LendingPool {}
+
+impl Pack for LendingPool {
+  const LEN: usize = 1 + 1 + 32 + 32;
+  fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+    let src = array_ref![src, 0, LendingPool::LEN];
+    let (is_initialized, authority_bump_seed, reserve_token_account, lx_token_mint) =
+      array_refs![src, 1, 1, 32, 32];
+    let is_initialized = match is_initialized {
+      [0] => false,
+      [1] => true,
+      _ => return Err(ProgramError::InvalidAccountData),
+    };
+    Ok(LendingPool {
+      is_initialized,
+      authority_bump_seed: authority_bump_seed[0],
+      reserve_token_account: Pubkey::new_from_array(*reserve_token_account),
+      lx_token_mint: Pubkey::new_from_array(*lx_token_mint),
+    })
+  }
+
+  fn pack_into_slice(&self, dst: &mut [u8]) {
+    let dst = array_mut_ref![dst, 0, LendingPool::LEN];
+    let (is_initialized_dst, authority_bump_seed_dst, reserve_token_account_dst, lx_token_mint_dst) =
+      mut_array_refs![dst, 1, 1, 32, 32];
+    is_initialized_dst[0] = self.is_initialized as u8;
+    authority_bump_seed_dst[0] = self.authority_bump_seed;
+    reserve_token_account_dst.copy_from_slice(self.reserve_token_account.as_ref());
+    lx_token_mint_dst.copy_from_slice(self.lx_token_mint.as_ref());
+  }
+}
+
+impl IsInitialized for LendingPool {
+  fn is_initialized(&self) -> bool {
+    self.is_initialized
+  }
+}
@@ -0,0 +1,6 @@
+//! Prints the Vault's instruction & storage schemas as JSON.
+//!
+//! cargo run --example schema --features schema > client/src/vault_schema.json
+fn main() {
+    print!("{}", vault::schema::json());
+}
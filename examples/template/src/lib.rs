@@ -1,69 +1,67 @@
 use solana_program::{
+  account_info::AccountInfo,
   entrypoint,
-  account_info::{next_account_info, AccountInfo},
   entrypoint::ProgramResult,
-  instruction::AccountMeta,
   msg,
-  program::{invoke, invoke_signed},
   program_error::ProgramError,
-  program_option::COption,
-  program_pack::{IsInitialized, Pack},
   pubkey::Pubkey,
-  sysvar::{rent::Rent, Sysvar},
 };
 
-use strategy_api::{error::StrategyError::InvalidInstruction, strategy_instruction::StrategyInstruction};
+use strategy_api::strategy::{EstimateValueAccounts, Strategy, TransferAccounts};
 
 // TODO:
-// * Create Anchor-wrapper
 // * Log calls
 use solana_program;
 
+pub struct TemplateStrategy;
+
+impl Strategy for TemplateStrategy {
+  fn deposit(_program_id: &Pubkey, accounts: &TransferAccounts, amount: u64) -> ProgramResult {
+    msg!(
+      "Depositing {} from {} to {}",
+      amount,
+      accounts.source_token_account.key,
+      accounts.target_token_account.key
+    );
+    // TODO(strategist): Implement logic.
+
+    // DepositToPoolParams
+    // https://www.oxygen.org/docs-protocol.html#deposit-assets-to-a-pool
+    // https://explorer.solana.com/tx/29d8BexxZBPrTi8vT1y8XHfTYrgaLgmdsStd4XgGGZnwZvqLgnVXVVGvVxWkRJru5hoFS9b83vwCPRBH5uNWpHeW
+    Ok(())
+  }
+
+  fn withdraw(_program_id: &Pubkey, accounts: &TransferAccounts, amount: u64) -> ProgramResult {
+    msg!(
+      "Withdrawing {} from {} to {}",
+      amount,
+      accounts.source_token_account.key,
+      accounts.target_token_account.key
+    );
+    // TODO(strategist): Implement logic.
+    Ok(())
+  }
+
+  fn estimate_value(
+    _program_id: &Pubkey,
+    _accounts: &EstimateValueAccounts,
+  ) -> Result<u64, ProgramError> {
+    // TODO(strategist): Value the lX held in `accounts.position_token_account`.
+    Ok(0)
+  }
+}
+
 entrypoint!(process_instruction);
 pub fn process_instruction(
   program_id: &Pubkey,
   accounts: &[AccountInfo],
   instruction_data: &[u8],
 ) -> ProgramResult {
-  msg!("Unpacking instruction");
-  let (tag, rest) = instruction_data.split_first().ok_or(InvalidInstruction)?;
-  let instruction = StrategyInstruction::unpack(instruction_data, *tag)?;
-  let account_info_iter = &mut accounts.iter();
-  for (i, account) in account_info_iter.enumerate() {
-    msg!("account #{}:  {}", i, account.key);
-  }
-
-  match instruction {
-    StrategyInstruction::Deposit { amount } => {
-      msg!("StrategyInstruction: Deposit {}", amount);
-      // TODO(strategist): Implement logic.
-      // let account_info_iter = &mut accounts.iter();
-      // let token_program = next_account_info(account_info_iter)?;
-      // let source_token_account = next_account_info(account_info_iter)?;
-      // let target_token_account = next_account_info(account_info_iter)?;
-
-      // DepositToPoolParams
-      // https://www.oxygen.org/docs-protocol.html#deposit-assets-to-a-pool
-      // https://explorer.solana.com/tx/29d8BexxZBPrTi8vT1y8XHfTYrgaLgmdsStd4XgGGZnwZvqLgnVXVVGvVxWkRJru5hoFS9b83vwCPRBH5uNWpHeW
-
-    }
-    StrategyInstruction::Withdraw { amount } => {
-      msg!("StrategyInstruction: Withdraw {}", amount);
-      // TODO(strategist): Implement logic.
-      // let account_info_iter = &mut accounts.iter();
-      // let token_program = next_account_info(account_info_iter)?;
-      // let source_token_account = next_account_info(account_info_iter)?;
-      // let target_token_account = next_account_info(account_info_iter)?;
-    }
-    StrategyInstruction::EstimateValue {} => {
-      msg!("StrategyInstruction: EstimateValue");
-      // TODO(strategist): Implement logic.
-      // let account_info_iter = &mut accounts.iter();
-      // let vault_program = next_account_info(account_info_iter)?;
-      // let source_token_account = next_account_info(account_info_iter)?;
-    }
-  }
-  Ok(())
+  strategy_api::strategy::process_instruction::<TemplateStrategy>(
+    program_id,
+    accounts,
+    instruction_data,
+  )
 }
 
 // Random based on Token ID's ID. Defines Vault::id().
@@ -51,8 +51,9 @@ async fn test() {
         &spl_token::id(),
         &mint_client_vault_accounts[0][1].pubkey(), // Client X token account
         &mint_client_vault_accounts[0][2].pubkey(), // Strategy X token account
-        vec![],
-        99 // amount
+        vec![AccountMeta::new_readonly(program_test_context.payer.pubkey(), true)], // source authority
+        99, // amount
+        0,  // signer_num
       )
       .unwrap(),
       StrategyInstruction::withdraw(
@@ -61,8 +62,9 @@ async fn test() {
         &spl_token::id(),
         &mint_client_vault_accounts[0][1].pubkey(), // Client X token account
         &mint_client_vault_accounts[0][2].pubkey(), // Strategy X token account
-        vec![],
-        99 // Amount of lX tokens being used 
+        vec![AccountMeta::new_readonly(program_test_context.payer.pubkey(), true)], // source authority
+        99, // Amount of lX tokens being used
+        0,  // signer_num
       )
       .unwrap(),
       StrategyInstruction::estimate_value(
@@ -70,6 +72,7 @@ async fn test() {
         &::template::id(),
         &spl_token::id(),  // TODO: Vault/memory program
         &spl_token::id(),  // TODO: Memory storage
+        &mint_client_vault_accounts[0][2].pubkey(), // Strategy X token account
         vec![],
       )
       .unwrap(),
@@ -0,0 +1,875 @@
+//! Anchor-compatible IDLs for the Vault program & the strategy interface.
+//!
+//! Instruction arguments & the Vault's storage layout are read from their Borsh schemas and errors
+//! from the programs' error enums, so only the account lists below are maintained by hand. They
+//! mirror the "Accounts expected" docs on VaultInstruction & StrategyInstruction.
+//!
+//! The IDLs follow Anchor's 0.30 format, using the one-byte instruction tags as discriminators.
+//! Accounts which can't be expressed statically (multisig signers, strategy extra accounts) are
+//! described in the instruction docs & passed as remaining accounts.
+use borsh::schema::{BorshSchemaContainer, Definition, Fields};
+use serde_json::{json, Map, Value};
+use solana_program::{pubkey::Pubkey, system_program, sysvar};
+use std::convert::TryFrom;
+use strategy_api::{
+    error::StrategyError,
+    strategy_instruction::{DEPOSIT, ESTIMATE_VALUE, WITHDRAW},
+};
+use vault::{
+    error::VaultError,
+    inspect::VaultPreview,
+    instruction::{
+        VaultInstruction, LLX_TOKEN_MINT_SEED, VAULT_AUTHORITY_SEED, VAULT_REGISTRY_SEED,
+        VAULT_TOKEN_ACCOUNT_SEED,
+    },
+    state::{RegisteredVault, Vault, VaultRegistry, VaultStatus},
+};
+
+/// The Anchor IDL spec version emitted.
+pub const SPEC: &str = "0.1.0";
+
+/// The Vault program's IDL.
+pub fn vault_idl() -> Value {
+    let schema = BorshSchemaContainer::for_type::<VaultInstruction>();
+    let variants = match schema.get_definition(schema.declaration()) {
+        Some(Definition::Enum { variants, .. }) => variants,
+        _ => panic!("VaultInstruction isn't an enum"),
+    };
+    let instructions = variants
+        .iter()
+        .map(|(discriminant, name, declaration)| {
+            let (docs, accounts) = vault_instruction_accounts(name);
+            let mut instruction = json!({
+                "name": snake_case(name),
+                "docs": [docs],
+                "discriminator": [discriminant],
+                "accounts": accounts,
+                "args": fields(&schema, declaration),
+            });
+            if let Some(returns) = vault_instruction_returns(name) {
+                instruction["returns"] = idl_type(returns);
+            }
+            instruction
+        })
+        .collect::<Vec<_>>();
+
+    let state = BorshSchemaContainer::for_type::<Vault>();
+    let preview = BorshSchemaContainer::for_type::<VaultPreview>();
+    let registered_vault = BorshSchemaContainer::for_type::<RegisteredVault>();
+    let vault_registry = BorshSchemaContainer::for_type::<VaultRegistry>();
+    let status = BorshSchemaContainer::for_type::<VaultStatus>();
+    json!({
+        "address": vault::id().to_string(),
+        "metadata": {
+            "name": "vault",
+            "version": env!("CARGO_PKG_VERSION"),
+            "spec": SPEC,
+        },
+        "instructions": instructions,
+        "accounts": [{
+            "name": "Vault",
+            // Vault storage isn't prefixed with a discriminator; identify it by size & owner.
+            "discriminator": [],
+        }, {
+            "name": "RegisteredVault",
+            // Identify registry entries by size & x_token_mint, or by their derived address.
+            "discriminator": [],
+        }, {
+            "name": "VaultRegistry",
+            // Look registries up by their derived address.
+            "discriminator": [],
+        }],
+        "errors": errors(|code| VaultError::try_from(code).ok()),
+        "types": [{
+            "name": "Vault",
+            "type": {
+                "kind": "struct",
+                "fields": fields(&state, state.declaration()),
+            },
+        }, {
+            "name": "VaultPreview",
+            "type": {
+                "kind": "struct",
+                "fields": fields(&preview, preview.declaration()),
+            },
+        }, {
+            "name": "RegisteredVault",
+            "type": {
+                "kind": "struct",
+                "fields": fields(&registered_vault, registered_vault.declaration()),
+            },
+        }, {
+            "name": "VaultRegistry",
+            "type": {
+                "kind": "struct",
+                "fields": fields(&vault_registry, vault_registry.declaration()),
+            },
+        }, {
+            "name": "VaultStatus",
+            "type": {
+                "kind": "enum",
+                "variants": enum_variants(&status, status.declaration()),
+            },
+        }],
+    })
+}
+
+/// The IDL of the StrategyInstruction interface, using the default instruction IDs.
+///
+/// The interface has no program of its own, so the address is the default pubkey: clients should
+/// substitute the strategy's program ID.
+pub fn strategy_idl() -> Value {
+    let transfer_args = json!([
+        { "name": "amount", "type": "u64" },
+        { "name": "signer_num", "type": "u8" },
+    ]);
+    json!({
+        "address": Pubkey::default().to_string(),
+        "metadata": {
+            "name": "strategy",
+            "version": env!("CARGO_PKG_VERSION"),
+            "spec": SPEC,
+        },
+        "instructions": [
+            {
+                "name": "deposit",
+                "docs": [
+                    "Deposits X tokens into the strategy in exchange for lX tokens. Multisig signers \
+                    follow the source authority & strategy extra accounts come last.",
+                ],
+                "discriminator": [DEPOSIT],
+                "accounts": [
+                    account("token_program", false, false, "SPL Token program"),
+                    account("source", true, false, "The source wallet containing X tokens."),
+                    account("target", true, false, "The target wallet for lX tokens."),
+                    account("source_authority", false, true, SOURCE_AUTHORITY_DOCS),
+                ],
+                "args": transfer_args,
+            },
+            {
+                "name": "withdraw",
+                "docs": [
+                    "Redeems lX tokens from the strategy for X tokens. Multisig signers follow the \
+                    source authority & strategy extra accounts come last.",
+                ],
+                "discriminator": [WITHDRAW],
+                "accounts": [
+                    account("token_program", false, false, "SPL Token program"),
+                    account("source", true, false, "Source wallet for the derivative token (lX)."),
+                    account("target", true, false, "Target token (X) wallet."),
+                    account("source_authority", false, true, SOURCE_AUTHORITY_DOCS),
+                ],
+                "args": transfer_args,
+            },
+            {
+                "name": "estimate_value",
+                "docs": [
+                    "Returns the X value of the lX held in the calling Vault's token account as a \
+                    little-endian u64 via return data. Strategy extra accounts come last.",
+                ],
+                "discriminator": [ESTIMATE_VALUE],
+                "accounts": [
+                    account("vault_program", false, false, "The calling Vault program."),
+                    account("shared_memory", true, false, "Shared memory output."),
+                    account(
+                        "position_token_account",
+                        false,
+                        false,
+                        "The calling Vault's lX token account.",
+                    ),
+                ],
+                "args": [],
+            },
+        ],
+        "errors": errors(|code| StrategyError::try_from(code).ok()),
+    })
+}
+
+const SOURCE_AUTHORITY_DOCS: &str =
+    "Source authority; if it's an SPL Token multisig, pass it as a non-signer followed by its \
+    signer_num signers.";
+
+/// Account lists for each VaultInstruction, keyed by variant name.
+fn vault_instruction_accounts(name: &str) -> (&'static str, Vec<Value>) {
+    let token_program = || address(account("token_program", false, false, ""), &spl_token::id());
+    let vault_authority = || {
+        pda(
+            account("vault_authority", false, false, "The Vault authority."),
+            vec![
+                const_seed(VAULT_AUTHORITY_SEED),
+                account_seed("vault_storage"),
+            ],
+        )
+    };
+    let vault_registry = || {
+        pda(
+            account(
+                "vault_registry",
+                true,
+                false,
+                "The X mint's Vault registry.",
+            ),
+            vec![
+                const_seed(VAULT_REGISTRY_SEED),
+                json!({ "kind": "account", "path": "vault_storage.x_token_mint", "account": "Vault" }),
+            ],
+        )
+    };
+    let llx_token_mint = || {
+        pda(
+            account("llx_token_mint", true, false, "The Vault's llX mint."),
+            vec![
+                account_seed("vault_storage"),
+                const_seed(LLX_TOKEN_MINT_SEED),
+            ],
+        )
+    };
+    let vault_token_account = || {
+        pda(
+            account(
+                "vault_token_account",
+                true,
+                false,
+                "The Vault's lX token account, or X token account if hodling.",
+            ),
+            vec![
+                account_seed("vault_storage"),
+                const_seed(VAULT_TOKEN_ACCOUNT_SEED),
+            ],
+        )
+    };
+    let vault_storage = || account("vault_storage", true, false, "The Vault storage account.");
+    let transfer = |source: &str, target: &str| {
+        vec![
+            token_program(),
+            account(source, true, false, ""),
+            account(target, true, false, ""),
+            account("source_authority", false, true, SOURCE_AUTHORITY_DOCS),
+            vault_storage(),
+            account("strategy_program", false, false, ""),
+            llx_token_mint(),
+            vault_authority(),
+            vault_token_account(),
+        ]
+    };
+    let registered_vault = || {
+        pda(
+            account(
+                "registered_vault",
+                true,
+                false,
+                "The Vault's registry entry.",
+            ),
+            vec![
+                const_seed(VAULT_REGISTRY_SEED),
+                json!({ "kind": "account", "path": "vault_storage.x_token_mint", "account": "Vault" }),
+                account_seed("vault_storage"),
+            ],
+        )
+    };
+    let preview = || {
+        vec![
+            readonly(vault_storage()),
+            readonly(llx_token_mint()),
+            readonly(vault_token_account()),
+        ]
+    };
+    match name {
+        "InitializeVault" => (
+            "Creates a Vault, its token account & llX mint. The payer becomes its governance.",
+            vec![
+                account("payer", true, true, "Payer for the new accounts."),
+                account(
+                    "vault_storage",
+                    true,
+                    false,
+                    "Vault storage account (vault ID).",
+                ),
+                vault_token_account(),
+                llx_token_mint(),
+                account("x_token_mint", false, false, "The underlying X mint."),
+                account(
+                    "vault_token_mint",
+                    false,
+                    false,
+                    "The strategy's lX mint, or X if hodling.",
+                ),
+                vault_authority(),
+                token_program(),
+                account("strategy_program", false, false, ""),
+                address(
+                    account("system_program", false, false, ""),
+                    &system_program::id(),
+                ),
+                address(account("rent", false, false, ""), &sysvar::rent::id()),
+            ],
+        ),
+        "Deposit" => (
+            "Deposits X tokens in exchange for at least min_shares llX tokens. Strategy extra \
+            accounts come last.",
+            transfer("client_x_token_account", "client_llx_token_account"),
+        ),
+        "Withdraw" => (
+            "Redeems llX tokens for X tokens via the strategy. Strategy extra accounts come last.",
+            transfer("client_llx_token_account", "client_x_token_account"),
+        ),
+        "EstimateValue" => (
+            "Estimates the Vault's value in X, recording any loss against its debt. Strategy \
+            Vaults take their strategy program next, followed by the strategy's extra accounts.",
+            vec![
+                account("program", false, false, "Unused."),
+                account("shared_memory", true, false, "Shared memory output."),
+                vault_storage(),
+                readonly(vault_token_account()),
+            ],
+        ),
+        "EstimatePositionValue" => (
+            "Estimates the X value of the llX held by a Vault using this Vault as its strategy. \
+            Accounts after the Vault storage are as for EstimateValue.",
+            vec![
+                account("program", false, false, "The calling Vault program."),
+                account("shared_memory", true, false, "Shared memory output."),
+                account(
+                    "position_token_account",
+                    false,
+                    false,
+                    "The outer Vault's token account, holding the llX position.",
+                ),
+                account(
+                    "outer_vault_storage",
+                    false,
+                    false,
+                    "The outer Vault storage account.",
+                ),
+                readonly(llx_token_mint()),
+                vault_storage(),
+                readonly(vault_token_account()),
+            ],
+        ),
+        "AcknowledgeLoss" => (
+            "Acknowledges recorded losses, unblocking deposits.",
+            vec![
+                account("governance", false, true, "Vault governance."),
+                vault_storage(),
+            ],
+        ),
+        "WithdrawInKind" => (
+            "Redeems llX tokens for the Vault's lX tokens, bypassing the strategy.",
+            vec![
+                token_program(),
+                account("client_llx_token_account", true, false, ""),
+                account("client_lx_token_account", true, false, ""),
+                account("source_authority", false, true, SOURCE_AUTHORITY_DOCS),
+                vault_storage(),
+                llx_token_mint(),
+                vault_authority(),
+                vault_token_account(),
+            ],
+        ),
+        "ZapDeposit" => {
+            let mut accounts = vec![
+                account("client_input_token_account", true, false, ""),
+                address(
+                    account("swap_program", false, false, "Token Swap program."),
+                    &vault::swap::id(),
+                ),
+                account("swap", false, false, "Token Swap pool."),
+                account("swap_authority", false, false, "Token Swap pool authority."),
+                account(
+                    "swap_source",
+                    true,
+                    false,
+                    "The pool's input token account.",
+                ),
+                account(
+                    "swap_destination",
+                    true,
+                    false,
+                    "The pool's X token account.",
+                ),
+                account("pool_mint", true, false, ""),
+                account("pool_fee_account", true, false, ""),
+            ];
+            accounts.extend(transfer(
+                "client_x_token_account",
+                "client_llx_token_account",
+            ));
+            (
+                "Swaps a token into X via an SPL Token Swap pool, then deposits the X received. \
+                The source authority must be a signer.",
+                accounts,
+            )
+        }
+        "PreviewVault" => (
+            "Reports the Vault's value & price per share via return data, without modifying any \
+            accounts. A client llX token account may follow to report its redeemable X.",
+            preview(),
+        ),
+        "PreviewDeposit" => (
+            "Reports the llX tokens Deposit would mint via return data, without moving any tokens.",
+            preview(),
+        ),
+        "PreviewWithdraw" => (
+            "Reports the X tokens Withdraw would pay out via return data, without moving any \
+            tokens. Strategy Vaults are priced against their last estimate.",
+            preview(),
+        ),
+        "Mint" => (
+            "Mints exactly the given llX tokens in exchange for X tokens, rounding the X charged \
+            up. Strategy extra accounts come last.",
+            transfer("client_x_token_account", "client_llx_token_account"),
+        ),
+        "WithdrawAssets" => (
+            "Withdraws at least the given X tokens via the strategy, rounding the llX burned up. \
+            Strategy extra accounts come last.",
+            transfer("client_llx_token_account", "client_x_token_account"),
+        ),
+        "ConvertToShares" => (
+            "Reports the llX tokens the given X tokens are worth via return data.",
+            preview(),
+        ),
+        "ConvertToAssets" => (
+            "Reports the X tokens the given llX tokens are worth via return data.",
+            preview(),
+        ),
+        "MaxDeposit" => (
+            "Reports the most X tokens Deposit currently accepts via return data.",
+            preview(),
+        ),
+        "MaxWithdraw" => {
+            let mut accounts = preview();
+            accounts.push(account("client_llx_token_account", false, false, ""));
+            (
+                "Reports the most X tokens the client llX token account can withdraw via return \
+                data.",
+                accounts,
+            )
+        }
+        "MaxMint" => (
+            "Reports the most llX tokens Mint currently mints via return data.",
+            preview(),
+        ),
+        "MaxRedeem" => {
+            let mut accounts = preview();
+            accounts.push(account("client_llx_token_account", false, false, ""));
+            (
+                "Reports the most llX tokens the client llX token account can redeem via return \
+                data.",
+                accounts,
+            )
+        }
+        "PreviewMint" => (
+            "Reports the X tokens Mint would charge via return data, without moving any tokens.",
+            preview(),
+        ),
+        "PreviewWithdrawAssets" => (
+            "Reports the llX tokens WithdrawAssets would burn via return data, without moving any \
+            tokens. Strategy Vaults are priced against their last estimate.",
+            preview(),
+        ),
+        "RegisterVault" => (
+            "Registers a Vault for its X mint: creates its registry entry & appends it to the \
+            mint's Vault registry. A separate step from InitializeVault, which doesn't register \
+            the Vault; registering a retired Vault reactivates it.",
+            vec![
+                account(
+                    "governance",
+                    true,
+                    true,
+                    "Vault governance; pays for the registry entry & space.",
+                ),
+                readonly(vault_storage()),
+                registered_vault(),
+                vault_registry(),
+                address(
+                    account("system_program", false, false, ""),
+                    &system_program::id(),
+                ),
+            ],
+        ),
+        "DeregisterVault" => (
+            "Marks a Vault's registry entry as retired, e.g. when shutting it down, & swap-removes \
+            it from the mint's Vault registry.",
+            vec![
+                account(
+                    "governance",
+                    true,
+                    true,
+                    "Vault governance; receives the registry's freed rent.",
+                ),
+                readonly(vault_storage()),
+                registered_vault(),
+                vault_registry(),
+            ],
+        ),
+        "InitializeVaultWithAccounts" => (
+            "Creates a Vault from an existing token account & llX mint, moving the owner & mint \
+            authority to the Vault authority separately. Multisig signers of the owner, then of \
+            the mint authority, come last.",
+            vec![
+                account("governance", false, true, "The Vault's governance."),
+                account(
+                    "vault_storage",
+                    true,
+                    false,
+                    "Vault storage account (vault ID).",
+                ),
+                account(
+                    "vault_token_account",
+                    true,
+                    false,
+                    "The existing lX token account, or X token account if hodling.",
+                ),
+                account("llx_token_mint", true, false, "The existing llX mint."),
+                account("x_token_mint", false, false, "The underlying X mint."),
+                vault_authority(),
+                token_program(),
+                account("strategy_program", false, false, ""),
+                address(account("rent", false, false, ""), &sysvar::rent::id()),
+                account(
+                    "vault_token_account_owner",
+                    false,
+                    true,
+                    "The token account's owner; if it's an SPL Token multisig, pass it as a \
+                    non-signer.",
+                ),
+                account(
+                    "llx_token_mint_authority",
+                    false,
+                    true,
+                    "The llX mint authority; if it's an SPL Token multisig, pass it as a \
+                    non-signer.",
+                ),
+            ],
+        ),
+        "SetWithdrawalFee" => (
+            "Sets the fee kept from each withdrawal, in basis points.",
+            vec![
+                account("governance", false, true, "Vault governance."),
+                vault_storage(),
+            ],
+        ),
+        "SetPaused" => (
+            "Pauses or resumes deposits.",
+            vec![
+                account("governance", false, true, "Vault governance."),
+                vault_storage(),
+            ],
+        ),
+        _ => panic!("No IDL accounts listed for VaultInstruction::{}", name),
+    }
+}
+
+/// The Borsh-encoded return data type of each VaultInstruction, keyed by variant name.
+fn vault_instruction_returns(name: &str) -> Option<&'static str> {
+    match name {
+        "PreviewVault" => Some("VaultPreview"),
+        "PreviewDeposit"
+        | "PreviewWithdraw"
+        | "ConvertToShares"
+        | "ConvertToAssets"
+        | "MaxDeposit"
+        | "MaxWithdraw"
+        | "MaxMint"
+        | "MaxRedeem"
+        | "PreviewMint"
+        | "PreviewWithdrawAssets"
+        | "EstimatePositionValue" => Some("u64"),
+        _ => None,
+    }
+}
+
+fn account(name: &str, writable: bool, signer: bool, docs: &str) -> Value {
+    let mut account = Map::new();
+    account.insert("name".into(), name.into());
+    if !docs.is_empty() {
+        account.insert("docs".into(), json!([docs]));
+    }
+    if writable {
+        account.insert("writable".into(), true.into());
+    }
+    if signer {
+        account.insert("signer".into(), true.into());
+    }
+    Value::Object(account)
+}
+
+fn readonly(mut account: Value) -> Value {
+    account.as_object_mut().unwrap().remove("writable");
+    account
+}
+
+fn address(mut account: Value, address: &Pubkey) -> Value {
+    account["address"] = address.to_string().into();
+    account
+}
+
+fn pda(mut account: Value, seeds: Vec<Value>) -> Value {
+    account["pda"] = json!({ "seeds": seeds });
+    account
+}
+
+fn const_seed(seed: &[u8]) -> Value {
+    json!({ "kind": "const", "value": seed })
+}
+
+fn account_seed(path: &str) -> Value {
+    json!({ "kind": "account", "path": path })
+}
+
+/// The named fields of the struct `declaration` in `schema`.
+fn fields(schema: &BorshSchemaContainer, declaration: &str) -> Vec<Value> {
+    match schema.get_definition(declaration) {
+        Some(Definition::Struct {
+            fields: Fields::NamedFields(fields),
+        }) => fields
+            .iter()
+            .map(|(name, declaration)| json!({ "name": name, "type": idl_type(declaration) }))
+            .collect(),
+        Some(Definition::Struct {
+            fields: Fields::Empty,
+        }) => vec![],
+        _ => panic!("{} isn't a struct with named fields", declaration),
+    }
+}
+
+/// The unit variants of the enum `declaration` in `schema`.
+fn enum_variants(schema: &BorshSchemaContainer, declaration: &str) -> Vec<Value> {
+    match schema.get_definition(declaration) {
+        Some(Definition::Enum { variants, .. }) => variants
+            .iter()
+            .map(|(_, name, _)| json!({ "name": name }))
+            .collect(),
+        _ => panic!("{} isn't an enum", declaration),
+    }
+}
+
+/// Maps a Borsh type declaration to an IDL type.
+fn idl_type(declaration: &str) -> Value {
+    match declaration {
+        "bool" | "u8" | "u16" | "u32" | "u64" | "u128" | "i8" | "i16" | "i32" | "i64" | "i128" => {
+            declaration.into()
+        }
+        "Pubkey" => "pubkey".into(),
+        "Vec<u8>" => "bytes".into(),
+        "String" => "string".into(),
+        _ => {
+            let generic = |prefix: &str| {
+                declaration
+                    .strip_prefix(prefix)
+                    .and_then(|inner| inner.strip_suffix('>'))
+            };
+            if let Some(inner) = generic("Option<") {
+                json!({ "option": idl_type(inner) })
+            } else if let Some(inner) = generic("Vec<") {
+                json!({ "vec": idl_type(inner) })
+            } else {
+                json!({ "defined": { "name": declaration } })
+            }
+        }
+    }
+}
+
+/// Lists error codes from 0 until `error` returns None.
+fn errors<E: std::fmt::Debug + std::fmt::Display>(error: impl Fn(u32) -> Option<E>) -> Vec<Value> {
+    (0..)
+        .map(|code| (code, error(code)))
+        .take_while(|(_, error)| error.is_some())
+        .map(|(code, error)| {
+            let error = error.unwrap();
+            json!({ "code": code, "name": format!("{:?}", error), "msg": error.to_string() })
+        })
+        .collect()
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::instruction::{AccountMeta, Instruction};
+    use std::{fs, path::Path};
+    use vault::instruction::transfer_account_metas;
+
+    fn published(name: &str) -> Value {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(name);
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_published_idls_are_current() {
+        assert!(
+            published("vault.json") == vault_idl() && published("strategy.json") == strategy_idl(),
+            "The published IDLs are stale: regenerate them with `cargo run`"
+        );
+    }
+
+    /// Checks `instruction` against its IDL entry: each listed account's flags, fixed address &
+    /// PDA seeds. `vault_storage` & `x_token_mint` resolve account seeds.
+    fn check_instruction(vault_storage: &Pubkey, x_token_mint: &Pubkey, instruction: &Instruction) {
+        let idl = vault_idl();
+        let entry = idl["instructions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|entry| entry["discriminator"] == json!([instruction.data[0]]))
+            .unwrap_or_else(|| panic!("No IDL entry for tag {}", instruction.data[0]));
+        let accounts = entry["accounts"].as_array().unwrap();
+        assert!(instruction.accounts.len() >= accounts.len());
+        for (account, meta) in accounts.iter().zip(instruction.accounts.iter()) {
+            let name = format!("{}.{}", entry["name"], account["name"]);
+            assert_eq!(account["writable"] == true, meta.is_writable, "{}", name);
+            assert_eq!(account["signer"] == true, meta.is_signer, "{}", name);
+            if let Some(address) = account["address"].as_str() {
+                assert_eq!(address, meta.pubkey.to_string(), "{}", name);
+            }
+            if let Some(seeds) = account["pda"]["seeds"].as_array() {
+                let seeds = seeds
+                    .iter()
+                    .map(|seed| match seed["path"].as_str() {
+                        Some("vault_storage") => vault_storage.to_bytes().to_vec(),
+                        Some("vault_storage.x_token_mint") => x_token_mint.to_bytes().to_vec(),
+                        Some(path) => panic!("Unknown seed path {}", path),
+                        None => serde_json::from_value(seed["value"].clone()).unwrap(),
+                    })
+                    .collect::<Vec<Vec<u8>>>();
+                let seeds = seeds.iter().map(Vec::as_slice).collect::<Vec<_>>();
+                let (address, _) = Pubkey::find_program_address(&seeds, &vault::id());
+                assert_eq!(address, meta.pubkey, "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_instructions_match_builders() {
+        let program_id = vault::id();
+        let storage = Pubkey::new_unique();
+        let x_token_mint = Pubkey::new_unique();
+        let client = Pubkey::new_unique();
+        let (vault_token_account, _) =
+            VaultInstruction::find_vault_token_account(&program_id, &storage);
+        let (llx_token_mint, _) = VaultInstruction::find_llx_token_mint(&program_id, &storage);
+        let vault = Vault {
+            is_initialized: true,
+            hodl: true,
+            llx_token_mint_id: llx_token_mint,
+            x_token_mint,
+            lx_token_mint: x_token_mint,
+            vault_token_account,
+            last_estimated_value: 0,
+            strategy_program_id: program_id,
+            strategy_program_deposit_instruction_id: 0,
+            strategy_program_withdraw_instruction_id: 1,
+            strategy_program_estimate_instruction_id: 2,
+            strategy_data_account: None,
+            governance: client,
+            total_debt: 0,
+            total_loss: 0,
+            unacknowledged_loss: 0,
+            block_deposits_on_loss: false,
+            withdrawal_fee_bps: 0,
+            paused: false,
+        };
+        let transfer_metas = || {
+            let mut metas = vec![AccountMeta::new_readonly(client, true)];
+            metas.extend(transfer_account_metas(
+                &program_id,
+                &storage,
+                &vault,
+                vec![],
+            ));
+            metas
+        };
+        let token_program = spl_token::id();
+        let instructions = vec![
+            VaultInstruction::initialize_vault(
+                &program_id,
+                &client,
+                &storage,
+                &x_token_mint,
+                &x_token_mint,
+                &token_program,
+                &program_id,
+                true,
+                0,
+                1,
+                2,
+                false,
+            ),
+            VaultInstruction::deposit(
+                &program_id,
+                &token_program,
+                &client,
+                &client,
+                transfer_metas(),
+                1,
+                0,
+                0,
+            ),
+            VaultInstruction::withdraw(
+                &program_id,
+                &token_program,
+                &client,
+                &client,
+                transfer_metas(),
+                1,
+                0,
+            ),
+            VaultInstruction::withdraw_in_kind(
+                &program_id,
+                &token_program,
+                &client,
+                &client,
+                &client,
+                &[],
+                &storage,
+                &vault,
+                1,
+            ),
+            VaultInstruction::acknowledge_loss(&program_id, &client, &storage),
+            VaultInstruction::preview_vault(&program_id, &storage, &vault, Some(&client)),
+            VaultInstruction::max_withdraw(&program_id, &storage, &vault, &client),
+            VaultInstruction::max_redeem(&program_id, &storage, &vault, &client),
+            VaultInstruction::preview_mint(&program_id, &storage, &vault, 1),
+            VaultInstruction::register_vault(&program_id, &client, &storage, &x_token_mint),
+            VaultInstruction::deregister_vault(&program_id, &client, &storage, &x_token_mint),
+            VaultInstruction::initialize_vault_with_accounts(
+                &program_id,
+                &client,
+                &storage,
+                &client,
+                &client,
+                &[],
+                &client,
+                &client,
+                &[],
+                &x_token_mint,
+                &token_program,
+                &program_id,
+                true,
+                0,
+                1,
+                2,
+                false,
+            ),
+            VaultInstruction::set_withdrawal_fee(&program_id, &client, &storage, 50),
+            VaultInstruction::set_paused(&program_id, &client, &storage, true),
+        ];
+        for instruction in instructions {
+            check_instruction(&storage, &x_token_mint, &instruction.unwrap());
+        }
+    }
+}
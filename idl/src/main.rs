@@ -0,0 +1,24 @@
+//! Writes the Vault & strategy IDLs.
+//!
+//! cargo run [OUT_DIR]
+//!
+//! OUT_DIR defaults to this crate's directory, where the published IDLs live.
+use std::{env, fs, path::PathBuf};
+
+fn main() {
+    let out_dir = env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")));
+    for (name, idl) in [
+        ("vault.json", vault_idl::vault_idl()),
+        ("strategy.json", vault_idl::strategy_idl()),
+    ]
+    .iter()
+    {
+        let path = out_dir.join(name);
+        fs::write(&path, serde_json::to_string_pretty(idl).unwrap() + "\n")
+            .unwrap_or_else(|e| panic!("Failed to write {}: {}", path.display(), e));
+        println!("Wrote {}", path.display());
+    }
+}
@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+use solana_program::program_error::ProgramError;
+
+#[derive(Error, Debug, Copy, Clone)]
+pub enum MockStrategyError {
+  #[error("Invalid Instruction")]
+  InvalidInstruction,
+  #[error("Forced failure")]
+  ForcedFailure,
+  #[error("Account inconsistency - possible setup failure")]
+  AccountInconsistency,
+}
+
+impl From<MockStrategyError> for ProgramError {
+  fn from(e: MockStrategyError) -> Self {
+    ProgramError::Custom(e as u32)
+  }
+}
@@ -0,0 +1,217 @@
+use crate::error::MockStrategyError::InvalidInstruction;
+use solana_program::{
+  instruction::{AccountMeta, Instruction},
+  program_error::ProgramError,
+  program_option::COption,
+  pubkey::Pubkey,
+};
+
+use std::convert::TryInto;
+use std::mem::size_of;
+
+/// Admin instructions; StrategyInstruction uses the default strategy IDs (0-2).
+pub enum MockStrategyInstruction {
+  /// Creates a mock strategy.
+  ///
+  /// Accounts expected:
+  /// 1. `[signer]` Admin, reserve token account owner & lX mint owner
+  /// 2. `[writeable]` Mock storage account
+  /// 3. `[writeable]` X token account which will hold the reserve
+  /// 4. `[writeable]` The lX mint account
+  /// 5. `[]` SPL Token program
+  Initialize,
+
+  /// Reconfigures the mock's behaviour.
+  ///
+  /// Accounts expected:
+  /// 1. `[signer]` Admin
+  /// 2. `[writeable]` Mock storage account
+  Configure {
+    reported_value: COption<u64>,
+    withdraw_shortfall: u64,
+    flags: u8,
+  },
+}
+
+pub const INITIALIZE: u8 = 3;
+pub const CONFIGURE: u8 = 4;
+
+impl MockStrategyInstruction {
+  pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+    let (tag, rest) = input.split_first().ok_or(InvalidInstruction)?;
+    Ok(match *tag {
+      INITIALIZE => Self::Initialize,
+      CONFIGURE => {
+        let reported_value = match rest.first() {
+          Some(0) => COption::None,
+          Some(1) => COption::Some(unpack_u64(rest.get(1..9))?),
+          _ => return Err(InvalidInstruction.into()),
+        };
+        let withdraw_shortfall = unpack_u64(rest.get(9..17))?;
+        let flags = *rest.get(17).ok_or(InvalidInstruction)?;
+        Self::Configure {
+          reported_value,
+          withdraw_shortfall,
+          flags,
+        }
+      }
+      _ => return Err(InvalidInstruction.into()),
+    })
+  }
+
+  fn pack(&self) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(size_of::<Self>());
+    match *self {
+      Self::Initialize => buf.push(INITIALIZE),
+      Self::Configure {
+        reported_value,
+        withdraw_shortfall,
+        flags,
+      } => {
+        buf.push(CONFIGURE);
+        match reported_value {
+          COption::Some(value) => {
+            buf.push(1);
+            buf.extend_from_slice(&value.to_le_bytes());
+          }
+          COption::None => {
+            buf.push(0);
+            buf.extend_from_slice(&[0; 8]);
+          }
+        }
+        buf.extend_from_slice(&withdraw_shortfall.to_le_bytes());
+        buf.push(flags);
+      }
+    }
+    buf
+  }
+
+  /// Derives the authority which owns the mock's reserve & lX mint.
+  pub fn find_authority(program_id: &Pubkey, storage_account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[storage_account.as_ref()], program_id)
+  }
+
+  pub fn initialize(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    storage_account: &Pubkey,
+    reserve_token_account: &Pubkey,
+    lx_token_mint: &Pubkey,
+    token_program_id: &Pubkey,
+  ) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+      program_id: *program_id,
+      accounts: vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(*storage_account, false),
+        AccountMeta::new(*reserve_token_account, false),
+        AccountMeta::new(*lx_token_mint, false),
+        AccountMeta::new_readonly(*token_program_id, false),
+      ],
+      data: Self::Initialize.pack(),
+    })
+  }
+
+  pub fn configure(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    storage_account: &Pubkey,
+    reported_value: COption<u64>,
+    withdraw_shortfall: u64,
+    flags: u8,
+  ) -> Result<Instruction, ProgramError> {
+    Ok(Instruction {
+      program_id: *program_id,
+      accounts: vec![
+        AccountMeta::new_readonly(*admin, true),
+        AccountMeta::new(*storage_account, false),
+      ],
+      data: Self::Configure {
+        reported_value,
+        withdraw_shortfall,
+        flags,
+      }
+      .pack(),
+    })
+  }
+}
+
+/// Accounts appended to StrategyInstruction#Deposit and StrategyInstruction#Withdraw.
+///
+/// 1. `[]` Mock storage account
+/// 2. `[writeable]` Reserve token account
+/// 3. `[writeable]` lX mint
+/// 4. `[]` Mock authority
+pub fn transfer_account_metas(
+  program_id: &Pubkey,
+  storage_account: &Pubkey,
+  reserve_token_account: &Pubkey,
+  lx_token_mint: &Pubkey,
+) -> Vec<AccountMeta> {
+  let (authority, _) = MockStrategyInstruction::find_authority(program_id, storage_account);
+  vec![
+    AccountMeta::new_readonly(*storage_account, false),
+    AccountMeta::new(*reserve_token_account, false),
+    AccountMeta::new(*lx_token_mint, false),
+    AccountMeta::new_readonly(authority, false),
+  ]
+}
+
+/// Accounts appended to StrategyInstruction#EstimateValue, after the Vault's lX token account.
+///
+/// 1. `[]` Mock storage account
+/// 2. `[]` Reserve token account
+/// 3. `[]` lX mint
+///
+/// When the mock is configured to REENTER, these are followed by the calling Vault's accounts 3+ of
+/// VaultInstruction#EstimateValue, e.g. from `vault::instruction::estimate_value_account_metas`, and
+/// the mock invokes a Vault EstimateValue with them.
+pub fn estimate_value_account_metas(
+  storage_account: &Pubkey,
+  reserve_token_account: &Pubkey,
+  lx_token_mint: &Pubkey,
+) -> Vec<AccountMeta> {
+  vec![
+    AccountMeta::new_readonly(*storage_account, false),
+    AccountMeta::new_readonly(*reserve_token_account, false),
+    AccountMeta::new_readonly(*lx_token_mint, false),
+  ]
+}
+
+/// Accounts appended to StrategyInstruction#Deposit and StrategyInstruction#Withdraw after the
+/// above when the mock is configured to REENTER, i.e. those of the Vault Withdraw it invokes. The
+/// withdrawal is authorized by the mock authority.
+///
+/// 1. `[]` The Vault program
+/// 2. `[]` SPL Token program
+/// 3. `[]` Mock authority
+/// 4. `[writeable]` llX token account owned by the mock authority
+/// 5. `[writeable]` X token account
+/// 6. `[]` The calling Vault's accounts 6+ of VaultInstruction#Withdraw, e.g. from
+///    `vault::instruction::transfer_account_metas`
+pub fn reenter_account_metas(
+  program_id: &Pubkey,
+  storage_account: &Pubkey,
+  vault_program_id: &Pubkey,
+  llx_token_account: &Pubkey,
+  x_token_account: &Pubkey,
+  vault_account_metas: Vec<AccountMeta>,
+) -> Vec<AccountMeta> {
+  let (authority, _) = MockStrategyInstruction::find_authority(program_id, storage_account);
+  let mut accounts = vec![
+    AccountMeta::new_readonly(*vault_program_id, false),
+    AccountMeta::new_readonly(spl_token::id(), false),
+    AccountMeta::new_readonly(authority, false),
+    AccountMeta::new(*llx_token_account, false),
+    AccountMeta::new(*x_token_account, false),
+  ];
+  accounts.extend(vault_account_metas);
+  accounts
+}
+
+fn unpack_u64(slice: Option<&[u8]>) -> Result<u64, ProgramError> {
+  slice
+    .and_then(|slice| slice.try_into().ok())
+    .map(u64::from_le_bytes)
+    .ok_or_else(|| InvalidInstruction.into())
+}
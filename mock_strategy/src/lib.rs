@@ -0,0 +1,15 @@
+//! A test-only strategy whose behaviour is controlled through admin instructions.
+//!
+//! The mock holds deposited X tokens in a reserve and mints lX tokens 1:1. Its reported value,
+//! withdrawal payouts and reverting operations can be configured, which makes it possible to
+//! deterministically test the Vault against misbehaving strategies, including ones which try to
+//! reenter the calling Vault.
+
+#[cfg(not(feature = "no-entrypoint"))]
+pub mod entrypoint;
+pub mod error;
+pub mod instruction;
+pub mod processor;
+pub mod state;
+
+solana_program::declare_id!("D96tm7pKk7E31hNpFMmYwd195oAM8MUbd8GttdUjXFtB");
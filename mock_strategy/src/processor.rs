@@ -0,0 +1,347 @@
+use solana_program::{
+  account_info::{next_account_info, AccountInfo},
+  entrypoint::ProgramResult,
+  instruction::AccountMeta,
+  msg,
+  program::{invoke, invoke_signed},
+  program_error::ProgramError,
+  program_option::COption,
+  program_pack::{IsInitialized, Pack},
+  pubkey::Pubkey,
+};
+use strategy_api::strategy::{EstimateValueAccounts, Strategy, TransferAccounts};
+use vault::instruction::VaultInstruction;
+
+use crate::{
+  error::MockStrategyError,
+  instruction::MockStrategyInstruction,
+  state::{MockStrategy, FAIL_DEPOSIT, FAIL_ESTIMATE, FAIL_WITHDRAW, REENTER},
+};
+
+pub struct Processor;
+impl Processor {
+  fn process_initialize(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin = next_account_info(account_info_iter)?;
+    let storage_account = next_account_info(account_info_iter)?;
+    let reserve_token_account = next_account_info(account_info_iter)?;
+    let lx_token_mint = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+
+    if !admin.is_signer {
+      return Err(ProgramError::MissingRequiredSignature);
+    }
+    if storage_account.owner != program_id {
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut mock = MockStrategy::unpack_unchecked(&storage_account.data.borrow())?;
+    if mock.is_initialized() {
+      return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    let (authority, bump_seed) =
+      MockStrategyInstruction::find_authority(program_id, storage_account.key);
+    mock.is_initialized = true;
+    mock.admin = *admin.key;
+    mock.authority_bump_seed = bump_seed;
+    mock.reserve_token_account = *reserve_token_account.key;
+    mock.lx_token_mint = *lx_token_mint.key;
+    mock.reported_value = COption::None;
+    mock.withdraw_shortfall = 0;
+    mock.flags = 0;
+    MockStrategy::pack(mock, &mut storage_account.data.borrow_mut())?;
+
+    invoke(
+      &spl_token::instruction::set_authority(
+        token_program.key,
+        reserve_token_account.key,
+        Some(&authority),
+        spl_token::instruction::AuthorityType::AccountOwner,
+        admin.key,
+        &[admin.key],
+      )?,
+      &[
+        reserve_token_account.clone(),
+        admin.clone(),
+        token_program.clone(),
+      ],
+    )?;
+    invoke(
+      &spl_token::instruction::set_authority(
+        token_program.key,
+        lx_token_mint.key,
+        Some(&authority),
+        spl_token::instruction::AuthorityType::MintTokens,
+        admin.key,
+        &[admin.key],
+      )?,
+      &[lx_token_mint.clone(), admin.clone(), token_program.clone()],
+    )
+  }
+
+  fn process_configure(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    reported_value: COption<u64>,
+    withdraw_shortfall: u64,
+    flags: u8,
+  ) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let admin = next_account_info(account_info_iter)?;
+    let storage_account = next_account_info(account_info_iter)?;
+    let mut mock = Self::unpack_mock(program_id, storage_account)?;
+    if !admin.is_signer || mock.admin != *admin.key {
+      return Err(ProgramError::MissingRequiredSignature);
+    }
+    msg!(
+      "Configuring mock: value {:?} shortfall {} flags {}",
+      reported_value,
+      withdraw_shortfall,
+      flags
+    );
+    mock.reported_value = reported_value;
+    mock.withdraw_shortfall = withdraw_shortfall;
+    mock.flags = flags;
+    MockStrategy::pack(mock, &mut storage_account.data.borrow_mut())
+  }
+
+  fn unpack_mock(
+    program_id: &Pubkey,
+    storage_account: &AccountInfo,
+  ) -> Result<MockStrategy, ProgramError> {
+    if storage_account.owner != program_id {
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    MockStrategy::unpack(&storage_account.data.borrow())
+  }
+
+  /// Passes `accounts` along with their privileges, as the Vault does for strategy accounts.
+  fn account_metas(accounts: &[AccountInfo]) -> Vec<AccountMeta> {
+    accounts
+      .iter()
+      .map(|account| {
+        if account.is_writable {
+          AccountMeta::new(*account.key, account.is_signer)
+        } else {
+          AccountMeta::new_readonly(*account.key, account.is_signer)
+        }
+      })
+      .collect()
+  }
+
+  /// Invokes a Vault Withdraw of the mock authority's llX; see
+  /// [reenter_account_metas](../instruction/fn.reenter_account_metas.html). The runtime should
+  /// reject it when the calling Vault is still on the stack.
+  fn reenter_withdraw(
+    storage_account: &AccountInfo,
+    mock: &MockStrategy,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let vault_program = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+    let llx_token_account = next_account_info(account_info_iter)?;
+    let x_token_account = next_account_info(account_info_iter)?;
+
+    let mut account_metas = vec![AccountMeta::new_readonly(*authority.key, true)];
+    account_metas.extend(Self::account_metas(account_info_iter.as_slice()));
+    msg!("Reentering Vault {} with a Withdraw", vault_program.key);
+    let authority_seeds: &[&[u8]] = &[storage_account.key.as_ref(), &[mock.authority_bump_seed]];
+    invoke_signed(
+      &VaultInstruction::withdraw(
+        vault_program.key,
+        token_program.key,
+        llx_token_account.key,
+        x_token_account.key,
+        account_metas,
+        1, // amount
+        0, // signer_num
+      )?,
+      accounts,
+      &[authority_seeds],
+    )
+  }
+
+  fn transfer(
+    program_id: &Pubkey,
+    accounts: &TransferAccounts,
+    amount: u64,
+    is_deposit: bool,
+  ) -> ProgramResult {
+    let account_info_iter = &mut accounts.extra_accounts.iter();
+    let storage_account = next_account_info(account_info_iter)?;
+    let reserve_token_account = next_account_info(account_info_iter)?;
+    let lx_token_mint = next_account_info(account_info_iter)?;
+    let authority = next_account_info(account_info_iter)?;
+
+    let mock = Self::unpack_mock(program_id, storage_account)?;
+    if mock.reserve_token_account != *reserve_token_account.key
+      || mock.lx_token_mint != *lx_token_mint.key
+    {
+      return Err(MockStrategyError::AccountInconsistency.into());
+    }
+    if mock.has_flag(if is_deposit { FAIL_DEPOSIT } else { FAIL_WITHDRAW }) {
+      msg!("Mock configured to fail");
+      return Err(MockStrategyError::ForcedFailure.into());
+    }
+    if mock.has_flag(REENTER) {
+      Self::reenter_withdraw(storage_account, &mock, account_info_iter.as_slice())?;
+    }
+
+    let authority_seeds: &[&[u8]] = &[storage_account.key.as_ref(), &[mock.authority_bump_seed]];
+    if is_deposit {
+      let mut transfer_accounts = vec![
+        accounts.source_token_account.clone(),
+        reserve_token_account.clone(),
+      ];
+      transfer_accounts.extend(accounts.authority_accounts());
+      transfer_accounts.push(accounts.token_program.clone());
+      invoke(
+        &spl_token::instruction::transfer(
+          accounts.token_program.key,
+          accounts.source_token_account.key,
+          reserve_token_account.key,
+          accounts.source_authority.key,
+          &accounts.signer_pubkeys(),
+          amount,
+        )?,
+        &transfer_accounts,
+      )?;
+      invoke_signed(
+        &spl_token::instruction::mint_to(
+          accounts.token_program.key,
+          lx_token_mint.key,
+          accounts.target_token_account.key,
+          authority.key,
+          &[],
+          amount,
+        )?,
+        &[
+          lx_token_mint.clone(),
+          accounts.target_token_account.clone(),
+          authority.clone(),
+          accounts.token_program.clone(),
+        ],
+        &[authority_seeds],
+      )
+    } else {
+      let mut burn_accounts = vec![accounts.source_token_account.clone(), lx_token_mint.clone()];
+      burn_accounts.extend(accounts.authority_accounts());
+      burn_accounts.push(accounts.token_program.clone());
+      invoke(
+        &spl_token::instruction::burn(
+          accounts.token_program.key,
+          accounts.source_token_account.key,
+          lx_token_mint.key,
+          accounts.source_authority.key,
+          &accounts.signer_pubkeys(),
+          amount,
+        )?,
+        &burn_accounts,
+      )?;
+      let payout = amount.saturating_sub(mock.withdraw_shortfall);
+      msg!("Paying out {} X for {} lX", payout, amount);
+      invoke_signed(
+        &spl_token::instruction::transfer(
+          accounts.token_program.key,
+          reserve_token_account.key,
+          accounts.target_token_account.key,
+          authority.key,
+          &[],
+          payout,
+        )?,
+        &[
+          reserve_token_account.clone(),
+          accounts.target_token_account.clone(),
+          authority.clone(),
+          accounts.token_program.clone(),
+        ],
+        &[authority_seeds],
+      )
+    }
+  }
+}
+
+impl Strategy for Processor {
+  fn deposit(program_id: &Pubkey, accounts: &TransferAccounts, amount: u64) -> ProgramResult {
+    Self::transfer(program_id, accounts, amount, true)
+  }
+
+  fn withdraw(program_id: &Pubkey, accounts: &TransferAccounts, amount: u64) -> ProgramResult {
+    Self::transfer(program_id, accounts, amount, false)
+  }
+
+  fn estimate_value(
+    program_id: &Pubkey,
+    accounts: &EstimateValueAccounts,
+  ) -> Result<u64, ProgramError> {
+    let account_info_iter = &mut accounts.extra_accounts.iter();
+    let storage_account = next_account_info(account_info_iter)?;
+    let reserve_token_account = next_account_info(account_info_iter)?;
+    let lx_token_mint = next_account_info(account_info_iter)?;
+
+    let mock = Self::unpack_mock(program_id, storage_account)?;
+    if mock.reserve_token_account != *reserve_token_account.key
+      || mock.lx_token_mint != *lx_token_mint.key
+    {
+      return Err(MockStrategyError::AccountInconsistency.into());
+    }
+    if mock.has_flag(FAIL_ESTIMATE) {
+      msg!("Mock configured to fail");
+      return Err(MockStrategyError::ForcedFailure.into());
+    }
+    if mock.has_flag(REENTER) {
+      msg!("Reentering Vault {} with an EstimateValue", accounts.vault_program.key);
+      let mut reenter_accounts = vec![
+        accounts.vault_program.clone(),
+        accounts.shared_memory_account.clone(),
+      ];
+      reenter_accounts.extend(account_info_iter.cloned());
+      invoke(
+        &VaultInstruction::estimate_value(
+          accounts.vault_program.key,
+          accounts.vault_program.key,
+          accounts.shared_memory_account.key,
+          Self::account_metas(&reenter_accounts[2..]),
+        )?,
+        &reenter_accounts,
+      )?;
+    }
+    if let COption::Some(value) = mock.reported_value {
+      msg!("Reporting configured value {}", value);
+      return Ok(value);
+    }
+    let position =
+      spl_token::state::Account::unpack(&accounts.position_token_account.data.borrow())?;
+    let reserve = spl_token::state::Account::unpack(&reserve_token_account.data.borrow())?;
+    let lx_mint = spl_token::state::Mint::unpack(&lx_token_mint.data.borrow())?;
+    if position.mint != mock.lx_token_mint {
+      return Err(MockStrategyError::AccountInconsistency.into());
+    }
+    if lx_mint.supply == 0 {
+      return Ok(0);
+    }
+    Ok((position.amount as u128 * reserve.amount as u128 / lx_mint.supply as u128) as u64)
+  }
+
+  fn process_custom_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+  ) -> ProgramResult {
+    match MockStrategyInstruction::unpack(instruction_data)? {
+      MockStrategyInstruction::Initialize => {
+        msg!("MockStrategyInstruction: Initialize");
+        Self::process_initialize(program_id, accounts)
+      }
+      MockStrategyInstruction::Configure {
+        reported_value,
+        withdraw_shortfall,
+        flags,
+      } => {
+        msg!("MockStrategyInstruction: Configure");
+        Self::process_configure(program_id, accounts, reported_value, withdraw_shortfall, flags)
+      }
+    }
+  }
+}
@@ -0,0 +1,120 @@
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use solana_program::{
+  program_error::ProgramError,
+  program_option::COption,
+  program_pack::{IsInitialized, Pack, Sealed},
+  pubkey::Pubkey,
+};
+
+/// Deposit reverts.
+pub const FAIL_DEPOSIT: u8 = 1;
+/// Withdraw reverts.
+pub const FAIL_WITHDRAW: u8 = 1 << 1;
+/// EstimateValue reverts.
+pub const FAIL_ESTIMATE: u8 = 1 << 2;
+/// Deposit & Withdraw invoke a Vault Withdraw, and EstimateValue a Vault EstimateValue, back into
+/// the calling Vault; see [reenter_account_metas](../instruction/fn.reenter_account_metas.html).
+pub const REENTER: u8 = 1 << 3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MockStrategy {
+  pub is_initialized: bool,
+  /// The only account allowed to reconfigure the mock.
+  pub admin: Pubkey,
+  /// Bump seed of the mock's authority, derived from its storage account.
+  pub authority_bump_seed: u8,
+  /// X token account holding all deposits; owned by the mock's authority.
+  pub reserve_token_account: Pubkey,
+  /// Mint of the mock's lX token; minted by the mock's authority.
+  pub lx_token_mint: Pubkey,
+  /// If set, EstimateValue reports this value instead of the position's share of the reserve.
+  pub reported_value: COption<u64>,
+  /// Number of X tokens withheld from every withdrawal.
+  pub withdraw_shortfall: u64,
+  /// Bitmask of FAIL_* & REENTER flags.
+  pub flags: u8,
+}
+
+impl MockStrategy {
+  pub fn has_flag(&self, flag: u8) -> bool {
+    self.flags & flag != 0
+  }
+}
+
+impl Sealed for MockStrategy {}
+
+impl Pack for MockStrategy {
+  const LEN: usize = 1 + 32 + 1 + 32 + 32 + 9 + 8 + 1;
+  fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+    let src = array_ref![src, 0, MockStrategy::LEN];
+    let (
+      is_initialized,
+      admin,
+      authority_bump_seed,
+      reserve_token_account,
+      lx_token_mint,
+      reported_value,
+      withdraw_shortfall,
+      flags,
+    ) = array_refs![src, 1, 32, 1, 32, 32, 9, 8, 1];
+    let is_initialized = match is_initialized {
+      [0] => false,
+      [1] => true,
+      _ => return Err(ProgramError::InvalidAccountData),
+    };
+    let (reported_value_tag, reported_value) = array_refs![reported_value, 1, 8];
+    let reported_value = match reported_value_tag {
+      [0] => COption::None,
+      [1] => COption::Some(u64::from_le_bytes(*reported_value)),
+      _ => return Err(ProgramError::InvalidAccountData),
+    };
+    Ok(MockStrategy {
+      is_initialized,
+      admin: Pubkey::new_from_array(*admin),
+      authority_bump_seed: authority_bump_seed[0],
+      reserve_token_account: Pubkey::new_from_array(*reserve_token_account),
+      lx_token_mint: Pubkey::new_from_array(*lx_token_mint),
+      reported_value,
+      withdraw_shortfall: u64::from_le_bytes(*withdraw_shortfall),
+      flags: flags[0],
+    })
+  }
+
+  fn pack_into_slice(&self, dst: &mut [u8]) {
+    let dst = array_mut_ref![dst, 0, MockStrategy::LEN];
+    let (
+      is_initialized_dst,
+      admin_dst,
+      authority_bump_seed_dst,
+      reserve_token_account_dst,
+      lx_token_mint_dst,
+      reported_value_dst,
+      withdraw_shortfall_dst,
+      flags_dst,
+    ) = mut_array_refs![dst, 1, 32, 1, 32, 32, 9, 8, 1];
+    is_initialized_dst[0] = self.is_initialized as u8;
+    admin_dst.copy_from_slice(self.admin.as_ref());
+    authority_bump_seed_dst[0] = self.authority_bump_seed;
+    reserve_token_account_dst.copy_from_slice(self.reserve_token_account.as_ref());
+    lx_token_mint_dst.copy_from_slice(self.lx_token_mint.as_ref());
+    let (reported_value_tag_dst, reported_value_body_dst) = mut_array_refs![reported_value_dst, 1, 8];
+    match self.reported_value {
+      COption::Some(value) => {
+        reported_value_tag_dst[0] = 1;
+        reported_value_body_dst.copy_from_slice(&value.to_le_bytes());
+      }
+      COption::None => {
+        reported_value_tag_dst[0] = 0;
+        *reported_value_body_dst = [0; 8];
+      }
+    }
+    withdraw_shortfall_dst.copy_from_slice(&self.withdraw_shortfall.to_le_bytes());
+    flags_dst[0] = self.flags;
+  }
+}
+
+impl IsInitialized for MockStrategy {
+  fn is_initialized(&self) -> bool {
+    self.is_initialized
+  }
+}
@@ -0,0 +1,1139 @@
+//! Rust client for the Vault program.
+//!
+//! [VaultClient] builds unsigned [Transaction]s for the common Vault flows. It derives the Vault's
+//! PDAs and reads Vault storage over RPC to fill in the accounts each instruction expects,
+//! including those of Vaults used as another Vault's strategy. Other strategies' extra accounts
+//! are supplied by the caller.
+//!
+//! Returned transactions carry a recent blockhash; sign them with
+//! `transaction.sign(&signers, transaction.message.recent_blockhash)`.
+use solana_client::{
+    client_error::ClientError,
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+    rpc_request::MAX_MULTIPLE_ACCOUNTS,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    system_instruction,
+};
+use solana_sdk::{account::Account, transaction::Transaction};
+use spl_token::state::{Account as TokenAccount, Mint};
+use std::convert::TryInto;
+use thiserror::Error;
+use vault::{
+    inspect::{self, VaultPreview},
+    instruction::{
+        self, VaultInstruction, STRATEGY_DEPOSIT_INSTRUCTION_ID, STRATEGY_ESTIMATE_INSTRUCTION_ID,
+        STRATEGY_WITHDRAW_INSTRUCTION_ID,
+    },
+    state::{RegisteredVault, Vault, VaultRegistry},
+};
+
+/// Offset of Vault#llx_token_mint_id within Vault storage (after is_initialized & hodl).
+const LLX_TOKEN_MINT_OFFSET: usize = 2;
+
+/// Size of the shared memory account written by EstimateValue.
+const ESTIMATE_LEN: usize = 8;
+
+#[derive(Error, Debug)]
+pub enum VaultClientError {
+    // Boxed as ClientError is large & would bloat every Result.
+    #[error(transparent)]
+    Rpc(Box<ClientError>),
+    #[error(transparent)]
+    Program(#[from] ProgramError),
+    #[error("{0} is not an initialized Vault")]
+    NotAVault(Pubkey),
+    #[error("No Vault issues {0}")]
+    UnknownLlxMint(Pubkey),
+    #[error("{0} does not hold an estimate")]
+    InvalidEstimate(Pubkey),
+}
+
+impl From<ClientError> for VaultClientError {
+    fn from(e: ClientError) -> Self {
+        VaultClientError::Rpc(Box::new(e))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, VaultClientError>;
+
+/// The strategy a new Vault invests in.
+pub enum VaultStrategy {
+    /// Holds X directly.
+    Hodl,
+    /// Deposits into another Vault, identified by its storage account.
+    Vault(Pubkey),
+    /// Deposits into a program implementing the strategy interface.
+    Program {
+        program_id: Pubkey,
+        /// The strategy's derivative (lX) mint.
+        lx_token_mint: Pubkey,
+        deposit_instruction_id: u8,
+        withdraw_instruction_id: u8,
+        estimate_instruction_id: u8,
+    },
+}
+
+pub struct VaultClient {
+    rpc: RpcClient,
+    program_id: Pubkey,
+}
+
+impl VaultClient {
+    /// A client for the Vault deployed at `vault::id()`.
+    pub fn new(rpc: RpcClient) -> Self {
+        Self::with_program_id(rpc, vault::id())
+    }
+
+    pub fn with_program_id(rpc: RpcClient, program_id: Pubkey) -> Self {
+        VaultClient { rpc, program_id }
+    }
+
+    pub fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+
+    pub fn program_id(&self) -> &Pubkey {
+        &self.program_id
+    }
+
+    pub fn get_vault(&self, vault_storage_account: &Pubkey) -> Result<Vault> {
+        let account = self.rpc.get_account(vault_storage_account)?;
+        self.unpack_vault(vault_storage_account, &account)
+    }
+
+    /// Summarizes the Vault and, if given, the position held in `client_llx_token_account`. See
+    /// [inspect](vault::inspect) for the underlying functions.
+    pub fn preview_vault(
+        &self,
+        vault_storage_account: &Pubkey,
+        client_llx_token_account: Option<&Pubkey>,
+    ) -> Result<VaultPreview> {
+        let vault = self.get_vault(vault_storage_account)?;
+        let llx_token_mint = Mint::unpack(&self.rpc.get_account_data(&vault.llx_token_mint_id)?)?;
+        let vault_token_account =
+            TokenAccount::unpack(&self.rpc.get_account_data(&vault.vault_token_account)?)?;
+        let client_llx_token_account = match client_llx_token_account {
+            Some(pubkey) => Some(TokenAccount::unpack(&self.rpc.get_account_data(pubkey)?)?),
+            None => None,
+        };
+        Ok(inspect::preview(
+            &vault,
+            &vault_token_account,
+            &llx_token_mint,
+            client_llx_token_account.as_ref(),
+        ))
+    }
+
+    /// Finds the storage account of the Vault issuing `llx_token_mint`.
+    pub fn find_vault_by_llx_mint(&self, llx_token_mint: &Pubkey) -> Result<Pubkey> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new(
+                LLX_TOKEN_MINT_OFFSET,
+                MemcmpEncodedBytes::Bytes(llx_token_mint.to_bytes().to_vec()),
+            ))]),
+            account_config: RpcAccountInfoConfig::default(),
+            ..RpcProgramAccountsConfig::default()
+        };
+        self.rpc
+            .get_program_accounts_with_config(&self.program_id, config)?
+            .into_iter()
+            .find(|(pubkey, account)| self.unpack_vault(pubkey, account).is_ok())
+            .map(|(pubkey, _)| pubkey)
+            .ok_or(VaultClientError::UnknownLlxMint(*llx_token_mint))
+    }
+
+    /// Creates `vault_storage_account` & initializes a Vault in it, with `payer` as governance, then
+    /// registers it for `x_token_mint`.
+    ///
+    /// Signers: `payer` & `vault_storage_account`.
+    pub fn create_vault(
+        &self,
+        payer: &Pubkey,
+        vault_storage_account: &Pubkey,
+        x_token_mint: &Pubkey,
+        strategy: VaultStrategy,
+        block_deposits_on_loss: bool,
+    ) -> Result<Transaction> {
+        // HODL Vaults ignore the instruction IDs.
+        let (strategy_program, vault_token_mint, hodl, ids) = match strategy {
+            VaultStrategy::Hodl => (self.program_id, *x_token_mint, true, (0, 0, 0)),
+            VaultStrategy::Vault(inner_storage_account) => {
+                let inner = self.get_vault(&inner_storage_account)?;
+                (
+                    self.program_id,
+                    inner.llx_token_mint_id,
+                    false,
+                    (
+                        STRATEGY_DEPOSIT_INSTRUCTION_ID,
+                        STRATEGY_WITHDRAW_INSTRUCTION_ID,
+                        STRATEGY_ESTIMATE_INSTRUCTION_ID,
+                    ),
+                )
+            }
+            VaultStrategy::Program {
+                program_id,
+                lx_token_mint,
+                deposit_instruction_id,
+                withdraw_instruction_id,
+                estimate_instruction_id,
+            } => (
+                program_id,
+                lx_token_mint,
+                false,
+                (
+                    deposit_instruction_id,
+                    withdraw_instruction_id,
+                    estimate_instruction_id,
+                ),
+            ),
+        };
+        let lamports = self
+            .rpc
+            .get_minimum_balance_for_rent_exemption(Vault::LEN)?;
+        self.transaction(
+            payer,
+            &[
+                system_instruction::create_account(
+                    payer,
+                    vault_storage_account,
+                    lamports,
+                    Vault::LEN as u64,
+                    &self.program_id,
+                ),
+                VaultInstruction::initialize_vault(
+                    &self.program_id,
+                    payer,
+                    vault_storage_account,
+                    x_token_mint,
+                    &vault_token_mint,
+                    &spl_token::id(),
+                    &strategy_program,
+                    hodl,
+                    ids.0,
+                    ids.1,
+                    ids.2,
+                    block_deposits_on_loss,
+                )?,
+                VaultInstruction::register_vault(
+                    &self.program_id,
+                    payer,
+                    vault_storage_account,
+                    x_token_mint,
+                )?,
+            ],
+        )
+    }
+
+    /// Retires a Vault's registry entry & removes it from its mint's Vault registry, e.g. when
+    /// shutting it down. The registry's freed rent is refunded to `governance`.
+    ///
+    /// Signers: `governance`, who also pays.
+    pub fn deregister_vault(
+        &self,
+        governance: &Pubkey,
+        vault_storage_account: &Pubkey,
+    ) -> Result<Transaction> {
+        let vault = self.get_vault(vault_storage_account)?;
+        let instruction = VaultInstruction::deregister_vault(
+            &self.program_id,
+            governance,
+            vault_storage_account,
+            &vault.x_token_mint,
+        )?;
+        self.transaction(governance, &[instruction])
+    }
+
+    /// Acknowledges a Vault's recorded losses, unblocking deposits if it blocks them on loss.
+    ///
+    /// Signers: `governance`, who also pays.
+    pub fn acknowledge_loss(
+        &self,
+        governance: &Pubkey,
+        vault_storage_account: &Pubkey,
+    ) -> Result<Transaction> {
+        let instruction = VaultInstruction::acknowledge_loss(
+            &self.program_id,
+            governance,
+            vault_storage_account,
+        )?;
+        self.transaction(governance, &[instruction])
+    }
+
+    /// Sets the fee a Vault keeps from each withdrawal, in basis points of the payout.
+    ///
+    /// Signers: `governance`, who also pays.
+    pub fn set_withdrawal_fee(
+        &self,
+        governance: &Pubkey,
+        vault_storage_account: &Pubkey,
+        fee_bps: u16,
+    ) -> Result<Transaction> {
+        let instruction = VaultInstruction::set_withdrawal_fee(
+            &self.program_id,
+            governance,
+            vault_storage_account,
+            fee_bps,
+        )?;
+        self.transaction(governance, &[instruction])
+    }
+
+    /// Pauses or resumes deposits into a Vault. Withdrawals stay open either way.
+    ///
+    /// Signers: `governance`, who also pays.
+    pub fn set_paused(
+        &self,
+        governance: &Pubkey,
+        vault_storage_account: &Pubkey,
+        paused: bool,
+    ) -> Result<Transaction> {
+        let instruction = VaultInstruction::set_paused(
+            &self.program_id,
+            governance,
+            vault_storage_account,
+            paused,
+        )?;
+        self.transaction(governance, &[instruction])
+    }
+
+    /// The Active Vaults registered for `x_token_mint`, in the order of the mint's Vault registry.
+    /// Retired Vaults leave the registry, but their entries remain at `find_registered_vault`.
+    ///
+    /// Anyone can register a Vault, so vet entries (e.g. their governance & strategy) before
+    /// trusting them.
+    pub fn get_registered_vaults(&self, x_token_mint: &Pubkey) -> Result<Vec<RegisteredVault>> {
+        let (registry_id, _) =
+            VaultInstruction::find_vault_registry(&self.program_id, x_token_mint);
+        let registry = match self
+            .rpc
+            .get_account_with_commitment(&registry_id, self.rpc.commitment())?
+            .value
+        {
+            Some(account) => VaultRegistry::unpack(&account.data)?,
+            None => return Ok(vec![]),
+        };
+        let entries = registry
+            .vaults
+            .iter()
+            .map(|vault_storage_account| {
+                VaultInstruction::find_registered_vault(
+                    &self.program_id,
+                    x_token_mint,
+                    vault_storage_account,
+                )
+                .0
+            })
+            .collect::<Vec<_>>();
+        let mut registered = Vec::with_capacity(entries.len());
+        for chunk in entries.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            for account in self.rpc.get_multiple_accounts(chunk)?.into_iter().flatten() {
+                registered.push(RegisteredVault::unpack(&account.data)?);
+            }
+        }
+        Ok(registered)
+    }
+
+    /// Deposits `amount` X tokens from `client_x_token_account` for at least `min_shares` llX
+    /// tokens.
+    ///
+    /// `strategy_extra_accounts` are the extra accounts of the innermost strategy program, if any.
+    /// Signers: `payer` & `source_authority`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn deposit(
+        &self,
+        payer: &Pubkey,
+        vault_storage_account: &Pubkey,
+        client_x_token_account: &Pubkey,
+        client_llx_token_account: &Pubkey,
+        source_authority: &Pubkey,
+        amount: u64,
+        min_shares: u64,
+        strategy_extra_accounts: Vec<AccountMeta>,
+    ) -> Result<Transaction> {
+        let mut accounts = vec![AccountMeta::new_readonly(*source_authority, true)];
+        accounts
+            .extend(self.transfer_account_metas(vault_storage_account, strategy_extra_accounts)?);
+        let instruction = VaultInstruction::deposit(
+            &self.program_id,
+            &spl_token::id(),
+            client_x_token_account,
+            client_llx_token_account,
+            accounts,
+            amount,
+            0,
+            min_shares,
+        )?;
+        self.transaction(payer, &[instruction])
+    }
+
+    /// Redeems `amount` llX tokens from `client_llx_token_account` for X tokens.
+    ///
+    /// `strategy_extra_accounts` are the extra accounts of the innermost strategy program, if any.
+    /// Signers: `payer` & `source_authority`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn withdraw(
+        &self,
+        payer: &Pubkey,
+        vault_storage_account: &Pubkey,
+        client_llx_token_account: &Pubkey,
+        client_x_token_account: &Pubkey,
+        source_authority: &Pubkey,
+        amount: u64,
+        strategy_extra_accounts: Vec<AccountMeta>,
+    ) -> Result<Transaction> {
+        let mut accounts = vec![AccountMeta::new_readonly(*source_authority, true)];
+        accounts
+            .extend(self.transfer_account_metas(vault_storage_account, strategy_extra_accounts)?);
+        let instruction = VaultInstruction::withdraw(
+            &self.program_id,
+            &spl_token::id(),
+            client_llx_token_account,
+            client_x_token_account,
+            accounts,
+            amount,
+            0,
+        )?;
+        self.transaction(payer, &[instruction])
+    }
+
+    /// Creates `shared_memory_account` & estimates the Vault's value into it; read the result with
+    /// [get_estimate](Self::get_estimate) once the transaction lands.
+    ///
+    /// `strategy_extra_accounts` are the extra accounts of the innermost strategy program, if any.
+    /// Signers: `payer` & `shared_memory_account`.
+    pub fn estimate_value(
+        &self,
+        payer: &Pubkey,
+        vault_storage_account: &Pubkey,
+        shared_memory_account: &Pubkey,
+        strategy_extra_accounts: Vec<AccountMeta>,
+    ) -> Result<Transaction> {
+        let lamports = self
+            .rpc
+            .get_minimum_balance_for_rent_exemption(ESTIMATE_LEN)?;
+        self.transaction(
+            payer,
+            &[
+                system_instruction::create_account(
+                    payer,
+                    shared_memory_account,
+                    lamports,
+                    ESTIMATE_LEN as u64,
+                    &self.program_id,
+                ),
+                VaultInstruction::estimate_value(
+                    &self.program_id,
+                    &self.program_id,
+                    shared_memory_account,
+                    self.estimate_account_metas(vault_storage_account, strategy_extra_accounts)?,
+                )?,
+            ],
+        )
+    }
+
+    /// Reads the value written by [estimate_value](Self::estimate_value).
+    pub fn get_estimate(&self, shared_memory_account: &Pubkey) -> Result<u64> {
+        let data = self.rpc.get_account_data(shared_memory_account)?;
+        data.get(..ESTIMATE_LEN)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(VaultClientError::InvalidEstimate(*shared_memory_account))
+    }
+
+    /// Accounts 6+ of Deposit & Withdraw: the Vault's accounts followed by its strategy's.
+    pub fn transfer_account_metas(
+        &self,
+        vault_storage_account: &Pubkey,
+        strategy_extra_accounts: Vec<AccountMeta>,
+    ) -> Result<Vec<AccountMeta>> {
+        let vault = self.get_vault(vault_storage_account)?;
+        let strategy_extra_accounts = if vault.hodl {
+            // HODL Vaults take no strategy accounts.
+            vec![]
+        } else if vault.strategy_program_id == self.program_id {
+            let inner_storage_account = self.find_vault_by_llx_mint(&vault.lx_token_mint)?;
+            self.transfer_account_metas(&inner_storage_account, strategy_extra_accounts)?
+        } else {
+            strategy_extra_accounts
+        };
+        Ok(instruction::transfer_account_metas(
+            &self.program_id,
+            vault_storage_account,
+            &vault,
+            strategy_extra_accounts,
+        ))
+    }
+
+    /// Accounts 3+ of EstimateValue.
+    pub fn estimate_account_metas(
+        &self,
+        vault_storage_account: &Pubkey,
+        strategy_extra_accounts: Vec<AccountMeta>,
+    ) -> Result<Vec<AccountMeta>> {
+        let vault = self.get_vault(vault_storage_account)?;
+        Ok(instruction::estimate_value_account_metas(
+            vault_storage_account,
+            &vault,
+            self.strategy_estimate_account_metas(
+                vault_storage_account,
+                &vault,
+                strategy_extra_accounts,
+            )?,
+        ))
+    }
+
+    /// The strategy extra accounts `vault` passes to its strategy's EstimateValue: those of
+    /// EstimatePositionValue if it invests in another Vault, otherwise `strategy_extra_accounts`.
+    fn strategy_estimate_account_metas(
+        &self,
+        vault_storage_account: &Pubkey,
+        vault: &Vault,
+        strategy_extra_accounts: Vec<AccountMeta>,
+    ) -> Result<Vec<AccountMeta>> {
+        if vault.hodl || vault.strategy_program_id != self.program_id {
+            return Ok(strategy_extra_accounts);
+        }
+        let inner_storage_account = self.find_vault_by_llx_mint(&vault.lx_token_mint)?;
+        let inner = self.get_vault(&inner_storage_account)?;
+        Ok(instruction::estimate_position_value_account_metas(
+            vault_storage_account,
+            &inner_storage_account,
+            &inner,
+            self.strategy_estimate_account_metas(
+                &inner_storage_account,
+                &inner,
+                strategy_extra_accounts,
+            )?,
+        ))
+    }
+
+    fn unpack_vault(&self, vault_storage_account: &Pubkey, account: &Account) -> Result<Vault> {
+        if account.owner != self.program_id {
+            return Err(VaultClientError::NotAVault(*vault_storage_account));
+        }
+        match Vault::unpack_unchecked(&account.data) {
+            Ok(vault) if vault.is_initialized() => Ok(vault),
+            _ => Err(VaultClientError::NotAVault(*vault_storage_account)),
+        }
+    }
+
+    fn transaction(&self, payer: &Pubkey, instructions: &[Instruction]) -> Result<Transaction> {
+        let mut transaction = Transaction::new_with_payer(instructions, Some(payer));
+        transaction.message.recent_blockhash = self.rpc.get_latest_blockhash()?;
+        Ok(transaction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+    use solana_account_decoder::{UiAccount, UiAccountEncoding};
+    use solana_client::{
+        client_error::Result as ClientResult,
+        rpc_client::RpcClientConfig,
+        rpc_request::RpcRequest,
+        rpc_response::{
+            Response, RpcBlockhash, RpcKeyedAccount, RpcResponseContext, RpcVersionInfo,
+        },
+        rpc_sender::{RpcSender, RpcTransportStats},
+    };
+    use solana_sdk::{account::AccountSharedData, commitment_config::CommitmentConfig, hash::Hash};
+    use std::str::FromStr;
+    use vault::state::VaultStatus;
+
+    /// The blockhash & rent-exempt balance returned by the mock RPC node.
+    const MOCK_BLOCKHASH: &str = "7RoSF9fUmdphVCpabEoefH81WwrW7orsWonXWqTXkKV8";
+    const MOCK_RENT: u64 = 20;
+
+    /// An RPC node holding `accounts`, answering the requests VaultClient makes.
+    struct MockRpc {
+        accounts: Vec<(Pubkey, Account)>,
+    }
+
+    #[async_trait]
+    impl RpcSender for MockRpc {
+        async fn send(&self, request: RpcRequest, params: Value) -> ClientResult<Value> {
+            let context = RpcResponseContext {
+                slot: 1,
+                api_version: None,
+            };
+            let pubkey = |value: &Value| Pubkey::from_str(value.as_str().unwrap()).unwrap();
+            let encode = |(pubkey, account): &(Pubkey, Account)| {
+                UiAccount::encode(pubkey, account, UiAccountEncoding::Base64, None, None)
+            };
+            Ok(match request {
+                RpcRequest::GetAccountInfo => {
+                    let pubkey = pubkey(&params[0]);
+                    let value = self.accounts.iter().find(|(key, _)| *key == pubkey);
+                    json!(Response {
+                        context,
+                        value: value.map(encode),
+                    })
+                }
+                RpcRequest::GetMultipleAccounts => {
+                    let value: Vec<_> = params[0]
+                        .as_array()
+                        .unwrap()
+                        .iter()
+                        .map(|key| {
+                            let pubkey = pubkey(key);
+                            self.accounts
+                                .iter()
+                                .find(|(key, _)| *key == pubkey)
+                                .map(encode)
+                        })
+                        .collect();
+                    json!(Response { context, value })
+                }
+                RpcRequest::GetProgramAccounts => {
+                    let program_id = pubkey(&params[0]);
+                    let config: RpcProgramAccountsConfig =
+                        serde_json::from_value(params[1].clone()).unwrap();
+                    let filters = config.filters.unwrap_or_default();
+                    let accounts: Vec<_> = self
+                        .accounts
+                        .iter()
+                        .filter(|(_, account)| {
+                            let shared = AccountSharedData::from(account.clone());
+                            account.owner == program_id
+                                && filters.iter().all(|filter| filter.allows(&shared))
+                        })
+                        .map(|keyed_account| RpcKeyedAccount {
+                            pubkey: keyed_account.0.to_string(),
+                            account: encode(keyed_account),
+                        })
+                        .collect();
+                    json!(accounts)
+                }
+                RpcRequest::GetLatestBlockhash => json!(Response {
+                    context,
+                    value: RpcBlockhash {
+                        blockhash: MOCK_BLOCKHASH.to_string(),
+                        last_valid_block_height: 1,
+                    },
+                }),
+                RpcRequest::GetMinimumBalanceForRentExemption => json!(MOCK_RENT),
+                RpcRequest::GetVersion => json!(RpcVersionInfo {
+                    solana_core: "1.9.0".to_string(),
+                    feature_set: None,
+                }),
+                _ => unimplemented!("{}", request),
+            })
+        }
+
+        fn get_transport_stats(&self) -> RpcTransportStats {
+            RpcTransportStats::default()
+        }
+
+        fn url(&self) -> String {
+            "mock".to_string()
+        }
+    }
+
+    /// A client whose RPC node holds `accounts`.
+    fn client(accounts: Vec<(Pubkey, Account)>) -> VaultClient {
+        let rpc = RpcClient::new_sender(
+            MockRpc { accounts },
+            RpcClientConfig::with_commitment(CommitmentConfig::default()),
+        );
+        VaultClient::new(rpc)
+    }
+
+    fn hodl_vault(storage: &Pubkey, x_token_mint: &Pubkey) -> Vault {
+        let program_id = vault::id();
+        Vault {
+            is_initialized: true,
+            hodl: true,
+            llx_token_mint_id: VaultInstruction::find_llx_token_mint(&program_id, storage).0,
+            x_token_mint: *x_token_mint,
+            lx_token_mint: *x_token_mint,
+            vault_token_account: VaultInstruction::find_vault_token_account(&program_id, storage).0,
+            last_estimated_value: 0,
+            strategy_program_id: program_id,
+            strategy_program_deposit_instruction_id: 0,
+            strategy_program_withdraw_instruction_id: 0,
+            strategy_program_estimate_instruction_id: 0,
+            strategy_data_account: None,
+            governance: Pubkey::new_unique(),
+            total_debt: 0,
+            total_loss: 0,
+            unacknowledged_loss: 0,
+            block_deposits_on_loss: false,
+            withdrawal_fee_bps: 0,
+            paused: false,
+        }
+    }
+
+    /// A Vault in `storage` investing in `strategy_program_id`, whose derivative is `lx_token_mint`.
+    fn strategy_vault(
+        storage: &Pubkey,
+        x_token_mint: &Pubkey,
+        strategy_program_id: &Pubkey,
+        lx_token_mint: &Pubkey,
+    ) -> Vault {
+        Vault {
+            hodl: false,
+            lx_token_mint: *lx_token_mint,
+            strategy_program_id: *strategy_program_id,
+            strategy_program_deposit_instruction_id: STRATEGY_DEPOSIT_INSTRUCTION_ID,
+            strategy_program_withdraw_instruction_id: STRATEGY_WITHDRAW_INSTRUCTION_ID,
+            strategy_program_estimate_instruction_id: STRATEGY_ESTIMATE_INSTRUCTION_ID,
+            ..hodl_vault(storage, x_token_mint)
+        }
+    }
+
+    fn vault_account(vault: &Vault, owner: &Pubkey) -> Account {
+        let mut data = vec![0; Vault::LEN];
+        Vault::pack(*vault, &mut data).unwrap();
+        Account {
+            lamports: MOCK_RENT,
+            data,
+            owner: *owner,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    fn expected(payer: &Pubkey, instructions: &[Instruction]) -> Transaction {
+        let mut transaction = Transaction::new_with_payer(instructions, Some(payer));
+        transaction.message.recent_blockhash = Hash::from_str(MOCK_BLOCKHASH).unwrap();
+        transaction
+    }
+
+    #[test]
+    fn test_create_hodl_vault() {
+        let (payer, storage, x_token_mint) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let program_id = vault::id();
+        let transaction = client(vec![])
+            .create_vault(&payer, &storage, &x_token_mint, VaultStrategy::Hodl, true)
+            .unwrap();
+        assert_eq!(
+            transaction,
+            expected(
+                &payer,
+                &[
+                    system_instruction::create_account(
+                        &payer,
+                        &storage,
+                        MOCK_RENT,
+                        Vault::LEN as u64,
+                        &program_id,
+                    ),
+                    VaultInstruction::initialize_vault(
+                        &program_id,
+                        &payer,
+                        &storage,
+                        &x_token_mint,
+                        &x_token_mint,
+                        &spl_token::id(),
+                        &program_id,
+                        true,
+                        0,
+                        0,
+                        0,
+                        true,
+                    )
+                    .unwrap(),
+                    VaultInstruction::register_vault(&program_id, &payer, &storage, &x_token_mint)
+                        .unwrap(),
+                ],
+            )
+        );
+    }
+
+    #[test]
+    fn test_create_nested_vault() {
+        let (payer, storage, inner_storage, x_token_mint) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let program_id = vault::id();
+        let inner = hodl_vault(&inner_storage, &x_token_mint);
+        let transaction = client(vec![(inner_storage, vault_account(&inner, &program_id))])
+            .create_vault(
+                &payer,
+                &storage,
+                &x_token_mint,
+                VaultStrategy::Vault(inner_storage),
+                false,
+            )
+            .unwrap();
+        assert_eq!(
+            transaction.message.instructions[1].data,
+            VaultInstruction::initialize_vault(
+                &program_id,
+                &payer,
+                &storage,
+                &x_token_mint,
+                &inner.llx_token_mint_id,
+                &spl_token::id(),
+                &program_id,
+                false,
+                STRATEGY_DEPOSIT_INSTRUCTION_ID,
+                STRATEGY_WITHDRAW_INSTRUCTION_ID,
+                STRATEGY_ESTIMATE_INSTRUCTION_ID,
+                false,
+            )
+            .unwrap()
+            .data
+        );
+    }
+
+    #[test]
+    fn test_hodl_transfers() {
+        let (payer, storage, x_token_mint) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let (client_x, client_llx) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let program_id = vault::id();
+        let vault = hodl_vault(&storage, &x_token_mint);
+        let accounts = || vec![(storage, vault_account(&vault, &program_id))];
+        let mut metas = vec![AccountMeta::new_readonly(payer, true)];
+        metas.extend(instruction::transfer_account_metas(
+            &program_id,
+            &storage,
+            &vault,
+            vec![],
+        ));
+        // HODL Vaults take no strategy extra accounts, so any passed are dropped.
+        let extra = || vec![AccountMeta::new(Pubkey::new_unique(), false)];
+
+        let deposit = client(accounts())
+            .deposit(
+                &payer,
+                &storage,
+                &client_x,
+                &client_llx,
+                &payer,
+                5,
+                4,
+                extra(),
+            )
+            .unwrap();
+        let expected_deposit = VaultInstruction::deposit(
+            &program_id,
+            &spl_token::id(),
+            &client_x,
+            &client_llx,
+            metas.clone(),
+            5,
+            0,
+            4,
+        )
+        .unwrap();
+        assert_eq!(deposit, expected(&payer, &[expected_deposit]));
+
+        let withdraw = client(accounts())
+            .withdraw(&payer, &storage, &client_llx, &client_x, &payer, 5, extra())
+            .unwrap();
+        let expected_withdraw = VaultInstruction::withdraw(
+            &program_id,
+            &spl_token::id(),
+            &client_llx,
+            &client_x,
+            metas,
+            5,
+            0,
+        )
+        .unwrap();
+        assert_eq!(withdraw, expected(&payer, &[expected_withdraw]));
+    }
+
+    #[test]
+    fn test_strategy_account_metas() {
+        let (storage, x_token_mint, strategy_program, lx_token_mint) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let program_id = vault::id();
+        let vault = strategy_vault(&storage, &x_token_mint, &strategy_program, &lx_token_mint);
+        let client = client(vec![(storage, vault_account(&vault, &program_id))]);
+        let extra = vec![AccountMeta::new(Pubkey::new_unique(), false)];
+        let (vault_authority, _) = VaultInstruction::find_vault_authority(&program_id, &storage);
+
+        // Other strategies' extra accounts are passed through after the Vault's own.
+        let mut expected_transfer_metas = vec![
+            AccountMeta::new(storage, false),
+            AccountMeta::new_readonly(strategy_program, false),
+            AccountMeta::new(vault.llx_token_mint_id, false),
+            AccountMeta::new_readonly(vault_authority, false),
+            AccountMeta::new(vault.vault_token_account, false),
+        ];
+        expected_transfer_metas.extend(extra.clone());
+        assert_eq!(
+            client
+                .transfer_account_metas(&storage, extra.clone())
+                .unwrap(),
+            expected_transfer_metas
+        );
+        let mut expected_estimate_metas = vec![
+            AccountMeta::new(storage, false),
+            AccountMeta::new_readonly(vault.vault_token_account, false),
+            AccountMeta::new_readonly(strategy_program, false),
+        ];
+        expected_estimate_metas.extend(extra.clone());
+        assert_eq!(
+            client.estimate_account_metas(&storage, extra).unwrap(),
+            expected_estimate_metas
+        );
+    }
+
+    #[test]
+    fn test_nested_account_metas() {
+        let (storage, inner_storage, x_token_mint) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let program_id = vault::id();
+        let inner = hodl_vault(&inner_storage, &x_token_mint);
+        let outer = strategy_vault(
+            &storage,
+            &x_token_mint,
+            &program_id,
+            &inner.llx_token_mint_id,
+        );
+        let client = client(vec![
+            (storage, vault_account(&outer, &program_id)),
+            (inner_storage, vault_account(&inner, &program_id)),
+        ]);
+        assert_eq!(
+            client
+                .find_vault_by_llx_mint(&inner.llx_token_mint_id)
+                .unwrap(),
+            inner_storage
+        );
+        assert!(matches!(
+            client.find_vault_by_llx_mint(&x_token_mint),
+            Err(VaultClientError::UnknownLlxMint(_))
+        ));
+
+        // Transfers pass the inner Vault's accounts as the outer Vault's strategy extra accounts.
+        let (outer_authority, _) = VaultInstruction::find_vault_authority(&program_id, &storage);
+        let (inner_authority, _) =
+            VaultInstruction::find_vault_authority(&program_id, &inner_storage);
+        assert_eq!(
+            client.transfer_account_metas(&storage, vec![]).unwrap(),
+            vec![
+                AccountMeta::new(storage, false),
+                AccountMeta::new_readonly(program_id, false),
+                AccountMeta::new(outer.llx_token_mint_id, false),
+                AccountMeta::new_readonly(outer_authority, false),
+                AccountMeta::new(outer.vault_token_account, false),
+                AccountMeta::new(inner_storage, false),
+                AccountMeta::new_readonly(program_id, false),
+                AccountMeta::new(inner.llx_token_mint_id, false),
+                AccountMeta::new_readonly(inner_authority, false),
+                AccountMeta::new(inner.vault_token_account, false),
+            ]
+        );
+
+        // Estimates value the outer Vault's position via the inner Vault's EstimatePositionValue.
+        assert_eq!(
+            client.estimate_account_metas(&storage, vec![]).unwrap(),
+            vec![
+                AccountMeta::new(storage, false),
+                AccountMeta::new_readonly(outer.vault_token_account, false),
+                AccountMeta::new_readonly(program_id, false),
+                AccountMeta::new_readonly(storage, false),
+                AccountMeta::new_readonly(inner.llx_token_mint_id, false),
+                AccountMeta::new(inner_storage, false),
+                AccountMeta::new_readonly(inner.vault_token_account, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_acknowledge_loss() {
+        let (governance, storage) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let transaction = client(vec![])
+            .acknowledge_loss(&governance, &storage)
+            .unwrap();
+        assert_eq!(
+            transaction,
+            expected(
+                &governance,
+                &[
+                    VaultInstruction::acknowledge_loss(&vault::id(), &governance, &storage)
+                        .unwrap()
+                ],
+            )
+        );
+    }
+
+    #[test]
+    fn test_governance_settings() {
+        let (governance, storage) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let client = client(vec![]);
+        assert_eq!(
+            client
+                .set_withdrawal_fee(&governance, &storage, 50)
+                .unwrap(),
+            expected(
+                &governance,
+                &[
+                    VaultInstruction::set_withdrawal_fee(&vault::id(), &governance, &storage, 50)
+                        .unwrap()
+                ],
+            )
+        );
+        assert_eq!(
+            client.set_paused(&governance, &storage, true).unwrap(),
+            expected(
+                &governance,
+                &[
+                    VaultInstruction::set_paused(&vault::id(), &governance, &storage, true)
+                        .unwrap()
+                ],
+            )
+        );
+    }
+
+    #[test]
+    fn test_hodl_estimate_value() {
+        let (payer, storage, shared_memory) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let program_id = vault::id();
+        let vault = hodl_vault(&storage, &Pubkey::new_unique());
+        let transaction = client(vec![(storage, vault_account(&vault, &program_id))])
+            .estimate_value(&payer, &storage, &shared_memory, vec![])
+            .unwrap();
+        assert_eq!(
+            transaction,
+            expected(
+                &payer,
+                &[
+                    system_instruction::create_account(
+                        &payer,
+                        &shared_memory,
+                        MOCK_RENT,
+                        ESTIMATE_LEN as u64,
+                        &program_id,
+                    ),
+                    VaultInstruction::estimate_value(
+                        &program_id,
+                        &program_id,
+                        &shared_memory,
+                        instruction::estimate_value_account_metas(&storage, &vault, vec![]),
+                    )
+                    .unwrap(),
+                ],
+            )
+        );
+    }
+
+    #[test]
+    fn test_get_vault() {
+        let storage = Pubkey::new_unique();
+        let vault = hodl_vault(&storage, &Pubkey::new_unique());
+        let account = vault_account(&vault, &vault::id());
+        assert_eq!(
+            client(vec![(storage, account)])
+                .get_vault(&storage)
+                .unwrap(),
+            vault
+        );
+
+        // Accounts owned by other programs, or uninitialized, aren't Vaults.
+        let account = vault_account(&vault, &spl_token::id());
+        assert!(matches!(
+            client(vec![(storage, account)]).get_vault(&storage),
+            Err(VaultClientError::NotAVault(pubkey)) if pubkey == storage
+        ));
+        let uninitialized = Vault {
+            is_initialized: false,
+            ..vault
+        };
+        let account = vault_account(&uninitialized, &vault::id());
+        assert!(matches!(
+            client(vec![(storage, account)]).get_vault(&storage),
+            Err(VaultClientError::NotAVault(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_registered_vaults() {
+        let x_token_mint = Pubkey::new_unique();
+        let program_account = |data| Account {
+            lamports: MOCK_RENT,
+            data,
+            owner: vault::id(),
+            executable: false,
+            rent_epoch: 0,
+        };
+        let registered = RegisteredVault {
+            x_token_mint,
+            vault_storage_account: Pubkey::new_unique(),
+            strategy_program_id: vault::id(),
+            hodl: true,
+            status: VaultStatus::Active,
+        };
+        let mut data = vec![0; RegisteredVault::LEN];
+        registered.pack(&mut data).unwrap();
+        let (entry, _) = VaultInstruction::find_registered_vault(
+            &vault::id(),
+            &x_token_mint,
+            &registered.vault_storage_account,
+        );
+        let registry = VaultRegistry {
+            x_token_mint,
+            vaults: vec![registered.vault_storage_account],
+        };
+        let mut registry_data = vec![0; VaultRegistry::space(1)];
+        registry.pack(&mut registry_data).unwrap();
+        let (registry_id, _) = VaultInstruction::find_vault_registry(&vault::id(), &x_token_mint);
+        let client = client(vec![
+            (entry, program_account(data)),
+            (registry_id, program_account(registry_data)),
+        ]);
+        assert_eq!(
+            client.get_registered_vaults(&x_token_mint).unwrap(),
+            vec![registered]
+        );
+        assert_eq!(
+            client.get_registered_vaults(&Pubkey::new_unique()).unwrap(),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_get_estimate() {
+        let shared_memory = Pubkey::new_unique();
+        let account = |data: Vec<u8>| Account {
+            lamports: MOCK_RENT,
+            data,
+            owner: vault::id(),
+            executable: false,
+            rent_epoch: 0,
+        };
+        let estimate = client(vec![(shared_memory, account(42u64.to_le_bytes().to_vec()))])
+            .get_estimate(&shared_memory)
+            .unwrap();
+        assert_eq!(estimate, 42);
+        assert!(matches!(
+            client(vec![(shared_memory, account(vec![1, 2, 3]))]).get_estimate(&shared_memory),
+            Err(VaultClientError::InvalidEstimate(_))
+        ));
+    }
+}
@@ -1,8 +1,10 @@
+use num_enum::TryFromPrimitive;
 use thiserror::Error;
 
 use solana_program::program_error::ProgramError;
 
-#[derive(Error, Debug, Copy, Clone)]
+#[derive(Error, Debug, Copy, Clone, TryFromPrimitive)]
+#[repr(u32)]
 pub enum VaultError {
     #[error("Invalid Instruction")]
     InvalidInstruction,
@@ -14,6 +16,26 @@ pub enum VaultError {
     ForcedCrash,
     #[error("Account inconsistency - possible setup failure")]
     AccountInconsistency,
+    #[error("Strategy did not return an estimated value")]
+    MissingStrategyEstimate,
+    #[error("Deposits are blocked until governance acknowledges the reported loss")]
+    DepositsBlockedOnLoss,
+    #[error("Invalid authority")]
+    InvalidAuthority,
+    #[error("Arithmetic overflow")]
+    Overflow,
+    #[error("Received less than the minimum amount")]
+    ExceededSlippage,
+    #[error("Vault is already registered")]
+    AlreadyRegistered,
+    #[error("Vault is not registered")]
+    NotRegistered,
+    #[error("Vault has llX tokens outstanding but no assets")]
+    NoAssets,
+    #[error("Deposits are paused")]
+    Paused,
+    #[error("Fee exceeds the maximum")]
+    InvalidFee,
 }
 
 impl From<VaultError> for ProgramError {
@@ -21,3 +43,14 @@ impl From<VaultError> for ProgramError {
         ProgramError::Custom(e as u32)
     }
 }
+
+/// Errors decoding [VaultEvent](../events/enum.VaultEvent.html)s off-chain.
+#[derive(Error, Debug)]
+pub enum EventError {
+    #[error("Vault event data isn't valid base64")]
+    InvalidBase64,
+    #[error("Invalid Vault event: {0}")]
+    InvalidEvent(#[from] std::io::Error),
+    #[error("Program logs don't follow the invoke stack")]
+    UnbalancedLogs,
+}
@@ -0,0 +1,321 @@
+//! Machine-readable records of Vault state changes.
+//!
+//! Each event is Borsh-encoded & logged once via `sol_log_data`, i.e. as a
+//! `Program data: <base64>` log line. Indexers should decode a transaction's events with
+//! [VaultEvent::from_logs], which only accepts lines logged while the Vault program is executing:
+//! any program can log bytes that look like a Vault event.
+//!
+//! Harvests are recorded by `Estimate`, as EstimateValue is the Vault's harvest. Withdrawal fees
+//! are recorded on each `Withdraw`, and governance's config changes by their own events.
+use crate::error::EventError;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use solana_program::{log::sol_log_data, pubkey::Pubkey};
+
+/// Prefix of log lines written by `sol_log_data`.
+pub const LOG_PREFIX: &str = "Program data: ";
+
+/// Prefix of the runtime's `Program <id> invoke [<depth>]`, `Program <id> success` &
+/// `Program <id> failed: <error>` log lines.
+const PROGRAM_PREFIX: &str = "Program ";
+
+/// Fixed-point scale of `price_per_share`: the X value of one llX token is
+/// `price_per_share / PRICE_PER_SHARE_SCALE`.
+pub const PRICE_PER_SHARE_SCALE: u64 = 1_000_000_000;
+
+/// Events are Borsh-encoded: a one byte tag (the discriminant below) followed by the variant's
+/// fields. See client/src/vault_schema.json for the machine-readable layout.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+#[borsh(use_discriminant = true)]
+#[repr(u8)]
+pub enum VaultEvent {
+    /// A Vault was created.
+    Initialize {
+        vault: Pubkey,
+        governance: Pubkey,
+        strategy_program: Pubkey,
+        x_token_mint: Pubkey,
+        lx_token_mint: Pubkey,
+        llx_token_mint: Pubkey,
+        hodl: bool,
+        block_deposits_on_loss: bool,
+    } = 0,
+    /// X tokens were deposited in exchange for `shares` llX tokens.
+    Deposit {
+        vault: Pubkey,
+        authority: Pubkey,
+        /// Owner of the llX account the shares were minted to.
+        recipient: Pubkey,
+        amount: u64,
+        shares: u64,
+        price_per_share: u64,
+    } = 1,
+    /// `shares` llX tokens were burned in exchange for `amount` of the Vault's tokens: X for HODL
+    /// Vaults, otherwise lX which is redeemed via the strategy unless `in_kind`.
+    Withdraw {
+        vault: Pubkey,
+        authority: Pubkey,
+        /// Owner of the account the withdrawal was paid to.
+        recipient: Pubkey,
+        amount: u64,
+        /// The withdrawal fee kept by the Vault, in the same tokens as `amount`.
+        fee: u64,
+        shares: u64,
+        price_per_share: u64,
+        in_kind: bool,
+    } = 2,
+    /// The Vault's holdings were valued at `value` X, recognizing `loss` against its debt.
+    Estimate {
+        vault: Pubkey,
+        value: u64,
+        total_debt: u64,
+        loss: u64,
+    } = 3,
+    /// Governance acknowledged `amount` of recorded losses.
+    AcknowledgeLoss {
+        vault: Pubkey,
+        governance: Pubkey,
+        amount: u64,
+    } = 4,
+    /// Governance registered, or reactivated, the Vault for its X mint.
+    Register {
+        vault: Pubkey,
+        governance: Pubkey,
+        x_token_mint: Pubkey,
+    } = 5,
+    /// Governance retired the Vault's registry entry.
+    Deregister {
+        vault: Pubkey,
+        governance: Pubkey,
+        x_token_mint: Pubkey,
+    } = 6,
+    /// Governance set the withdrawal fee.
+    SetWithdrawalFee {
+        vault: Pubkey,
+        governance: Pubkey,
+        fee_bps: u16,
+    } = 7,
+    /// Governance paused or resumed deposits.
+    SetPaused {
+        vault: Pubkey,
+        governance: Pubkey,
+        paused: bool,
+    } = 8,
+}
+
+impl VaultEvent {
+    /// The X value of one llX token, scaled by [PRICE_PER_SHARE_SCALE].
+    pub fn price_per_share(total_assets: u64, llx_supply: u64) -> u64 {
+        if llx_supply == 0 {
+            return PRICE_PER_SHARE_SCALE;
+        }
+        let price = total_assets as u128 * PRICE_PER_SHARE_SCALE as u128 / llx_supply as u128;
+        price.min(u64::MAX as u128) as u64
+    }
+
+    /// Logs the event.
+    pub fn emit(&self) {
+        sol_log_data(&[&self.pack()]);
+    }
+
+    /// Decodes the events the Vault program `program_id` logged in a transaction's `logs`,
+    /// including those logged under CPI. Lines logged by other programs are skipped, as are
+    /// events logged by any invocation that failed, since its state changes were rolled back.
+    pub fn from_logs<S: AsRef<str>>(
+        program_id: &Pubkey,
+        logs: &[S],
+    ) -> Result<Vec<Self>, EventError> {
+        let program_id = program_id.to_string();
+        // Each invocation's program & the events logged under it, kept until it succeeds.
+        let mut invoke_stack: Vec<(&str, Vec<Self>)> = Vec::new();
+        let mut events = Vec::new();
+        for log in logs {
+            let log = log.as_ref();
+            if let Some(data) = log.strip_prefix(LOG_PREFIX) {
+                match invoke_stack.last_mut() {
+                    Some((id, frame_events)) if *id == program_id => {
+                        let data = STANDARD
+                            .decode(data)
+                            .map_err(|_| EventError::InvalidBase64)?;
+                        frame_events.push(Self::unpack(&data)?);
+                    }
+                    _ => {}
+                }
+            } else if let Some(rest) = log.strip_prefix(PROGRAM_PREFIX) {
+                let mut words = rest.split(' ');
+                match (words.next(), words.next()) {
+                    (Some(id), Some("invoke")) => invoke_stack.push((id, Vec::new())),
+                    (Some(id), Some(outcome)) if outcome == "success" || outcome == "failed:" => {
+                        let (_, frame_events) = invoke_stack
+                            .pop()
+                            .filter(|(invoked, _)| *invoked == id)
+                            .ok_or(EventError::UnbalancedLogs)?;
+                        if outcome == "success" {
+                            match invoke_stack.last_mut() {
+                                Some((_, caller_events)) => caller_events.extend(frame_events),
+                                None => events.extend(frame_events),
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        borsh::to_vec(self).unwrap()
+    }
+
+    pub fn unpack(input: &[u8]) -> Result<Self, EventError> {
+        Ok(Self::try_from_slice(input)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events() -> Vec<VaultEvent> {
+        let key = |n| Pubkey::new_from_array([n; 32]);
+        vec![
+            VaultEvent::Initialize {
+                vault: key(1),
+                governance: key(2),
+                strategy_program: key(3),
+                x_token_mint: key(4),
+                lx_token_mint: key(5),
+                llx_token_mint: key(6),
+                hodl: true,
+                block_deposits_on_loss: false,
+            },
+            VaultEvent::Deposit {
+                vault: key(1),
+                authority: key(2),
+                recipient: key(3),
+                amount: 10,
+                shares: 9,
+                price_per_share: PRICE_PER_SHARE_SCALE,
+            },
+            VaultEvent::Withdraw {
+                vault: key(1),
+                authority: key(2),
+                recipient: key(3),
+                amount: 10,
+                fee: 1,
+                shares: 9,
+                price_per_share: u64::MAX,
+                in_kind: true,
+            },
+            VaultEvent::Estimate {
+                vault: key(1),
+                value: 5,
+                total_debt: 7,
+                loss: 2,
+            },
+            VaultEvent::AcknowledgeLoss {
+                vault: key(1),
+                governance: key(2),
+                amount: 2,
+            },
+            VaultEvent::Register {
+                vault: key(1),
+                governance: key(2),
+                x_token_mint: key(3),
+            },
+            VaultEvent::Deregister {
+                vault: key(1),
+                governance: key(2),
+                x_token_mint: key(3),
+            },
+            VaultEvent::SetWithdrawalFee {
+                vault: key(1),
+                governance: key(2),
+                fee_bps: 50,
+            },
+            VaultEvent::SetPaused {
+                vault: key(1),
+                governance: key(2),
+                paused: true,
+            },
+        ]
+    }
+
+    fn data_log(event: &VaultEvent) -> String {
+        format!("{}{}", LOG_PREFIX, STANDARD.encode(event.pack()))
+    }
+
+    #[test]
+    fn test_pack_unpack() {
+        for (tag, event) in events().iter().enumerate() {
+            let data = event.pack();
+            assert_eq!(data[0] as usize, tag);
+            assert_eq!(VaultEvent::unpack(&data).unwrap(), *event);
+            assert!(VaultEvent::unpack(&data[..data.len() - 1]).is_err());
+            let mut trailing = data.clone();
+            trailing.push(0);
+            assert!(VaultEvent::unpack(&trailing).is_err());
+        }
+        assert!(VaultEvent::unpack(&[]).is_err());
+        assert!(VaultEvent::unpack(&[9]).is_err());
+    }
+
+    #[test]
+    fn test_from_logs() {
+        let vault = Pubkey::new_from_array([7; 32]);
+        let other = Pubkey::new_from_array([8; 32]);
+        let events = events();
+        let logs = vec![
+            // Logged before any program runs, e.g. by a malformed log stream.
+            data_log(&events[0]),
+            format!("Program {} invoke [1]", other),
+            // Another program logging a Vault event isn't the Vault.
+            data_log(&events[1]),
+            format!("Program {} invoke [2]", vault),
+            "Program log: Instruction: Deposit".to_string(),
+            data_log(&events[2]),
+            format!("Program {} consumed 100 of 200000 compute units", vault),
+            format!("Program return: {} AQ==", vault),
+            format!("Program {} success", vault),
+            data_log(&events[3]),
+            format!("Program {} success", other),
+            format!("Program {} invoke [1]", vault),
+            data_log(&events[4]),
+            format!("Program {} failed: custom program error: 0x1", vault),
+        ];
+        // events[4] was rolled back with the failed invocation.
+        assert_eq!(
+            VaultEvent::from_logs(&vault, &logs).unwrap(),
+            vec![events[2].clone()]
+        );
+        assert_eq!(
+            VaultEvent::from_logs(&other, &logs).unwrap(),
+            vec![events[1].clone(), events[3].clone()]
+        );
+
+        // A successful CPI is still rolled back if its caller fails.
+        let rolled_back = [
+            format!("Program {} invoke [1]", other),
+            format!("Program {} invoke [2]", vault),
+            data_log(&events[5]),
+            format!("Program {} success", vault),
+            format!("Program {} failed: custom program error: 0x1", other),
+        ];
+        assert_eq!(VaultEvent::from_logs(&vault, &rolled_back).unwrap(), vec![]);
+
+        let unbalanced = [format!("Program {} success", vault)];
+        assert!(matches!(
+            VaultEvent::from_logs(&vault, &unbalanced),
+            Err(EventError::UnbalancedLogs)
+        ));
+        let invalid = [
+            format!("Program {} invoke [1]", vault),
+            format!("{}AAAA", LOG_PREFIX),
+        ];
+        assert!(matches!(
+            VaultEvent::from_logs(&vault, &invalid),
+            Err(EventError::InvalidEvent(_))
+        ));
+    }
+}
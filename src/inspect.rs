@@ -0,0 +1,254 @@
+//! Read-only views of a Vault's holdings & positions.
+//!
+//! These are pure functions over Vault storage, the llX mint & the Vault's token accounts, so
+//! clients can price llX tokens without reimplementing the processor's math. The same values are
+//! available on-chain via VaultInstruction#PreviewVault, #PreviewDeposit & #PreviewWithdraw.
+//!
+//! Previews follow the processor: Deposit & Withdraw are priced on the Vault's lX balance, with
+//! lX converted to & from X at the strategy's last estimate, while Mint & WithdrawAssets are priced
+//! on the estimate directly. They include the withdrawal fee, and fail or report zero as execution
+//! would while deposits are paused or blocked on loss. The Vault has no locked profit or deposit
+//! caps, so previews don't model either.
+use crate::{error::VaultError, events::VaultEvent, state::Vault};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
+use solana_program::program_error::ProgramError;
+use spl_token::state::{Account, Mint};
+
+/// The result of VaultInstruction#PreviewVault, Borsh-encoded in return data.
+#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct VaultPreview {
+    pub total_assets: u64,
+    pub llx_supply: u64,
+    /// See [price_per_share].
+    pub price_per_share: u64,
+    /// X redeemable for the given client llX account, if any, net of the withdrawal fee.
+    pub redeemable: Option<u64>,
+}
+
+impl VaultPreview {
+    /// Decodes PreviewVault's return data.
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// The X held by the Vault: its balance if hodling, otherwise the strategy's last estimate.
+///
+/// Strategy holdings are only known as of the last EstimateValue, so call it immediately beforehand
+/// for an accurate value.
+pub fn total_assets(vault: &Vault, vault_token_account: &Account) -> u64 {
+    if vault.hodl {
+        vault_token_account.amount
+    } else {
+        vault.last_estimated_value
+    }
+}
+
+/// The X value of one llX token, scaled by
+/// [PRICE_PER_SHARE_SCALE](../events/constant.PRICE_PER_SHARE_SCALE.html).
+pub fn price_per_share(vault: &Vault, vault_token_account: &Account, llx_token_mint: &Mint) -> u64 {
+    VaultEvent::price_per_share(
+        total_assets(vault, vault_token_account),
+        llx_token_mint.supply,
+    )
+}
+
+/// The X redeemable for `shares` llX tokens before the withdrawal fee, or None if they exceed the
+/// llX supply.
+pub fn redeemable(
+    vault: &Vault,
+    vault_token_account: &Account,
+    llx_token_mint: &Mint,
+    shares: u64,
+) -> Option<u64> {
+    Vault::assets_for_shares(
+        shares,
+        total_assets(vault, vault_token_account),
+        llx_token_mint.supply,
+    )
+}
+
+/// Summarizes the Vault and, if given, the position held in `client_llx_token_account`.
+pub fn preview(
+    vault: &Vault,
+    vault_token_account: &Account,
+    llx_token_mint: &Mint,
+    client_llx_token_account: Option<&Account>,
+) -> VaultPreview {
+    VaultPreview {
+        total_assets: total_assets(vault, vault_token_account),
+        llx_supply: llx_token_mint.supply,
+        price_per_share: price_per_share(vault, vault_token_account, llx_token_mint),
+        redeemable: client_llx_token_account.and_then(|account| {
+            preview_withdraw(vault, vault_token_account, llx_token_mint, account.amount).ok()
+        }),
+    }
+}
+
+/// The llX tokens Deposit would mint for `amount` X tokens.
+///
+/// Deposits into strategy Vaults are priced on the lX the strategy returns, so this is only as
+/// accurate as the last estimate.
+pub fn preview_deposit(
+    vault: &Vault,
+    vault_token_account: &Account,
+    llx_token_mint: &Mint,
+    amount: u64,
+) -> Result<u64, VaultError> {
+    check_deposits(vault, vault_token_account, llx_token_mint)?;
+    // The first deposit mints 1:1 with the X deposited.
+    if llx_token_mint.supply == 0 {
+        return Ok(amount);
+    }
+    let received = x_to_lx(vault, vault_token_account, amount).ok_or(VaultError::Overflow)?;
+    Vault::shares_for_deposit(received, vault_token_account.amount, llx_token_mint.supply)
+        .ok_or(VaultError::Overflow)
+}
+
+/// The X tokens Mint would charge for `shares` llX tokens.
+pub fn preview_mint(
+    vault: &Vault,
+    vault_token_account: &Account,
+    llx_token_mint: &Mint,
+    shares: u64,
+) -> Result<u64, VaultError> {
+    let total_assets = check_deposits(vault, vault_token_account, llx_token_mint)?;
+    Vault::assets_for_mint(shares, total_assets, llx_token_mint.supply).ok_or(VaultError::Overflow)
+}
+
+/// Fails as Deposit & Mint would if the Vault isn't accepting deposits, otherwise returns its
+/// total assets.
+fn check_deposits(
+    vault: &Vault,
+    vault_token_account: &Account,
+    llx_token_mint: &Mint,
+) -> Result<u64, VaultError> {
+    if vault.deposits_blocked() {
+        return Err(VaultError::DepositsBlockedOnLoss);
+    }
+    if vault.paused {
+        return Err(VaultError::Paused);
+    }
+    let total_assets = total_assets(vault, vault_token_account);
+    if llx_token_mint.supply > 0 && (total_assets == 0 || vault_token_account.amount == 0) {
+        return Err(VaultError::NoAssets);
+    }
+    Ok(total_assets)
+}
+
+/// The X tokens Withdraw would pay out for `shares` llX tokens, net of the withdrawal fee.
+///
+/// For strategy Vaults the payout is whatever the strategy returns for the Vault's lX, so this is
+/// only as accurate as the last estimate.
+pub fn preview_withdraw(
+    vault: &Vault,
+    vault_token_account: &Account,
+    llx_token_mint: &Mint,
+    shares: u64,
+) -> Result<u64, VaultError> {
+    Vault::assets_for_shares(shares, vault_token_account.amount, llx_token_mint.supply)
+        .and_then(|payout| vault.after_withdrawal_fee(payout))
+        .and_then(|payout| lx_to_x(vault, vault_token_account, payout))
+        .ok_or(VaultError::Overflow)
+}
+
+/// The lX a strategy is expected to return for `amount` X, at its last estimate. HODL Vaults hold
+/// X itself, as does a strategy Vault holding nothing yet.
+fn x_to_lx(vault: &Vault, vault_token_account: &Account, amount: u64) -> Option<u64> {
+    if vault.hodl {
+        return Some(amount);
+    }
+    Vault::shares_for_deposit(
+        amount,
+        vault.last_estimated_value,
+        vault_token_account.amount,
+    )
+}
+
+/// The X a strategy is expected to return for `amount` of the Vault's lX, at its last estimate.
+fn lx_to_x(vault: &Vault, vault_token_account: &Account, amount: u64) -> Option<u64> {
+    if vault.hodl {
+        return Some(amount);
+    }
+    Vault::assets_for_shares(
+        amount,
+        vault.last_estimated_value,
+        vault_token_account.amount,
+    )
+}
+
+/// The llX tokens WithdrawAssets would burn for `assets` X tokens after the withdrawal fee, or
+/// None if the Vault doesn't hold that many.
+pub fn preview_withdraw_assets(
+    vault: &Vault,
+    vault_token_account: &Account,
+    llx_token_mint: &Mint,
+    assets: u64,
+) -> Option<u64> {
+    Vault::shares_for_withdrawal(
+        vault.withdrawal_before_fee(assets)?,
+        total_assets(vault, vault_token_account),
+        llx_token_mint.supply,
+    )
+}
+
+/// The llX tokens `assets` X tokens are worth at the current price, ignoring any deposit limits.
+pub fn convert_to_shares(
+    vault: &Vault,
+    vault_token_account: &Account,
+    llx_token_mint: &Mint,
+    assets: u64,
+) -> Option<u64> {
+    Vault::shares_for_deposit(
+        assets,
+        total_assets(vault, vault_token_account),
+        llx_token_mint.supply,
+    )
+}
+
+/// The X tokens `shares` llX tokens are worth at the current price.
+pub fn convert_to_assets(
+    vault: &Vault,
+    vault_token_account: &Account,
+    llx_token_mint: &Mint,
+    shares: u64,
+) -> Option<u64> {
+    redeemable(vault, vault_token_account, llx_token_mint, shares)
+}
+
+/// The most X tokens Deposit currently accepts: none while deposits are blocked or paused,
+/// otherwise unlimited.
+pub fn max_deposit(vault: &Vault) -> u64 {
+    if vault.deposits_blocked() || vault.paused {
+        0
+    } else {
+        u64::MAX
+    }
+}
+
+/// The most llX tokens Mint currently mints, as for [max_deposit].
+pub fn max_mint(vault: &Vault) -> u64 {
+    max_deposit(vault)
+}
+
+/// The most llX tokens the holder of `client_llx_token_account` can redeem: its whole balance.
+pub fn max_redeem(client_llx_token_account: &Account) -> u64 {
+    client_llx_token_account.amount
+}
+
+/// The most X tokens the holder of `client_llx_token_account` can withdraw, net of the
+/// withdrawal fee.
+pub fn max_withdraw(
+    vault: &Vault,
+    vault_token_account: &Account,
+    llx_token_mint: &Mint,
+    client_llx_token_account: &Account,
+) -> Option<u64> {
+    preview_withdraw(
+        vault,
+        vault_token_account,
+        llx_token_mint,
+        client_llx_token_account.amount,
+    )
+    .ok()
+}
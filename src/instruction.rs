@@ -1,17 +1,18 @@
-use crate::error::{VaultError, VaultError::InvalidInstruction};
+use crate::{error::VaultError::InvalidInstruction, state::Vault};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use solana_program::program_error::ProgramError;
 use solana_program::{
     instruction::{AccountMeta, Instruction},
-    msg,
-    program_option::COption,
     pubkey::Pubkey,
-    sysvar,
+    system_program, sysvar,
 };
 use strategy_api::strategy_instruction::{create_estimate_value, create_transfer};
 
-use std::convert::TryInto;
-use std::mem::size_of;
-
+/// Instructions are Borsh-encoded: a one byte tag (the discriminant below) followed by the
+/// variant's fields. See client/src/vault_schema.json for the machine-readable layout.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+#[borsh(use_discriminant = true)]
+#[repr(u8)]
 pub enum VaultInstruction {
     /// Creates a defi Vault.
     ///
@@ -39,223 +40,557 @@ pub enum VaultInstruction {
     /// it in a data account which will be included in calls to the strategy instance. Extra
     /// accounts passed to Deposit/Withdraw functions will be passed along to strategies.
     ///
+    /// Vaults implement the StrategyInstruction interface themselves under the stable
+    /// [STRATEGY_DEPOSIT_INSTRUCTION_ID] etc., so one Vault can invest in another: see
+    /// [initialize_nested_vault](#method.initialize_nested_vault).
+    ///
     /// TODO(006): Consider reusing X & lX token accounts depending on whether or not the Vault is
     /// a HODL vault. Also, drop the strategy_data_account - it's not needed.
     ///
+    /// The Vault's token account & llX mint are created by this instruction at addresses derived
+    /// from the Vault storage account (see `find_vault_token_account` & `find_llx_token_mint`),
+    /// owned by the Vault authority. The llX mint uses the same decimals as the underlying X mint.
+    /// Both the X & lX mints are recorded so that client accounts can be checked on every transfer.
+    ///
+    /// To bootstrap a Vault from an existing token account & llX mint instead, e.g. a mint created
+    /// by another team or a multisig, use InitializeVaultWithAccounts.
+    ///
+    /// This doesn't register the Vault for discovery: governance does that with a separate
+    /// RegisterVault instruction, e.g. in the same transaction.
+    ///
     /// Accounts expected:
-    /// // TODO(014): Separate token owner from mint owner.
-    /// `[signer]` Vault token account owner & mint owner
-    /// `[writeable]` Vault storage account (vault ID)
-    /// `[]` Vault's lX token account or X token account if hodling  
-    /// `[]` The llX mint account
-    /// `[]` The strategy program
-    /// `[]` The rent sysvar
-    /// `[]` (Optional) Strategy instance data account
+    /// 1. `[signer, writeable]` Payer for the new accounts; becomes the Vault's governance.
+    /// 2. `[writeable]` Vault storage account (vault ID)
+    /// 3. `[writeable]` Vault's lX token account or X token account if hodling (uninitialized)
+    /// 4. `[writeable]` The llX mint account (uninitialized)
+    /// 5. `[]` The underlying X mint
+    /// 6. `[]` The mint of the Vault's token account: the strategy's lX mint, or X if hodling.
+    /// 7. `[]` The Vault authority (program derived address).
+    /// 8. `[]` SPL Token program
+    /// 9. `[]` The strategy program
+    /// 10. `[]` System program
+    /// 11. `[]` The rent sysvar
     InitializeVault {
         // TODO(007): Governance address, strategist address, keeper address.
-        // TODO(008): Withdrawal fee.
+        // Withdrawal fees are set afterwards via SetWithdrawalFee.
         // https://github.com/yearn/yearn-vaults/blob/master/contracts/BaseStrategy.sol#L781
+        hodl: bool,
         strategy_program_deposit_instruction_id: u8,
         strategy_program_withdraw_instruction_id: u8,
         strategy_program_estimate_instruction_id: u8,
-        hodl: bool,
-        debug_crash: bool,
-    },
+        // Whether deposits are rejected while a reported loss is unacknowledged by governance.
+        block_deposits_on_loss: bool,
+    } = 0,
 
     /// Deposits a given token into the vault.
     ///
-    /// Note this API is an implementation of the StrategyInstruction#Deposit instruction.
+    /// Note this API is an implementation of the StrategyInstruction#Deposit instruction, with
+    /// ID [STRATEGY_DEPOSIT_INSTRUCTION_ID].
+    ///
+    /// llX tokens are minted in proportion to the Vault's assets: the HODL account balance, or the
+    /// last estimated value for strategy Vaults. Fails while deposits are blocked on a loss, and
+    /// with ExceededSlippage if the deposit buys no llX or fewer than `min_shares`.
+    ///
+    /// StrategyInstruction#Deposit packs no `min_shares`, so outer Vaults deposit with none.
     ///
     /// Accounts expected:
     /// 1. `[]` SPL Token program
-    /// 2. `[signer]` The source wallet containing X tokens.
-    /// 3. `[]` The target wallet for llX tokens.
-    /// 4+ `[]` Source signers
-    /// 5. `[]` The Vault storage account.
-    /// 6. `[]` The strategy program.
-    /// 7. `[]` (Optional) X SPL account owned by Vault if hodling.
-    /// 8+. `[]` Strategy extra accoounts (see StrategyInstruction#Deposit)
-    /// TODO(009):: Signer pubkeys for multisignature wallets - need signer_num param.
-    Deposit { amount: u64, debug_crash: bool },
+    /// 2. `[writeable]` The source wallet containing X tokens; must hold the Vault's X mint.
+    /// 3. `[writeable]` The target wallet for llX tokens, which may be owned by anyone.
+    /// 4. `[]` Source authority; a signer unless it's an SPL Token multisig.
+    /// 5. `[signer]` `signer_num` multisig signers, if any.
+    /// 6. `[writeable]` The Vault storage account.
+    /// 7. `[]` The strategy program.
+    /// 8. `[writeable]` The llX mint account.
+    /// 9. `[]` The Vault authority (program derived address).
+    /// 10. `[writeable]` The Vault's lX token account, or X token account if hodling.
+    /// 11. `[]` The remaining accounts: strategy extra accounts (see StrategyInstruction#Deposit)
+    Deposit {
+        amount: u64,
+        signer_num: u8,
+        // The fewest llX tokens the depositor accepts, e.g. from PreviewDeposit.
+        min_shares: u64,
+    } = 1,
 
     /// Withdraws a token from the vault.
     ///
-    /// Note this API is an implementation of the StrategyInstruction#Withdraw instruction.
+    /// Note this API is an implementation of the StrategyInstruction#Withdraw instruction, with
+    /// ID [STRATEGY_WITHDRAW_INSTRUCTION_ID].
+    ///
+    /// The llX tokens are burned and the client receives the same share of the Vault's holdings, so
+    /// any recognized losses are shared pro-rata between all llX holders.
     ///
     /// Accounts expected:
     /// 1. `[]` SPL Token program
-    /// 2. `[signer]` Source Wallet for derivative token (lX).
-    /// 3. `[]` Target token (X) wallet target.
-    /// 4+ `[]` Source signers
-    /// 5. `[]` The Vault storage account.
-    /// 6. `[]` The strategy program.
-    /// 7. `[]` (Optional) X SPL account owned by Vault if hodling.
-    /// 8+. `[]` Strategy extra accoounts (see StrategyInstruction#Withdraw)
-    /// TODO(009):: Signer pubkeys for multisignature wallets - need signer_num param.
+    /// 2. `[writeable]` Source Wallet for derivative token (llX).
+    /// 3. `[writeable]` Target token (X) wallet target; must hold the Vault's X mint, but may be
+    ///    owned by anyone.
+    /// 4. `[]` Source authority; a signer unless it's an SPL Token multisig.
+    /// 5. `[signer]` `signer_num` multisig signers, if any.
+    /// 6. `[writeable]` The Vault storage account.
+    /// 7. `[]` The strategy program.
+    /// 8. `[writeable]` The llX mint account.
+    /// 9. `[]` The Vault authority (program derived address).
+    /// 10. `[writeable]` The Vault's lX token account, or X token account if hodling.
+    /// 11. `[]` The remaining accounts: strategy extra accounts (see StrategyInstruction#Withdraw)
     Withdraw {
         amount: u64, // # of derivative tokens.
-        debug_crash: bool,
-    },
+        signer_num: u8,
+    } = 2,
 
     /// Estimates the underlying value of the vault in its native asset.
     ///
     /// This instruction stores its results in a temporary account using the Shared Memory program.
     /// https://spl.solana.com/shared-memory
     ///
+    /// If the estimate is below the Vault's recorded debt, the difference is recorded as a loss.
+    /// This is deliberately also the Vault's harvest: gains stay invested in the strategy, so
+    /// marking the position to market is all a harvest would do, and there's no separate Harvest
+    /// instruction. The value is also returned via return data. Vaults investing in this Vault use
+    /// EstimatePositionValue instead, which values only their own llX.
+    ///
+    /// Strategies are only asked to value the Vault's own lX token account, which is checked
+    /// against the Vault storage and passed to them in a fixed slot.
+    ///
     /// Accounts expected:
     /// 1. `[]` Shared Memory program
-    /// 1. `[]` Shared memory output
+    /// 2. `[writeable]` Shared memory output
+    /// 3. `[writeable]` The Vault storage account.
+    /// 4. `[]` The Vault's lX token account, or X token account if hodling.
+    /// 5. `[]` The strategy program, unless hodling.
+    /// 6. `[*]` The remaining accounts: any additional accounts required by the strategy
+    EstimateValue = 3,
+
+    // 4 was WriteData, which let anyone write to Vault-owned accounts. Estimates are written to
+    // shared memory by EstimateValue & EstimatePositionValue only.
+    /// Acknowledges any losses recorded since the last acknowledgement, unblocking deposits.
+    ///
+    /// Accounts expected:
+    /// 1. `[signer]` Vault governance
+    /// 2. `[writeable]` The Vault storage account.
+    AcknowledgeLoss = 5,
+
+    /// Redeems llX tokens for the Vault's lX tokens directly, bypassing the strategy.
+    ///
+    /// The llX tokens are burned and the client receives the same pro-rata share of the Vault's lX
+    /// account as Withdraw would redeem, so clients can exit even if the strategy can't withdraw.
+    /// For HODL Vaults this is equivalent to Withdraw.
+    ///
+    /// Accounts expected:
+    /// 1. `[]` SPL Token program
+    /// 2. `[writeable]` Source Wallet for derivative token (llX).
+    /// 3. `[writeable]` Target lX wallet; must hold the Vault's lX mint.
+    /// 4. `[]` Source authority; a signer unless it's an SPL Token multisig.
+    /// 5. `[signer]` `signer_num` multisig signers, if any.
+    /// 6. `[writeable]` The Vault storage account.
+    /// 7. `[writeable]` The llX mint account.
+    /// 8. `[]` The Vault authority (program derived address).
+    /// 9. `[writeable]` The Vault's lX token account, or X token account if hodling.
+    WithdrawInKind {
+        amount: u64, // # of derivative tokens.
+        signer_num: u8,
+    } = 6,
+
+    /// Swaps a token other than X into X via an SPL Token Swap pool, then deposits it.
+    ///
+    /// The swapped X lands in the client's X account and the amount received is deposited as per
+    /// Deposit. Fails if fewer than `minimum_x_out` X tokens are received. The source authority
+    /// must be a signer: the swap program doesn't support multisig authorities.
+    ///
+    /// Accounts expected:
+    /// 1. `[writeable]` The source wallet containing the input token.
+    /// 2. `[]` Token Swap program; must be the SPL Token Swap program.
+    /// 3. `[]` Token Swap pool.
+    /// 4. `[]` Token Swap pool authority.
+    /// 5. `[writeable]` The pool's input token account.
+    /// 6. `[writeable]` The pool's X token account.
+    /// 7. `[writeable]` The pool token mint.
+    /// 8. `[writeable]` The pool fee account.
+    /// 9. The remaining accounts: Deposit accounts (see Deposit) without multisig signers; the
+    ///    client X account receives X.
+    ZapDeposit { amount_in: u64, minimum_x_out: u64 } = 7,
+
+    /// Reports the Vault's value & price per share without modifying any accounts; simulate it to
+    /// inspect a Vault. The result is a Borsh-encoded [VaultPreview](../inspect/struct.VaultPreview.html)
+    /// in return data.
+    ///
+    /// Accounts expected:
+    /// 1. `[]` The Vault storage account.
+    /// 2. `[]` The llX mint account.
+    /// 3. `[]` The Vault's lX token account, or X token account if hodling.
+    /// 4. `[]` (Optional) A client llX token account whose redeemable X to report.
+    PreviewVault = 8,
+
+    /// Reports the llX tokens Deposit would mint for `amount` X tokens, as a little-endian u64 in
+    /// return data, without moving any tokens. Fails like Deposit if deposits are blocked.
+    ///
+    /// Accounts expected: as PreviewVault, without the client llX token account.
+    PreviewDeposit { amount: u64 } = 9,
+
+    /// Reports the X tokens Withdraw would pay out for `shares` llX tokens, as a little-endian u64
+    /// in return data, without moving any tokens. Strategy Vaults are priced against their last
+    /// estimate, so call EstimateValue first in the same transaction.
+    ///
+    /// Accounts expected: as PreviewVault, without the client llX token account.
+    PreviewWithdraw { shares: u64 } = 10,
+
+    /// Mints exactly `shares` llX tokens in exchange for the X tokens they're worth, rounded up.
+    /// Fails with VaultError#ExceededSlippage if the X deposited buys fewer, e.g. if the strategy
+    /// returns less lX than estimated. Deposit fixes the X tokens deposited instead.
+    ///
+    /// Accounts expected: as Deposit.
+    Mint { shares: u64, signer_num: u8 } = 11,
+
+    /// Withdraws at least `amount` X tokens in exchange for the llX tokens they're worth, rounded
+    /// up. Withdraw fixes the llX tokens redeemed instead. Strategy Vaults are priced against
+    /// their last estimate, and fail with VaultError#ExceededSlippage if the strategy pays out
+    /// less than `amount`.
+    ///
+    /// Accounts expected: as Withdraw.
+    WithdrawAssets { amount: u64, signer_num: u8 } = 12,
+
+    /// Reports the llX tokens `assets` X tokens are worth, as a little-endian u64 in return data.
+    ///
+    /// Accounts expected: as PreviewVault, without the client llX token account.
+    ConvertToShares { assets: u64 } = 13,
+
+    /// Reports the X tokens `shares` llX tokens are worth, as a little-endian u64 in return data.
+    ///
+    /// Accounts expected: as PreviewVault, without the client llX token account.
+    ConvertToAssets { shares: u64 } = 14,
+
+    /// Reports the most X tokens Deposit currently accepts, as a little-endian u64 in return data.
+    ///
+    /// Accounts expected: as PreviewVault, without the client llX token account.
+    MaxDeposit = 15,
+
+    /// Reports the most X tokens the holder of a client llX token account can withdraw, as a
+    /// little-endian u64 in return data.
+    ///
+    /// Accounts expected: as PreviewVault; the client llX token account is required.
+    MaxWithdraw = 16,
+
+    /// Estimates the X value of the llX tokens held by a Vault using this Vault as its strategy.
+    /// The position must be that outer Vault's token account. This Vault's own value is estimated
+    /// & recorded as by EstimateValue.
+    ///
+    /// Note this API is an implementation of the StrategyInstruction#EstimateValue instruction, with
+    /// ID [STRATEGY_ESTIMATE_INSTRUCTION_ID]. The value is written to shared memory & returned
+    /// via return data.
+    ///
+    /// Accounts expected:
+    /// 1. `[]` The calling Vault program
+    /// 2. `[writeable]` Shared memory output
+    /// 3. `[]` The outer Vault's token account, holding the position in llX.
+    /// 4. `[]` The outer Vault storage account.
+    /// 5. `[]` The llX mint account.
+    /// 6. The remaining accounts: as EstimateValue from account 3, the Vault storage account.
+    EstimatePositionValue = 17,
+
+    /// Registers a Vault for its X mint so that clients can discover it on-chain: creates the
+    /// Vault's registry entry and appends its storage account to the mint's Vault registry,
+    /// creating the registry on first use. Registering a retired Vault reactivates it.
+    ///
+    /// This is a separate step from InitializeVault & InitializeVaultWithAccounts, which don't
+    /// register the Vault; governance should run it right after, e.g. in the same transaction.
+    /// Governance pays the rent of the entry & of the registry's extra 32 bytes.
+    ///
+    /// Accounts expected:
+    /// 1. `[signer, writeable]` The Vault's governance; pays for the registry entry & space.
     /// 2. `[]` The Vault storage account.
-    /// 3. `[]` (Optional) X SPL account owned by Vault if hodling.
-    /// 4+ `[*]` Strategy extra accounts - any additional accounts required by strategy
-    EstimateValue { debug_crash: bool },
+    /// 3. `[writeable]` The Vault's registry entry (see `find_registered_vault`).
+    /// 4. `[writeable]` The X mint's Vault registry (see `find_vault_registry`).
+    /// 5. `[]` System program
+    RegisterVault = 18,
 
-    /// A helper utility which functions similarly to the (unlaunched) Shared Memory program.
+    /// Marks a Vault's registry entry as retired, e.g. when shutting it down, and swap-removes
+    /// the Vault from its mint's Vault registry, refunding the freed rent to governance. The entry
+    /// is kept so that existing holders can still find the Vault.
     ///
-    /// Data is read directly from the account memory.
-    WriteData {
-        debug_crash: bool, // data: &'a [u8]
-    },
+    /// Accounts expected:
+    /// 1. `[signer, writeable]` The Vault's governance; receives the registry's freed rent.
+    /// 2. `[]` The Vault storage account.
+    /// 3. `[writeable]` The Vault's registry entry (see `find_registered_vault`).
+    /// 4. `[writeable]` The X mint's Vault registry (see `find_vault_registry`).
+    DeregisterVault = 19,
+
+    /// Reports the most llX tokens Mint currently mints, as a little-endian u64 in return data.
+    ///
+    /// Accounts expected: as PreviewVault, without the client llX token account.
+    MaxMint = 20,
+
+    /// Reports the most llX tokens the holder of a client llX token account can redeem via
+    /// Withdraw, as a little-endian u64 in return data.
+    ///
+    /// Accounts expected: as PreviewVault; the client llX token account is required.
+    MaxRedeem = 21,
+
+    /// Reports the X tokens Mint would charge for `shares` llX tokens, as a little-endian u64 in
+    /// return data, without moving any tokens. Fails like Mint if deposits are blocked.
+    ///
+    /// Accounts expected: as PreviewVault, without the client llX token account.
+    PreviewMint { shares: u64 } = 22,
+
+    /// Reports the llX tokens WithdrawAssets would burn for `amount` X tokens, as a little-endian
+    /// u64 in return data, without moving any tokens. Strategy Vaults are priced against their
+    /// last estimate.
+    ///
+    /// Accounts expected: as PreviewVault, without the client llX token account.
+    PreviewWithdrawAssets { amount: u64 } = 23,
+
+    /// Creates a Vault as per InitializeVault, but from an existing token account & llX mint
+    /// rather than ones created at derived addresses. The token account's owner & the llX mint's
+    /// authority each sign separately, and each is moved to the Vault authority with its own
+    /// SetAuthority, so they may be different keys or SPL Token multisigs.
+    ///
+    /// The token account must be empty, with no delegate or close authority. The llX mint must
+    /// have no supply & no freeze authority, and the same decimals as the underlying X mint.
+    /// Builders take the Vault's state to address its recorded accounts, so they work the same
+    /// for such Vaults.
+    ///
+    /// Accounts expected:
+    /// 1. `[signer]` The Vault's governance.
+    /// 2. `[writeable]` Vault storage account (vault ID)
+    /// 3. `[writeable]` The existing lX token account, or X token account if hodling.
+    /// 4. `[writeable]` The existing llX mint account.
+    /// 5. `[]` The underlying X mint
+    /// 6. `[]` The Vault authority (program derived address).
+    /// 7. `[]` SPL Token program
+    /// 8. `[]` The strategy program
+    /// 9. `[]` The rent sysvar
+    /// 10. `[]` The token account's owner; a signer unless it's an SPL Token multisig.
+    /// 11. `[]` The llX mint authority; a signer unless it's an SPL Token multisig.
+    /// 12. `[signer]` `vault_token_account_signer_num` multisig signers of the owner, if any.
+    /// 13. `[signer]` `llx_token_mint_signer_num` multisig signers of the mint authority, if any.
+    InitializeVaultWithAccounts {
+        hodl: bool,
+        strategy_program_deposit_instruction_id: u8,
+        strategy_program_withdraw_instruction_id: u8,
+        strategy_program_estimate_instruction_id: u8,
+        block_deposits_on_loss: bool,
+        vault_token_account_signer_num: u8,
+        llx_token_mint_signer_num: u8,
+    } = 24,
+
+    /// Sets the fee kept from each withdrawal, in basis points of its payout, up to
+    /// [MAX_WITHDRAWAL_FEE_BPS](crate::state::MAX_WITHDRAWAL_FEE_BPS). The fee stays in the Vault,
+    /// accruing to the remaining llX holders. Withdraw, WithdrawAssets & WithdrawInKind all pay it.
+    ///
+    /// Accounts expected:
+    /// 1. `[signer]` Vault governance
+    /// 2. `[writeable]` The Vault storage account.
+    SetWithdrawalFee { fee_bps: u16 } = 25,
+
+    /// Pauses or resumes deposits, e.g. while a strategy is investigated. Withdrawals stay open
+    /// while paused so that holders can always exit.
+    ///
+    /// Accounts expected:
+    /// 1. `[signer]` Vault governance
+    /// 2. `[writeable]` The Vault storage account.
+    SetPaused { paused: bool } = 26,
+
+    /// Fails with VaultError#ForcedCrash. Append to a transaction to simulate it (& inspect its
+    /// logs) without committing any changes. Only available with the `debug-crash` feature.
+    ///
+    /// No accounts expected.
+    #[cfg(feature = "debug-crash")]
+    DebugCrash = 255,
 }
-pub const CRASH_FLAG: u8 = 64;
+pub const VAULT_AUTHORITY_SEED: &[u8] = b"vault";
+pub const VAULT_TOKEN_ACCOUNT_SEED: &[u8] = b"token";
+pub const LLX_TOKEN_MINT_SEED: &[u8] = b"llx_mint";
+pub const VAULT_REGISTRY_SEED: &[u8] = b"registry";
+
+/// The StrategyInstruction IDs of a Vault, for Vaults which invest in other Vaults. These are the
+/// tags of Deposit, Withdraw & EstimatePositionValue and won't change.
+pub const STRATEGY_DEPOSIT_INSTRUCTION_ID: u8 = 1;
+pub const STRATEGY_WITHDRAW_INSTRUCTION_ID: u8 = 2;
+pub const STRATEGY_ESTIMATE_INSTRUCTION_ID: u8 = 17;
+
+/// The length of a packed StrategyInstruction#Deposit: the tag, amount & signer_num.
+const STRATEGY_DEPOSIT_LEN: usize = 10;
 
 impl VaultInstruction {
     /// Unpacks a byte buffer into a [VaultInstruction](enum.VaultInstruction.html).
     pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
-        let (tag_raw, rest) = input.split_first().ok_or(InvalidInstruction)?;
-        let debug_crash: bool = *tag_raw >= CRASH_FLAG;
-        let tag = if *tag_raw >= CRASH_FLAG {
-            *tag_raw - CRASH_FLAG
-        } else {
-            *tag_raw
-        };
-        msg!("Debug crash: {} {} {}", debug_crash, tag_raw, tag);
-        Ok(match tag {
-            0 => {
-                let hodl = *rest.get(0).unwrap();
-                let strategy_program_deposit_instruction_id = *rest.get(1).unwrap();
-                let strategy_program_withdraw_instruction_id = *rest.get(2).unwrap();
-                let strategy_program_estimate_instruction_id = *rest.get(3).unwrap();
-                Self::InitializeVault {
-                    hodl: if hodl == 1 { true } else { false },
-                    strategy_program_deposit_instruction_id,
-                    strategy_program_withdraw_instruction_id,
-                    strategy_program_estimate_instruction_id,
-                    debug_crash,
-                }
-            }
-            1 | 2 => {
-                let amount = rest
-                    .get(..8)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u64::from_le_bytes)
-                    .ok_or(InvalidInstruction)?;
-                match tag {
-                    1 => Self::Deposit {
-                        amount,
-                        debug_crash,
-                    },
-                    2 => Self::Withdraw {
-                        amount,
-                        debug_crash,
-                    },
-                    _ => return Err(VaultError::InvalidInstruction.into()),
-                }
-            }
-            3 => Self::EstimateValue { debug_crash },
-            4 => {
-                // Data unpacked separately.
-                Self::WriteData { debug_crash }
-            }
-            _ => return Err(VaultError::InvalidInstruction.into()),
-        })
+        // Outer Vaults deposit via StrategyInstruction#Deposit, which packs no min_shares.
+        let padded;
+        let input =
+            if input.len() == STRATEGY_DEPOSIT_LEN && input[0] == STRATEGY_DEPOSIT_INSTRUCTION_ID {
+                padded = [input, &0u64.to_le_bytes()].concat();
+                &padded
+            } else {
+                input
+            };
+        Self::try_from_slice(input).map_err(|_| InvalidInstruction.into())
     }
 
     fn pack(&self) -> Vec<u8> {
-        let mut buf = Vec::with_capacity(size_of::<Self>());
-        match self {
-            &Self::InitializeVault {
-                hodl,
-                strategy_program_deposit_instruction_id,
-                strategy_program_withdraw_instruction_id,
-                strategy_program_estimate_instruction_id,
-                debug_crash,
-            } => {
-                buf.push(0 + (if debug_crash { CRASH_FLAG } else { 0 }));
-                buf.push(hodl as u8);
-                buf.push(strategy_program_deposit_instruction_id);
-                buf.push(strategy_program_withdraw_instruction_id);
-                buf.push(strategy_program_estimate_instruction_id);
-            }
-            &Self::Deposit {
-                amount,
-                debug_crash,
-            } => {
-                buf.push(1 + (if debug_crash { CRASH_FLAG } else { 0 }));
-                buf.extend_from_slice(&amount.to_le_bytes());
-            }
+        borsh::to_vec(self).unwrap()
+    }
 
-            &Self::Withdraw {
-                amount,
-                debug_crash,
-            } => {
-                buf.push(2 + (if debug_crash { CRASH_FLAG } else { 0 }));
-                buf.extend_from_slice(&amount.to_le_bytes());
-            }
-            &Self::EstimateValue { debug_crash } => {
-                buf.push(3 + (if debug_crash { CRASH_FLAG } else { 0 }));
-            }
-            // Data packed separately.
-            &Self::WriteData { debug_crash } => {
-                buf.push(4 + (if debug_crash { CRASH_FLAG } else { 0 }));
-            }
-        }
-        buf
+    /// The authority which owns the Vault's token account & llX mint. Each Vault has its own, so
+    /// one Vault can never sign for another's accounts.
+    pub fn find_vault_authority(
+        vault_program_id: &Pubkey,
+        vault_storage_account: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[VAULT_AUTHORITY_SEED, vault_storage_account.as_ref()],
+            vault_program_id,
+        )
     }
 
-    pub fn write_data(
+    /// The Vault's lX (or X if hodling) token account created by InitializeVault. Vaults created by
+    /// InitializeVaultWithAccounts record theirs in their storage instead.
+    pub fn find_vault_token_account(
         vault_program_id: &Pubkey,
-        shared_memory_account: &Pubkey,
-        data: &[u8],
+        vault_storage_account: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[vault_storage_account.as_ref(), VAULT_TOKEN_ACCOUNT_SEED],
+            vault_program_id,
+        )
+    }
+
+    /// The Vault's llX mint created by InitializeVault; see `find_vault_token_account`.
+    pub fn find_llx_token_mint(
+        vault_program_id: &Pubkey,
+        vault_storage_account: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[vault_storage_account.as_ref(), LLX_TOKEN_MINT_SEED],
+            vault_program_id,
+        )
+    }
+
+    /// The registry listing the Active Vaults investing `x_token_mint`.
+    pub fn find_vault_registry(vault_program_id: &Pubkey, x_token_mint: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[VAULT_REGISTRY_SEED, x_token_mint.as_ref()],
+            vault_program_id,
+        )
+    }
+
+    /// The registry entry of the Vault `vault_storage_account`, investing `x_token_mint`.
+    pub fn find_registered_vault(
+        vault_program_id: &Pubkey,
+        x_token_mint: &Pubkey,
+        vault_storage_account: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                VAULT_REGISTRY_SEED,
+                x_token_mint.as_ref(),
+                vault_storage_account.as_ref(),
+            ],
+            vault_program_id,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_vault(
+        vault_program_id: &Pubkey,
+        payer: &Pubkey,
+        vault_storage_account: &Pubkey,
+        x_token_mint: &Pubkey,
+        vault_token_mint: &Pubkey,
+        token_program: &Pubkey,
+        strategy_program: &Pubkey,
+        hodl: bool,
+        strategy_program_deposit_instruction_id: u8,
+        strategy_program_withdraw_instruction_id: u8,
+        strategy_program_estimate_instruction_id: u8,
+        block_deposits_on_loss: bool,
     ) -> Result<Instruction, ProgramError> {
-        let accounts = vec![AccountMeta::new(*shared_memory_account, false)];
-        let mut instruction_data = Self::WriteData { debug_crash: false }.pack();
-        instruction_data.extend(data);
+        let (vault_token_account, _) =
+            Self::find_vault_token_account(vault_program_id, vault_storage_account);
+        let (llx_token_mint, _) =
+            Self::find_llx_token_mint(vault_program_id, vault_storage_account);
+        let (vault_authority, _) =
+            Self::find_vault_authority(vault_program_id, vault_storage_account);
+        let accounts = vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*vault_storage_account, false),
+            AccountMeta::new(vault_token_account, false),
+            AccountMeta::new(llx_token_mint, false),
+            AccountMeta::new_readonly(*x_token_mint, false),
+            AccountMeta::new_readonly(*vault_token_mint, false),
+            AccountMeta::new_readonly(vault_authority, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(*strategy_program, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+        ];
+        let data = VaultInstruction::InitializeVault {
+            strategy_program_deposit_instruction_id,
+            strategy_program_withdraw_instruction_id,
+            strategy_program_estimate_instruction_id,
+            hodl,
+            block_deposits_on_loss,
+        }
+        .pack();
         Ok(Instruction {
             program_id: *vault_program_id,
             accounts,
-            data: instruction_data,
+            data,
         })
     }
 
-    pub fn initialize_vault(
+    /// Creates an InitializeVaultWithAccounts instruction. The signer lists are the multisig
+    /// signers of the token account owner & llX mint authority; empty for single-key authorities.
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_vault_with_accounts(
         vault_program_id: &Pubkey,
-        initializer: &Pubkey,
+        governance: &Pubkey,
         vault_storage_account: &Pubkey,
         vault_token_account: &Pubkey,
-        llx_token_mint_id: &Pubkey,
+        vault_token_account_owner: &Pubkey,
+        vault_token_account_signers: &[&Pubkey],
+        llx_token_mint: &Pubkey,
+        llx_token_mint_authority: &Pubkey,
+        llx_token_mint_signers: &[&Pubkey],
+        x_token_mint: &Pubkey,
         token_program: &Pubkey,
         strategy_program: &Pubkey,
         hodl: bool,
         strategy_program_deposit_instruction_id: u8,
         strategy_program_withdraw_instruction_id: u8,
         strategy_program_estimate_instruction_id: u8,
+        block_deposits_on_loss: bool,
     ) -> Result<Instruction, ProgramError> {
-        let accounts = vec![
-            AccountMeta::new_readonly(*initializer, true),
+        let (vault_authority, _) =
+            Self::find_vault_authority(vault_program_id, vault_storage_account);
+        let mut accounts = vec![
+            AccountMeta::new_readonly(*governance, true),
             AccountMeta::new(*vault_storage_account, false),
             AccountMeta::new(*vault_token_account, false),
-            AccountMeta::new(*llx_token_mint_id, false),
+            AccountMeta::new(*llx_token_mint, false),
+            AccountMeta::new_readonly(*x_token_mint, false),
+            AccountMeta::new_readonly(vault_authority, false),
             AccountMeta::new_readonly(*token_program, false),
             AccountMeta::new_readonly(*strategy_program, false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(
+                *vault_token_account_owner,
+                vault_token_account_signers.is_empty(),
+            ),
+            AccountMeta::new_readonly(*llx_token_mint_authority, llx_token_mint_signers.is_empty()),
         ];
-        let data = VaultInstruction::InitializeVault {
+        for signer_pubkey in vault_token_account_signers
+            .iter()
+            .chain(llx_token_mint_signers.iter())
+        {
+            accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+        }
+        let data = VaultInstruction::InitializeVaultWithAccounts {
+            hodl,
             strategy_program_deposit_instruction_id,
             strategy_program_withdraw_instruction_id,
             strategy_program_estimate_instruction_id,
-            hodl,
-            debug_crash: false,
+            block_deposits_on_loss,
+            vault_token_account_signer_num: vault_token_account_signers.len() as u8,
+            llx_token_mint_signer_num: llx_token_mint_signers.len() as u8,
         }
         .pack();
         Ok(Instruction {
@@ -265,48 +600,168 @@ impl VaultInstruction {
         })
     }
 
+    /// Initializes a Vault which invests in the Vault issuing `inner_llx_token_mint`.
+    pub fn initialize_nested_vault(
+        vault_program_id: &Pubkey,
+        payer: &Pubkey,
+        vault_storage_account: &Pubkey,
+        x_token_mint: &Pubkey,
+        inner_llx_token_mint: &Pubkey,
+        block_deposits_on_loss: bool,
+    ) -> Result<Instruction, ProgramError> {
+        Self::initialize_vault(
+            vault_program_id,
+            payer,
+            vault_storage_account,
+            x_token_mint,
+            inner_llx_token_mint,
+            &spl_token::id(),
+            vault_program_id,
+            false,
+            STRATEGY_DEPOSIT_INSTRUCTION_ID,
+            STRATEGY_WITHDRAW_INSTRUCTION_ID,
+            STRATEGY_ESTIMATE_INSTRUCTION_ID,
+            block_deposits_on_loss,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn deposit(
         vault_program_id: &Pubkey,
         token_program_id: &Pubkey,
         client_x_token_account: &Pubkey,
-        client_lx_token_account: &Pubkey,
+        client_llx_token_account: &Pubkey,
         additional_account_metas: Vec<AccountMeta>,
         amount: u64,
+        signer_num: u8,
+        min_shares: u64,
     ) -> Result<Instruction, ProgramError> {
-        return create_transfer(
+        create_transfer(
             Self::Deposit {
                 amount,
-                debug_crash: false,
+                signer_num,
+                min_shares,
             }
             .pack(),
             vault_program_id,
             token_program_id,
             client_x_token_account,
-            client_lx_token_account,
+            client_llx_token_account,
             additional_account_metas,
-        );
+        )
     }
 
     pub fn withdraw(
         vault_program_id: &Pubkey,
         token_program_id: &Pubkey,
-        client_lx_token_account: &Pubkey,
+        client_llx_token_account: &Pubkey,
         client_x_token_account: &Pubkey,
         additional_account_metas: Vec<AccountMeta>,
         amount: u64,
+        signer_num: u8,
     ) -> Result<Instruction, ProgramError> {
-        return create_transfer(
-            Self::Withdraw {
-                amount,
-                debug_crash: false,
-            }
-            .pack(),
+        create_transfer(
+            Self::Withdraw { amount, signer_num }.pack(),
             vault_program_id,
             token_program_id,
-            client_lx_token_account,
+            client_llx_token_account,
             client_x_token_account,
             additional_account_metas,
-        );
+        )
+    }
+
+    /// Creates a ZapDeposit instruction. `swap_account_metas` are created by
+    /// [zap_account_metas](../swap/fn.zap_account_metas.html).
+    #[allow(clippy::too_many_arguments)]
+    pub fn zap_deposit(
+        vault_program_id: &Pubkey,
+        token_program_id: &Pubkey,
+        client_input_token_account: &Pubkey,
+        client_x_token_account: &Pubkey,
+        client_llx_token_account: &Pubkey,
+        swap_account_metas: Vec<AccountMeta>,
+        additional_account_metas: Vec<AccountMeta>,
+        amount_in: u64,
+        minimum_x_out: u64,
+    ) -> Result<Instruction, ProgramError> {
+        let data = Self::ZapDeposit {
+            amount_in,
+            minimum_x_out,
+        }
+        .pack();
+        let deposit = create_transfer(
+            data,
+            vault_program_id,
+            token_program_id,
+            client_x_token_account,
+            client_llx_token_account,
+            additional_account_metas,
+        )?;
+        let mut accounts = vec![AccountMeta::new(*client_input_token_account, false)];
+        accounts.extend(swap_account_metas);
+        accounts.extend(deposit.accounts);
+        Ok(Instruction {
+            accounts,
+            ..deposit
+        })
+    }
+
+    /// Creates a WithdrawInKind instruction for `vault`, stored in `vault_storage_account`.
+    /// `signer_pubkeys` are the signers of `source_authority` if it's an SPL Token multisig.
+    #[allow(clippy::too_many_arguments)]
+    pub fn withdraw_in_kind(
+        vault_program_id: &Pubkey,
+        token_program_id: &Pubkey,
+        client_llx_token_account: &Pubkey,
+        client_lx_token_account: &Pubkey,
+        source_authority: &Pubkey,
+        signer_pubkeys: &[&Pubkey],
+        vault_storage_account: &Pubkey,
+        vault: &Vault,
+        amount: u64,
+    ) -> Result<Instruction, ProgramError> {
+        let (vault_authority, _) =
+            Self::find_vault_authority(vault_program_id, vault_storage_account);
+        let mut accounts = vec![
+            AccountMeta::new_readonly(*token_program_id, false),
+            AccountMeta::new(*client_llx_token_account, false),
+            AccountMeta::new(*client_lx_token_account, false),
+            AccountMeta::new_readonly(*source_authority, signer_pubkeys.is_empty()),
+        ];
+        for signer_pubkey in signer_pubkeys.iter() {
+            accounts.push(AccountMeta::new_readonly(**signer_pubkey, true));
+        }
+        accounts.extend(vec![
+            AccountMeta::new(*vault_storage_account, false),
+            AccountMeta::new(vault.llx_token_mint_id, false),
+            AccountMeta::new_readonly(vault_authority, false),
+            AccountMeta::new(vault.vault_token_account, false),
+        ]);
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts,
+            data: Self::WithdrawInKind {
+                amount,
+                signer_num: signer_pubkeys.len() as u8,
+            }
+            .pack(),
+        })
+    }
+
+    pub fn acknowledge_loss(
+        vault_program_id: &Pubkey,
+        governance: &Pubkey,
+        vault_storage_account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        let accounts = vec![
+            AccountMeta::new_readonly(*governance, true),
+            AccountMeta::new(*vault_storage_account, false),
+        ];
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts,
+            data: Self::AcknowledgeLoss.pack(),
+        })
     }
 
     pub fn estimate_value(
@@ -315,12 +770,444 @@ impl VaultInstruction {
         shared_memory_account: &Pubkey,
         additional_account_metas: Vec<AccountMeta>,
     ) -> Result<Instruction, ProgramError> {
-        return create_estimate_value(
-            Self::EstimateValue { debug_crash: false }.pack(),
+        create_estimate_value(
+            Self::EstimateValue.pack(),
             program_id,
             vault_program_id,
             shared_memory_account,
             additional_account_metas,
+        )
+    }
+
+    pub fn estimate_position_value(
+        vault_program_id: &Pubkey,
+        shared_memory_account: &Pubkey,
+        additional_account_metas: Vec<AccountMeta>,
+    ) -> Result<Instruction, ProgramError> {
+        create_estimate_value(
+            Self::EstimatePositionValue.pack(),
+            vault_program_id,
+            vault_program_id,
+            shared_memory_account,
+            additional_account_metas,
+        )
+    }
+
+    pub fn preview_vault(
+        vault_program_id: &Pubkey,
+        vault_storage_account: &Pubkey,
+        vault: &Vault,
+        client_llx_token_account: Option<&Pubkey>,
+    ) -> Result<Instruction, ProgramError> {
+        let mut accounts = Self::preview_account_metas(vault_storage_account, vault);
+        if let Some(client_llx_token_account) = client_llx_token_account {
+            accounts.push(AccountMeta::new_readonly(*client_llx_token_account, false));
+        }
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts,
+            data: Self::PreviewVault.pack(),
+        })
+    }
+
+    pub fn preview_deposit(
+        vault_program_id: &Pubkey,
+        vault_storage_account: &Pubkey,
+        vault: &Vault,
+        amount: u64,
+    ) -> Result<Instruction, ProgramError> {
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts: Self::preview_account_metas(vault_storage_account, vault),
+            data: Self::PreviewDeposit { amount }.pack(),
+        })
+    }
+
+    pub fn preview_withdraw(
+        vault_program_id: &Pubkey,
+        vault_storage_account: &Pubkey,
+        vault: &Vault,
+        shares: u64,
+    ) -> Result<Instruction, ProgramError> {
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts: Self::preview_account_metas(vault_storage_account, vault),
+            data: Self::PreviewWithdraw { shares }.pack(),
+        })
+    }
+
+    pub fn mint(
+        vault_program_id: &Pubkey,
+        token_program_id: &Pubkey,
+        client_x_token_account: &Pubkey,
+        client_llx_token_account: &Pubkey,
+        additional_account_metas: Vec<AccountMeta>,
+        shares: u64,
+        signer_num: u8,
+    ) -> Result<Instruction, ProgramError> {
+        create_transfer(
+            Self::Mint { shares, signer_num }.pack(),
+            vault_program_id,
+            token_program_id,
+            client_x_token_account,
+            client_llx_token_account,
+            additional_account_metas,
+        )
+    }
+
+    pub fn withdraw_assets(
+        vault_program_id: &Pubkey,
+        token_program_id: &Pubkey,
+        client_llx_token_account: &Pubkey,
+        client_x_token_account: &Pubkey,
+        additional_account_metas: Vec<AccountMeta>,
+        amount: u64,
+        signer_num: u8,
+    ) -> Result<Instruction, ProgramError> {
+        create_transfer(
+            Self::WithdrawAssets { amount, signer_num }.pack(),
+            vault_program_id,
+            token_program_id,
+            client_llx_token_account,
+            client_x_token_account,
+            additional_account_metas,
+        )
+    }
+
+    pub fn convert_to_shares(
+        vault_program_id: &Pubkey,
+        vault_storage_account: &Pubkey,
+        vault: &Vault,
+        assets: u64,
+    ) -> Result<Instruction, ProgramError> {
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts: Self::preview_account_metas(vault_storage_account, vault),
+            data: Self::ConvertToShares { assets }.pack(),
+        })
+    }
+
+    pub fn convert_to_assets(
+        vault_program_id: &Pubkey,
+        vault_storage_account: &Pubkey,
+        vault: &Vault,
+        shares: u64,
+    ) -> Result<Instruction, ProgramError> {
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts: Self::preview_account_metas(vault_storage_account, vault),
+            data: Self::ConvertToAssets { shares }.pack(),
+        })
+    }
+
+    pub fn max_deposit(
+        vault_program_id: &Pubkey,
+        vault_storage_account: &Pubkey,
+        vault: &Vault,
+    ) -> Result<Instruction, ProgramError> {
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts: Self::preview_account_metas(vault_storage_account, vault),
+            data: Self::MaxDeposit.pack(),
+        })
+    }
+
+    pub fn max_withdraw(
+        vault_program_id: &Pubkey,
+        vault_storage_account: &Pubkey,
+        vault: &Vault,
+        client_llx_token_account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        let mut accounts = Self::preview_account_metas(vault_storage_account, vault);
+        accounts.push(AccountMeta::new_readonly(*client_llx_token_account, false));
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts,
+            data: Self::MaxWithdraw.pack(),
+        })
+    }
+
+    pub fn max_mint(
+        vault_program_id: &Pubkey,
+        vault_storage_account: &Pubkey,
+        vault: &Vault,
+    ) -> Result<Instruction, ProgramError> {
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts: Self::preview_account_metas(vault_storage_account, vault),
+            data: Self::MaxMint.pack(),
+        })
+    }
+
+    pub fn max_redeem(
+        vault_program_id: &Pubkey,
+        vault_storage_account: &Pubkey,
+        vault: &Vault,
+        client_llx_token_account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        let mut accounts = Self::preview_account_metas(vault_storage_account, vault);
+        accounts.push(AccountMeta::new_readonly(*client_llx_token_account, false));
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts,
+            data: Self::MaxRedeem.pack(),
+        })
+    }
+
+    pub fn preview_mint(
+        vault_program_id: &Pubkey,
+        vault_storage_account: &Pubkey,
+        vault: &Vault,
+        shares: u64,
+    ) -> Result<Instruction, ProgramError> {
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts: Self::preview_account_metas(vault_storage_account, vault),
+            data: Self::PreviewMint { shares }.pack(),
+        })
+    }
+
+    pub fn preview_withdraw_assets(
+        vault_program_id: &Pubkey,
+        vault_storage_account: &Pubkey,
+        vault: &Vault,
+        amount: u64,
+    ) -> Result<Instruction, ProgramError> {
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts: Self::preview_account_metas(vault_storage_account, vault),
+            data: Self::PreviewWithdrawAssets { amount }.pack(),
+        })
+    }
+
+    /// The accounts of PreviewVault & the other views, without the client llX token account.
+    fn preview_account_metas(vault_storage_account: &Pubkey, vault: &Vault) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(*vault_storage_account, false),
+            AccountMeta::new_readonly(vault.llx_token_mint_id, false),
+            AccountMeta::new_readonly(vault.vault_token_account, false),
+        ]
+    }
+
+    pub fn register_vault(
+        vault_program_id: &Pubkey,
+        governance: &Pubkey,
+        vault_storage_account: &Pubkey,
+        x_token_mint: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        let (registered_vault, _) =
+            Self::find_registered_vault(vault_program_id, x_token_mint, vault_storage_account);
+        let (vault_registry, _) = Self::find_vault_registry(vault_program_id, x_token_mint);
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts: vec![
+                AccountMeta::new(*governance, true),
+                AccountMeta::new_readonly(*vault_storage_account, false),
+                AccountMeta::new(registered_vault, false),
+                AccountMeta::new(vault_registry, false),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+            data: Self::RegisterVault.pack(),
+        })
+    }
+
+    pub fn deregister_vault(
+        vault_program_id: &Pubkey,
+        governance: &Pubkey,
+        vault_storage_account: &Pubkey,
+        x_token_mint: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        let (registered_vault, _) =
+            Self::find_registered_vault(vault_program_id, x_token_mint, vault_storage_account);
+        let (vault_registry, _) = Self::find_vault_registry(vault_program_id, x_token_mint);
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts: vec![
+                AccountMeta::new(*governance, true),
+                AccountMeta::new_readonly(*vault_storage_account, false),
+                AccountMeta::new(registered_vault, false),
+                AccountMeta::new(vault_registry, false),
+            ],
+            data: Self::DeregisterVault.pack(),
+        })
+    }
+
+    pub fn set_withdrawal_fee(
+        vault_program_id: &Pubkey,
+        governance: &Pubkey,
+        vault_storage_account: &Pubkey,
+        fee_bps: u16,
+    ) -> Result<Instruction, ProgramError> {
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*governance, true),
+                AccountMeta::new(*vault_storage_account, false),
+            ],
+            data: Self::SetWithdrawalFee { fee_bps }.pack(),
+        })
+    }
+
+    pub fn set_paused(
+        vault_program_id: &Pubkey,
+        governance: &Pubkey,
+        vault_storage_account: &Pubkey,
+        paused: bool,
+    ) -> Result<Instruction, ProgramError> {
+        Ok(Instruction {
+            program_id: *vault_program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*governance, true),
+                AccountMeta::new(*vault_storage_account, false),
+            ],
+            data: Self::SetPaused { paused }.pack(),
+        })
+    }
+
+    /// Creates a DebugCrash instruction.
+    #[cfg(feature = "debug-crash")]
+    pub fn debug_crash(vault_program_id: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: *vault_program_id,
+            accounts: vec![],
+            data: Self::DebugCrash.pack(),
+        }
+    }
+}
+
+/// Accounts 6+ of Deposit & Withdraw for `vault`, i.e. the strategy extra accounts another Vault
+/// passes when investing in it. `strategy_extra_accounts` are `vault`'s own strategy's, if any.
+///
+/// 1. `[writeable]` The Vault storage account.
+/// 2. `[]` The strategy program.
+/// 3. `[writeable]` The llX mint account.
+/// 4. `[]` The Vault authority (program derived address).
+/// 5. `[writeable]` The Vault's lX token account, or X token account if hodling.
+///
+/// Followed by any strategy extra accounts.
+pub fn transfer_account_metas(
+    vault_program_id: &Pubkey,
+    vault_storage_account: &Pubkey,
+    vault: &Vault,
+    strategy_extra_accounts: Vec<AccountMeta>,
+) -> Vec<AccountMeta> {
+    let (vault_authority, _) =
+        VaultInstruction::find_vault_authority(vault_program_id, vault_storage_account);
+    let mut accounts = vec![
+        AccountMeta::new(*vault_storage_account, false),
+        AccountMeta::new_readonly(vault.strategy_program_id, false),
+        AccountMeta::new(vault.llx_token_mint_id, false),
+        AccountMeta::new_readonly(vault_authority, false),
+        AccountMeta::new(vault.vault_token_account, false),
+    ];
+    accounts.extend(strategy_extra_accounts);
+    accounts
+}
+
+/// Accounts 3+ of EstimateValue for `vault`, i.e. the strategy extra accounts another Vault passes
+/// when estimating its value. `strategy_extra_accounts` are `vault`'s own strategy's, if any.
+///
+/// 1. `[writeable]` The Vault storage account.
+/// 2. `[]` The Vault's lX token account, or X token account if hodling.
+/// 3. `[]` The strategy program, unless hodling.
+///
+/// Followed by any strategy extra accounts.
+pub fn estimate_value_account_metas(
+    vault_storage_account: &Pubkey,
+    vault: &Vault,
+    strategy_extra_accounts: Vec<AccountMeta>,
+) -> Vec<AccountMeta> {
+    let mut accounts = vec![
+        AccountMeta::new(*vault_storage_account, false),
+        AccountMeta::new_readonly(vault.vault_token_account, false),
+    ];
+    if !vault.hodl {
+        accounts.push(AccountMeta::new_readonly(vault.strategy_program_id, false));
+        accounts.extend(strategy_extra_accounts);
+    }
+    accounts
+}
+
+/// Accounts 4+ of EstimatePositionValue for `vault`, i.e. the strategy extra accounts the outer
+/// Vault `outer_storage_account` passes when estimating its value. The position itself is the
+/// outer Vault's token account, which it passes to its strategy in a fixed slot.
+pub fn estimate_position_value_account_metas(
+    outer_storage_account: &Pubkey,
+    vault_storage_account: &Pubkey,
+    vault: &Vault,
+    strategy_extra_accounts: Vec<AccountMeta>,
+) -> Vec<AccountMeta> {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*outer_storage_account, false),
+        AccountMeta::new_readonly(vault.llx_token_mint_id, false),
+    ];
+    accounts.extend(estimate_value_account_metas(
+        vault_storage_account,
+        vault,
+        strategy_extra_accounts,
+    ));
+    accounts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strategy_api::strategy_instruction::StrategyInstruction;
+
+    #[test]
+    fn test_unpack_short_input() {
+        let initialize = VaultInstruction::InitializeVault {
+            hodl: true,
+            strategy_program_deposit_instruction_id: 1,
+            strategy_program_withdraw_instruction_id: 2,
+            strategy_program_estimate_instruction_id: 3,
+            block_deposits_on_loss: false,
+        };
+        let deposit = VaultInstruction::Deposit {
+            amount: 10,
+            signer_num: 1,
+            min_shares: 9,
+        };
+        for instruction in [initialize, deposit].iter() {
+            let data = instruction.pack();
+            assert_eq!(VaultInstruction::unpack(&data).unwrap(), *instruction);
+            for len in 0..data.len() {
+                if len == STRATEGY_DEPOSIT_LEN && data[0] == STRATEGY_DEPOSIT_INSTRUCTION_ID {
+                    continue;
+                }
+                assert_eq!(
+                    VaultInstruction::unpack(&data[..len]),
+                    Err(InvalidInstruction.into())
+                );
+            }
+        }
+        assert_eq!(
+            VaultInstruction::unpack(&[4]),
+            Err(InvalidInstruction.into())
+        );
+    }
+
+    #[test]
+    fn test_unpack_strategy_deposit() {
+        let data = StrategyInstruction::deposit(
+            STRATEGY_DEPOSIT_INSTRUCTION_ID,
+            &Pubkey::new_unique(),
+            &spl_token::id(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            vec![],
+            10,
+            1,
+        )
+        .unwrap()
+        .data;
+        assert_eq!(
+            VaultInstruction::unpack(&data),
+            Ok(VaultInstruction::Deposit {
+                amount: 10,
+                signer_num: 1,
+                min_shares: 0,
+            })
         );
     }
 }
@@ -1,10 +1,21 @@
-use solana_program;
-
+#[macro_use]
+pub mod log;
+#[cfg(not(feature = "no-entrypoint"))]
 pub mod entrypoint;
 pub mod error;
+pub mod events;
+pub mod inspect;
 pub mod instruction;
 pub mod processor;
+#[cfg(feature = "schema")]
+pub mod schema;
 pub mod state;
+pub mod swap;
+pub mod tokenized_vault;
+
+// Re-exported for the exported macros, e.g. debug_msg!.
+#[doc(hidden)]
+pub use solana_program;
 
 
 // Random based on Token ID's ID. Defines Vault::id().
@@ -0,0 +1,25 @@
+//! Logging helpers.
+
+/// Logs like `msg!`, but only when built with the `debug-logs` feature.
+///
+/// Use for verbose tracing (account keys, intermediate amounts). Errors & events should keep
+/// using `msg!` & [VaultEvent](crate::events::VaultEvent) so they're logged in release builds.
+/// The feature is that of this crate, not of the crate invoking the macro.
+#[cfg(feature = "debug-logs")]
+#[macro_export]
+macro_rules! debug_msg {
+    ($($arg:tt)+) => {
+        $crate::solana_program::msg!($($arg)+);
+    };
+}
+
+/// Logs like `msg!`, but only when built with the `debug-logs` feature.
+#[cfg(not(feature = "debug-logs"))]
+#[macro_export]
+macro_rules! debug_msg {
+    ($($arg:tt)+) => {
+        if false {
+            $crate::solana_program::msg!($($arg)+);
+        }
+    };
+}
@@ -1,45 +1,63 @@
 use solana_program::{
-  account_info::{next_account_info, AccountInfo},
+  account_info::{next_account_info, next_account_infos, AccountInfo},
   entrypoint::ProgramResult,
   instruction::AccountMeta,
   msg,
-  program::{invoke, invoke_signed},
+  program::{get_return_data, invoke, invoke_signed, set_return_data},
   program_error::ProgramError,
-  program_option::COption,
   program_pack::{IsInitialized, Pack},
   pubkey::Pubkey,
+  system_instruction,
   sysvar::{rent::Rent, Sysvar},
 };
 
-use crate::{error::VaultError, instruction::VaultInstruction, state::Vault};
+use crate::{
+  error::VaultError,
+  events::VaultEvent,
+  inspect,
+  instruction::{
+    VaultInstruction, LLX_TOKEN_MINT_SEED, VAULT_AUTHORITY_SEED, VAULT_REGISTRY_SEED,
+    VAULT_TOKEN_ACCOUNT_SEED,
+  },
+  state::{RegisteredVault, Vault, VaultRegistry, VaultStatus, MAX_WITHDRAWAL_FEE_BPS},
+  swap,
+};
 use strategy_api::strategy_instruction::StrategyInstruction;
+use std::convert::TryInto;
 
 pub struct Processor;
+
+/// The side of a transfer whose amount is fixed by the instruction.
+#[derive(Clone, Copy)]
+enum TransferAmount {
+  /// X tokens.
+  Assets(u64),
+  /// llX tokens.
+  Shares(u64),
+}
+
 impl Processor {
   pub fn process(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
   ) -> ProgramResult {
-    msg!("Unpacking instruction");
+    debug_msg!("Unpacking instruction");
     let instruction = VaultInstruction::unpack(instruction_data)?;
-    // TODO(011): Remove dev logs or gate.
-    // let account_info_iter = &mut accounts.iter();
-    // for (i, account) in account_info_iter.enumerate() {
-    //   msg!("account #{}:  {}", i, account.key);
-    // }
+    for (i, account) in accounts.iter().enumerate() {
+      debug_msg!("account #{}:  {}", i, account.key);
+    }
 
-    let mut _debug_crash = false;
     match instruction {
       VaultInstruction::InitializeVault {
         hodl,
         strategy_program_deposit_instruction_id,
         strategy_program_withdraw_instruction_id,
         strategy_program_estimate_instruction_id,
-        debug_crash,
+        block_deposits_on_loss,
       } => {
         msg!("Instruction: InitializeVault");
-        msg!(
+        debug_msg!(
           "Init vault: dep {} with {} est {}",
           // hodl,
           strategy_program_deposit_instruction_id,
@@ -53,49 +71,192 @@ impl Processor {
           strategy_program_deposit_instruction_id,
           strategy_program_withdraw_instruction_id,
           strategy_program_estimate_instruction_id,
+          block_deposits_on_loss,
         )?;
-        _debug_crash = debug_crash;
       }
-      VaultInstruction::Deposit {
-        amount,
-        debug_crash,
+      VaultInstruction::InitializeVaultWithAccounts {
+        hodl,
+        strategy_program_deposit_instruction_id,
+        strategy_program_withdraw_instruction_id,
+        strategy_program_estimate_instruction_id,
+        block_deposits_on_loss,
+        vault_token_account_signer_num,
+        llx_token_mint_signer_num,
       } => {
+        msg!("Instruction: InitializeVaultWithAccounts");
+        Self::process_initialize_vault_with_accounts(
+          program_id,
+          accounts,
+          hodl,
+          strategy_program_deposit_instruction_id,
+          strategy_program_withdraw_instruction_id,
+          strategy_program_estimate_instruction_id,
+          block_deposits_on_loss,
+          vault_token_account_signer_num,
+          llx_token_mint_signer_num,
+        )?;
+      }
+      VaultInstruction::Deposit { amount, signer_num, min_shares } => {
         msg!("Instruction: Deposit {}", amount);
-        Self::process_transfer(program_id, accounts, amount, true)?;
-        _debug_crash = debug_crash;
+        Self::process_transfer(
+          program_id,
+          accounts,
+          TransferAmount::Assets(amount),
+          signer_num,
+          true,
+          min_shares,
+        )?;
       }
-      VaultInstruction::Withdraw {
-        amount,
-        debug_crash,
-      } => {
+      VaultInstruction::Withdraw { amount, signer_num } => {
         msg!("Instruction: Withdraw {}", amount);
-        Self::process_transfer(program_id, accounts, amount, false)?;
-        _debug_crash = debug_crash;
+        Self::process_transfer(
+          program_id,
+          accounts,
+          TransferAmount::Shares(amount),
+          signer_num,
+          false,
+          0,
+        )?;
       }
-      VaultInstruction::EstimateValue { debug_crash } => {
+      VaultInstruction::EstimateValue => {
         msg!("Instruction: EstimateValue");
         Self::process_estimate_value(program_id, accounts)?;
-        _debug_crash = debug_crash;
       }
-      VaultInstruction::WriteData { debug_crash } => {
-        msg!("Instruction: WriteData");
-        let (_, data) = instruction_data
-          .split_first()
-          .ok_or(VaultError::InvalidInstruction)?;
-        Self::process_write_data(accounts, data)?;
-        _debug_crash = debug_crash;
+      VaultInstruction::EstimatePositionValue => {
+        msg!("Instruction: EstimatePositionValue");
+        Self::process_estimate_position_value(program_id, accounts)?;
+      }
+      VaultInstruction::AcknowledgeLoss => {
+        msg!("Instruction: AcknowledgeLoss");
+        Self::process_acknowledge_loss(program_id, accounts)?;
+      }
+      VaultInstruction::RegisterVault => {
+        msg!("Instruction: RegisterVault");
+        Self::process_register_vault(program_id, accounts)?;
+      }
+      VaultInstruction::DeregisterVault => {
+        msg!("Instruction: DeregisterVault");
+        Self::process_deregister_vault(program_id, accounts)?;
+      }
+      VaultInstruction::WithdrawInKind { amount, signer_num } => {
+        msg!("Instruction: WithdrawInKind {}", amount);
+        Self::process_withdraw_in_kind(program_id, accounts, amount, signer_num)?;
+      }
+      VaultInstruction::ZapDeposit { amount_in, minimum_x_out } => {
+        msg!("Instruction: ZapDeposit {}", amount_in);
+        Self::process_zap_deposit(program_id, accounts, amount_in, minimum_x_out)?;
+      }
+      VaultInstruction::Mint { shares, signer_num } => {
+        msg!("Instruction: Mint {}", shares);
+        Self::process_transfer(
+          program_id,
+          accounts,
+          TransferAmount::Shares(shares),
+          signer_num,
+          true,
+          0,
+        )?;
+      }
+      VaultInstruction::WithdrawAssets { amount, signer_num } => {
+        msg!("Instruction: WithdrawAssets {}", amount);
+        Self::process_transfer(
+          program_id,
+          accounts,
+          TransferAmount::Assets(amount),
+          signer_num,
+          false,
+          0,
+        )?;
+      }
+      VaultInstruction::PreviewVault => {
+        msg!("Instruction: PreviewVault");
+        Self::process_preview_vault(program_id, accounts)?;
+      }
+      VaultInstruction::PreviewDeposit { amount } => {
+        msg!("Instruction: PreviewDeposit {}", amount);
+        Self::process_preview_amount(program_id, accounts, |vault, vault_token, llx_mint| {
+          inspect::preview_deposit(vault, vault_token, llx_mint, amount)
+        })?;
+      }
+      VaultInstruction::PreviewWithdraw { shares } => {
+        msg!("Instruction: PreviewWithdraw {}", shares);
+        Self::process_preview_amount(program_id, accounts, |vault, vault_token, llx_mint| {
+          inspect::preview_withdraw(vault, vault_token, llx_mint, shares)
+        })?;
+      }
+      VaultInstruction::ConvertToShares { assets } => {
+        msg!("Instruction: ConvertToShares {}", assets);
+        Self::process_preview_amount(program_id, accounts, |vault, vault_token, llx_mint| {
+          inspect::convert_to_shares(vault, vault_token, llx_mint, assets)
+            .ok_or(VaultError::Overflow)
+        })?;
+      }
+      VaultInstruction::ConvertToAssets { shares } => {
+        msg!("Instruction: ConvertToAssets {}", shares);
+        Self::process_preview_amount(program_id, accounts, |vault, vault_token, llx_mint| {
+          inspect::convert_to_assets(vault, vault_token, llx_mint, shares)
+            .ok_or(VaultError::Overflow)
+        })?;
+      }
+      VaultInstruction::MaxDeposit => {
+        msg!("Instruction: MaxDeposit");
+        Self::process_preview_amount(program_id, accounts, |vault, _, _| {
+          Ok(inspect::max_deposit(vault))
+        })?;
+      }
+      VaultInstruction::MaxWithdraw => {
+        msg!("Instruction: MaxWithdraw");
+        Self::process_client_preview_amount(
+          program_id,
+          accounts,
+          |vault, vault_token, llx_mint, client_llx| {
+            inspect::max_withdraw(vault, vault_token, llx_mint, client_llx)
+              .ok_or(VaultError::Overflow)
+          },
+        )?;
+      }
+      VaultInstruction::MaxMint => {
+        msg!("Instruction: MaxMint");
+        Self::process_preview_amount(program_id, accounts, |vault, _, _| {
+          Ok(inspect::max_mint(vault))
+        })?;
+      }
+      VaultInstruction::MaxRedeem => {
+        msg!("Instruction: MaxRedeem");
+        Self::process_client_preview_amount(program_id, accounts, |_, _, _, client_llx| {
+          Ok(inspect::max_redeem(client_llx))
+        })?;
+      }
+      VaultInstruction::PreviewMint { shares } => {
+        msg!("Instruction: PreviewMint {}", shares);
+        Self::process_preview_amount(program_id, accounts, |vault, vault_token, llx_mint| {
+          inspect::preview_mint(vault, vault_token, llx_mint, shares)
+        })?;
+      }
+      VaultInstruction::PreviewWithdrawAssets { amount } => {
+        msg!("Instruction: PreviewWithdrawAssets {}", amount);
+        Self::process_preview_amount(program_id, accounts, |vault, vault_token, llx_mint| {
+          inspect::preview_withdraw_assets(vault, vault_token, llx_mint, amount)
+            .ok_or(VaultError::Overflow)
+        })?;
+      }
+      VaultInstruction::SetWithdrawalFee { fee_bps } => {
+        msg!("Instruction: SetWithdrawalFee {}", fee_bps);
+        Self::process_set_withdrawal_fee(program_id, accounts, fee_bps)?;
+      }
+      VaultInstruction::SetPaused { paused } => {
+        msg!("Instruction: SetPaused {}", paused);
+        Self::process_set_paused(program_id, accounts, paused)?;
+      }
+      #[cfg(feature = "debug-crash")]
+      VaultInstruction::DebugCrash => {
+        msg!("Force crashing app.");
+        return Err(VaultError::ForcedCrash.into());
       }
     }
-
-    if _debug_crash {
-      msg!("Force crashing app.");
-      return Err(VaultError::ForcedCrash.into());
-    } else {
-      Ok(())
-    }
+    Ok(())
   }
 
-
   fn process_initialize_vault(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -103,35 +264,50 @@ impl Processor {
     strategy_program_deposit_instruction_id: u8,
     strategy_program_withdraw_instruction_id: u8,
     strategy_program_estimate_instruction_id: u8,
+    block_deposits_on_loss: bool,
   ) -> ProgramResult {
-    msg!("Initializing vault");
+    debug_msg!("Initializing vault");
     let account_info_iter = &mut accounts.iter();
-    // TODO(014): Separate token owner from mint owner.
-    let token_account_owner = next_account_info(account_info_iter)?;
-    msg!("token_account_owner {}", token_account_owner.key);
+    let payer = next_account_info(account_info_iter)?;
+    debug_msg!("payer {}", payer.key);
     let storage_account = next_account_info(account_info_iter)?;
-    // msg!("w {}", storage_account.key);
     let vault_token_account = next_account_info(account_info_iter)?;
-    msg!("vault_token_account {}", vault_token_account.key);
-    let llx_token_mint_id = next_account_info(account_info_iter)?;
-    msg!("llx_token_mint_id {}", llx_token_mint_id.key);
+    debug_msg!("vault_token_account {}", vault_token_account.key);
+    let llx_token_mint = next_account_info(account_info_iter)?;
+    debug_msg!("llx_token_mint {}", llx_token_mint.key);
+    let x_token_mint = next_account_info(account_info_iter)?;
+    let vault_token_mint = next_account_info(account_info_iter)?;
+    let vault_authority = next_account_info(account_info_iter)?;
     let token_program = next_account_info(account_info_iter)?;
-    msg!("token_program {}", token_program.key);
+    debug_msg!("token_program {}", token_program.key);
     let strategy_program = next_account_info(account_info_iter)?;
-    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let system_program = next_account_info(account_info_iter)?;
+    let rent_info = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(rent_info)?;
 
-    if *vault_token_account.owner != *token_program.key
-      || *llx_token_mint_id.owner != *token_program.key
+    if !payer.is_signer {
+      return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *token_program.key != spl_token::id()
+      || *x_token_mint.owner != *token_program.key
+      || *vault_token_mint.owner != *token_program.key
     {
       msg!(
-        "vault_token_account.owner {} != *token_program.key {} llx_token_mint_id {}",
-        vault_token_account.owner,
+        "x_token_mint.owner {} vault_token_mint.owner {} != *token_program.key {}",
+        x_token_mint.owner,
+        vault_token_mint.owner,
         *token_program.key,
-        *llx_token_mint_id.owner
       );
       return Err(ProgramError::IncorrectProgramId);
     }
+    if hodl && *x_token_mint.key != *vault_token_mint.key {
+      msg!("HODL Vaults hold X tokens directly");
+      return Err(VaultError::AccountInconsistency.into());
+    }
 
+    if storage_account.owner != program_id {
+      return Err(ProgramError::IncorrectProgramId);
+    }
     if !rent.is_exempt(storage_account.lamports(), storage_account.data_len()) {
       return Err(VaultError::NotRentExempt.into());
     }
@@ -141,104 +317,332 @@ impl Processor {
       return Err(ProgramError::AccountAlreadyInitialized);
     }
 
-    storage_info.is_initialized = true;
-    storage_info.hodl = hodl;
-    storage_info.vault_token_account = *vault_token_account.key;
-    storage_info.llx_token_mint_id = *llx_token_mint_id.key;
-    storage_info.strategy_program_id = *strategy_program.key;
-    storage_info.strategy_program_deposit_instruction_id = strategy_program_deposit_instruction_id;
-    storage_info.strategy_program_withdraw_instruction_id =
-      strategy_program_withdraw_instruction_id;
-    storage_info.strategy_program_estimate_instruction_id =
-      strategy_program_estimate_instruction_id;
-    storage_info.last_estimated_value = 0;
-    // Write the info to the actual account.
-    Vault::pack(storage_info, &mut storage_account.data.borrow_mut())?;
-    // msg!("storage_account.data {}", storage_account.data);
-    // Transfer ownership of the temp account to this program via a derived address.
-    let (pda, _bump_seed) = Pubkey::find_program_address(&[b"vault"], program_id);
-    msg!(
-      "Transferring program vault token {} ownership from {} to {}",
-      vault_token_account.key, token_account_owner.key, pda
-    );
-    if !token_account_owner.is_signer {
-      return Err(ProgramError::MissingRequiredSignature);
+    let (pda, _bump_seed) = VaultInstruction::find_vault_authority(program_id, storage_account.key);
+    let (vault_token_account_id, vault_token_account_bump_seed) =
+      VaultInstruction::find_vault_token_account(program_id, storage_account.key);
+    let (llx_token_mint_id, llx_token_mint_bump_seed) =
+      VaultInstruction::find_llx_token_mint(program_id, storage_account.key);
+    if *vault_authority.key != pda
+      || *vault_token_account.key != vault_token_account_id
+      || *llx_token_mint.key != llx_token_mint_id
+    {
+      msg!("Vault accounts aren't at their derived addresses");
+      return Err(VaultError::AccountInconsistency.into());
     }
-    let account_owner_change_ix = spl_token::instruction::set_authority(
-      token_program.key,
-      vault_token_account.key,
-      Some(&pda),
-      spl_token::instruction::AuthorityType::AccountOwner,
-      // TODO(014): Separate token owner from mint owner.
-      token_account_owner.key,
-      &[&token_account_owner.key],
-    )?;
+    let decimals = spl_token::state::Mint::unpack(&x_token_mint.data.borrow())?.decimals;
 
+    debug_msg!("Creating Vault token account {}", vault_token_account.key);
+    invoke_signed(
+      &system_instruction::create_account(
+        payer.key,
+        vault_token_account.key,
+        rent.minimum_balance(spl_token::state::Account::LEN),
+        spl_token::state::Account::LEN as u64,
+        token_program.key,
+      ),
+      &[
+        payer.clone(),
+        vault_token_account.clone(),
+        system_program.clone(),
+      ],
+      &[&[
+        storage_account.key.as_ref(),
+        VAULT_TOKEN_ACCOUNT_SEED,
+        &[vault_token_account_bump_seed],
+      ]],
+    )?;
     invoke(
-      &account_owner_change_ix,
+      &spl_token::instruction::initialize_account(
+        token_program.key,
+        vault_token_account.key,
+        vault_token_mint.key,
+        &pda,
+      )?,
       &[
         vault_token_account.clone(),
-        token_account_owner.clone(),
+        vault_token_mint.clone(),
+        vault_authority.clone(),
+        rent_info.clone(),
         token_program.clone(),
       ],
     )?;
-    let internal_account = spl_token::state::Account::unpack(&vault_token_account.data.borrow()).unwrap();
-    msg!("account {} token authority/owner {}",vault_token_account.key, internal_account.owner);
 
-    msg!("Calling the token program to transfer X vault token account ownership");
-    let mint_owner_change_ix = spl_token::instruction::set_authority(
-      token_program.key,
-      llx_token_mint_id.key,
-      Some(&pda),
-      spl_token::instruction::AuthorityType::MintTokens,
-      token_account_owner.key,
-      &[&token_account_owner.key],
+    debug_msg!("Creating llX mint {} with {} decimals", llx_token_mint.key, decimals);
+    invoke_signed(
+      &system_instruction::create_account(
+        payer.key,
+        llx_token_mint.key,
+        rent.minimum_balance(spl_token::state::Mint::LEN),
+        spl_token::state::Mint::LEN as u64,
+        token_program.key,
+      ),
+      &[payer.clone(), llx_token_mint.clone(), system_program.clone()],
+      &[&[
+        storage_account.key.as_ref(),
+        LLX_TOKEN_MINT_SEED,
+        &[llx_token_mint_bump_seed],
+      ]],
     )?;
-
-    msg!("Calling the token program to transfer llX token mint authority");
-    msg!(
-      "Token program: {}. Transferring minting control {} -> {}",
-      token_program.key,
-      token_account_owner.key,
-      pda
-    );
     invoke(
-      &mint_owner_change_ix,
+      &spl_token::instruction::initialize_mint(
+        token_program.key,
+        llx_token_mint.key,
+        &pda,
+        None,
+        decimals,
+      )?,
       &[
-        llx_token_mint_id.clone(),
-        token_account_owner.clone(),
+        llx_token_mint.clone(),
+        rent_info.clone(),
         token_program.clone(),
       ],
     )?;
+
+    storage_info.vault_token_account = *vault_token_account.key;
+    storage_info.llx_token_mint_id = *llx_token_mint.key;
+    storage_info.x_token_mint = *x_token_mint.key;
+    storage_info.lx_token_mint = *vault_token_mint.key;
+    storage_info.strategy_program_id = *strategy_program.key;
+    storage_info.governance = *payer.key;
+    Self::initialize_storage(
+      storage_account,
+      storage_info,
+      hodl,
+      strategy_program_deposit_instruction_id,
+      strategy_program_withdraw_instruction_id,
+      strategy_program_estimate_instruction_id,
+      block_deposits_on_loss,
+    )
+  }
+
+  /// Initializes a Vault from an existing token account & llX mint, handing each to the Vault
+  /// authority with the signatures of its own current authority.
+  #[allow(clippy::too_many_arguments)]
+  fn process_initialize_vault_with_accounts(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    hodl: bool,
+    strategy_program_deposit_instruction_id: u8,
+    strategy_program_withdraw_instruction_id: u8,
+    strategy_program_estimate_instruction_id: u8,
+    block_deposits_on_loss: bool,
+    vault_token_account_signer_num: u8,
+    llx_token_mint_signer_num: u8,
+  ) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let governance = next_account_info(account_info_iter)?;
+    let storage_account = next_account_info(account_info_iter)?;
+    let vault_token_account = next_account_info(account_info_iter)?;
+    debug_msg!("vault_token_account {}", vault_token_account.key);
+    let llx_token_mint = next_account_info(account_info_iter)?;
+    debug_msg!("llx_token_mint {}", llx_token_mint.key);
+    let x_token_mint = next_account_info(account_info_iter)?;
+    let vault_authority = next_account_info(account_info_iter)?;
+    let token_program = next_account_info(account_info_iter)?;
+    let strategy_program = next_account_info(account_info_iter)?;
+    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
+    let vault_token_account_owner = next_account_info(account_info_iter)?;
+    let llx_token_mint_authority = next_account_info(account_info_iter)?;
+    let vault_token_account_signers =
+      next_account_infos(account_info_iter, vault_token_account_signer_num as usize)?;
+    let llx_token_mint_signers =
+      next_account_infos(account_info_iter, llx_token_mint_signer_num as usize)?;
+
+    if !governance.is_signer {
+      return Err(ProgramError::MissingRequiredSignature);
+    }
+    if *token_program.key != spl_token::id()
+      || *x_token_mint.owner != *token_program.key
+      || *vault_token_account.owner != *token_program.key
+      || *llx_token_mint.owner != *token_program.key
+    {
+      msg!("Vault accounts must be SPL Token accounts");
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    if storage_account.owner != program_id {
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    if !rent.is_exempt(storage_account.lamports(), storage_account.data_len()) {
+      return Err(VaultError::NotRentExempt.into());
+    }
+    let mut storage_info = Vault::unpack_unchecked(&storage_account.data.borrow())?;
+    if storage_info.is_initialized() {
+      return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    let (pda, _bump_seed) = VaultInstruction::find_vault_authority(program_id, storage_account.key);
+    if *vault_authority.key != pda {
+      msg!("Vault authority isn't at its derived address");
+      return Err(VaultError::AccountInconsistency.into());
+    }
+
+    // Nobody else may keep a claim on the Vault's holdings or be able to mint llX.
+    let x_mint_info = spl_token::state::Mint::unpack(&x_token_mint.data.borrow())?;
+    let vault_token_info = spl_token::state::Account::unpack(&vault_token_account.data.borrow())?;
+    let llx_mint_info = spl_token::state::Mint::unpack(&llx_token_mint.data.borrow())?;
+    if vault_token_info.amount != 0
+      || vault_token_info.delegate.is_some()
+      || vault_token_info.close_authority.is_some()
+    {
+      msg!("Vault token account {} must be empty & unencumbered", vault_token_account.key);
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    if hodl && vault_token_info.mint != *x_token_mint.key {
+      msg!("HODL Vaults hold X tokens directly");
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    if llx_mint_info.supply != 0
+      || llx_mint_info.freeze_authority.is_some()
+      || llx_mint_info.decimals != x_mint_info.decimals
+      || *llx_token_mint.key == vault_token_info.mint
+      || *llx_token_mint.key == *x_token_mint.key
+    {
+      msg!("llX mint {} must be unissued, unfrozen & match X", llx_token_mint.key);
+      return Err(VaultError::AccountInconsistency.into());
+    }
+
+    debug_msg!("Handing {} to the Vault authority", vault_token_account.key);
+    let mut set_owner_accounts = vec![
+      vault_token_account.clone(),
+      vault_token_account_owner.clone(),
+    ];
+    set_owner_accounts.extend(vault_token_account_signers.iter().cloned());
+    set_owner_accounts.push(token_program.clone());
+    invoke(
+      &spl_token::instruction::set_authority(
+        token_program.key,
+        vault_token_account.key,
+        Some(&pda),
+        spl_token::instruction::AuthorityType::AccountOwner,
+        vault_token_account_owner.key,
+        &vault_token_account_signers
+          .iter()
+          .map(|account| account.key)
+          .collect::<Vec<&Pubkey>>(),
+      )?,
+      &set_owner_accounts,
+    )?;
+    debug_msg!("Handing {} to the Vault authority", llx_token_mint.key);
+    let mut set_minter_accounts = vec![llx_token_mint.clone(), llx_token_mint_authority.clone()];
+    set_minter_accounts.extend(llx_token_mint_signers.iter().cloned());
+    set_minter_accounts.push(token_program.clone());
+    invoke(
+      &spl_token::instruction::set_authority(
+        token_program.key,
+        llx_token_mint.key,
+        Some(&pda),
+        spl_token::instruction::AuthorityType::MintTokens,
+        llx_token_mint_authority.key,
+        &llx_token_mint_signers
+          .iter()
+          .map(|account| account.key)
+          .collect::<Vec<&Pubkey>>(),
+      )?,
+      &set_minter_accounts,
+    )?;
+
+    storage_info.vault_token_account = *vault_token_account.key;
+    storage_info.llx_token_mint_id = *llx_token_mint.key;
+    storage_info.x_token_mint = *x_token_mint.key;
+    storage_info.lx_token_mint = vault_token_info.mint;
+    storage_info.strategy_program_id = *strategy_program.key;
+    storage_info.governance = *governance.key;
+    Self::initialize_storage(
+      storage_account,
+      storage_info,
+      hodl,
+      strategy_program_deposit_instruction_id,
+      strategy_program_withdraw_instruction_id,
+      strategy_program_estimate_instruction_id,
+      block_deposits_on_loss,
+    )
+  }
+
+  /// Completes `storage_info`, whose accounts & governance are set, as a new Vault & records it.
+  fn initialize_storage(
+    storage_account: &AccountInfo,
+    mut storage_info: Vault,
+    hodl: bool,
+    strategy_program_deposit_instruction_id: u8,
+    strategy_program_withdraw_instruction_id: u8,
+    strategy_program_estimate_instruction_id: u8,
+    block_deposits_on_loss: bool,
+  ) -> ProgramResult {
+    storage_info.is_initialized = true;
+    storage_info.hodl = hodl;
+    storage_info.strategy_program_deposit_instruction_id = strategy_program_deposit_instruction_id;
+    storage_info.strategy_program_withdraw_instruction_id =
+      strategy_program_withdraw_instruction_id;
+    storage_info.strategy_program_estimate_instruction_id =
+      strategy_program_estimate_instruction_id;
+    storage_info.last_estimated_value = 0;
+    storage_info.total_debt = 0;
+    storage_info.total_loss = 0;
+    storage_info.unacknowledged_loss = 0;
+    storage_info.block_deposits_on_loss = block_deposits_on_loss;
+    storage_info.withdrawal_fee_bps = 0;
+    storage_info.paused = false;
+    // Write the info to the actual account.
+    Vault::pack(storage_info, &mut storage_account.data.borrow_mut())?;
+    VaultEvent::Initialize {
+      vault: *storage_account.key,
+      governance: storage_info.governance,
+      strategy_program: storage_info.strategy_program_id,
+      x_token_mint: storage_info.x_token_mint,
+      lx_token_mint: storage_info.lx_token_mint,
+      llx_token_mint: storage_info.llx_token_mint_id,
+      hodl,
+      block_deposits_on_loss,
+    }
+    .emit();
     Ok(())
   }
 
+  /// Deposits (or withdraws) X tokens for llX tokens. Deposits are usually sized in X & withdrawals
+  /// in llX; otherwise the other side is priced, rounding in the Vault's favour. Deposits must buy
+  /// at least one & `min_shares` llX tokens.
   fn process_transfer(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
-    amount: u64,
+    amount: TransferAmount,
+    signer_num: u8,
     is_deposit: bool,
+    min_shares: u64,
   ) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
     let token_program = next_account_info(account_info_iter)?;
-    msg!("token_program {}", token_program.key);
+    debug_msg!("token_program {}", token_program.key);
     let source_token_account = next_account_info(account_info_iter)?;
-    msg!("source_token_account {}", source_token_account.key);
+    debug_msg!("source_token_account {}", source_token_account.key);
     let target_token_account = next_account_info(account_info_iter)?;
-    msg!("target_token_account {}", target_token_account.key);
+    debug_msg!("target_token_account {}", target_token_account.key);
     // Additional account metas:
-    // TODO(009): Support more than one source authority.
     let source_authority = next_account_info(account_info_iter)?;
-    msg!("source_authority {}", source_authority.key);
+    debug_msg!("source_authority {}", source_authority.key);
+    // Signers for multisig source authorities.
+    let signer_accounts = next_account_infos(account_info_iter, signer_num as usize)?;
+    let signer_pubkeys = signer_accounts
+      .iter()
+      .map(|account| account.key)
+      .collect::<Vec<&Pubkey>>();
+    let mut authority_accounts = vec![source_authority.clone()];
+    authority_accounts.extend(signer_accounts.iter().cloned());
     let storage_account = next_account_info(account_info_iter)?;
-    msg!("storage_account {}", storage_account.key);
+    debug_msg!("storage_account {}", storage_account.key);
     let strategy_program = next_account_info(account_info_iter)?;
-    msg!("strategy_program {}", strategy_program.key);
+    debug_msg!("strategy_program {}", strategy_program.key);
+    let llx_token_mint = next_account_info(account_info_iter)?;
+    let vault_authority = next_account_info(account_info_iter)?;
+    // lX token account for strategy Vaults, X token account for HODL Vaults.
+    let vault_token_account = next_account_info(account_info_iter)?;
 
-    if storage_account.owner != strategy_program.key {
-      msg!("Storage account strat not right");
+    if *token_program.key != spl_token::id() {
+      return Err(ProgramError::IncorrectProgramId);
     }
-    let storage_info = Vault::unpack_unchecked(&storage_account.data.borrow())?;
+    if storage_account.owner != program_id {
+      msg!("Storage account {} not owned by the Vault", storage_account.key);
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut storage_info = Vault::unpack_unchecked(&storage_account.data.borrow())?;
     if !storage_info.is_initialized {
       msg!(
         "Storage not configured! {} {}",
@@ -252,165 +656,673 @@ impl Processor {
       msg!("Invalid strategy program provided!");
       return Err(VaultError::InvalidInstruction.into());
     }
+    let (pda, bump_seed) = VaultInstruction::find_vault_authority(program_id, storage_account.key);
+    let authority_seeds: &[&[u8]] =
+      &[VAULT_AUTHORITY_SEED, storage_account.key.as_ref(), &[bump_seed]];
+    if *llx_token_mint.key != storage_info.llx_token_mint_id
+      || *vault_token_account.key != storage_info.vault_token_account
+      || *vault_authority.key != pda
+    {
+      msg!("Vault accounts don't match the Vault storage");
+      return Err(VaultError::AccountInconsistency.into());
+    }
 
-    // Charge fees
-    if is_deposit {
-      // TODO(001): implement.
-      msg!("Mint llX tokens to client account");
+    // Client funds only move between client X accounts, the Vault's own lX (or X) account and
+    // client llX accounts.
+    let vault_token_info = spl_token::state::Account::unpack(&vault_token_account.data.borrow())?;
+    if vault_token_info.owner != pda || vault_token_info.mint != storage_info.lx_token_mint {
+      msg!("Vault token account {} isn't the Vault's lX account", vault_token_account.key);
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    if *source_token_account.key == *vault_token_account.key
+      || *target_token_account.key == *vault_token_account.key
+    {
+      msg!("Client accounts can't be the Vault's token account");
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    let (client_x_token_account, client_llx_token_account) = if is_deposit {
+      (source_token_account, target_token_account)
     } else {
-      // TODO(002): implement.
-      msg!("Transfer & burn llX tokens from client");
+      (target_token_account, source_token_account)
+    };
+    let client_llx_info =
+      spl_token::state::Account::unpack(&client_llx_token_account.data.borrow())?;
+    if client_llx_info.mint != *llx_token_mint.key {
+      msg!("Client llX account {} has the wrong mint", client_llx_token_account.key);
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    let client_x_info = spl_token::state::Account::unpack(&client_x_token_account.data.borrow())?;
+    if client_x_info.mint != storage_info.x_token_mint {
+      msg!("Client X account {} has the wrong mint", client_x_token_account.key);
+      return Err(VaultError::AccountInconsistency.into());
     }
+    // The recipient needn't be owned by the source authority, e.g. aggregators depositing on
+    // behalf of their users, so record who it belongs to in the emitted event.
+    let recipient_owner = if is_deposit {
+      client_llx_info.owner
+    } else {
+      client_x_info.owner
+    };
 
-    let (pda, bump_seed) = Pubkey::find_program_address(&[b"vault"], program_id);
-    // Check if this is a HODL Vault; if so, we deposit & withdraw from
-    if storage_info.hodl {
-      let x_token_account = next_account_info(account_info_iter)?;
-      msg!("Calling the token program to transfer tokens");
-      if is_deposit {
+    let llx_supply = spl_token::state::Mint::unpack(&llx_token_mint.data.borrow())?.supply;
+    let vault_balance = vault_token_info.amount;
+    // Strategy holdings are only known as of the last estimate, so EstimateValue should be called
+    // immediately beforehand for accurate pricing.
+    let total_assets = inspect::total_assets(&storage_info, &vault_token_info);
+    let price_per_share = VaultEvent::price_per_share(total_assets, llx_supply);
+    // After a total loss llX can't be priced, so mint no more until the Vault holds assets again.
+    if is_deposit && llx_supply > 0 && (total_assets == 0 || vault_balance == 0) {
+      msg!("Vault has {} llX tokens outstanding but no assets", llx_supply);
+      return Err(VaultError::NoAssets.into());
+    }
+    // Mint mints exactly the shares requested.
+    let mint_shares = match (amount, is_deposit) {
+      (TransferAmount::Shares(shares), true) => Some(shares),
+      _ => None,
+    };
+    // WithdrawAssets pays out at least the X requested.
+    let withdraw_assets = match (amount, is_deposit) {
+      (TransferAmount::Assets(assets), false) => Some(assets),
+      _ => None,
+    };
+    // X deposited, or llX withdrawn.
+    let amount = match (amount, is_deposit) {
+      (TransferAmount::Assets(amount), true) | (TransferAmount::Shares(amount), false) => amount,
+      (TransferAmount::Shares(shares), true) => {
+        Vault::assets_for_mint(shares, total_assets, llx_supply).ok_or(VaultError::Overflow)?
+      }
+      (TransferAmount::Assets(assets), false) => {
+        // Burn enough llX to cover the withdrawal fee as well.
+        let assets = storage_info.withdrawal_before_fee(assets).ok_or(VaultError::Overflow)?;
+        Vault::shares_for_withdrawal(assets, total_assets, llx_supply)
+          .ok_or(VaultError::Overflow)?
+      }
+    };
+
+    // Pass through the source authority & its signers above the extra accounts. Withdrawals from
+    // strategies are authorized by the Vault.
+    let mut account_metas = if is_deposit {
+      let mut metas = vec![AccountMeta::new_readonly(
+        *source_authority.key,
+        source_authority.is_signer,
+      )];
+      metas.extend(
+        signer_accounts
+          .iter()
+          .map(|account| AccountMeta::new_readonly(*account.key, true)),
+      );
+      metas
+    } else {
+      vec![AccountMeta::new_readonly(pda, true)]
+    };
+    account_metas.extend(
+      account_info_iter
+        .map(|account| {
+          if account.is_writable {
+            AccountMeta::new(*account.key, account.is_signer)
+          } else {
+            AccountMeta::new_readonly(*account.key, account.is_signer)
+          }
+        })
+        .collect::<Vec<AccountMeta>>(),
+    );
+
+    if is_deposit {
+      if storage_info.deposits_blocked() {
+        msg!(
+          "Deposits blocked: {} loss not acknowledged",
+          storage_info.unacknowledged_loss
+        );
+        return Err(VaultError::DepositsBlockedOnLoss.into());
+      }
+      if storage_info.paused {
+        msg!("Deposits paused by governance");
+        return Err(VaultError::Paused.into());
+      }
+      if storage_info.hodl {
+        debug_msg!(
+          "Depositing {} to hodl account {}",
+          amount,
+          vault_token_account.key
+        );
         let transfer_to_vault_ix = spl_token::instruction::transfer(
           token_program.key,
           source_token_account.key,
-          x_token_account.key,
-          &source_authority.key,
-          &[&source_authority.key],
+          vault_token_account.key,
+          source_authority.key,
+          &signer_pubkeys,
           amount,
         )?;
-        msg!(
-          "Depositing {} to hodl account {}",
-          amount,
-          x_token_account.key
+        let mut transfer_accounts = vec![source_token_account.clone(), vault_token_account.clone()];
+        transfer_accounts.extend(authority_accounts);
+        transfer_accounts.push(token_program.clone());
+        invoke(&transfer_to_vault_ix, &transfer_accounts)?;
+      } else {
+        debug_msg!(
+          "Depositing into strategy {}",
+          storage_info.strategy_program_deposit_instruction_id
         );
-        invoke(
-          &transfer_to_vault_ix,
-          &[
-            source_token_account.clone(),
-            x_token_account.clone(),
-            source_authority.clone(),
-            token_program.clone(),
-          ],
+        let instruction = StrategyInstruction::deposit(
+          storage_info.strategy_program_deposit_instruction_id,
+          strategy_program.key,
+          token_program.key,
+          source_token_account.key,
+          vault_token_account.key,
+          // Pass along any additional accounts.
+          account_metas,
+          amount,
+          signer_num,
         )?;
+        invoke(&instruction, accounts)?;
+      }
+
+      // Price the deposit on the lX it added to the Vault's holdings, the same basis withdrawals
+      // are paid out on, rather than on the last estimate.
+      let received = spl_token::state::Account::unpack(&vault_token_account.data.borrow())?
+        .amount
+        .checked_sub(vault_balance)
+        .ok_or(VaultError::Overflow)?;
+      let llx_amount = if llx_supply == 0 {
+        amount
       } else {
-        msg!(
-          "Withdrawing from hodl account {} to {}. Owner {}",
-          x_token_account.key,
-          target_token_account.key,
-          pda
-        );
-        if x_token_account.owner != token_program.key|| target_token_account.owner != token_program.key {
-          msg!("Incorrect owner {} {} {}", x_token_account.owner, target_token_account.owner, token_program.key);
-        }
-        
-        msg!("Owner {} {} {}", x_token_account.owner, target_token_account.owner, token_program.key);
-        let internal_account = spl_token::state::Account::unpack(&x_token_account.data.borrow()).unwrap();
-        msg!("internal_account {}", internal_account.owner);
-        if internal_account.owner != pda {
-          msg!("Internal account owner does not match pda {}", pda);
-          return Err(VaultError::AccountInconsistency.into());
+        Vault::shares_for_deposit(received, vault_balance, llx_supply)
+          .ok_or(VaultError::Overflow)?
+      };
+      // Any rounding surplus stays with the Vault's holders.
+      let llx_amount = match mint_shares {
+        Some(shares) if llx_amount < shares => {
+          msg!("Deposit only bought {} of {} llX tokens", llx_amount, shares);
+          return Err(VaultError::ExceededSlippage.into());
         }
+        Some(shares) => shares,
+        None => llx_amount,
+      };
+      // A donation to the Vault's token account can inflate the price of llX until deposits round
+      // down to nothing, handing them to the existing holders.
+      if llx_amount == 0 || llx_amount < min_shares {
+        msg!("Deposit only bought {} llX tokens, below the minimum of {}", llx_amount, min_shares);
+        return Err(VaultError::ExceededSlippage.into());
+      }
+
+      debug_msg!("Minting {} llX tokens to {}", llx_amount, target_token_account.key);
+      let mint_ix = spl_token::instruction::mint_to(
+        token_program.key,
+        llx_token_mint.key,
+        target_token_account.key,
+        &pda,
+        &[],
+        llx_amount,
+      )?;
+      invoke_signed(
+        &mint_ix,
+        &[
+          llx_token_mint.clone(),
+          target_token_account.clone(),
+          vault_authority.clone(),
+          token_program.clone(),
+        ],
+        &[authority_seeds],
+      )?;
+
+      storage_info.total_debt = storage_info
+        .total_debt
+        .checked_add(amount)
+        .ok_or(VaultError::Overflow)?;
+      storage_info.last_estimated_value = storage_info
+        .last_estimated_value
+        .checked_add(amount)
+        .ok_or(VaultError::Overflow)?;
+      VaultEvent::Deposit {
+        vault: *storage_account.key,
+        authority: *source_authority.key,
+        recipient: recipient_owner,
+        amount,
+        shares: llx_amount,
+        price_per_share,
+      }
+      .emit();
+    } else {
+      // Withdrawals are priced against the Vault's holdings so losses are shared pro-rata. The
+      // withdrawal fee stays invested, so it's neither paid out nor taken off the Vault's books.
+      let (payout, fee, withdrawn_debt, withdrawn_value) =
+        Self::withdrawal_amounts(&storage_info, amount, vault_balance, llx_supply)?;
+
+      debug_msg!("Burning {} llX tokens from {}", amount, source_token_account.key);
+      let burn_ix = spl_token::instruction::burn(
+        token_program.key,
+        source_token_account.key,
+        llx_token_mint.key,
+        source_authority.key,
+        &signer_pubkeys,
+        amount,
+      )?;
+      let mut burn_accounts = vec![source_token_account.clone(), llx_token_mint.clone()];
+      burn_accounts.extend(authority_accounts);
+      burn_accounts.push(token_program.clone());
+      invoke(&burn_ix, &burn_accounts)?;
+
+      if storage_info.hodl {
+        debug_msg!(
+          "Withdrawing {} from hodl account {} to {}",
+          payout,
+          vault_token_account.key,
+          target_token_account.key
+        );
         let transfer_to_client_ix = spl_token::instruction::transfer(
           token_program.key,
-          x_token_account.key,
+          vault_token_account.key,
           target_token_account.key,
           &pda,
-          &[&pda],
-          amount,
+          &[],
+          payout,
         )?;
         invoke_signed(
           &transfer_to_client_ix,
           &[
-            x_token_account.clone(),
+            vault_token_account.clone(),
             target_token_account.clone(),
-            source_authority.clone(),
+            vault_authority.clone(),
             token_program.clone(),
           ],
-          &[&[&b"vault"[..], &[bump_seed]]],
+          &[authority_seeds],
         )?;
-      }
-    } else {
-      // Pass through the source authority above the extra signers.
-      let mut account_metas = vec![AccountMeta::new_readonly(*source_authority.key, true)];
-      account_metas.extend(
-        account_info_iter
-          .map(|account| {
-            if account.is_writable {
-              AccountMeta::new(*account.key, account.is_signer)
-            } else {
-              AccountMeta::new_readonly(*account.key, account.is_signer)
-            }
-          })
-          .collect::<Vec<AccountMeta>>(),
-      );
-
-      if is_deposit {
-        msg!(
-          "Depositing into strategy {}",
-          storage_info.strategy_program_deposit_instruction_id
-        );
-        let instruction = StrategyInstruction::deposit(
-          storage_info.strategy_program_deposit_instruction_id,
-          program_id,
-          &token_program.key,
-          &source_token_account.key,
-          &target_token_account.key,
-          // Pass along any additional accounts.
-          account_metas,
-          amount,
-        )?;
-        invoke(&instruction, &accounts)?;
       } else {
-        msg!(
-          "Withdrawing from strategy {}",
+        debug_msg!(
+          "Withdrawing {} lX from strategy {}",
+          payout,
           storage_info.strategy_program_withdraw_instruction_id
         );
         let instruction = StrategyInstruction::withdraw(
           storage_info.strategy_program_withdraw_instruction_id,
-          program_id,
-          &token_program.key,
-          &source_token_account.key,
-          &target_token_account.key,
+          strategy_program.key,
+          token_program.key,
+          vault_token_account.key,
+          target_token_account.key,
           // Pass along any additional accounts.
           account_metas,
-          amount,
+          payout,
+          0, // The Vault authority isn't a multisig.
         )?;
-        invoke_signed(&instruction, &accounts, &[&[&b"vault"[..], &[bump_seed]]])?;
+        invoke_signed(&instruction, accounts, &[authority_seeds])?;
+      }
+
+      // The strategy may return less X than the lX redeemed is estimated to be worth, so check
+      // what the client received against its balance from before the payout.
+      if let Some(assets) = withdraw_assets {
+        let received = spl_token::state::Account::unpack(&target_token_account.data.borrow())?
+          .amount
+          .checked_sub(client_x_info.amount)
+          .ok_or(VaultError::Overflow)?;
+        if received < assets {
+          msg!("Withdrawal only paid {} of {} X tokens", received, assets);
+          return Err(VaultError::ExceededSlippage.into());
+        }
+      }
+
+      storage_info.total_debt = storage_info
+        .total_debt
+        .checked_sub(withdrawn_debt)
+        .ok_or(VaultError::Overflow)?;
+      storage_info.last_estimated_value = storage_info
+        .last_estimated_value
+        .checked_sub(withdrawn_value)
+        .ok_or(VaultError::Overflow)?;
+      VaultEvent::Withdraw {
+        vault: *storage_account.key,
+        authority: *source_authority.key,
+        recipient: recipient_owner,
+        amount: payout,
+        fee,
+        shares: amount,
+        price_per_share,
+        in_kind: false,
       }
+      .emit();
     }
+    Vault::pack(storage_info, &mut storage_account.data.borrow_mut())?;
+    Ok(())
+  }
+
+  /// Prices a withdrawal of `shares` llX tokens, returning the payout net of the withdrawal fee,
+  /// the fee, and the debt & estimated value it takes off the Vault's books.
+  fn withdrawal_amounts(
+    storage_info: &Vault,
+    shares: u64,
+    vault_balance: u64,
+    llx_supply: u64,
+  ) -> Result<(u64, u64, u64, u64), ProgramError> {
+    let net_of_fee = |total| {
+      Vault::assets_for_shares(shares, total, llx_supply)
+        .and_then(|amount| storage_info.after_withdrawal_fee(amount))
+        .ok_or(VaultError::Overflow)
+    };
+    let gross_payout =
+      Vault::assets_for_shares(shares, vault_balance, llx_supply).ok_or(VaultError::Overflow)?;
+    let payout = storage_info
+      .after_withdrawal_fee(gross_payout)
+      .ok_or(VaultError::Overflow)?;
+    Ok((
+      payout,
+      gross_payout - payout,
+      net_of_fee(storage_info.total_debt)?,
+      net_of_fee(storage_info.last_estimated_value)?,
+    ))
+  }
+
+  fn process_zap_deposit(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount_in: u64,
+    minimum_x_out: u64,
+  ) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let input_token_account = next_account_info(account_info_iter)?;
+    let swap_program = next_account_info(account_info_iter)?;
+    let swap_info = next_account_info(account_info_iter)?;
+    let swap_authority = next_account_info(account_info_iter)?;
+    let swap_source = next_account_info(account_info_iter)?;
+    let swap_destination = next_account_info(account_info_iter)?;
+    let pool_mint = next_account_info(account_info_iter)?;
+    let pool_fee_account = next_account_info(account_info_iter)?;
+    // The remaining accounts are exactly those of a Deposit.
+    let deposit_accounts = account_info_iter.as_slice();
+    let deposit_info_iter = &mut deposit_accounts.iter();
+    let token_program = next_account_info(deposit_info_iter)?;
+    let client_x_token_account = next_account_info(deposit_info_iter)?;
+    let _client_llx_token_account = next_account_info(deposit_info_iter)?;
+    let source_authority = next_account_info(deposit_info_iter)?;
+
+    if *token_program.key != spl_token::id() || *swap_program.key != swap::id() {
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    let x_before = spl_token::state::Account::unpack(&client_x_token_account.data.borrow())?.amount;
+    debug_msg!("Swapping {} via pool {}", amount_in, swap_info.key);
+    invoke(
+      &swap::swap(
+        &swap::SwapAccounts {
+          token_program_id: token_program.key,
+          swap: swap_info.key,
+          authority: swap_authority.key,
+          user_transfer_authority: source_authority.key,
+          source: input_token_account.key,
+          swap_source: swap_source.key,
+          swap_destination: swap_destination.key,
+          destination: client_x_token_account.key,
+          pool_mint: pool_mint.key,
+          pool_fee: pool_fee_account.key,
+        },
+        amount_in,
+        minimum_x_out,
+      )?,
+      &[
+        swap_info.clone(),
+        swap_authority.clone(),
+        source_authority.clone(),
+        input_token_account.clone(),
+        swap_source.clone(),
+        swap_destination.clone(),
+        client_x_token_account.clone(),
+        pool_mint.clone(),
+        pool_fee_account.clone(),
+        token_program.clone(),
+        swap_program.clone(),
+      ],
+    )?;
+    let x_after = spl_token::state::Account::unpack(&client_x_token_account.data.borrow())?.amount;
+    let x_out = x_after.checked_sub(x_before).ok_or(VaultError::Overflow)?;
+    if x_out < minimum_x_out {
+      msg!("Swap returned {} X, expected at least {}", x_out, minimum_x_out);
+      return Err(VaultError::ExceededSlippage.into());
+    }
+
+    // Deposits the swapped X, which also checks that the client X account holds the Vault's X.
+    Self::process_transfer(program_id, deposit_accounts, TransferAmount::Assets(x_out), 0, true, 0)
+  }
+
+  fn process_withdraw_in_kind(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    signer_num: u8,
+  ) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let token_program = next_account_info(account_info_iter)?;
+    let source_token_account = next_account_info(account_info_iter)?;
+    let target_token_account = next_account_info(account_info_iter)?;
+    let source_authority = next_account_info(account_info_iter)?;
+    let signer_accounts = next_account_infos(account_info_iter, signer_num as usize)?;
+    let signer_pubkeys = signer_accounts
+      .iter()
+      .map(|account| account.key)
+      .collect::<Vec<&Pubkey>>();
+    let storage_account = next_account_info(account_info_iter)?;
+    let llx_token_mint = next_account_info(account_info_iter)?;
+    let vault_authority = next_account_info(account_info_iter)?;
+    let vault_token_account = next_account_info(account_info_iter)?;
+
+    if *token_program.key != spl_token::id() {
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    if storage_account.owner != program_id {
+      msg!("Storage account {} not owned by the Vault", storage_account.key);
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut storage_info = Vault::unpack_unchecked(&storage_account.data.borrow())?;
+    if !storage_info.is_initialized() {
+      msg!("Storage not configured!");
+      return Err(VaultError::InvalidInstruction.into());
+    }
+    let (pda, bump_seed) = VaultInstruction::find_vault_authority(program_id, storage_account.key);
+    let authority_seeds: &[&[u8]] =
+      &[VAULT_AUTHORITY_SEED, storage_account.key.as_ref(), &[bump_seed]];
+    if *llx_token_mint.key != storage_info.llx_token_mint_id
+      || *vault_token_account.key != storage_info.vault_token_account
+      || *vault_authority.key != pda
+    {
+      msg!("Vault accounts don't match the Vault storage");
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    if *target_token_account.key == *vault_token_account.key {
+      msg!("Client accounts can't be the Vault's token account");
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    let client_llx_info = spl_token::state::Account::unpack(&source_token_account.data.borrow())?;
+    if client_llx_info.mint != *llx_token_mint.key {
+      msg!("Client llX account {} has the wrong mint", source_token_account.key);
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    let client_lx_info = spl_token::state::Account::unpack(&target_token_account.data.borrow())?;
+    if client_lx_info.mint != storage_info.lx_token_mint {
+      msg!("Client lX account {} has the wrong mint", target_token_account.key);
+      return Err(VaultError::AccountInconsistency.into());
+    }
+
+    let llx_supply = spl_token::state::Mint::unpack(&llx_token_mint.data.borrow())?.supply;
+    let vault_balance = spl_token::state::Account::unpack(&vault_token_account.data.borrow())?.amount;
+    let (payout, fee, withdrawn_debt, withdrawn_value) =
+      Self::withdrawal_amounts(&storage_info, amount, vault_balance, llx_supply)?;
+
+    debug_msg!("Burning {} llX tokens from {}", amount, source_token_account.key);
+    let burn_ix = spl_token::instruction::burn(
+      token_program.key,
+      source_token_account.key,
+      llx_token_mint.key,
+      source_authority.key,
+      &signer_pubkeys,
+      amount,
+    )?;
+    let mut burn_accounts = vec![
+      source_token_account.clone(),
+      llx_token_mint.clone(),
+      source_authority.clone(),
+    ];
+    burn_accounts.extend(signer_accounts.iter().cloned());
+    burn_accounts.push(token_program.clone());
+    invoke(&burn_ix, &burn_accounts)?;
+
+    debug_msg!("Transferring {} lX tokens to {}", payout, target_token_account.key);
+    invoke_signed(
+      &spl_token::instruction::transfer(
+        token_program.key,
+        vault_token_account.key,
+        target_token_account.key,
+        &pda,
+        &[],
+        payout,
+      )?,
+      &[
+        vault_token_account.clone(),
+        target_token_account.clone(),
+        vault_authority.clone(),
+        token_program.clone(),
+      ],
+      &[authority_seeds],
+    )?;
+
+    let total_assets = if storage_info.hodl {
+      vault_balance
+    } else {
+      storage_info.last_estimated_value
+    };
+    storage_info.total_debt = storage_info
+      .total_debt
+      .checked_sub(withdrawn_debt)
+      .ok_or(VaultError::Overflow)?;
+    storage_info.last_estimated_value = storage_info
+      .last_estimated_value
+      .checked_sub(withdrawn_value)
+      .ok_or(VaultError::Overflow)?;
+    Vault::pack(storage_info, &mut storage_account.data.borrow_mut())?;
+    VaultEvent::Withdraw {
+      vault: *storage_account.key,
+      authority: *source_authority.key,
+      recipient: client_lx_info.owner,
+      amount: payout,
+      fee,
+      shares: amount,
+      price_per_share: VaultEvent::price_per_share(total_assets, llx_supply),
+      in_kind: true,
+    }
+    .emit();
     Ok(())
   }
 
   fn process_estimate_value(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-    msg!(
+    debug_msg!(
       "Estimate Value!--------------------------------------------------------------------------"
     );
     let account_info_iter = &mut accounts.iter();
     let _ = next_account_info(account_info_iter)?; // program
     let temp_memory_account = next_account_info(account_info_iter)?;
+    let (_, value) =
+      Self::estimate_value(program_id, accounts, temp_memory_account, account_info_iter)?;
+
+    Self::write_shared_memory(program_id, temp_memory_account, &value.to_le_bytes())?;
+    // Return the estimate as well so that the caller needn't read shared memory.
+    set_return_data(&value.to_le_bytes());
+    Ok(())
+  }
+
+  /// Values the llX position held in a token account, so that this Vault can be used as another
+  /// Vault's strategy. The Vault's value is estimated first, exactly as by EstimateValue.
+  fn process_estimate_position_value(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+  ) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let _ = next_account_info(account_info_iter)?; // program
+    let temp_memory_account = next_account_info(account_info_iter)?;
+    let position_token_account = next_account_info(account_info_iter)?;
+    let outer_storage_account = next_account_info(account_info_iter)?;
+    let llx_token_mint = next_account_info(account_info_iter)?;
+    let (storage_info, value) =
+      Self::estimate_value(program_id, accounts, temp_memory_account, account_info_iter)?;
+
+    // Only ever value an outer Vault's own holding, so callers can't forge nested valuations by
+    // passing some other llX account.
+    if outer_storage_account.owner != program_id {
+      msg!("Outer storage account {} not owned by the Vault", outer_storage_account.key);
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    let outer_storage_info = Vault::unpack_unchecked(&outer_storage_account.data.borrow())?;
+    if !outer_storage_info.is_initialized()
+      || *position_token_account.key != outer_storage_info.vault_token_account
+    {
+      msg!("Position account {} isn't the outer Vault's token account", position_token_account.key);
+      return Err(VaultError::AccountInconsistency.into());
+    }
+
+    if *llx_token_mint.key != storage_info.llx_token_mint_id {
+      msg!("Invalid llX mint provided {}", llx_token_mint.key);
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    if *position_token_account.owner != spl_token::id() {
+      msg!("Position account {} not owned by SPL Token", position_token_account.key);
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    let position = spl_token::state::Account::unpack(&position_token_account.data.borrow())?;
+    if position.mint != storage_info.llx_token_mint_id {
+      msg!("Position account {} has the wrong mint", position_token_account.key);
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    let (outer_authority, _) =
+      VaultInstruction::find_vault_authority(program_id, outer_storage_account.key);
+    if position.owner != outer_authority {
+      msg!("Position account {} isn't owned by the outer Vault", position_token_account.key);
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    let llx_supply = spl_token::state::Mint::unpack(&llx_token_mint.data.borrow())?.supply;
+    let position_value =
+      Vault::assets_for_shares(position.amount, value, llx_supply).ok_or(VaultError::Overflow)?;
+    debug_msg!("Position of {} llX is worth {}", position.amount, position_value);
+
+    Self::write_shared_memory(program_id, temp_memory_account, &position_value.to_le_bytes())?;
+    set_return_data(&position_value.to_le_bytes());
+    Ok(())
+  }
+
+  /// Estimates & records the value of the Vault whose storage account is next in
+  /// `account_info_iter`, returning its updated storage & the value.
+  fn estimate_value(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    temp_memory_account: &AccountInfo,
+    account_info_iter: &mut std::slice::Iter<AccountInfo>,
+  ) -> Result<(Vault, u64), ProgramError> {
     let storage_account = next_account_info(account_info_iter)?;
 
-    msg!("Unpacking storage {}", storage_account.key);
-    let storage_info = Vault::unpack_unchecked(&storage_account.data.borrow())?;
-    msg!("Unpacked storage");
+    if storage_account.owner != program_id {
+      msg!("Storage account {} not owned by the Vault", storage_account.key);
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    debug_msg!("Unpacking storage {}", storage_account.key);
+    let mut storage_info = Vault::unpack_unchecked(&storage_account.data.borrow())?;
+    debug_msg!("Unpacked storage");
     if !storage_info.is_initialized() {
       msg!("Storage not configured!");
       return Err(VaultError::InvalidInstruction.into());
     }
 
-    if storage_info.hodl {
-      // Derive the value directly from the storage account.
-      let x_token_account = next_account_info(account_info_iter)?;
-      let internal_account =
-        spl_token::state::Account::unpack_unchecked(&x_token_account.data.borrow()).unwrap();
-      msg!(
+    // Only the Vault's own holdings are ever valued, whatever the caller passes as extra accounts.
+    let vault_token_account = next_account_info(account_info_iter)?;
+    if *vault_token_account.key != storage_info.vault_token_account
+      || *vault_token_account.owner != spl_token::id()
+    {
+      msg!("Invalid Vault token account provided {}", vault_token_account.key);
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    let vault_token_info = spl_token::state::Account::unpack(&vault_token_account.data.borrow())?;
+    if vault_token_info.mint != storage_info.lx_token_mint {
+      msg!("Vault token account {} has the wrong mint", vault_token_account.key);
+      return Err(VaultError::AccountInconsistency.into());
+    }
+
+    let value = if storage_info.hodl {
+      debug_msg!(
         "Estimating value from HODL vault: {}",
-        internal_account.amount
+        vault_token_info.amount
       );
-      let instruction = VaultInstruction::write_data(
-        program_id,
-        temp_memory_account.key,
-        &internal_account.amount.to_le_bytes(),
-      )?;
-      invoke(&instruction, &accounts)?;
+      vault_token_info.amount
     } else {
       // Estimating value from a strategy.
       let strategy_program = next_account_info(account_info_iter)?;
@@ -431,7 +1343,7 @@ impl Processor {
           }
         })
         .collect::<Vec<AccountMeta>>();
-      msg!(
+      debug_msg!(
         "Estimating value on strategy program! {}",
         storage_info.strategy_program_estimate_instruction_id
       );
@@ -440,28 +1352,466 @@ impl Processor {
         strategy_program.key,
         program_id,
         temp_memory_account.key,
+        vault_token_account.key,
         account_metas,
       )?;
-      invoke(&instruction, &accounts)?;
+      invoke(&instruction, accounts)?;
+      // Strategies can't reenter this program to write their estimate, so they return it instead.
+      match get_return_data() {
+        Some((returning_program_id, data)) if returning_program_id == *strategy_program.key => {
+          let value: [u8; 8] = data
+            .as_slice()
+            .try_into()
+            .map_err(|_| VaultError::MissingStrategyEstimate)?;
+          u64::from_le_bytes(value)
+        }
+        _ => {
+          msg!("Strategy {} did not return an estimate", strategy_program.key);
+          return Err(VaultError::MissingStrategyEstimate.into());
+        }
+      }
+    };
+
+    storage_info.last_estimated_value = value;
+    let loss = storage_info.total_debt.saturating_sub(value);
+    if loss > 0 {
+      debug_msg!("Recording loss of {} against debt {}", loss, storage_info.total_debt);
+      storage_info.total_loss = storage_info
+        .total_loss
+        .checked_add(loss)
+        .ok_or(VaultError::Overflow)?;
+      storage_info.unacknowledged_loss = storage_info
+        .unacknowledged_loss
+        .checked_add(loss)
+        .ok_or(VaultError::Overflow)?;
+      storage_info.total_debt = value;
+    }
+    Vault::pack(storage_info, &mut storage_account.data.borrow_mut())?;
+    VaultEvent::Estimate {
+      vault: *storage_account.key,
+      value,
+      total_debt: storage_info.total_debt,
+      loss,
     }
+    .emit();
+    Ok((storage_info, value))
+  }
+
+  fn process_acknowledge_loss(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let governance = next_account_info(account_info_iter)?;
+    let storage_account = next_account_info(account_info_iter)?;
+
+    if storage_account.owner != program_id {
+      msg!("Storage account {} not owned by the Vault", storage_account.key);
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut storage_info = Vault::unpack_unchecked(&storage_account.data.borrow())?;
+    if !storage_info.is_initialized() {
+      msg!("Storage not configured!");
+      return Err(VaultError::InvalidInstruction.into());
+    }
+    if *governance.key != storage_info.governance {
+      return Err(VaultError::InvalidAuthority.into());
+    }
+    if !governance.is_signer {
+      return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    debug_msg!("Acknowledging loss of {}", storage_info.unacknowledged_loss);
+    VaultEvent::AcknowledgeLoss {
+      vault: *storage_account.key,
+      governance: *governance.key,
+      amount: storage_info.unacknowledged_loss,
+    }
+    .emit();
+    storage_info.unacknowledged_loss = 0;
+    Vault::pack(storage_info, &mut storage_account.data.borrow_mut())?;
+    Ok(())
+  }
+
+  fn process_register_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let governance = next_account_info(account_info_iter)?;
+    let storage_account = next_account_info(account_info_iter)?;
+    let entry_account = next_account_info(account_info_iter)?;
+    let registry_account = next_account_info(account_info_iter)?;
+    let system_program = next_account_info(account_info_iter)?;
+
+    let storage_info = Self::unpack_governed_vault(program_id, governance, storage_account)?;
+    let entry_bump_seed =
+      Self::check_registered_vault(program_id, entry_account, storage_account, &storage_info)?;
+    let registry_bump_seed =
+      Self::check_vault_registry(program_id, registry_account, &storage_info)?;
+    let rent = Rent::get()?;
+    if entry_account.data_is_empty() {
+      debug_msg!("Creating registry entry {}", entry_account.key);
+      invoke_signed(
+        &system_instruction::create_account(
+          governance.key,
+          entry_account.key,
+          rent.minimum_balance(RegisteredVault::LEN),
+          RegisteredVault::LEN as u64,
+          program_id,
+        ),
+        &[
+          governance.clone(),
+          entry_account.clone(),
+          system_program.clone(),
+        ],
+        &[&[
+          VAULT_REGISTRY_SEED,
+          storage_info.x_token_mint.as_ref(),
+          storage_account.key.as_ref(),
+          &[entry_bump_seed],
+        ]],
+      )?;
+    } else {
+      if entry_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+      }
+      if RegisteredVault::unpack(&entry_account.data.borrow())?.status == VaultStatus::Active {
+        return Err(VaultError::AlreadyRegistered.into());
+      }
+      debug_msg!("Reactivating Vault {}", storage_account.key);
+    }
+
+    RegisteredVault {
+      x_token_mint: storage_info.x_token_mint,
+      vault_storage_account: *storage_account.key,
+      strategy_program_id: storage_info.strategy_program_id,
+      hodl: storage_info.hodl,
+      status: VaultStatus::Active,
+    }
+    .pack(&mut entry_account.data.borrow_mut())?;
+
+    let mut registry = if registry_account.data_is_empty() {
+      debug_msg!("Creating Vault registry {}", registry_account.key);
+      let space = VaultRegistry::space(0);
+      invoke_signed(
+        &system_instruction::create_account(
+          governance.key,
+          registry_account.key,
+          rent.minimum_balance(space),
+          space as u64,
+          program_id,
+        ),
+        &[
+          governance.clone(),
+          registry_account.clone(),
+          system_program.clone(),
+        ],
+        &[&[
+          VAULT_REGISTRY_SEED,
+          storage_info.x_token_mint.as_ref(),
+          &[registry_bump_seed],
+        ]],
+      )?;
+      VaultRegistry {
+        x_token_mint: storage_info.x_token_mint,
+        vaults: vec![],
+      }
+    } else {
+      if registry_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+      }
+      VaultRegistry::unpack(&registry_account.data.borrow())?
+    };
+    registry.vaults.push(*storage_account.key);
+    let space = VaultRegistry::space(registry.vaults.len());
+    let top_up = rent
+      .minimum_balance(space)
+      .saturating_sub(registry_account.lamports());
+    if top_up > 0 {
+      invoke(
+        &system_instruction::transfer(governance.key, registry_account.key, top_up),
+        &[
+          governance.clone(),
+          registry_account.clone(),
+          system_program.clone(),
+        ],
+      )?;
+    }
+    registry_account.realloc(space, false)?;
+    registry.pack(&mut registry_account.data.borrow_mut())?;
+
+    VaultEvent::Register {
+      vault: *storage_account.key,
+      governance: *governance.key,
+      x_token_mint: storage_info.x_token_mint,
+    }
+    .emit();
+    Ok(())
+  }
+
+  fn process_deregister_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let governance = next_account_info(account_info_iter)?;
+    let storage_account = next_account_info(account_info_iter)?;
+    let entry_account = next_account_info(account_info_iter)?;
+    let registry_account = next_account_info(account_info_iter)?;
+
+    let storage_info = Self::unpack_governed_vault(program_id, governance, storage_account)?;
+    Self::check_registered_vault(program_id, entry_account, storage_account, &storage_info)?;
+    Self::check_vault_registry(program_id, registry_account, &storage_info)?;
+    if entry_account.owner != program_id || registry_account.owner != program_id {
+      return Err(VaultError::NotRegistered.into());
+    }
+    let mut entry = RegisteredVault::unpack(&entry_account.data.borrow())?;
+    if entry.status != VaultStatus::Active {
+      return Err(VaultError::NotRegistered.into());
+    }
+    entry.status = VaultStatus::Retired;
+    entry.pack(&mut entry_account.data.borrow_mut())?;
+
+    let mut registry = VaultRegistry::unpack(&registry_account.data.borrow())?;
+    let index = registry
+      .vaults
+      .iter()
+      .position(|vault| vault == storage_account.key)
+      .ok_or(VaultError::NotRegistered)?;
+    registry.vaults.swap_remove(index);
+    let space = VaultRegistry::space(registry.vaults.len());
+    registry_account.realloc(space, false)?;
+    registry.pack(&mut registry_account.data.borrow_mut())?;
+    // Refund the rent of the freed space.
+    let surplus = registry_account
+      .lamports()
+      .saturating_sub(Rent::get()?.minimum_balance(space));
+    **registry_account.try_borrow_mut_lamports()? -= surplus;
+    **governance.try_borrow_mut_lamports()? += surplus;
+
+    VaultEvent::Deregister {
+      vault: *storage_account.key,
+      governance: *governance.key,
+      x_token_mint: storage_info.x_token_mint,
+    }
+    .emit();
+    Ok(())
+  }
+
+  fn process_set_withdrawal_fee(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fee_bps: u16,
+  ) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let governance = next_account_info(account_info_iter)?;
+    let storage_account = next_account_info(account_info_iter)?;
+
+    let mut storage_info = Self::unpack_governed_vault(program_id, governance, storage_account)?;
+    if fee_bps > MAX_WITHDRAWAL_FEE_BPS {
+      msg!("Withdrawal fee {} exceeds {} bps", fee_bps, MAX_WITHDRAWAL_FEE_BPS);
+      return Err(VaultError::InvalidFee.into());
+    }
+    storage_info.withdrawal_fee_bps = fee_bps;
+    Vault::pack(storage_info, &mut storage_account.data.borrow_mut())?;
+    VaultEvent::SetWithdrawalFee {
+      vault: *storage_account.key,
+      governance: *governance.key,
+      fee_bps,
+    }
+    .emit();
+    Ok(())
+  }
+
+  fn process_set_paused(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    paused: bool,
+  ) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let governance = next_account_info(account_info_iter)?;
+    let storage_account = next_account_info(account_info_iter)?;
+
+    let mut storage_info = Self::unpack_governed_vault(program_id, governance, storage_account)?;
+    storage_info.paused = paused;
+    Vault::pack(storage_info, &mut storage_account.data.borrow_mut())?;
+    VaultEvent::SetPaused {
+      vault: *storage_account.key,
+      governance: *governance.key,
+      paused,
+    }
+    .emit();
     Ok(())
   }
 
-  fn process_write_data(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+  /// Unpacks the storage of a Vault, checking that `governance` is its governance & a signer.
+  fn unpack_governed_vault(
+    program_id: &Pubkey,
+    governance: &AccountInfo,
+    storage_account: &AccountInfo,
+  ) -> Result<Vault, ProgramError> {
+    if storage_account.owner != program_id {
+      msg!("Storage account {} not owned by the Vault", storage_account.key);
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    let storage_info = Vault::unpack_unchecked(&storage_account.data.borrow())?;
+    if !storage_info.is_initialized() {
+      msg!("Storage not configured!");
+      return Err(VaultError::InvalidInstruction.into());
+    }
+    if *governance.key != storage_info.governance {
+      return Err(VaultError::InvalidAuthority.into());
+    }
+    if !governance.is_signer {
+      return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(storage_info)
+  }
+
+  /// Checks that `registry_account` is the Vault's registry entry, returning its bump seed.
+  fn check_registered_vault(
+    program_id: &Pubkey,
+    entry_account: &AccountInfo,
+    storage_account: &AccountInfo,
+    storage_info: &Vault,
+  ) -> Result<u8, ProgramError> {
+    let (entry_id, bump_seed) = VaultInstruction::find_registered_vault(
+      program_id,
+      &storage_info.x_token_mint,
+      storage_account.key,
+    );
+    if *entry_account.key != entry_id {
+      msg!("Invalid registry entry provided {}", entry_account.key);
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    Ok(bump_seed)
+  }
+
+  fn check_vault_registry(
+    program_id: &Pubkey,
+    registry_account: &AccountInfo,
+    storage_info: &Vault,
+  ) -> Result<u8, ProgramError> {
+    let (registry_id, bump_seed) =
+      VaultInstruction::find_vault_registry(program_id, &storage_info.x_token_mint);
+    if *registry_account.key != registry_id {
+      msg!("Invalid Vault registry provided {}", registry_account.key);
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    Ok(bump_seed)
+  }
+
+  fn process_preview_vault(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
     let account_info_iter = &mut accounts.iter();
-    // TODO(Security): Ensure we don't screw with the other storage accounts. This should probably
-    // be moved to a separate program, like the Shared Memory program...
+    let (storage_info, vault_token_info, llx_mint_info) =
+      Self::unpack_preview_accounts(program_id, account_info_iter)?;
+    let client_llx_info = match next_account_info(account_info_iter).ok() {
+      Some(account) => {
+        let info = spl_token::state::Account::unpack(&account.data.borrow())?;
+        if info.mint != storage_info.llx_token_mint_id {
+          msg!("Client llX account {} has the wrong mint", account.key);
+          return Err(VaultError::AccountInconsistency.into());
+        }
+        Some(info)
+      }
+      None => None,
+    };
+
+    let preview = inspect::preview(
+      &storage_info,
+      &vault_token_info,
+      &llx_mint_info,
+      client_llx_info.as_ref(),
+    );
+    let data = borsh::to_vec(&preview).map_err(|e| ProgramError::BorshIoError(e.to_string()))?;
+    set_return_data(&data);
+    Ok(())
+  }
+
+  /// Writes the amount computed by `preview` from the Vault's accounts to return data.
+  fn process_preview_amount(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    preview: impl FnOnce(
+      &Vault,
+      &spl_token::state::Account,
+      &spl_token::state::Mint,
+    ) -> Result<u64, VaultError>,
+  ) -> ProgramResult {
+    let (storage_info, vault_token_info, llx_mint_info) =
+      Self::unpack_preview_accounts(program_id, &mut accounts.iter())?;
+    let amount = preview(&storage_info, &vault_token_info, &llx_mint_info)?;
+    set_return_data(&amount.to_le_bytes());
+    Ok(())
+  }
+
+  /// As `process_preview_amount`, for previews of a client llX token account, which follows the
+  /// Preview accounts.
+  fn process_client_preview_amount(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    preview: impl FnOnce(
+      &Vault,
+      &spl_token::state::Account,
+      &spl_token::state::Mint,
+      &spl_token::state::Account,
+    ) -> Result<u64, VaultError>,
+  ) -> ProgramResult {
+    let account_info_iter = &mut accounts.iter();
+    let (storage_info, vault_token_info, llx_mint_info) =
+      Self::unpack_preview_accounts(program_id, account_info_iter)?;
+    let client_llx_token_account = next_account_info(account_info_iter)?;
+    let client_llx_info =
+      spl_token::state::Account::unpack(&client_llx_token_account.data.borrow())?;
+    if client_llx_info.mint != storage_info.llx_token_mint_id {
+      msg!("Client llX account {} has the wrong mint", client_llx_token_account.key);
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    let amount = preview(&storage_info, &vault_token_info, &llx_mint_info, &client_llx_info)?;
+    set_return_data(&amount.to_le_bytes());
+    Ok(())
+  }
+
+  /// Unpacks the Vault storage, Vault token account & llX mint expected by the Preview
+  /// instructions.
+  fn unpack_preview_accounts<'a, 'b: 'a>(
+    program_id: &Pubkey,
+    account_info_iter: &mut impl Iterator<Item = &'a AccountInfo<'b>>,
+  ) -> Result<(Vault, spl_token::state::Account, spl_token::state::Mint), ProgramError> {
     let storage_account = next_account_info(account_info_iter)?;
-    if storage_account.lamports() > 0 {
-      msg!("Data should only be written to temporary accounts");
-      // return Err(VaultError::InvalidInstruction.into()); TODO
+    let llx_token_mint = next_account_info(account_info_iter)?;
+    let vault_token_account = next_account_info(account_info_iter)?;
+
+    if storage_account.owner != program_id {
+      msg!("Storage account {} not owned by the Vault", storage_account.key);
+      return Err(ProgramError::IncorrectProgramId);
+    }
+    let storage_info = Vault::unpack_unchecked(&storage_account.data.borrow())?;
+    if !storage_info.is_initialized() {
+      msg!("Storage not configured!");
+      return Err(VaultError::InvalidInstruction.into());
+    }
+    if *llx_token_mint.key != storage_info.llx_token_mint_id
+      || *vault_token_account.key != storage_info.vault_token_account
+    {
+      msg!("Vault accounts don't match the Vault storage");
+      return Err(VaultError::AccountInconsistency.into());
+    }
+    let llx_mint_info = spl_token::state::Mint::unpack(&llx_token_mint.data.borrow())?;
+    let vault_token_info = spl_token::state::Account::unpack(&vault_token_account.data.borrow())?;
+    Ok((storage_info, vault_token_info, llx_mint_info))
+  }
+
+  /// Writes an estimate into a temporary account, as the (unlaunched) Shared Memory program would.
+  ///
+  /// The account must be owned by the Vault program & sized exactly for `data`, so Vault storage &
+  /// registry entries, which are always larger, can never be overwritten.
+  fn write_shared_memory(
+    program_id: &Pubkey,
+    shared_memory_account: &AccountInfo,
+    data: &[u8],
+  ) -> ProgramResult {
+    if shared_memory_account.owner != program_id {
+      msg!("Shared memory account must be owned by the Vault program");
+      return Err(ProgramError::IncorrectProgramId);
     }
-    if storage_account.data_len() < data.len() {
-      msg!("Need more space in storage account");
-      return Err(ProgramError::InvalidArgument);
+    if shared_memory_account.data_len() != data.len() {
+      msg!("Shared memory account must hold exactly {} bytes", data.len());
+      return Err(ProgramError::InvalidAccountData);
     }
-    // Write data into the temporary account storage.
-    storage_account.data.borrow_mut().clone_from_slice(data);
+    shared_memory_account.data.borrow_mut().clone_from_slice(data);
     Ok(())
   }
 }
@@ -0,0 +1,87 @@
+//! Machine-readable layouts of the Vault's instructions & storage.
+//!
+//! [json] renders the Borsh schemas of [VaultInstruction], [Vault], [RegisteredVault],
+//! [VaultRegistry], PreviewVault's return data ([VaultPreview]) & logged [VaultEvent]s as JSON so
+//! clients can derive their encoders instead of mirroring instruction.rs & state.rs by hand. The
+//! published copy lives in client/src/vault_schema.json; regenerate it with
+//! `cargo run --example schema --features schema`.
+use crate::{
+    events::VaultEvent,
+    inspect::VaultPreview,
+    instruction::VaultInstruction,
+    state::{RegisteredVault, Vault, VaultRegistry},
+};
+use borsh::schema::{BorshSchemaContainer, Definition, Fields};
+use serde_json::{json, Value};
+
+/// The schemas of [VaultInstruction], [Vault], [VaultPreview], [RegisteredVault], [VaultRegistry] &
+/// [VaultEvent], keyed by "instruction", "state", "preview", "registry", "vault_registry" &
+/// "event".
+pub fn json() -> String {
+    let schemas = json!({
+        "instruction": container_json(&BorshSchemaContainer::for_type::<VaultInstruction>()),
+        "state": container_json(&BorshSchemaContainer::for_type::<Vault>()),
+        "preview": container_json(&BorshSchemaContainer::for_type::<VaultPreview>()),
+        "registry": container_json(&BorshSchemaContainer::for_type::<RegisteredVault>()),
+        "vault_registry": container_json(&BorshSchemaContainer::for_type::<VaultRegistry>()),
+        "event": container_json(&BorshSchemaContainer::for_type::<VaultEvent>()),
+    });
+    format!("{}\n", serde_json::to_string_pretty(&schemas).unwrap())
+}
+
+fn container_json(container: &BorshSchemaContainer) -> Value {
+    let definitions = container
+        .definitions()
+        .map(|(declaration, definition)| (declaration.clone(), definition_json(definition)))
+        .collect::<serde_json::Map<_, _>>();
+    json!({
+        "declaration": container.declaration(),
+        "definitions": definitions,
+    })
+}
+
+fn definition_json(definition: &Definition) -> Value {
+    match definition {
+        Definition::Primitive(size) => json!({"kind": "primitive", "size": size}),
+        Definition::Sequence {
+            length_width,
+            length_range,
+            elements,
+        } => json!({
+            "kind": "sequence",
+            "length_width": length_width,
+            "length_range": [length_range.start(), length_range.end()],
+            "elements": elements,
+        }),
+        Definition::Tuple { elements } => json!({"kind": "tuple", "elements": elements}),
+        Definition::Enum {
+            tag_width,
+            variants,
+        } => json!({
+            "kind": "enum",
+            "tag_width": tag_width,
+            "variants": variants
+                .iter()
+                .map(|(discriminant, name, declaration)| json!({
+                    "discriminant": discriminant,
+                    "name": name,
+                    "declaration": declaration,
+                }))
+                .collect::<Vec<_>>(),
+        }),
+        Definition::Struct { fields } => {
+            let fields = match fields {
+                Fields::NamedFields(fields) => fields
+                    .iter()
+                    .map(|(name, declaration)| json!({"name": name, "declaration": declaration}))
+                    .collect::<Vec<_>>(),
+                Fields::UnnamedFields(fields) => fields
+                    .iter()
+                    .map(|declaration| json!({ "declaration": declaration }))
+                    .collect::<Vec<_>>(),
+                Fields::Empty => vec![],
+            };
+            json!({"kind": "struct", "fields": fields})
+        }
+    }
+}
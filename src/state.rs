@@ -1,17 +1,24 @@
-use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use borsh::{BorshDeserialize, BorshSchema, BorshSerialize};
 use solana_program::{
     program_error::ProgramError,
-    program_option::COption,
     program_pack::{IsInitialized, Pack, Sealed},
     pubkey::Pubkey,
 };
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+/// Vault storage, Borsh-encoded. See client/src/vault_schema.json for the machine-readable layout.
+#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
 pub struct Vault {
     pub is_initialized: bool,
     pub hodl: bool,
     pub llx_token_mint_id: Pubkey,
-    pub vault_token_account: Pubkey, // Either an X or lX token account.
+    // The underlying asset (X) mint. Client X accounts are checked against this on every transfer.
+    pub x_token_mint: Pubkey,
+    // The strategy derivative (lX) mint, i.e. the mint of vault_token_account. Equal to
+    // x_token_mint for HODL Vaults.
+    pub lx_token_mint: Pubkey,
+    // The Vault's lX account (X if hodling), owned by the Vault's PDA. Deposits & withdrawals are
+    // only ever routed through this account.
+    pub vault_token_account: Pubkey,
     // TODO(012): Calculate this dynamically & return with Shared Memory program.
     // This value is only valid so long as the EstimateValue function was called immediately before.
     pub last_estimated_value: u64,
@@ -19,92 +26,138 @@ pub struct Vault {
     pub strategy_program_deposit_instruction_id: u8,
     pub strategy_program_withdraw_instruction_id: u8,
     pub strategy_program_estimate_instruction_id: u8,
-    pub strategy_data_account: COption<Pubkey>,
+    pub strategy_data_account: Option<Pubkey>,
+    // TODO(007): Separate governance from the initializer.
+    pub governance: Pubkey,
+    // X placed into the strategy (or HODL account) net of withdrawals & recognized losses.
+    pub total_debt: u64,
+    // Cumulative losses recognized when an estimate came back below total_debt.
+    pub total_loss: u64,
+    // Losses which governance hasn't yet acknowledged via AcknowledgeLoss.
+    pub unacknowledged_loss: u64,
+    pub block_deposits_on_loss: bool,
+    // Fee in basis points of each withdrawal, left in the Vault for the remaining llX holders.
+    pub withdrawal_fee_bps: u16,
+    // Set by governance to stop deposits. Withdrawals stay open so holders can always exit.
+    pub paused: bool,
+}
+
+/// Basis points in a whole, i.e. a fee of BPS_SCALE takes everything.
+pub const BPS_SCALE: u64 = 10_000;
+
+/// The highest withdrawal fee governance may set: 10%.
+pub const MAX_WITHDRAWAL_FEE_BPS: u16 = 1_000;
+
+impl Vault {
+    /// Returns the number of llX tokens minted for depositing `amount` X tokens into a Vault
+    /// holding `total_assets` with `llx_supply` llX tokens outstanding.
+    ///
+    /// Returns None if llX is outstanding but the Vault holds nothing, e.g. after a total loss, as
+    /// llX can't then be priced.
+    pub fn shares_for_deposit(amount: u64, total_assets: u64, llx_supply: u64) -> Option<u64> {
+        if llx_supply == 0 {
+            return Some(amount);
+        }
+        mul_div(amount, llx_supply, total_assets)
+    }
+
+    /// Returns the pro-rata portion of `total` owned by `shares` of `llx_supply` llX tokens.
+    ///
+    /// Used to price withdrawals against the Vault's actual holdings so that any losses are shared
+    /// by all llX holders.
+    pub fn assets_for_shares(shares: u64, total: u64, llx_supply: u64) -> Option<u64> {
+        if shares > llx_supply {
+            return None;
+        }
+        if llx_supply == 0 {
+            return Some(0);
+        }
+        mul_div(shares, total, llx_supply)
+    }
+
+    /// Returns the X tokens which must be deposited to mint at least `shares` llX tokens, i.e. the
+    /// inverse of [shares_for_deposit](Self::shares_for_deposit), rounded up.
+    ///
+    /// Returns None if llX is outstanding but the Vault holds nothing.
+    pub fn assets_for_mint(shares: u64, total_assets: u64, llx_supply: u64) -> Option<u64> {
+        if llx_supply == 0 {
+            return Some(shares);
+        }
+        if total_assets == 0 {
+            return None;
+        }
+        mul_div_ceil(shares, total_assets, llx_supply)
+    }
+
+    /// Returns the llX tokens which must be burned to withdraw at least `amount` of `total_assets`,
+    /// i.e. the inverse of [assets_for_shares](Self::assets_for_shares), rounded up.
+    pub fn shares_for_withdrawal(amount: u64, total_assets: u64, llx_supply: u64) -> Option<u64> {
+        if amount == 0 {
+            return Some(0);
+        }
+        let shares = mul_div_ceil(amount, llx_supply, total_assets)?;
+        if shares > llx_supply {
+            return None;
+        }
+        Some(shares)
+    }
+
+    pub fn deposits_blocked(&self) -> bool {
+        self.block_deposits_on_loss && self.unacknowledged_loss > 0
+    }
+
+    /// Returns the fee kept from a withdrawal paying out `payout` before fees, rounded up.
+    pub fn withdrawal_fee(&self, payout: u64) -> Option<u64> {
+        mul_div_ceil(payout, self.withdrawal_fee_bps as u64, BPS_SCALE)
+    }
+
+    /// Returns what's left of `payout` once the withdrawal fee is kept.
+    pub fn after_withdrawal_fee(&self, payout: u64) -> Option<u64> {
+        payout.checked_sub(self.withdrawal_fee(payout)?)
+    }
+
+    /// Returns the payout before fees which leaves at least `amount` after the withdrawal fee, i.e.
+    /// the inverse of deducting [withdrawal_fee](Self::withdrawal_fee), rounded up.
+    pub fn withdrawal_before_fee(&self, amount: u64) -> Option<u64> {
+        let net_scale = BPS_SCALE.checked_sub(self.withdrawal_fee_bps as u64)?;
+        mul_div_ceil(amount, BPS_SCALE, net_scale)
+    }
+}
+
+fn mul_div(a: u64, b: u64, c: u64) -> Option<u64> {
+    let result = (a as u128).checked_mul(b as u128)?.checked_div(c as u128)?;
+    if result > u64::MAX as u128 {
+        return None;
+    }
+    Some(result as u64)
+}
+
+fn mul_div_ceil(a: u64, b: u64, c: u64) -> Option<u64> {
+    if c == 0 {
+        return None;
+    }
+    // u128::div_ceil needs a newer toolchain than BPF builds use.
+    let product = (a as u128).checked_mul(b as u128)?;
+    let result = product / c as u128 + (product % c as u128 != 0) as u128;
+    if result > u64::MAX as u128 {
+        return None;
+    }
+    Some(result as u64)
 }
 
 impl Sealed for Vault {}
 
 impl Pack for Vault {
-    const LEN: usize = 1 + 1 + 32 + 32 + 8 + 32 + 1 + 1 + 1 + 36;
+    /// The largest serialized size, i.e. with a strategy_data_account set.
+    const LEN: usize =
+        1 + 1 + 32 + 32 + 32 + 32 + 8 + 32 + 1 + 1 + 1 + 33 + 32 + 8 + 8 + 8 + 1 + 2 + 1;
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let src = array_ref![src, 0, Vault::LEN];
-        let (
-            is_initialized,
-            hodl,
-            llx_token_mint_id,
-            vault_token_account,
-            last_estimated_value,
-            strategy_program_id,
-            strategy_program_deposit_instruction_id,
-            strategy_program_withdraw_instruction_id,
-            strategy_program_estimate_instruction_id,
-            strategy_data_account,
-        ) = array_refs![src, 1, 1, 32, 32, 8, 32, 1, 1, 1, 36];
-
-        let hodl = match hodl {
-            [0] => false,
-            [1] => true,
-            _ => return Err(ProgramError::InvalidAccountData),
-        };
-        let is_initialized = match is_initialized {
-            [0] => false,
-            [1] => true,
-            _ => return Err(ProgramError::InvalidAccountData),
-        };
-
-        let strategy_data_account = unpack_coption_key(strategy_data_account)?;
-        Ok(Vault {
-            is_initialized,
-            hodl,
-            llx_token_mint_id: Pubkey::new_from_array(*llx_token_mint_id),
-            vault_token_account: Pubkey::new_from_array(*vault_token_account),
-            last_estimated_value: u64::from_le_bytes(*last_estimated_value),
-            strategy_program_id: Pubkey::new_from_array(*strategy_program_id),
-            strategy_program_deposit_instruction_id: strategy_program_deposit_instruction_id[0],
-            strategy_program_withdraw_instruction_id: strategy_program_withdraw_instruction_id[0],
-            strategy_program_estimate_instruction_id: strategy_program_estimate_instruction_id[0],
-            strategy_data_account,
-        })
+        // Trailing bytes are left over when strategy_data_account is unset.
+        Self::deserialize(&mut &src[..]).map_err(|_| ProgramError::InvalidAccountData)
     }
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        let dst = array_mut_ref![dst, 0, Vault::LEN];
-        let (
-            is_initialized_dst,
-            hodl_dst,
-            llx_token_mint_id_dst,
-            vault_token_account_dst,
-            last_estimated_value_dst,
-            strategy_program_id_dst,
-            strategy_program_deposit_instruction_id_dst,
-            strategy_program_withdraw_instruction_id_dst,
-            strategy_program_estimate_instruction_id_dst,
-            strategy_data_account_dst,
-        ) = mut_array_refs![dst, 1, 1, 32, 32, 8, 32, 1, 1, 1, 36];
-
-        let Vault {
-            is_initialized,
-            hodl,
-            llx_token_mint_id,
-            vault_token_account,
-            last_estimated_value,
-            strategy_program_id,
-            strategy_program_deposit_instruction_id,
-            strategy_program_withdraw_instruction_id,
-            strategy_program_estimate_instruction_id,
-            strategy_data_account,
-        } = self;
-
-        is_initialized_dst[0] = *is_initialized as u8;
-        hodl_dst[0] = *hodl as u8;
-        llx_token_mint_id_dst.copy_from_slice(llx_token_mint_id.as_ref());
-        vault_token_account_dst.copy_from_slice(vault_token_account.as_ref());
-        strategy_program_id_dst.copy_from_slice(strategy_program_id.as_ref());
-        last_estimated_value_dst.copy_from_slice(&last_estimated_value.to_le_bytes());
-        strategy_program_deposit_instruction_id_dst[0] = *strategy_program_deposit_instruction_id;
-        strategy_program_withdraw_instruction_id_dst[0] = *strategy_program_withdraw_instruction_id;
-        strategy_program_estimate_instruction_id_dst[0] = *strategy_program_estimate_instruction_id;
-        pack_coption_key(strategy_data_account, strategy_data_account_dst);
+        self.serialize(&mut &mut dst[..]).unwrap();
     }
 }
 
@@ -114,25 +167,166 @@ impl IsInitialized for Vault {
     }
 }
 
-// Borrowed: https://docs.rs/spl-token/3.1.0/src/spl_token/state.rs.html#249
-fn pack_coption_key(src: &COption<Pubkey>, dst: &mut [u8; 36]) {
-    let (tag, body) = mut_array_refs![dst, 4, 32];
-    match src {
-        COption::Some(key) => {
-            *tag = [1, 0, 0, 0];
-            body.copy_from_slice(key.as_ref());
-        }
-        COption::None => {
-            *tag = [0; 4];
-        }
+/// Whether a registered Vault is in service.
+#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub enum VaultStatus {
+    Active,
+    /// Deregistered, e.g. while shutting down. The Vault leaves its mint's [VaultRegistry] but its
+    /// entry is kept so that holders can still find it to withdraw.
+    Retired,
+}
+
+/// A Vault registered for its X mint, Borsh-encoded at the address given by
+/// `VaultInstruction::find_registered_vault`. Active Vaults are also listed in their mint's
+/// [VaultRegistry]; look entries up from there.
+///
+/// Anyone can create & register a Vault, so clients should vet entries before trusting them.
+#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct RegisteredVault {
+    pub x_token_mint: Pubkey,
+    pub vault_storage_account: Pubkey,
+    pub strategy_program_id: Pubkey,
+    pub hodl: bool,
+    pub status: VaultStatus,
+}
+
+impl RegisteredVault {
+    pub const LEN: usize = 32 + 32 + 32 + 1 + 1;
+
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    pub fn pack(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        self.serialize(&mut &mut dst[..])
+            .map_err(|_| ProgramError::AccountDataTooSmall)
     }
 }
 
-fn unpack_coption_key(src: &[u8; 36]) -> Result<COption<Pubkey>, ProgramError> {
-    let (tag, body) = array_refs![src, 4, 32];
-    match *tag {
-        [0, 0, 0, 0] => Ok(COption::None),
-        [1, 0, 0, 0] => Ok(COption::Some(Pubkey::new_from_array(*body))),
-        _ => Err(ProgramError::InvalidAccountData),
+/// The storage accounts of an X mint's Active Vaults, Borsh-encoded at the address given by
+/// `VaultInstruction::find_vault_registry`. Registering a Vault grows it by one key and
+/// deregistering swap-removes the Vault's key, so the order isn't stable.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize, BorshSchema)]
+pub struct VaultRegistry {
+    pub x_token_mint: Pubkey,
+    pub vaults: Vec<Pubkey>,
+}
+
+impl VaultRegistry {
+    /// The serialized size of a registry listing `num_vaults` Vaults.
+    pub fn space(num_vaults: usize) -> usize {
+        32 + 4 + num_vaults * 32
+    }
+
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        Self::try_from_slice(src).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    pub fn pack(&self, dst: &mut [u8]) -> Result<(), ProgramError> {
+        self.serialize(&mut &mut dst[..])
+            .map_err(|_| ProgramError::AccountDataTooSmall)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul_div() {
+        assert_eq!(mul_div(10, 3, 4), Some(7));
+        assert_eq!(mul_div(u64::MAX, u64::MAX, u64::MAX), Some(u64::MAX));
+        assert_eq!(mul_div(u64::MAX, 2, 1), None);
+        assert_eq!(mul_div(1, 1, 0), None);
+
+        assert_eq!(mul_div_ceil(10, 3, 4), Some(8));
+        assert_eq!(mul_div_ceil(12, 3, 4), Some(9));
+        assert_eq!(mul_div_ceil(0, 3, 4), Some(0));
+        assert_eq!(mul_div_ceil(u64::MAX, u64::MAX, u64::MAX), Some(u64::MAX));
+        assert_eq!(mul_div_ceil(u64::MAX, 2, 1), None);
+        assert_eq!(mul_div_ceil(1, 1, 0), None);
+    }
+
+    #[test]
+    fn test_shares_for_deposit() {
+        // The first deposit mints 1:1.
+        assert_eq!(Vault::shares_for_deposit(100, 0, 0), Some(100));
+        assert_eq!(Vault::shares_for_deposit(100, 50, 0), Some(100));
+        // Later deposits mint pro-rata, rounding down.
+        assert_eq!(Vault::shares_for_deposit(100, 200, 100), Some(50));
+        assert_eq!(Vault::shares_for_deposit(3, 200, 100), Some(1));
+        // llX can't be priced after a total loss.
+        assert_eq!(Vault::shares_for_deposit(100, 0, 100), None);
+        assert_eq!(Vault::shares_for_deposit(u64::MAX, 1, 2), None);
+    }
+
+    #[test]
+    fn test_assets_for_shares() {
+        assert_eq!(Vault::assets_for_shares(50, 200, 100), Some(100));
+        assert_eq!(Vault::assets_for_shares(1, 3, 2), Some(1));
+        assert_eq!(Vault::assets_for_shares(100, 0, 100), Some(0));
+        assert_eq!(Vault::assets_for_shares(0, 0, 0), Some(0));
+        assert_eq!(Vault::assets_for_shares(101, 200, 100), None);
+    }
+
+    #[test]
+    fn test_assets_for_mint() {
+        assert_eq!(Vault::assets_for_mint(100, 0, 0), Some(100));
+        assert_eq!(Vault::assets_for_mint(50, 200, 100), Some(100));
+        // Rounds up, so the deposit always mints at least the requested shares.
+        assert_eq!(Vault::assets_for_mint(1, 3, 2), Some(2));
+        assert_eq!(
+            Vault::shares_for_deposit(Vault::assets_for_mint(1, 3, 2).unwrap(), 3, 2),
+            Some(1)
+        );
+        assert_eq!(Vault::assets_for_mint(100, 0, 100), None);
+    }
+
+    #[test]
+    fn test_shares_for_withdrawal() {
+        assert_eq!(Vault::shares_for_withdrawal(0, 0, 0), Some(0));
+        assert_eq!(Vault::shares_for_withdrawal(100, 200, 100), Some(50));
+        // Rounds up, so the withdrawal always burns enough shares for the requested assets.
+        assert_eq!(Vault::shares_for_withdrawal(1, 3, 2), Some(1));
+        assert_eq!(Vault::assets_for_shares(1, 3, 2), Some(1));
+        assert_eq!(Vault::shares_for_withdrawal(201, 200, 100), None);
+        assert_eq!(Vault::shares_for_withdrawal(1, 0, 100), None);
+    }
+
+    #[test]
+    fn test_withdrawal_fee() {
+        let mut vault = Vault::unpack_unchecked(&[0; Vault::LEN]).unwrap();
+        assert_eq!(vault.withdrawal_fee(1000), Some(0));
+        assert_eq!(vault.withdrawal_before_fee(1000), Some(1000));
+
+        vault.withdrawal_fee_bps = 50;
+        assert_eq!(vault.withdrawal_fee(1000), Some(5));
+        // Rounds up, so the Vault never under-charges.
+        assert_eq!(vault.withdrawal_fee(1), Some(1));
+        assert_eq!(vault.after_withdrawal_fee(1000), Some(995));
+        assert_eq!(vault.after_withdrawal_fee(1), Some(0));
+        assert_eq!(vault.withdrawal_before_fee(995), Some(1000));
+        for amount in [1, 7, 995, 5000, u64::MAX / BPS_SCALE].iter() {
+            let gross = vault.withdrawal_before_fee(*amount).unwrap();
+            assert!(vault.after_withdrawal_fee(gross).unwrap() >= *amount);
+        }
+
+        vault.withdrawal_fee_bps = MAX_WITHDRAWAL_FEE_BPS;
+        assert_eq!(vault.withdrawal_fee(1000), Some(100));
+        assert_eq!(vault.withdrawal_before_fee(900), Some(1000));
+    }
+
+    #[test]
+    fn test_vault_registry_space() {
+        let mut registry = VaultRegistry {
+            x_token_mint: Pubkey::new_unique(),
+            vaults: vec![],
+        };
+        for num_vaults in 0..3 {
+            let mut data = vec![0; VaultRegistry::space(num_vaults)];
+            registry.pack(&mut data).unwrap();
+            assert_eq!(VaultRegistry::unpack(&data), Ok(registry.clone()));
+            registry.vaults.push(Pubkey::new_unique());
+        }
     }
 }
@@ -0,0 +1,101 @@
+//! Minimal client for the SPL Token Swap program, used by ZapDeposit.
+//!
+//! Only the Swap instruction is needed, so it's encoded here rather than pulling the swap program
+//! (& its dependencies) into the Vault. Mirrors `spl_token_swap::instruction::swap`.
+//! https://github.com/solana-labs/solana-program-library/blob/master/token-swap/program/src/instruction.rs
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+use std::mem::size_of;
+
+// The SPL Token Swap program; ZapDeposit only swaps through pools it owns.
+solana_program::declare_id!("SwapsVeCiPHMUAtzQWZw7RjsKjgCjhwU55QGu4U1Szw");
+
+/// SwapInstruction::Swap
+const SWAP: u8 = 1;
+
+/// The accounts of a token-swap Swap instruction.
+pub struct SwapAccounts<'a> {
+    pub token_program_id: &'a Pubkey,
+    /// The token-swap pool.
+    pub swap: &'a Pubkey,
+    /// The pool's authority.
+    pub authority: &'a Pubkey,
+    pub user_transfer_authority: &'a Pubkey,
+    /// The user's account to swap from.
+    pub source: &'a Pubkey,
+    /// The pool's account for the source token.
+    pub swap_source: &'a Pubkey,
+    /// The pool's account for the destination token.
+    pub swap_destination: &'a Pubkey,
+    /// The user's account to swap into.
+    pub destination: &'a Pubkey,
+    pub pool_mint: &'a Pubkey,
+    pub pool_fee: &'a Pubkey,
+}
+
+/// Creates a token-swap Swap instruction.
+///
+/// Accounts expected by the swap program:
+/// 0. `[]` Token-swap
+/// 1. `[]` swap authority
+/// 2. `[signer]` user transfer authority
+/// 3. `[writeable]` token_(A|B) SOURCE Account, amount is transferable by user transfer authority
+/// 4. `[writeable]` token_(A|B) Base Account to swap INTO. Must be the SOURCE token.
+/// 5. `[writeable]` token_(A|B) Base Account to swap FROM. Must be the DESTINATION token.
+/// 6. `[writeable]` token_(A|B) DESTINATION Account assigned to USER as the owner.
+/// 7. `[writeable]` Pool token mint, to generate trading fees
+/// 8. `[writeable]` Fee account, to receive trading fees
+/// 9. `[]` Token program id
+pub fn swap(
+    accounts: &SwapAccounts,
+    amount_in: u64,
+    minimum_amount_out: u64,
+) -> Result<Instruction, ProgramError> {
+    let mut data = Vec::with_capacity(1 + 2 * size_of::<u64>());
+    data.push(SWAP);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+
+    let accounts = vec![
+        AccountMeta::new_readonly(*accounts.swap, false),
+        AccountMeta::new_readonly(*accounts.authority, false),
+        AccountMeta::new_readonly(*accounts.user_transfer_authority, true),
+        AccountMeta::new(*accounts.source, false),
+        AccountMeta::new(*accounts.swap_source, false),
+        AccountMeta::new(*accounts.swap_destination, false),
+        AccountMeta::new(*accounts.destination, false),
+        AccountMeta::new(*accounts.pool_mint, false),
+        AccountMeta::new(*accounts.pool_fee, false),
+        AccountMeta::new_readonly(*accounts.token_program_id, false),
+    ];
+
+    Ok(Instruction {
+        program_id: id(),
+        accounts,
+        data,
+    })
+}
+
+/// Account metas for the token-swap pool used by VaultInstruction#ZapDeposit, in the order the
+/// Vault expects them.
+pub fn zap_account_metas(
+    swap_pubkey: &Pubkey,
+    authority_pubkey: &Pubkey,
+    swap_source_pubkey: &Pubkey,
+    swap_destination_pubkey: &Pubkey,
+    pool_mint_pubkey: &Pubkey,
+    pool_fee_pubkey: &Pubkey,
+) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new_readonly(id(), false),
+        AccountMeta::new_readonly(*swap_pubkey, false),
+        AccountMeta::new_readonly(*authority_pubkey, false),
+        AccountMeta::new(*swap_source_pubkey, false),
+        AccountMeta::new(*swap_destination_pubkey, false),
+        AccountMeta::new(*pool_mint_pubkey, false),
+        AccountMeta::new(*pool_fee_pubkey, false),
+    ]
+}
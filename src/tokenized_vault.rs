@@ -0,0 +1,257 @@
+//! An ERC-4626-style interface to Vaults.
+//!
+//! [TokenizedVault] builds the standard tokenized-vault operations over VaultInstruction, so other
+//! protocols can treat any Vault as a yield-bearing asset without learning its account ordering.
+//! The asset is X & the share token llX. Operations map onto VaultInstruction as follows:
+//!
+//! | ERC-4626          | VaultInstruction      |
+//! |-------------------|-----------------------|
+//! | `deposit`         | Deposit               |
+//! | `mint`            | Mint                  |
+//! | `withdraw`        | WithdrawAssets        |
+//! | `redeem`          | Withdraw              |
+//! | `totalAssets`     | PreviewVault          |
+//! | `convertToShares` | ConvertToShares       |
+//! | `convertToAssets` | ConvertToAssets       |
+//! | `maxDeposit`      | MaxDeposit            |
+//! | `maxMint`         | MaxMint               |
+//! | `maxWithdraw`     | MaxWithdraw           |
+//! | `maxRedeem`       | MaxRedeem             |
+//! | `previewDeposit`  | PreviewDeposit        |
+//! | `previewMint`     | PreviewMint           |
+//! | `previewWithdraw` | PreviewWithdrawAssets |
+//! | `previewRedeem`   | PreviewWithdraw       |
+//!
+//! Views report their result as a little-endian u64 in return data (PreviewVault a Borsh-encoded
+//! [VaultPreview](../inspect/struct.VaultPreview.html)); simulate them to read it. Off-chain, the
+//! same values can be computed directly with [inspect](../inspect/index.html).
+//!
+//! Unlike ERC-4626, tokens are held in token accounts rather than by their owners' addresses:
+//! `owner` is the authority of the client token account tokens are taken from, and tokens are paid
+//! to the `receiver_*_token_account` given, which may belong to anyone.
+use crate::{
+    instruction::{transfer_account_metas, VaultInstruction},
+    state::Vault,
+};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// A Vault & the accounts its strategy needs, for building ERC-4626-style instructions.
+///
+/// Owners sign directly: use VaultInstruction for SPL Token multisig owners.
+pub struct TokenizedVault {
+    pub program_id: Pubkey,
+    pub vault_storage_account: Pubkey,
+    pub vault: Vault,
+    /// Strategy extra accounts, passed along by deposits & withdrawals.
+    pub strategy_account_metas: Vec<AccountMeta>,
+}
+
+impl TokenizedVault {
+    pub fn new(
+        program_id: &Pubkey,
+        vault_storage_account: &Pubkey,
+        vault: Vault,
+        strategy_account_metas: Vec<AccountMeta>,
+    ) -> Self {
+        TokenizedVault {
+            program_id: *program_id,
+            vault_storage_account: *vault_storage_account,
+            vault,
+            strategy_account_metas,
+        }
+    }
+
+    /// Deposits `assets` X tokens from `owner`'s `client_x_token_account`, minting llX tokens to
+    /// `receiver_llx_token_account`.
+    pub fn deposit(
+        &self,
+        assets: u64,
+        owner: &Pubkey,
+        client_x_token_account: &Pubkey,
+        receiver_llx_token_account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        VaultInstruction::deposit(
+            &self.program_id,
+            &spl_token::id(),
+            client_x_token_account,
+            receiver_llx_token_account,
+            self.transfer_account_metas(owner),
+            assets,
+            0,
+            0,
+        )
+    }
+
+    /// Mints exactly `shares` llX tokens to `receiver_llx_token_account`, paid for from `owner`'s
+    /// `client_x_token_account`.
+    pub fn mint(
+        &self,
+        shares: u64,
+        owner: &Pubkey,
+        client_x_token_account: &Pubkey,
+        receiver_llx_token_account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        VaultInstruction::mint(
+            &self.program_id,
+            &spl_token::id(),
+            client_x_token_account,
+            receiver_llx_token_account,
+            self.transfer_account_metas(owner),
+            shares,
+            0,
+        )
+    }
+
+    /// Withdraws at least `assets` X tokens to `receiver_x_token_account`, burning llX tokens from
+    /// `owner`'s `client_llx_token_account`.
+    pub fn withdraw(
+        &self,
+        assets: u64,
+        owner: &Pubkey,
+        client_llx_token_account: &Pubkey,
+        receiver_x_token_account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        VaultInstruction::withdraw_assets(
+            &self.program_id,
+            &spl_token::id(),
+            client_llx_token_account,
+            receiver_x_token_account,
+            self.transfer_account_metas(owner),
+            assets,
+            0,
+        )
+    }
+
+    /// Redeems `shares` llX tokens from `owner`'s `client_llx_token_account`, paying X tokens to
+    /// `receiver_x_token_account`.
+    pub fn redeem(
+        &self,
+        shares: u64,
+        owner: &Pubkey,
+        client_llx_token_account: &Pubkey,
+        receiver_x_token_account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        VaultInstruction::withdraw(
+            &self.program_id,
+            &spl_token::id(),
+            client_llx_token_account,
+            receiver_x_token_account,
+            self.transfer_account_metas(owner),
+            shares,
+            0,
+        )
+    }
+
+    pub fn total_assets(&self) -> Result<Instruction, ProgramError> {
+        VaultInstruction::preview_vault(
+            &self.program_id,
+            &self.vault_storage_account,
+            &self.vault,
+            None,
+        )
+    }
+
+    pub fn convert_to_shares(&self, assets: u64) -> Result<Instruction, ProgramError> {
+        VaultInstruction::convert_to_shares(
+            &self.program_id,
+            &self.vault_storage_account,
+            &self.vault,
+            assets,
+        )
+    }
+
+    pub fn convert_to_assets(&self, shares: u64) -> Result<Instruction, ProgramError> {
+        VaultInstruction::convert_to_assets(
+            &self.program_id,
+            &self.vault_storage_account,
+            &self.vault,
+            shares,
+        )
+    }
+
+    pub fn max_deposit(&self) -> Result<Instruction, ProgramError> {
+        VaultInstruction::max_deposit(&self.program_id, &self.vault_storage_account, &self.vault)
+    }
+
+    pub fn max_mint(&self) -> Result<Instruction, ProgramError> {
+        VaultInstruction::max_mint(&self.program_id, &self.vault_storage_account, &self.vault)
+    }
+
+    /// The most X tokens the holder of `client_llx_token_account` can withdraw.
+    pub fn max_withdraw(
+        &self,
+        client_llx_token_account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        VaultInstruction::max_withdraw(
+            &self.program_id,
+            &self.vault_storage_account,
+            &self.vault,
+            client_llx_token_account,
+        )
+    }
+
+    /// The most llX tokens the holder of `client_llx_token_account` can redeem.
+    pub fn max_redeem(
+        &self,
+        client_llx_token_account: &Pubkey,
+    ) -> Result<Instruction, ProgramError> {
+        VaultInstruction::max_redeem(
+            &self.program_id,
+            &self.vault_storage_account,
+            &self.vault,
+            client_llx_token_account,
+        )
+    }
+
+    pub fn preview_deposit(&self, assets: u64) -> Result<Instruction, ProgramError> {
+        VaultInstruction::preview_deposit(
+            &self.program_id,
+            &self.vault_storage_account,
+            &self.vault,
+            assets,
+        )
+    }
+
+    pub fn preview_mint(&self, shares: u64) -> Result<Instruction, ProgramError> {
+        VaultInstruction::preview_mint(
+            &self.program_id,
+            &self.vault_storage_account,
+            &self.vault,
+            shares,
+        )
+    }
+
+    pub fn preview_withdraw(&self, assets: u64) -> Result<Instruction, ProgramError> {
+        VaultInstruction::preview_withdraw_assets(
+            &self.program_id,
+            &self.vault_storage_account,
+            &self.vault,
+            assets,
+        )
+    }
+
+    pub fn preview_redeem(&self, shares: u64) -> Result<Instruction, ProgramError> {
+        VaultInstruction::preview_withdraw(
+            &self.program_id,
+            &self.vault_storage_account,
+            &self.vault,
+            shares,
+        )
+    }
+
+    /// Accounts 4+ of Deposit & Withdraw.
+    fn transfer_account_metas(&self, owner: &Pubkey) -> Vec<AccountMeta> {
+        let mut accounts = vec![AccountMeta::new_readonly(*owner, true)];
+        accounts.extend(transfer_account_metas(
+            &self.program_id,
+            &self.vault_storage_account,
+            &self.vault,
+            self.strategy_account_metas.clone(),
+        ));
+        accounts
+    }
+}
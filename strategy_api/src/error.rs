@@ -1,8 +1,10 @@
+use num_enum::TryFromPrimitive;
 use thiserror::Error;
 
 use solana_program::program_error::ProgramError;
 
-#[derive(Error, Debug, Copy, Clone)]
+#[derive(Error, Debug, Copy, Clone, TryFromPrimitive)]
+#[repr(u32)]
 pub enum StrategyError {
     #[error("Invalid Instruction")]
     InvalidInstruction,
@@ -1,2 +1,3 @@
+pub mod strategy;
 pub mod strategy_instruction;
 pub mod error;
\ No newline at end of file
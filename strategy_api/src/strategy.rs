@@ -0,0 +1,167 @@
+use crate::error::StrategyError::InvalidInstruction;
+use crate::strategy_instruction::{StrategyInstruction, DEPOSIT, ESTIMATE_VALUE, WITHDRAW};
+use solana_program::{
+  account_info::{next_account_info, next_account_infos, AccountInfo},
+  entrypoint::ProgramResult,
+  msg,
+  program::set_return_data,
+  program_error::ProgramError,
+  pubkey::Pubkey,
+};
+
+/// Accounts passed to StrategyInstruction#Deposit and StrategyInstruction#Withdraw.
+///
+/// Mirrors the account layout documented on StrategyInstruction.
+pub struct TransferAccounts<'a, 'b> {
+  pub token_program: &'a AccountInfo<'b>,
+  /// X token account on deposit, lX token account on withdraw.
+  pub source_token_account: &'a AccountInfo<'b>,
+  /// lX token account on deposit, X token account on withdraw.
+  pub target_token_account: &'a AccountInfo<'b>,
+  /// Either a signer or an SPL Token multisig account.
+  pub source_authority: &'a AccountInfo<'b>,
+  /// Signers of the source authority if it's a multisig, otherwise empty.
+  pub signer_accounts: &'a [AccountInfo<'b>],
+  /// Any additional accounts required by the strategy, in the order they were passed.
+  pub extra_accounts: &'a [AccountInfo<'b>],
+}
+
+impl<'a, 'b> TransferAccounts<'a, 'b> {
+  pub fn parse(accounts: &'a [AccountInfo<'b>], signer_num: u8) -> Result<Self, ProgramError> {
+    let account_info_iter = &mut accounts.iter();
+    let token_program = next_account_info(account_info_iter)?;
+    let source_token_account = next_account_info(account_info_iter)?;
+    let target_token_account = next_account_info(account_info_iter)?;
+    let source_authority = next_account_info(account_info_iter)?;
+    let signer_accounts = next_account_infos(account_info_iter, signer_num as usize)?;
+    Ok(TransferAccounts {
+      token_program,
+      source_token_account,
+      target_token_account,
+      source_authority,
+      signer_accounts,
+      extra_accounts: &accounts[4 + signer_num as usize..],
+    })
+  }
+
+  /// Multisig signer pubkeys, as expected by SPL Token instruction builders.
+  pub fn signer_pubkeys(&self) -> Vec<&Pubkey> {
+    self.signer_accounts.iter().map(|account| account.key).collect()
+  }
+
+  /// The source authority followed by any multisig signers, for passing to `invoke`.
+  pub fn authority_accounts(&self) -> Vec<AccountInfo<'b>> {
+    let mut accounts = vec![self.source_authority.clone()];
+    accounts.extend(self.signer_accounts.iter().cloned());
+    accounts
+  }
+}
+
+/// Accounts passed to StrategyInstruction#EstimateValue.
+pub struct EstimateValueAccounts<'a, 'b> {
+  /// The Vault program, which owns the shared memory account.
+  pub vault_program: &'a AccountInfo<'b>,
+  pub shared_memory_account: &'a AccountInfo<'b>,
+  /// The calling Vault's lX token account, the only position a strategy should value.
+  pub position_token_account: &'a AccountInfo<'b>,
+  /// Any additional accounts required by the strategy, in the order they were passed.
+  pub extra_accounts: &'a [AccountInfo<'b>],
+}
+
+impl<'a, 'b> EstimateValueAccounts<'a, 'b> {
+  pub fn parse(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+    let account_info_iter = &mut accounts.iter();
+    let vault_program = next_account_info(account_info_iter)?;
+    let shared_memory_account = next_account_info(account_info_iter)?;
+    let position_token_account = next_account_info(account_info_iter)?;
+    Ok(EstimateValueAccounts {
+      vault_program,
+      shared_memory_account,
+      position_token_account,
+      extra_accounts: &accounts[3..],
+    })
+  }
+
+  /// Reports `value` back to the calling Vault.
+  ///
+  /// Strategies can't call back into the Vault which invoked them (Solana disallows reentrancy), so
+  /// the value is returned via return data and the Vault copies it into the shared memory account.
+  pub fn write_value(&self, value: u64) -> ProgramResult {
+    set_return_data(&value.to_le_bytes());
+    Ok(())
+  }
+}
+
+/// A strategy implementation.
+///
+/// Strategists implement this trait and route their entrypoint through [process_instruction]; all
+/// instruction & account parsing is handled here. Strategies which share a program with other
+/// instructions can override the instruction IDs below and handle anything else in
+/// `process_custom_instruction`.
+pub trait Strategy {
+  const DEPOSIT_INSTRUCTION_ID: u8 = DEPOSIT;
+  const WITHDRAW_INSTRUCTION_ID: u8 = WITHDRAW;
+  const ESTIMATE_VALUE_INSTRUCTION_ID: u8 = ESTIMATE_VALUE;
+
+  /// Moves `amount` X tokens from the source account into the strategy and credits the target
+  /// account with lX tokens.
+  fn deposit(program_id: &Pubkey, accounts: &TransferAccounts, amount: u64) -> ProgramResult;
+
+  /// Redeems `amount` lX tokens from the source account and sends the X tokens to the target.
+  fn withdraw(program_id: &Pubkey, accounts: &TransferAccounts, amount: u64) -> ProgramResult;
+
+  /// Returns the current value of the lX held in `accounts.position_token_account`, denominated
+  /// in X.
+  fn estimate_value(
+    program_id: &Pubkey,
+    accounts: &EstimateValueAccounts,
+  ) -> Result<u64, ProgramError>;
+
+  /// Handles any instruction which isn't part of the StrategyInstruction interface.
+  fn process_custom_instruction(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+  ) -> ProgramResult {
+    Err(InvalidInstruction.into())
+  }
+}
+
+/// Dispatches a StrategyInstruction to the corresponding [Strategy] hook.
+///
+/// Estimated values are written back to the calling Vault automatically.
+pub fn process_instruction<S: Strategy>(
+  program_id: &Pubkey,
+  accounts: &[AccountInfo],
+  instruction_data: &[u8],
+) -> ProgramResult {
+  let (tag, _) = instruction_data
+    .split_first()
+    .ok_or(InvalidInstruction)?;
+  let instruction = if *tag == S::DEPOSIT_INSTRUCTION_ID {
+    StrategyInstruction::unpack(instruction_data, DEPOSIT)?
+  } else if *tag == S::WITHDRAW_INSTRUCTION_ID {
+    StrategyInstruction::unpack(instruction_data, WITHDRAW)?
+  } else if *tag == S::ESTIMATE_VALUE_INSTRUCTION_ID {
+    StrategyInstruction::unpack(instruction_data, ESTIMATE_VALUE)?
+  } else {
+    return S::process_custom_instruction(program_id, accounts, instruction_data);
+  };
+
+  match instruction {
+    StrategyInstruction::Deposit { amount, signer_num } => {
+      msg!("StrategyInstruction: Deposit {}", amount);
+      S::deposit(program_id, &TransferAccounts::parse(accounts, signer_num)?, amount)
+    }
+    StrategyInstruction::Withdraw { amount, signer_num } => {
+      msg!("StrategyInstruction: Withdraw {}", amount);
+      S::withdraw(program_id, &TransferAccounts::parse(accounts, signer_num)?, amount)
+    }
+    StrategyInstruction::EstimateValue {} => {
+      msg!("StrategyInstruction: EstimateValue");
+      let accounts = EstimateValueAccounts::parse(accounts)?;
+      let value = S::estimate_value(program_id, &accounts)?;
+      accounts.write_value(value)
+    }
+  }
+}
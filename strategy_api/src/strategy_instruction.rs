@@ -3,7 +3,6 @@ use solana_program::program_error::ProgramError;
 use solana_program::{
   instruction::{AccountMeta, Instruction},
   pubkey::Pubkey,
-  sysvar,
 };
 
 use std::convert::TryInto;
@@ -16,24 +15,25 @@ pub enum StrategyInstruction {
   ///
   /// Accounts expected:
   /// 1. `[]` SPL Token program
-  /// 2. `[signer]` The source wallet containing X tokens.
-  /// 3. `[]` The target wallet for llX tokens.
-  /// 4+ `[]` Source signers
-  /// 5+. `[*]` Strategy extra accounts - any additional accounts required by strategy
-  /// TODO(009):: Signer pubkeys for multisignature wallets - need signer_num param.
-  Deposit { amount: u64 },
+  /// 2. `[writeable]` The source wallet containing X tokens.
+  /// 3. `[writeable]` The target wallet for lX tokens.
+  /// 4. `[]` Source authority; a signer unless it's an SPL Token multisig.
+  /// 5. `[signer]` `signer_num` multisig signers, if any.
+  /// 6. `[*]` The remaining accounts: any additional accounts required by the strategy
+  Deposit { amount: u64, signer_num: u8 },
 
   /// Withdraws a token from the strategy.
   ///
   /// Accounts expected:
   /// 1. `[]` SPL Token program
-  /// 2. `[signer]` Source Wallet for derivative token (lX).
-  /// 3. `[]` Target token (X) wallet target.
-  /// 4+ `[]` Source signers
-  /// 5+. `[*]` Strategy extra accounts - any additional accounts required by strategy
-  /// TODO(009):: Signer pubkeys for multisignature wallets - need signer_num param.
+  /// 2. `[writeable]` Source Wallet for derivative token (lX).
+  /// 3. `[writeable]` Target token (X) wallet target.
+  /// 4. `[]` Source authority; a signer unless it's an SPL Token multisig.
+  /// 5. `[signer]` `signer_num` multisig signers, if any.
+  /// 6. `[*]` The remaining accounts: any additional accounts required by the strategy
   Withdraw {
     amount: u64, // # of derivative tokens.
+    signer_num: u8,
   },
 
   /// Estimates the underlying value of the vault in its native asset.
@@ -41,10 +41,14 @@ pub enum StrategyInstruction {
   /// This instruction stores its results in a temporary account using the Shared Memory program.
   /// https://spl.solana.com/shared-memory
   ///
+  /// Only the position in the calling Vault's own lX token account is valued; the Vault passes
+  /// it in a fixed slot so that callers can't substitute another account.
+  ///
   /// Accounts expected:
   /// 1. `[]` Vault program
-  /// 1. `[]` Shared memory output
-  /// 3+. `[*]` Strategy extra accounts - any additional accounts required by strategy
+  /// 2. `[writeable]` Shared memory output
+  /// 3. `[]` The calling Vault's lX token account.
+  /// 4. `[*]` The remaining accounts: any additional accounts required by the strategy
   EstimateValue {},
 }
 
@@ -64,12 +68,14 @@ impl StrategyInstruction {
         .and_then(|slice| slice.try_into().ok())
         .map(u64::from_le_bytes)
         .ok_or(InvalidInstruction)?;
+      // Strategies built before multisig support pack no signer count; treat them as having none.
+      let signer_num = rest.get(8).copied().unwrap_or(0);
       if strategy_instruction == DEPOSIT {
-        Ok(Self::Deposit { amount })
-      } else if  strategy_instruction == WITHDRAW {
-        Ok(Self::Withdraw { amount })
+        Ok(Self::Deposit { amount, signer_num })
+      } else if strategy_instruction == WITHDRAW {
+        Ok(Self::Withdraw { amount, signer_num })
       } else {
-        return Err(ProgramError::InvalidInstructionData);
+        Err(ProgramError::InvalidInstructionData)
       }
     }
   }
@@ -77,18 +83,21 @@ impl StrategyInstruction {
   fn pack(&self, instruction_id: u8) -> Vec<u8> {
     let mut buf = Vec::with_capacity(size_of::<Self>());
     buf.push(instruction_id);
-    match self {
-      &Self::Deposit { amount } => {
+    match *self {
+      Self::Deposit { amount, signer_num } => {
         buf.extend_from_slice(&amount.to_le_bytes());
+        buf.push(signer_num);
       }
-      &Self::Withdraw { amount } => {
+      Self::Withdraw { amount, signer_num } => {
         buf.extend_from_slice(&amount.to_le_bytes());
+        buf.push(signer_num);
       }
-      &Self::EstimateValue {} => {}
+      Self::EstimateValue {} => {}
     }
     buf
   }
 
+  #[allow(clippy::too_many_arguments)]
   pub fn deposit(
     instruction_id: u8,
     program_id: &Pubkey,
@@ -97,17 +106,19 @@ impl StrategyInstruction {
     target_pubkey: &Pubkey,
     additional_account_metas: Vec<AccountMeta>,
     amount: u64,
+    signer_num: u8,
   ) -> Result<Instruction, ProgramError> {
-    return create_transfer(
-      Self::Deposit { amount }.pack(instruction_id),
+    create_transfer(
+      Self::Deposit { amount, signer_num }.pack(instruction_id),
       program_id,
       token_program_id,
       source_pubkey,
       target_pubkey,
       additional_account_metas,
-    );
+    )
   }
 
+  #[allow(clippy::too_many_arguments)]
   pub fn withdraw(
     instruction_id: u8,
     program_id: &Pubkey,
@@ -116,15 +127,16 @@ impl StrategyInstruction {
     target_pubkey: &Pubkey,
     additional_account_metas: Vec<AccountMeta>,
     amount: u64,
+    signer_num: u8,
   ) -> Result<Instruction, ProgramError> {
-    return create_transfer(
-      Self::Withdraw { amount }.pack(instruction_id),
+    create_transfer(
+      Self::Withdraw { amount, signer_num }.pack(instruction_id),
       program_id,
       token_program_id,
       source_pubkey,
       target_pubkey,
       additional_account_metas,
-    );
+    )
   }
 
   pub fn estimate_value(
@@ -132,14 +144,17 @@ impl StrategyInstruction {
     program_id: &Pubkey,
     vault_program_id: &Pubkey,
     shared_memory_account: &Pubkey,
+    position_token_account: &Pubkey,
     additional_account_metas: Vec<AccountMeta>,
   ) -> Result<Instruction, ProgramError> {
+    let mut accounts = vec![AccountMeta::new_readonly(*position_token_account, false)];
+    accounts.extend(additional_account_metas);
     create_estimate_value(
       Self::EstimateValue {}.pack(instruction_id),
       program_id,
       vault_program_id,
       shared_memory_account,
-      additional_account_metas,
+      accounts,
     )
   }
 }
@@ -1,21 +1,50 @@
 #![cfg(feature = "test-bpf")]
 
 use {
-  ::vault::{state, instruction::VaultInstruction},
+  ::vault::{
+    events::{VaultEvent, PRICE_PER_SHARE_SCALE},
+    inspect::VaultPreview,
+    instruction::{
+      self as vault_instruction, VaultInstruction, STRATEGY_DEPOSIT_INSTRUCTION_ID,
+      STRATEGY_ESTIMATE_INSTRUCTION_ID, STRATEGY_WITHDRAW_INSTRUCTION_ID,
+    },
+    state::{self, RegisteredVault, VaultRegistry, VaultStatus},
+    swap::zap_account_metas,
+    tokenized_vault::TokenizedVault,
+  },
   assert_matches::*,
+  borsh::BorshDeserialize,
+  mock_strategy::{
+    instruction::{
+      estimate_value_account_metas, reenter_account_metas, transfer_account_metas,
+      MockStrategyInstruction,
+    },
+    state::{MockStrategy, FAIL_DEPOSIT, FAIL_ESTIMATE, FAIL_WITHDRAW, REENTER},
+  },
   solana_program::{
-    instruction::{AccountMeta},
+    instruction::{AccountMeta, Instruction},
     program_option::COption,
     program_pack::Pack,
     pubkey::Pubkey,
     rent::Rent,
     system_instruction,
   },
-  solana_program_test::{processor, ProgramTest, ProgramTestContext},
+  solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext},
   solana_sdk::signature::Keypair,
-  solana_sdk::{account::Account, signature::Signer, transaction::Transaction},
+  solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+  },
   // strategy_api::main,
   spl_token::{processor::Processor},
+  spl_token_swap::curve::{
+    base::{CurveType, SwapCurve},
+    constant_product::ConstantProductCurve,
+    fees::Fees,
+  },
+  strategy_api::strategy_instruction::{DEPOSIT, ESTIMATE_VALUE, WITHDRAW},
 };
 
 use std::convert::TryInto;
@@ -38,15 +67,19 @@ async fn test_hodl_vault() {
   
   let mut program_test_context = program_test.start_with_context().await;
   // A basic Vault has 3 relevant tokens: X (underlying asset), lX (strategy derivative), llX (vault
-  // derivative). We roughly need a client-managed & vault-managed SPL token account per-token.
-  // For succintnesss, we set all of these up together:
+  // derivative). The Vault creates its own token account & llX mint, so the client only needs X.
   let mint_client_vault_accounts =
-    create_tokens_and_accounts(&mut program_test_context, 4, 3).await;
+    create_tokens_and_accounts(&mut program_test_context, 1, 1).await;
   println!("XXXX");
 
   // Create Vault account
   let hodl_vault_storage_account = Keypair::new();
-  let vault_token_account: &Keypair = &mint_client_vault_accounts[0][2];
+  let (hodl_vault_token_account, _) =
+    VaultInstruction::find_vault_token_account(&::vault::id(), &hodl_vault_storage_account.pubkey());
+  let (hodl_llx_token_mint, _) =
+    VaultInstruction::find_llx_token_mint(&::vault::id(), &hodl_vault_storage_account.pubkey());
+  let (pda, _bump_seed) =
+    VaultInstruction::find_vault_authority(&::vault::id(), &hodl_vault_storage_account.pubkey());
   let mut transaction = Transaction::new_with_payer(
     &[
       // Create Vault storage acccount.
@@ -62,14 +95,15 @@ async fn test_hodl_vault() {
         &::vault::id(),
         &program_test_context.payer.pubkey(),
         &hodl_vault_storage_account.pubkey(),
-        &vault_token_account.pubkey(), // vault_token account (X)
-        &mint_client_vault_accounts[2][0].pubkey(), // llx mint account
+        &mint_client_vault_accounts[0][0].pubkey(), // X mint
+        &mint_client_vault_accounts[0][0].pubkey(), // vault_token account mint (X)
         &spl_token::id(),
         &::vault::id(), // Strategy program ID
         true,           // hodl
         99,             // unused deposit inst. ID
         99,             // unused withdraw inst. ID
         99,             // unused estimate value inst. ID
+        false,          // block deposits on loss
       )
       .unwrap(),
     ],
@@ -87,6 +121,11 @@ async fn test_hodl_vault() {
     Ok(())
   );
 
+  let payer = program_test_context.payer.pubkey();
+  check_mint(&mut program_test_context, &hodl_llx_token_mint, &COption::Some(pda), 6).await;
+  let client_llx_token_account =
+    create_token_account(&mut program_test_context, &hodl_llx_token_mint, &payer).await;
+
   // Transact with hodl vault.
   let mut transaction = Transaction::new_with_payer(
     &[
@@ -105,14 +144,18 @@ async fn test_hodl_vault() {
         &::vault::id(),
         &spl_token::id(),
         &mint_client_vault_accounts[0][1].pubkey(), // client_x_token account
-        &mint_client_vault_accounts[1][1].pubkey(), // client_lx_token account
+        &client_llx_token_account, // client_llx_token account
         vec![
           AccountMeta::new_readonly(program_test_context.payer.pubkey(), true), // source authority
-          AccountMeta::new_readonly(hodl_vault_storage_account.pubkey(), false),
+          AccountMeta::new(hodl_vault_storage_account.pubkey(), false),
           AccountMeta::new_readonly(::vault::id(), false),
-          AccountMeta::new(mint_client_vault_accounts[0][2].pubkey(), false), // hodl destination.
+          AccountMeta::new(hodl_llx_token_mint, false), // llX mint
+          AccountMeta::new_readonly(pda, false),
+          AccountMeta::new(hodl_vault_token_account, false), // hodl destination.
         ],
         100,
+        0, // signer_num
+        0, // min_shares
       )
       .unwrap(),
     ],
@@ -140,21 +183,76 @@ async fn test_hodl_vault() {
   )
   .await;
   println!("Checking vault_token_account_switched");
-  let (pda, bump_seed) = Pubkey::find_program_address(&[b"vault"], &::vault::id());
   println!("Test pda {} ", pda);
   check_token_account(
     &mut program_test_context,
-    &vault_token_account.pubkey(),
+    &hodl_vault_token_account,
     &COption::Some(pda),
     100,
   )
   .await;
+  check_token_account(
+    &mut program_test_context,
+    &client_llx_token_account,
+    &COption::None,
+    100,
+  )
+  .await;
 
   let additional_account_metas = vec![
-    AccountMeta::new_readonly(hodl_vault_storage_account.pubkey(), false), 
-    AccountMeta::new_readonly(mint_client_vault_accounts[0][2].pubkey(), false)
+    AccountMeta::new(hodl_vault_storage_account.pubkey(), false),
+    AccountMeta::new_readonly(hodl_vault_token_account, false)
   ];
   check_vault_value(&mut program_test_context, additional_account_metas, 100).await;
+  let hodl_vault_state =
+    get_vault_state(&mut program_test_context, &hodl_vault_storage_account.pubkey()).await;
+  assert_eq!(
+    simulate_return_data::<VaultPreview>(
+      &mut program_test_context,
+      VaultInstruction::preview_vault(
+        &::vault::id(),
+        &hodl_vault_storage_account.pubkey(),
+        &hodl_vault_state,
+        Some(&client_llx_token_account),
+      )
+      .unwrap(),
+    )
+    .await,
+    VaultPreview {
+      total_assets: 100,
+      llx_supply: 100,
+      price_per_share: PRICE_PER_SHARE_SCALE,
+      redeemable: Some(100),
+    }
+  );
+  assert_eq!(
+    simulate_return_data::<u64>(
+      &mut program_test_context,
+      VaultInstruction::preview_deposit(
+        &::vault::id(),
+        &hodl_vault_storage_account.pubkey(),
+        &hodl_vault_state,
+        50,
+      )
+      .unwrap(),
+    )
+    .await,
+    50
+  );
+  assert_eq!(
+    simulate_return_data::<u64>(
+      &mut program_test_context,
+      VaultInstruction::preview_withdraw(
+        &::vault::id(),
+        &hodl_vault_storage_account.pubkey(),
+        &hodl_vault_state,
+        40,
+      )
+      .unwrap(),
+    )
+    .await,
+    40
+  );
 
   let mut transaction = Transaction::new_with_payer(
     &[
@@ -162,15 +260,18 @@ async fn test_hodl_vault() {
       VaultInstruction::withdraw(
         &::vault::id(),
         &spl_token::id(),
-        &mint_client_vault_accounts[1][1].pubkey(), // client_lx_token account
+        &client_llx_token_account, // client_llx_token account
         &mint_client_vault_accounts[0][1].pubkey(), // client_x_token account
         vec![
-          AccountMeta::new_readonly(pda, false),
-          AccountMeta::new_readonly(hodl_vault_storage_account.pubkey(), false),
+          AccountMeta::new_readonly(program_test_context.payer.pubkey(), true), // source authority
+          AccountMeta::new(hodl_vault_storage_account.pubkey(), false),
           AccountMeta::new_readonly(::vault::id(), false),
-          AccountMeta::new(mint_client_vault_accounts[0][2].pubkey(), false), // hodl destination.
+          AccountMeta::new(hodl_llx_token_mint, false), // llX mint
+          AccountMeta::new_readonly(pda, false),
+          AccountMeta::new(hodl_vault_token_account, false), // hodl source.
         ],
         100,
+        0, // signer_num
       )
       .unwrap(),
     ],
@@ -197,14 +298,175 @@ async fn test_hodl_vault() {
   .await;
   check_token_account(
     &mut program_test_context,
-    &mint_client_vault_accounts[0][2].pubkey(),
+    &hodl_vault_token_account,
     &COption::Some(pda),
     0,
   )
   .await;
 
+  // Deposit on behalf of a third party, who then withdraws back to the depositor.
+  let recipient = Keypair::new();
+  let recipient_llx_token_account =
+    create_token_account(&mut program_test_context, &hodl_llx_token_mint, &recipient.pubkey()).await;
+  let hodl_metas = |source_authority: Pubkey| {
+    vec![
+      AccountMeta::new_readonly(source_authority, true),
+      AccountMeta::new(hodl_vault_storage_account.pubkey(), false),
+      AccountMeta::new_readonly(::vault::id(), false),
+      AccountMeta::new(hodl_llx_token_mint, false),
+      AccountMeta::new_readonly(pda, false),
+      AccountMeta::new(hodl_vault_token_account, false),
+    ]
+  };
+  assert_eq!(
+    process_instructions_with_events(
+      &mut program_test_context,
+      &[VaultInstruction::deposit(
+        &::vault::id(),
+        &spl_token::id(),
+        &mint_client_vault_accounts[0][1].pubkey(),
+        &recipient_llx_token_account,
+        hodl_metas(payer),
+        30,
+        0, // signer_num
+        0, // min_shares
+      )
+      .unwrap()],
+      &[],
+    )
+    .await,
+    vec![VaultEvent::Deposit {
+      vault: hodl_vault_storage_account.pubkey(),
+      authority: payer,
+      recipient: recipient.pubkey(),
+      amount: 30,
+      shares: 30,
+      price_per_share: PRICE_PER_SHARE_SCALE,
+    }]
+  );
+  check_token_account(&mut program_test_context, &recipient_llx_token_account, &COption::Some(recipient.pubkey()), 30).await;
+  check_token_account(&mut program_test_context, &mint_client_vault_accounts[0][1].pubkey(), &COption::None, 970).await;
+  assert_eq!(
+    process_instructions_with_events(
+      &mut program_test_context,
+      &[VaultInstruction::withdraw(
+        &::vault::id(),
+        &spl_token::id(),
+        &recipient_llx_token_account,
+        &mint_client_vault_accounts[0][1].pubkey(),
+        hodl_metas(recipient.pubkey()),
+        30,
+        0, // signer_num
+      )
+      .unwrap()],
+      &[&recipient],
+    )
+    .await,
+    vec![VaultEvent::Withdraw {
+      vault: hodl_vault_storage_account.pubkey(),
+      authority: recipient.pubkey(),
+      recipient: payer,
+      amount: 30,
+      fee: 0,
+      shares: 30,
+      price_per_share: PRICE_PER_SHARE_SCALE,
+      in_kind: false,
+    }]
+  );
+  check_token_account(&mut program_test_context, &recipient_llx_token_account, &COption::None, 0).await;
+  check_token_account(&mut program_test_context, &mint_client_vault_accounts[0][1].pubkey(), &COption::None, 1000).await;
+  check_token_account(&mut program_test_context, &hodl_vault_token_account, &COption::Some(pda), 0).await;
+
+  // The ERC-4626-style interface: mint exact shares, withdraw exact assets, then redeem the rest.
+  let tokenized_vault = TokenizedVault::new(
+    &::vault::id(),
+    &hodl_vault_storage_account.pubkey(),
+    get_vault_state(&mut program_test_context, &hodl_vault_storage_account.pubkey()).await,
+    vec![],
+  );
+  let client_x_token_account = mint_client_vault_accounts[0][1].pubkey();
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[tokenized_vault.mint(50, &payer, &client_x_token_account, &client_llx_token_account).unwrap()],
+      &[],
+    )
+    .await,
+    Ok(())
+  );
+  check_token_account(&mut program_test_context, &client_llx_token_account, &COption::None, 50).await;
+  check_token_account(&mut program_test_context, &client_x_token_account, &COption::None, 950).await;
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[tokenized_vault.withdraw(20, &payer, &client_llx_token_account, &client_x_token_account).unwrap()],
+      &[],
+    )
+    .await,
+    Ok(())
+  );
+  check_token_account(&mut program_test_context, &client_llx_token_account, &COption::None, 30).await;
+  check_token_account(&mut program_test_context, &client_x_token_account, &COption::None, 970).await;
+  for (instruction, expected) in vec![
+    (tokenized_vault.convert_to_shares(10).unwrap(), 10),
+    (tokenized_vault.convert_to_assets(10).unwrap(), 10),
+    (tokenized_vault.max_deposit().unwrap(), u64::MAX),
+    (tokenized_vault.max_mint().unwrap(), u64::MAX),
+    (tokenized_vault.max_withdraw(&client_llx_token_account).unwrap(), 30),
+    (tokenized_vault.max_redeem(&client_llx_token_account).unwrap(), 30),
+    (tokenized_vault.preview_mint(10).unwrap(), 10),
+    (tokenized_vault.preview_withdraw(10).unwrap(), 10),
+    (tokenized_vault.preview_redeem(30).unwrap(), 30),
+  ] {
+    assert_eq!(simulate_return_data::<u64>(&mut program_test_context, instruction).await, expected);
+  }
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[tokenized_vault.redeem(30, &payer, &client_llx_token_account, &client_x_token_account).unwrap()],
+      &[],
+    )
+    .await,
+    Ok(())
+  );
+  check_token_account(&mut program_test_context, &client_llx_token_account, &COption::None, 0).await;
+  check_token_account(&mut program_test_context, &client_x_token_account, &COption::None, 1000).await;
+  check_token_account(&mut program_test_context, &hodl_vault_token_account, &COption::Some(pda), 0).await;
+
+  // DebugCrash rolls back everything before it in the transaction.
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[
+        VaultInstruction::deposit(
+          &::vault::id(),
+          &spl_token::id(),
+          &mint_client_vault_accounts[0][1].pubkey(),
+          &client_llx_token_account,
+          hodl_metas(payer),
+          10,
+          0, // signer_num
+          0, // min_shares
+        )
+        .unwrap(),
+        VaultInstruction::debug_crash(&::vault::id()),
+      ],
+      &[],
+    )
+    .await,
+    Err(_)
+  );
+  check_token_account(&mut program_test_context, &mint_client_vault_accounts[0][1].pubkey(), &COption::None, 1000).await;
+  check_token_account(&mut program_test_context, &hodl_vault_token_account, &COption::Some(pda), 0).await;
+
   // Create wrapper vault which uses the hodl vault as a Strategy.
   let wrapper_vault_storage_account = Keypair::new();
+  let (wrapper_vault_token_account, _) =
+    VaultInstruction::find_vault_token_account(&::vault::id(), &wrapper_vault_storage_account.pubkey());
+  let (wrapper_llx_token_mint, _) =
+    VaultInstruction::find_llx_token_mint(&::vault::id(), &wrapper_vault_storage_account.pubkey());
+  let (wrapper_pda, _) =
+    VaultInstruction::find_vault_authority(&::vault::id(), &wrapper_vault_storage_account.pubkey());
   let mut transaction = Transaction::new_with_payer(
     &[
       // Create Vault storage acccount.
@@ -220,14 +482,15 @@ async fn test_hodl_vault() {
         &::vault::id(),
         &program_test_context.payer.pubkey(),
         &wrapper_vault_storage_account.pubkey(),
-        &mint_client_vault_accounts[2][2].pubkey(), // vault_token account (llX)
-        &mint_client_vault_accounts[3][0].pubkey(), // lllx mint account
+        &mint_client_vault_accounts[0][0].pubkey(), // X mint
+        &hodl_llx_token_mint, // vault_token account mint (llX)
         &spl_token::id(),
         &::vault::id(), // Strategy program ID
         false,          // hodl
-        1,              // deposit inst. ID
-        2,              // withdraw inst. ID
-        3,              // estimate value inst. ID
+        STRATEGY_DEPOSIT_INSTRUCTION_ID,
+        STRATEGY_WITHDRAW_INSTRUCTION_ID,
+        STRATEGY_ESTIMATE_INSTRUCTION_ID,
+        false,          // block deposits on loss
       )
       .unwrap(),
     ],
@@ -244,6 +507,8 @@ async fn test_hodl_vault() {
       .await,
     Ok(())
   );
+  let client_lllx_token_account =
+    create_token_account(&mut program_test_context, &wrapper_llx_token_mint, &payer).await;
 
   // Transact with Wrapper vault.
   let mut transaction = Transaction::new_with_payer(
@@ -255,20 +520,27 @@ async fn test_hodl_vault() {
         &::vault::id(),
         &spl_token::id(),
         &mint_client_vault_accounts[0][1].pubkey(), // client_x_token account
-        &mint_client_vault_accounts[3][1].pubkey(), // client_lllx_token account
+        &client_lllx_token_account, // client_lllx_token account
         // These will get passed through to the HODL vault beneath this.
         vec![
           // Source authority.
           AccountMeta::new_readonly(program_test_context.payer.pubkey(), true), // source authority
           // Outer vault accounts.
-          AccountMeta::new_readonly(wrapper_vault_storage_account.pubkey(), false),
+          AccountMeta::new(wrapper_vault_storage_account.pubkey(), false),
           AccountMeta::new_readonly(::vault::id(), false),
+          AccountMeta::new(wrapper_llx_token_mint, false), // lllX mint
+          AccountMeta::new_readonly(wrapper_pda, false),
+          AccountMeta::new(wrapper_vault_token_account, false), // vault_llx_token account
           // Inner vault accounts.
-          AccountMeta::new_readonly(hodl_vault_storage_account.pubkey(), false),
+          AccountMeta::new(hodl_vault_storage_account.pubkey(), false),
           AccountMeta::new_readonly(::vault::id(), false),
-          AccountMeta::new(mint_client_vault_accounts[0][2].pubkey(), false), // vault_x_token account
+          AccountMeta::new(hodl_llx_token_mint, false), // llX mint
+          AccountMeta::new_readonly(pda, false),
+          AccountMeta::new(hodl_vault_token_account, false), // vault_x_token account
         ],
         100,
+        0, // signer_num
+        0, // min_shares
       )
       .unwrap(),
     ],
@@ -297,19 +569,36 @@ async fn test_hodl_vault() {
   .await;
   check_token_account(
     &mut program_test_context,
-    &mint_client_vault_accounts[0][2].pubkey(),
+    &hodl_vault_token_account,
     &COption::Some(pda),
     100,
   )
   .await;
 
+  check_token_account(
+    &mut program_test_context,
+    &wrapper_vault_token_account,
+    &COption::Some(wrapper_pda),
+    100,
+  )
+  .await;
+  check_token_account(
+    &mut program_test_context,
+    &client_lllx_token_account,
+    &COption::None,
+    100,
+  )
+  .await;
+
   println!("wrapper_vault_storage_account: {}", wrapper_vault_storage_account.pubkey());
   let additional_account_metas = vec![
-    AccountMeta::new_readonly(wrapper_vault_storage_account.pubkey(), false), 
-    // Inner vault
+    AccountMeta::new(wrapper_vault_storage_account.pubkey(), false),
+    AccountMeta::new_readonly(wrapper_vault_token_account, false),
+    // Inner vault, valuing the wrapper's position.
     AccountMeta::new_readonly(::vault::id(), false),
-    AccountMeta::new_readonly(hodl_vault_storage_account.pubkey(), false), 
-    AccountMeta::new_readonly(mint_client_vault_accounts[0][2].pubkey(), false)
+    AccountMeta::new_readonly(hodl_llx_token_mint, false),
+    AccountMeta::new(hodl_vault_storage_account.pubkey(), false),
+    AccountMeta::new_readonly(hodl_vault_token_account, false)
   ];
   check_vault_value(&mut program_test_context, additional_account_metas, 100).await;
   
@@ -321,20 +610,26 @@ async fn test_hodl_vault() {
       VaultInstruction::withdraw(
         &::vault::id(),
         &spl_token::id(),
-        &mint_client_vault_accounts[3][1].pubkey(), // client_lllx_token account
+        &client_lllx_token_account, // client_lllx_token account
         &mint_client_vault_accounts[0][1].pubkey(), // client_x_token account
         vec![
           // Source authority
-          AccountMeta::new_readonly(pda, false), 
+          AccountMeta::new_readonly(program_test_context.payer.pubkey(), true),
           // Outer vault accounts.
-          AccountMeta::new_readonly(wrapper_vault_storage_account.pubkey(), false),
+          AccountMeta::new(wrapper_vault_storage_account.pubkey(), false),
           AccountMeta::new_readonly(::vault::id(), false),
+          AccountMeta::new(wrapper_llx_token_mint, false), // lllX mint
+          AccountMeta::new_readonly(wrapper_pda, false),
+          AccountMeta::new(wrapper_vault_token_account, false), // vault_llx_token account
           // Inner vault accounts.
-          AccountMeta::new_readonly(hodl_vault_storage_account.pubkey(), false),
+          AccountMeta::new(hodl_vault_storage_account.pubkey(), false),
           AccountMeta::new_readonly(::vault::id(), false),
-          AccountMeta::new(mint_client_vault_accounts[0][2].pubkey(), false), // hodl destination.
+          AccountMeta::new(hodl_llx_token_mint, false), // llX mint
+          AccountMeta::new_readonly(pda, false),
+          AccountMeta::new(hodl_vault_token_account, false), // hodl source.
         ],
         100,
+        0, // signer_num
       )
       .unwrap(),
     ],
@@ -360,88 +655,2597 @@ async fn test_hodl_vault() {
   .await;
   check_token_account(
     &mut program_test_context,
-    &mint_client_vault_accounts[0][2].pubkey(),
+    &hodl_vault_token_account,
     &COption::Some(pda),
     0,
   )
   .await;
 }
 
-/// Checks for expected values on a token account.
-async fn check_token_account(
-  program_test_context: &mut ProgramTestContext,
-  token_account_key: &Pubkey,
-  expected_owner: &COption<Pubkey>,
-  expected_amount: u64,
-) {
-  let token_account = program_test_context
-    .banks_client
-    .get_account(*token_account_key)
-    .await
+/// Tests a Vault whose strategy is the configurable mock strategy.
+#[tokio::test]
+async fn test_mock_strategy_vault() {
+  let mut program_test = ProgramTest::new(
+    "token_test",
+    spl_token::id(),
+    processor!(Processor::process),
+  );
+  program_test.add_program(
+    "vault_test",
+    ::vault::id(),
+    processor!(::vault::processor::Processor::process),
+  );
+  program_test.add_program(
+    "mock_strategy_test",
+    ::mock_strategy::id(),
+    processor!(strategy_api::strategy::process_instruction::<::mock_strategy::processor::Processor>),
+  );
+  let mut program_test_context = program_test.start_with_context().await;
+
+  // Row 0: X mint, client X account, mock reserve.
+  // Row 1: lX mint, client lX account.
+  // Row 2: unrelated Y mint, client Y account.
+  let mint_client_vault_accounts =
+    create_tokens_and_accounts(&mut program_test_context, 3, 2).await;
+  let client_x_token_account = mint_client_vault_accounts[0][1].pubkey();
+  let reserve_token_account = mint_client_vault_accounts[0][2].pubkey();
+  let lx_token_mint = mint_client_vault_accounts[1][0].pubkey();
+  let client_lx_token_account = mint_client_vault_accounts[1][1].pubkey();
+  let client_y_token_account = mint_client_vault_accounts[2][1].pubkey();
+  let payer = program_test_context.payer.pubkey();
+
+  let mock_storage_account = Keypair::new();
+  let vault_storage_account = Keypair::new();
+  let (pda, _bump_seed) =
+    VaultInstruction::find_vault_authority(&::vault::id(), &vault_storage_account.pubkey());
+  let (vault_lx_token_account, _) =
+    VaultInstruction::find_vault_token_account(&::vault::id(), &vault_storage_account.pubkey());
+  let (llx_token_mint, _) =
+    VaultInstruction::find_llx_token_mint(&::vault::id(), &vault_storage_account.pubkey());
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[
+        system_instruction::create_account(
+          &payer,
+          &mock_storage_account.pubkey(),
+          Rent::default().minimum_balance(MockStrategy::LEN),
+          MockStrategy::LEN as u64,
+          &::mock_strategy::id(),
+        ),
+        MockStrategyInstruction::initialize(
+          &::mock_strategy::id(),
+          &payer,
+          &mock_storage_account.pubkey(),
+          &reserve_token_account,
+          &lx_token_mint,
+          &spl_token::id(),
+        )
+        .unwrap(),
+        system_instruction::create_account(
+          &payer,
+          &vault_storage_account.pubkey(),
+          Rent::default().minimum_balance(::vault::state::Vault::LEN),
+          ::vault::state::Vault::LEN as u64,
+          &::vault::id(),
+        ),
+        VaultInstruction::initialize_vault(
+          &::vault::id(),
+          &payer,
+          &vault_storage_account.pubkey(),
+          &mint_client_vault_accounts[0][0].pubkey(),
+          &lx_token_mint,
+          &spl_token::id(),
+          &::mock_strategy::id(),
+          false, // hodl
+          DEPOSIT,
+          WITHDRAW,
+          ESTIMATE_VALUE,
+          false, // block deposits on loss
+        )
+        .unwrap(),
+        spl_token::instruction::mint_to(
+          &spl_token::id(),
+          &mint_client_vault_accounts[0][0].pubkey(),
+          &client_x_token_account,
+          &payer,
+          &[&payer],
+          1000,
+        )
+        .unwrap(),
+        spl_token::instruction::mint_to(
+          &spl_token::id(),
+          &mint_client_vault_accounts[2][0].pubkey(),
+          &client_y_token_account,
+          &payer,
+          &[&payer],
+          1000,
+        )
+        .unwrap(),
+      ],
+      &[&mock_storage_account, &vault_storage_account],
+    )
+    .await,
+    Ok(())
+  );
+  let client_llx_token_account =
+    create_token_account(&mut program_test_context, &llx_token_mint, &payer).await;
+
+  let mut transfer_metas = vec![
+    AccountMeta::new_readonly(payer, true), // source authority
+    AccountMeta::new(vault_storage_account.pubkey(), false),
+    AccountMeta::new_readonly(::mock_strategy::id(), false),
+    AccountMeta::new(llx_token_mint, false),
+    AccountMeta::new_readonly(pda, false),
+    AccountMeta::new(vault_lx_token_account, false),
+  ];
+  transfer_metas.extend(transfer_account_metas(
+    &::mock_strategy::id(),
+    &mock_storage_account.pubkey(),
+    &reserve_token_account,
+    &lx_token_mint,
+  ));
+  let mut estimate_account_metas = vec![
+    AccountMeta::new(vault_storage_account.pubkey(), false),
+    AccountMeta::new_readonly(vault_lx_token_account, false),
+    AccountMeta::new_readonly(::mock_strategy::id(), false),
+  ];
+  estimate_account_metas.extend(estimate_value_account_metas(
+    &mock_storage_account.pubkey(),
+    &reserve_token_account,
+    &lx_token_mint,
+  ));
+  let deposit = |amount| {
+    VaultInstruction::deposit(
+      &::vault::id(),
+      &spl_token::id(),
+      &client_x_token_account,
+      &client_llx_token_account,
+      transfer_metas.clone(),
+      amount,
+      0, // signer_num
+      0, // min_shares
+    )
     .unwrap()
-    .expect("Account unretrievable");
-  println!("check_token_account");
-  assert_eq!(token_account.owner, spl_token::id());
-  let internal_account = spl_token::state::Account::unpack(&token_account.data).unwrap();
-  if expected_owner.is_some() {
-    println!("Error: token {} Got {} Expected {}", token_account_key, internal_account.owner, expected_owner.unwrap());
-    assert_eq!(internal_account.owner, expected_owner.unwrap());
+  };
+  let withdraw = |amount| {
+    VaultInstruction::withdraw(
+      &::vault::id(),
+      &spl_token::id(),
+      &client_llx_token_account,
+      &client_x_token_account,
+      transfer_metas.clone(),
+      amount,
+      0, // signer_num
+    )
+    .unwrap()
+  };
+  let configure = |reported_value, withdraw_shortfall, flags| {
+    MockStrategyInstruction::configure(
+      &::mock_strategy::id(),
+      &payer,
+      &mock_storage_account.pubkey(),
+      reported_value,
+      withdraw_shortfall,
+      flags,
+    )
+    .unwrap()
+  };
+
+  assert_matches!(process_instructions(&mut program_test_context, &[deposit(100)], &[]).await, Ok(()));
+  check_token_account(&mut program_test_context, &client_x_token_account, &COption::None, 900).await;
+  check_token_account(&mut program_test_context, &reserve_token_account, &COption::None, 100).await;
+  check_token_account(&mut program_test_context, &vault_lx_token_account, &COption::Some(pda), 100).await;
+  check_token_account(&mut program_test_context, &client_llx_token_account, &COption::None, 100).await;
+  check_vault_value(&mut program_test_context, estimate_account_metas.clone(), 100).await;
+
+  let vault_state = get_vault_state(&mut program_test_context, &vault_storage_account.pubkey()).await;
+  assert_eq!(vault_state.x_token_mint, mint_client_vault_accounts[0][0].pubkey());
+  assert_eq!(vault_state.lx_token_mint, lx_token_mint);
+
+  // Client accounts can't stand in for the Vault's lX account, and must hold the Vault's X.
+  let misrouted = [
+    VaultInstruction::deposit(
+      &::vault::id(),
+      &spl_token::id(),
+      &client_x_token_account,
+      &vault_lx_token_account,
+      transfer_metas.clone(),
+      10,
+      0, // signer_num
+      0, // min_shares
+    )
+    .unwrap(),
+    VaultInstruction::withdraw(
+      &::vault::id(),
+      &spl_token::id(),
+      &client_llx_token_account,
+      &vault_lx_token_account,
+      transfer_metas.clone(),
+      10,
+      0, // signer_num
+    )
+    .unwrap(),
+    VaultInstruction::deposit(
+      &::vault::id(),
+      &spl_token::id(),
+      &client_y_token_account,
+      &client_llx_token_account,
+      transfer_metas.clone(),
+      10,
+      0, // signer_num
+      0, // min_shares
+    )
+    .unwrap(),
+  ];
+  for instruction in misrouted.iter() {
+    assert!(process_instructions(&mut program_test_context, &[instruction.clone()], &[]).await.is_err());
   }
-  println!("amounts: {} {}", internal_account.amount, expected_amount);
-  assert_eq!(internal_account.amount, expected_amount);
-}
+  let mut substituted_metas = transfer_metas.clone();
+  substituted_metas[5] = AccountMeta::new(client_lx_token_account, false);
+  assert!(process_instructions(
+    &mut program_test_context,
+    &[VaultInstruction::deposit(
+      &::vault::id(),
+      &spl_token::id(),
+      &client_x_token_account,
+      &client_llx_token_account,
+      substituted_metas,
+      10,
+      0, // signer_num
+      0, // min_shares
+    )
+    .unwrap()],
+    &[],
+  )
+  .await
+  .is_err());
 
+  // Reported gains & losses are passed through as-is.
+  assert_matches!(
+    process_instructions(&mut program_test_context, &[configure(COption::Some(80), 0, 0)], &[]).await,
+    Ok(())
+  );
+  check_vault_value(&mut program_test_context, estimate_account_metas.clone(), 80).await;
+  let vault_state = get_vault_state(&mut program_test_context, &vault_storage_account.pubkey()).await;
+  assert_eq!(vault_state.total_loss, 20);
+  assert_eq!(vault_state.total_debt, 80);
+  assert_matches!(
+    process_instructions(&mut program_test_context, &[configure(COption::Some(120), 0, 0)], &[]).await,
+    Ok(())
+  );
+  check_vault_value(&mut program_test_context, estimate_account_metas.clone(), 120).await;
 
-/// Checks for expected values on a token account.
-async fn check_vault_value(
-  program_test_context: &mut ProgramTestContext,
-  additional_account_metas: Vec<AccountMeta>,
-  expected_amount: u64,
-) {
-  let temp_memory_account = Keypair::new();
-  let mut transaction = Transaction::new_with_payer(
-    &[
-      // Create Vault storage acccount.
-      system_instruction::create_account(
-        &program_test_context.payer.pubkey(),
-        &temp_memory_account.pubkey(),
-        5,  // Need some rent in order to stay in memory past this transaction.
-        8, // sizeof(u64)
-        &::vault::id(),
-      ),
-      // Accounts: [vault_program, token_program, source_wallet, target_wallet, signers,
-      //           this_programs_accounts, child_strategy_accounts]
-      VaultInstruction::estimate_value(
-        &::vault::id(),
+  // Strategy reverts propagate through the Vault & leave balances untouched.
+  assert_matches!(
+    process_instructions(&mut program_test_context, &[configure(COption::None, 0, FAIL_ESTIMATE)], &[]).await,
+    Ok(())
+  );
+  assert!(estimate_vault_value(&mut program_test_context, estimate_account_metas.clone()).await.is_err());
+  assert_matches!(
+    process_instructions(&mut program_test_context, &[configure(COption::None, 0, FAIL_DEPOSIT)], &[]).await,
+    Ok(())
+  );
+  assert!(process_instructions(&mut program_test_context, &[deposit(10)], &[]).await.is_err());
+  assert_matches!(
+    process_instructions(&mut program_test_context, &[configure(COption::None, 0, FAIL_WITHDRAW)], &[]).await,
+    Ok(())
+  );
+  assert!(process_instructions(&mut program_test_context, &[withdraw(10)], &[]).await.is_err());
+  check_token_account(&mut program_test_context, &client_x_token_account, &COption::None, 900).await;
+  check_token_account(&mut program_test_context, &reserve_token_account, &COption::None, 100).await;
+  check_token_account(&mut program_test_context, &vault_lx_token_account, &COption::Some(pda), 100).await;
+
+  // Strategies returning fewer tokens than expected.
+  assert_matches!(
+    process_instructions(&mut program_test_context, &[configure(COption::None, 10, 0)], &[]).await,
+    Ok(())
+  );
+  // WithdrawAssets guarantees the X requested, so a short payout reverts it.
+  check_vault_value(&mut program_test_context, estimate_account_metas.clone(), 100).await;
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[VaultInstruction::withdraw_assets(
         &::vault::id(),
-        &temp_memory_account.pubkey(),
-        additional_account_metas,
+        &spl_token::id(),
+        &client_llx_token_account,
+        &client_x_token_account,
+        transfer_metas.clone(),
+        20,
+        0, // signer_num
       )
-      .unwrap(),
-    ],
-    Some(&program_test_context.payer.pubkey()),
+      .unwrap()],
+      &[],
+    )
+    .await,
+    Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(code)
+    ))) if code == ::vault::error::VaultError::ExceededSlippage as u32
   );
-  transaction.sign(
-    &[&program_test_context.payer, &temp_memory_account],
-    program_test_context.last_blockhash,
+  assert_matches!(process_instructions(&mut program_test_context, &[withdraw(50)], &[]).await, Ok(()));
+  check_token_account(&mut program_test_context, &client_x_token_account, &COption::None, 940).await;
+  check_token_account(&mut program_test_context, &reserve_token_account, &COption::None, 60).await;
+  check_token_account(&mut program_test_context, &vault_lx_token_account, &COption::Some(pda), 50).await;
+  check_token_account(&mut program_test_context, &client_llx_token_account, &COption::None, 50).await;
+
+  // Clients can still redeem lX directly while the strategy can't withdraw.
+  let withdraw_in_kind = |target, amount| {
+    VaultInstruction::withdraw_in_kind(
+      &::vault::id(),
+      &spl_token::id(),
+      &client_llx_token_account,
+      target,
+      &payer,
+      &[],
+      &vault_storage_account.pubkey(),
+      &vault_state,
+      amount,
+    )
+    .unwrap()
+  };
+  assert_matches!(
+    process_instructions(&mut program_test_context, &[configure(COption::None, 0, FAIL_WITHDRAW)], &[]).await,
+    Ok(())
+  );
+  assert!(process_instructions(&mut program_test_context, &[withdraw(20)], &[]).await.is_err());
+  assert!(process_instructions(
+    &mut program_test_context,
+    &[withdraw_in_kind(&client_x_token_account, 20)],
+    &[],
+  )
+  .await
+  .is_err());
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[withdraw_in_kind(&client_lx_token_account, 20)],
+      &[],
+    )
+    .await,
+    Ok(())
+  );
+  check_token_account(&mut program_test_context, &client_lx_token_account, &COption::None, 20).await;
+  check_token_account(&mut program_test_context, &vault_lx_token_account, &COption::Some(pda), 30).await;
+  check_token_account(&mut program_test_context, &client_llx_token_account, &COption::None, 30).await;
+  check_token_account(&mut program_test_context, &reserve_token_account, &COption::None, 60).await;
+}
+
+/// Tests that a strategy can't reenter the calling Vault. Native processors can't return the
+/// runtime's reentrancy error, so the Vault & mock are loaded from vault.so & mock_strategy.so
+/// (see `do.sh test`).
+#[tokio::test]
+async fn test_mock_strategy_reentrancy() {
+  let mut program_test = ProgramTest::new(
+    "token_test",
+    spl_token::id(),
+    processor!(Processor::process),
   );
+  program_test.prefer_bpf(true);
+  program_test.add_program("vault", ::vault::id(), None);
+  program_test.add_program("mock_strategy", ::mock_strategy::id(), None);
+  let mut program_test_context = program_test.start_with_context().await;
+
+  // Row 0: X mint, client X account, mock reserve.
+  // Row 1: lX mint.
+  let mint_client_vault_accounts =
+    create_tokens_and_accounts(&mut program_test_context, 2, 2).await;
+  let x_token_mint = mint_client_vault_accounts[0][0].pubkey();
+  let client_x_token_account = mint_client_vault_accounts[0][1].pubkey();
+  let reserve_token_account = mint_client_vault_accounts[0][2].pubkey();
+  let lx_token_mint = mint_client_vault_accounts[1][0].pubkey();
+  let payer = program_test_context.payer.pubkey();
 
+  let mock_storage_account = Keypair::new();
+  let vault_storage_account = Keypair::new();
+  let (mock_authority, _) =
+    MockStrategyInstruction::find_authority(&::mock_strategy::id(), &mock_storage_account.pubkey());
+  let (pda, _bump_seed) =
+    VaultInstruction::find_vault_authority(&::vault::id(), &vault_storage_account.pubkey());
+  let (vault_lx_token_account, _) =
+    VaultInstruction::find_vault_token_account(&::vault::id(), &vault_storage_account.pubkey());
+  let (llx_token_mint, _) =
+    VaultInstruction::find_llx_token_mint(&::vault::id(), &vault_storage_account.pubkey());
   assert_matches!(
-    program_test_context
-      .banks_client
-      .process_transaction(transaction)
-      .await,
+    process_instructions(
+      &mut program_test_context,
+      &[
+        system_instruction::create_account(
+          &payer,
+          &mock_storage_account.pubkey(),
+          Rent::default().minimum_balance(MockStrategy::LEN),
+          MockStrategy::LEN as u64,
+          &::mock_strategy::id(),
+        ),
+        MockStrategyInstruction::initialize(
+          &::mock_strategy::id(),
+          &payer,
+          &mock_storage_account.pubkey(),
+          &reserve_token_account,
+          &lx_token_mint,
+          &spl_token::id(),
+        )
+        .unwrap(),
+        system_instruction::create_account(
+          &payer,
+          &vault_storage_account.pubkey(),
+          Rent::default().minimum_balance(::vault::state::Vault::LEN),
+          ::vault::state::Vault::LEN as u64,
+          &::vault::id(),
+        ),
+        VaultInstruction::initialize_vault(
+          &::vault::id(),
+          &payer,
+          &vault_storage_account.pubkey(),
+          &x_token_mint,
+          &lx_token_mint,
+          &spl_token::id(),
+          &::mock_strategy::id(),
+          false, // hodl
+          DEPOSIT,
+          WITHDRAW,
+          ESTIMATE_VALUE,
+          false, // block deposits on loss
+        )
+        .unwrap(),
+        spl_token::instruction::mint_to(
+          &spl_token::id(),
+          &x_token_mint,
+          &client_x_token_account,
+          &payer,
+          &[&payer],
+          1000,
+        )
+        .unwrap(),
+      ],
+      &[&mock_storage_account, &vault_storage_account],
+    )
+    .await,
     Ok(())
   );
+  let client_llx_token_account =
+    create_token_account(&mut program_test_context, &llx_token_mint, &payer).await;
+  // The reentrant Withdraw redeems llX held by the mock.
+  let mock_llx_token_account =
+    create_token_account(&mut program_test_context, &llx_token_mint, &mock_authority).await;
 
-  let temp_memory_account = program_test_context
-    .banks_client
-    .get_account(temp_memory_account.pubkey())
-    .await
+  let vault_state = get_vault_state(&mut program_test_context, &vault_storage_account.pubkey()).await;
+  let mock_transfer_metas = transfer_account_metas(
+    &::mock_strategy::id(),
+    &mock_storage_account.pubkey(),
+    &reserve_token_account,
+    &lx_token_mint,
+  );
+  let mock_estimate_metas = estimate_value_account_metas(
+    &mock_storage_account.pubkey(),
+    &reserve_token_account,
+    &lx_token_mint,
+  );
+  let mut transfer_metas = vec![AccountMeta::new_readonly(payer, true)]; // source authority
+  transfer_metas.extend(vault_instruction::transfer_account_metas(
+    &::vault::id(),
+    &vault_storage_account.pubkey(),
+    &vault_state,
+    mock_transfer_metas.clone(),
+  ));
+  transfer_metas.extend(reenter_account_metas(
+    &::mock_strategy::id(),
+    &mock_storage_account.pubkey(),
+    &::vault::id(),
+    &mock_llx_token_account,
+    &client_x_token_account,
+    vault_instruction::transfer_account_metas(
+      &::vault::id(),
+      &vault_storage_account.pubkey(),
+      &vault_state,
+      mock_transfer_metas,
+    ),
+  ));
+  let mut estimate_account_metas = vault_instruction::estimate_value_account_metas(
+    &vault_storage_account.pubkey(),
+    &vault_state,
+    mock_estimate_metas.clone(),
+  );
+  estimate_account_metas.extend(vault_instruction::estimate_value_account_metas(
+    &vault_storage_account.pubkey(),
+    &vault_state,
+    mock_estimate_metas,
+  ));
+  let deposit = |amount| {
+    VaultInstruction::deposit(
+      &::vault::id(),
+      &spl_token::id(),
+      &client_x_token_account,
+      &client_llx_token_account,
+      transfer_metas.clone(),
+      amount,
+      0, // signer_num
+      0, // min_shares
+    )
     .unwrap()
-    .expect("Account unretrievable");
-  assert_eq!(u64::from_le_bytes(*s2a(&temp_memory_account.data[..8])), expected_amount);
+  };
+  let withdraw = |amount| {
+    VaultInstruction::withdraw(
+      &::vault::id(),
+      &spl_token::id(),
+      &client_llx_token_account,
+      &client_x_token_account,
+      transfer_metas.clone(),
+      amount,
+      0, // signer_num
+    )
+    .unwrap()
+  };
+  let configure = |flags| {
+    MockStrategyInstruction::configure(
+      &::mock_strategy::id(),
+      &payer,
+      &mock_storage_account.pubkey(),
+      COption::None,
+      0, // withdraw_shortfall
+      flags,
+    )
+    .unwrap()
+  };
+
+  // The extra accounts are ignored unless the mock reenters.
+  assert_matches!(process_instructions(&mut program_test_context, &[deposit(100)], &[]).await, Ok(()));
+  check_vault_value(&mut program_test_context, estimate_account_metas.clone(), 100).await;
+  let vault_state = get_vault_state(&mut program_test_context, &vault_storage_account.pubkey()).await;
+
+  assert_matches!(
+    process_instructions(&mut program_test_context, &[configure(REENTER)], &[]).await,
+    Ok(())
+  );
+  assert_matches!(
+    process_instructions(&mut program_test_context, &[deposit(10)], &[]).await,
+    Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+      0,
+      InstructionError::ReentrancyNotAllowed
+    )))
+  );
+  assert_matches!(
+    process_instructions(&mut program_test_context, &[withdraw(10)], &[]).await,
+    Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+      0,
+      InstructionError::ReentrancyNotAllowed
+    )))
+  );
+  assert_matches!(
+    estimate_vault_value(&mut program_test_context, estimate_account_metas.clone()).await,
+    Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+      1,
+      InstructionError::ReentrancyNotAllowed
+    )))
+  );
+  assert_eq!(
+    get_vault_state(&mut program_test_context, &vault_storage_account.pubkey()).await,
+    vault_state
+  );
+  check_token_account(&mut program_test_context, &client_x_token_account, &COption::None, 900).await;
+  check_token_account(&mut program_test_context, &reserve_token_account, &COption::None, 100).await;
+  check_token_account(&mut program_test_context, &vault_lx_token_account, &COption::Some(pda), 100).await;
+  check_token_account(&mut program_test_context, &client_llx_token_account, &COption::None, 100).await;
+  check_token_account(&mut program_test_context, &mock_llx_token_account, &COption::None, 0).await;
+
+  assert_matches!(process_instructions(&mut program_test_context, &[configure(0)], &[]).await, Ok(()));
+  assert_matches!(process_instructions(&mut program_test_context, &[withdraw(10)], &[]).await, Ok(()));
+  check_token_account(&mut program_test_context, &client_x_token_account, &COption::None, 910).await;
 }
+
+/// Tests that losses reported by a strategy are recorded & shared between llX holders.
+#[tokio::test]
+async fn test_vault_loss() {
+  let mut program_test = ProgramTest::new(
+    "token_test",
+    spl_token::id(),
+    processor!(Processor::process),
+  );
+  program_test.add_program(
+    "vault_test",
+    ::vault::id(),
+    processor!(::vault::processor::Processor::process),
+  );
+  program_test.add_program(
+    "mock_strategy_test",
+    ::mock_strategy::id(),
+    processor!(strategy_api::strategy::process_instruction::<::mock_strategy::processor::Processor>),
+  );
+  let mut program_test_context = program_test.start_with_context().await;
+
+  // Row 0: X mint, client X account, mock reserve.
+  // Row 1: lX mint.
+  let mint_client_vault_accounts =
+    create_tokens_and_accounts(&mut program_test_context, 2, 2).await;
+  let client_x_token_account = mint_client_vault_accounts[0][1].pubkey();
+  let reserve_token_account = mint_client_vault_accounts[0][2].pubkey();
+  let lx_token_mint = mint_client_vault_accounts[1][0].pubkey();
+  let payer = program_test_context.payer.pubkey();
+
+  let mock_storage_account = Keypair::new();
+  let vault_storage_account = Keypair::new();
+  let (pda, _bump_seed) =
+    VaultInstruction::find_vault_authority(&::vault::id(), &vault_storage_account.pubkey());
+  let (vault_lx_token_account, _) =
+    VaultInstruction::find_vault_token_account(&::vault::id(), &vault_storage_account.pubkey());
+  let (llx_token_mint, _) =
+    VaultInstruction::find_llx_token_mint(&::vault::id(), &vault_storage_account.pubkey());
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[
+        system_instruction::create_account(
+          &payer,
+          &mock_storage_account.pubkey(),
+          Rent::default().minimum_balance(MockStrategy::LEN),
+          MockStrategy::LEN as u64,
+          &::mock_strategy::id(),
+        ),
+        MockStrategyInstruction::initialize(
+          &::mock_strategy::id(),
+          &payer,
+          &mock_storage_account.pubkey(),
+          &reserve_token_account,
+          &lx_token_mint,
+          &spl_token::id(),
+        )
+        .unwrap(),
+        system_instruction::create_account(
+          &payer,
+          &vault_storage_account.pubkey(),
+          Rent::default().minimum_balance(::vault::state::Vault::LEN),
+          ::vault::state::Vault::LEN as u64,
+          &::vault::id(),
+        ),
+        VaultInstruction::initialize_vault(
+          &::vault::id(),
+          &payer,
+          &vault_storage_account.pubkey(),
+          &mint_client_vault_accounts[0][0].pubkey(),
+          &lx_token_mint,
+          &spl_token::id(),
+          &::mock_strategy::id(),
+          false, // hodl
+          DEPOSIT,
+          WITHDRAW,
+          ESTIMATE_VALUE,
+          true, // block deposits on loss
+        )
+        .unwrap(),
+        spl_token::instruction::mint_to(
+          &spl_token::id(),
+          &mint_client_vault_accounts[0][0].pubkey(),
+          &client_x_token_account,
+          &payer,
+          &[&payer],
+          1000,
+        )
+        .unwrap(),
+      ],
+      &[&mock_storage_account, &vault_storage_account],
+    )
+    .await,
+    Ok(())
+  );
+  let client_llx_token_account =
+    create_token_account(&mut program_test_context, &llx_token_mint, &payer).await;
+
+  let mut transfer_metas = vec![
+    AccountMeta::new_readonly(payer, true), // source authority
+    AccountMeta::new(vault_storage_account.pubkey(), false),
+    AccountMeta::new_readonly(::mock_strategy::id(), false),
+    AccountMeta::new(llx_token_mint, false),
+    AccountMeta::new_readonly(pda, false),
+    AccountMeta::new(vault_lx_token_account, false),
+  ];
+  transfer_metas.extend(transfer_account_metas(
+    &::mock_strategy::id(),
+    &mock_storage_account.pubkey(),
+    &reserve_token_account,
+    &lx_token_mint,
+  ));
+  let mut estimate_account_metas = vec![
+    AccountMeta::new(vault_storage_account.pubkey(), false),
+    AccountMeta::new_readonly(vault_lx_token_account, false),
+    AccountMeta::new_readonly(::mock_strategy::id(), false),
+  ];
+  estimate_account_metas.extend(estimate_value_account_metas(
+    &mock_storage_account.pubkey(),
+    &reserve_token_account,
+    &lx_token_mint,
+  ));
+  let deposit = |amount| {
+    VaultInstruction::deposit(
+      &::vault::id(),
+      &spl_token::id(),
+      &client_x_token_account,
+      &client_llx_token_account,
+      transfer_metas.clone(),
+      amount,
+      0, // signer_num
+      0, // min_shares
+    )
+    .unwrap()
+  };
+
+  assert_matches!(process_instructions(&mut program_test_context, &[deposit(100)], &[]).await, Ok(()));
+  check_token_account(&mut program_test_context, &client_llx_token_account, &COption::None, 100).await;
+
+  // Half of the strategy's holdings are lost.
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[MockStrategyInstruction::configure(
+        &::mock_strategy::id(),
+        &payer,
+        &mock_storage_account.pubkey(),
+        COption::Some(50),
+        0,
+        0,
+      )
+      .unwrap()],
+      &[],
+    )
+    .await,
+    Ok(())
+  );
+  check_vault_value(&mut program_test_context, estimate_account_metas.clone(), 50).await;
+  let vault_state = get_vault_state(&mut program_test_context, &vault_storage_account.pubkey()).await;
+  assert_eq!(vault_state.total_debt, 50);
+  assert_eq!(vault_state.total_loss, 50);
+  assert_eq!(vault_state.unacknowledged_loss, 50);
+
+  // Deposits are blocked until governance acknowledges the loss.
+  assert!(process_instructions(&mut program_test_context, &[deposit(10)], &[]).await.is_err());
+  let preview_deposit = |amount| {
+    VaultInstruction::preview_deposit(
+      &::vault::id(),
+      &vault_storage_account.pubkey(),
+      &vault_state,
+      amount,
+    )
+    .unwrap()
+  };
+  assert!(
+    process_instructions(&mut program_test_context, &[preview_deposit(10)], &[]).await.is_err()
+  );
+  let not_governance = Keypair::new();
+  assert!(process_instructions(
+    &mut program_test_context,
+    &[VaultInstruction::acknowledge_loss(
+      &::vault::id(),
+      &not_governance.pubkey(),
+      &vault_storage_account.pubkey(),
+    )
+    .unwrap()],
+    &[&not_governance],
+  )
+  .await
+  .is_err());
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[VaultInstruction::acknowledge_loss(
+        &::vault::id(),
+        &payer,
+        &vault_storage_account.pubkey(),
+      )
+      .unwrap()],
+      &[],
+    )
+    .await,
+    Ok(())
+  );
+  assert_eq!(
+    get_vault_state(&mut program_test_context, &vault_storage_account.pubkey())
+      .await
+      .unacknowledged_loss,
+    0
+  );
+
+  // New deposits are priced on the lX the strategy returns rather than its (here misreported)
+  // estimate, so 10 X still only buys 10 llX.
+  assert_matches!(process_instructions(&mut program_test_context, &[deposit(10)], &[]).await, Ok(()));
+  check_token_account(&mut program_test_context, &client_llx_token_account, &COption::None, 110).await;
+  check_token_account(&mut program_test_context, &vault_lx_token_account, &COption::Some(pda), 110).await;
+
+  // Withdrawing half the llX supply redeems half the Vault's lX.
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[VaultInstruction::withdraw(
+        &::vault::id(),
+        &spl_token::id(),
+        &client_llx_token_account,
+        &client_x_token_account,
+        transfer_metas.clone(),
+        55,
+        0, // signer_num
+      )
+      .unwrap()],
+      &[],
+    )
+    .await,
+    Ok(())
+  );
+  check_token_account(&mut program_test_context, &client_llx_token_account, &COption::None, 55).await;
+  check_token_account(&mut program_test_context, &vault_lx_token_account, &COption::Some(pda), 55).await;
+  check_token_account(&mut program_test_context, &client_x_token_account, &COption::None, 945).await;
+  let vault_state = get_vault_state(&mut program_test_context, &vault_storage_account.pubkey()).await;
+  assert_eq!(vault_state.total_debt, 30);
+  assert_eq!(vault_state.last_estimated_value, 30);
+}
+
+/// Tests a Vault transacting with accounts owned by an SPL Token multisig.
+#[tokio::test]
+async fn test_multisig_source_authority() {
+  let mut program_test = ProgramTest::new(
+    "token_test",
+    spl_token::id(),
+    processor!(Processor::process),
+  );
+  program_test.add_program(
+    "vault_test",
+    ::vault::id(),
+    processor!(::vault::processor::Processor::process),
+  );
+  let mut program_test_context = program_test.start_with_context().await;
+
+  let mint_client_vault_accounts =
+    create_tokens_and_accounts(&mut program_test_context, 1, 0).await;
+  let x_token_mint = mint_client_vault_accounts[0][0].pubkey();
+  let payer = program_test_context.payer.pubkey();
+
+  let vault_storage_account = Keypair::new();
+  let (pda, _bump_seed) =
+    VaultInstruction::find_vault_authority(&::vault::id(), &vault_storage_account.pubkey());
+  let (vault_x_token_account, _) =
+    VaultInstruction::find_vault_token_account(&::vault::id(), &vault_storage_account.pubkey());
+  let (llx_token_mint, _) =
+    VaultInstruction::find_llx_token_mint(&::vault::id(), &vault_storage_account.pubkey());
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[
+        system_instruction::create_account(
+          &payer,
+          &vault_storage_account.pubkey(),
+          Rent::default().minimum_balance(::vault::state::Vault::LEN),
+          ::vault::state::Vault::LEN as u64,
+          &::vault::id(),
+        ),
+        VaultInstruction::initialize_vault(
+          &::vault::id(),
+          &payer,
+          &vault_storage_account.pubkey(),
+          &x_token_mint,
+          &x_token_mint,
+          &spl_token::id(),
+          &::vault::id(), // Strategy program ID
+          true,           // hodl
+          99,             // unused deposit inst. ID
+          99,             // unused withdraw inst. ID
+          99,             // unused estimate value inst. ID
+          false,          // block deposits on loss
+        )
+        .unwrap(),
+      ],
+      &[&vault_storage_account],
+    )
+    .await,
+    Ok(())
+  );
+
+  // 2-of-3 multisig owning the client's X & llX accounts.
+  let multisig = Keypair::new();
+  let signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+  let client_x_token_account = Keypair::new();
+  let client_llx_token_account = Keypair::new();
+  let mut instructions = vec![
+    system_instruction::create_account(
+      &payer,
+      &multisig.pubkey(),
+      Rent::default().minimum_balance(spl_token::state::Multisig::LEN),
+      spl_token::state::Multisig::LEN as u64,
+      &spl_token::id(),
+    ),
+    spl_token::instruction::initialize_multisig(
+      &spl_token::id(),
+      &multisig.pubkey(),
+      &[&signers[0].pubkey(), &signers[1].pubkey(), &signers[2].pubkey()],
+      2,
+    )
+    .unwrap(),
+  ];
+  for (account, mint) in [
+    (&client_x_token_account, &x_token_mint),
+    (&client_llx_token_account, &llx_token_mint),
+  ]
+  .iter()
+  {
+    instructions.push(system_instruction::create_account(
+      &payer,
+      &account.pubkey(),
+      Rent::default().minimum_balance(spl_token::state::Account::LEN),
+      spl_token::state::Account::LEN as u64,
+      &spl_token::id(),
+    ));
+    instructions.push(
+      spl_token::instruction::initialize_account(
+        &spl_token::id(),
+        &account.pubkey(),
+        mint,
+        &multisig.pubkey(),
+      )
+      .unwrap(),
+    );
+  }
+  instructions.push(
+    spl_token::instruction::mint_to(
+      &spl_token::id(),
+      &x_token_mint,
+      &client_x_token_account.pubkey(),
+      &payer,
+      &[&payer],
+      1000,
+    )
+    .unwrap(),
+  );
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &instructions,
+      &[&multisig, &client_x_token_account, &client_llx_token_account],
+    )
+    .await,
+    Ok(())
+  );
+
+  let transfer_metas = |signers: &[&Keypair]| {
+    let mut metas = vec![AccountMeta::new_readonly(multisig.pubkey(), false)];
+    metas.extend(signers.iter().map(|signer| AccountMeta::new_readonly(signer.pubkey(), true)));
+    metas.extend(vec![
+      AccountMeta::new(vault_storage_account.pubkey(), false),
+      AccountMeta::new_readonly(::vault::id(), false),
+      AccountMeta::new(llx_token_mint, false),
+      AccountMeta::new_readonly(pda, false),
+      AccountMeta::new(vault_x_token_account, false),
+    ]);
+    metas
+  };
+
+  // A single multisig signer isn't enough.
+  assert!(process_instructions(
+    &mut program_test_context,
+    &[VaultInstruction::deposit(
+      &::vault::id(),
+      &spl_token::id(),
+      &client_x_token_account.pubkey(),
+      &client_llx_token_account.pubkey(),
+      transfer_metas(&[&signers[0]]),
+      100,
+      1, // signer_num
+      0, // min_shares
+    )
+    .unwrap()],
+    &[&signers[0]],
+  )
+  .await
+  .is_err());
+
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[VaultInstruction::deposit(
+        &::vault::id(),
+        &spl_token::id(),
+        &client_x_token_account.pubkey(),
+        &client_llx_token_account.pubkey(),
+        transfer_metas(&[&signers[0], &signers[2]]),
+        100,
+        2, // signer_num
+        0, // min_shares
+      )
+      .unwrap()],
+      &[&signers[0], &signers[2]],
+    )
+    .await,
+    Ok(())
+  );
+  check_token_account(&mut program_test_context, &client_x_token_account.pubkey(), &COption::None, 900).await;
+  check_token_account(&mut program_test_context, &client_llx_token_account.pubkey(), &COption::None, 100).await;
+  check_token_account(&mut program_test_context, &vault_x_token_account, &COption::Some(pda), 100).await;
+
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[VaultInstruction::withdraw(
+        &::vault::id(),
+        &spl_token::id(),
+        &client_llx_token_account.pubkey(),
+        &client_x_token_account.pubkey(),
+        transfer_metas(&[&signers[1], &signers[2]]),
+        40,
+        2, // signer_num
+      )
+      .unwrap()],
+      &[&signers[1], &signers[2]],
+    )
+    .await,
+    Ok(())
+  );
+  check_token_account(&mut program_test_context, &client_x_token_account.pubkey(), &COption::None, 940).await;
+  check_token_account(&mut program_test_context, &client_llx_token_account.pubkey(), &COption::None, 60).await;
+  check_token_account(&mut program_test_context, &vault_x_token_account, &COption::Some(pda), 60).await;
+}
+
+/// Tests initializing a Vault from an existing token account & llX mint held by single keys.
+#[tokio::test]
+async fn test_initialize_vault_with_accounts() {
+  let mut program_test = ProgramTest::new(
+    "token_test",
+    spl_token::id(),
+    processor!(Processor::process),
+  );
+  program_test.add_program(
+    "vault_test",
+    ::vault::id(),
+    processor!(::vault::processor::Processor::process),
+  );
+  let mut program_test_context = program_test.start_with_context().await;
+
+  let mint_client_vault_accounts =
+    create_tokens_and_accounts(&mut program_test_context, 1, 1).await;
+  let x_token_mint = mint_client_vault_accounts[0][0].pubkey();
+  let client_x_token_account = mint_client_vault_accounts[0][1].pubkey();
+  let payer = program_test_context.payer.pubkey();
+
+  let owner = Keypair::new();
+  let mint_authority = Keypair::new();
+  let vault_x_token_account =
+    create_token_account(&mut program_test_context, &x_token_mint, &owner.pubkey()).await;
+  let llx_token_mint = create_mint(&mut program_test_context, &mint_authority.pubkey(), 6).await;
+  let client_llx_token_account =
+    create_token_account(&mut program_test_context, &llx_token_mint, &payer).await;
+
+  let vault_storage_account = Keypair::new();
+  let (pda, _bump_seed) =
+    VaultInstruction::find_vault_authority(&::vault::id(), &vault_storage_account.pubkey());
+  let initialize = |llx_token_mint: &Pubkey| {
+    VaultInstruction::initialize_vault_with_accounts(
+      &::vault::id(),
+      &payer,
+      &vault_storage_account.pubkey(),
+      &vault_x_token_account,
+      &owner.pubkey(),
+      &[],
+      llx_token_mint,
+      &mint_authority.pubkey(),
+      &[],
+      &x_token_mint,
+      &spl_token::id(),
+      &::vault::id(), // Strategy program ID
+      true,           // hodl
+      99,             // unused deposit inst. ID
+      99,             // unused withdraw inst. ID
+      99,             // unused estimate value inst. ID
+      false,          // block deposits on loss
+    )
+    .unwrap()
+  };
+  let create_storage = system_instruction::create_account(
+    &payer,
+    &vault_storage_account.pubkey(),
+    Rent::default().minimum_balance(::vault::state::Vault::LEN),
+    ::vault::state::Vault::LEN as u64,
+    &::vault::id(),
+  );
+
+  // llX already in circulation can't be adopted.
+  let minted_llx_token_mint =
+    create_mint(&mut program_test_context, &mint_authority.pubkey(), 6).await;
+  let minted_llx_token_account =
+    create_token_account(&mut program_test_context, &minted_llx_token_mint, &payer).await;
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[spl_token::instruction::mint_to(
+        &spl_token::id(),
+        &minted_llx_token_mint,
+        &minted_llx_token_account,
+        &mint_authority.pubkey(),
+        &[],
+        1,
+      )
+      .unwrap()],
+      &[&mint_authority],
+    )
+    .await,
+    Ok(())
+  );
+  assert!(process_instructions(
+    &mut program_test_context,
+    &[create_storage.clone(), initialize(&minted_llx_token_mint)],
+    &[&vault_storage_account, &owner, &mint_authority],
+  )
+  .await
+  .is_err());
+
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[create_storage, initialize(&llx_token_mint)],
+      &[&vault_storage_account, &owner, &mint_authority],
+    )
+    .await,
+    Ok(())
+  );
+  check_token_account(&mut program_test_context, &vault_x_token_account, &COption::Some(pda), 0).await;
+  check_mint(&mut program_test_context, &llx_token_mint, &COption::Some(pda), 6).await;
+  let vault_state = get_vault_state(&mut program_test_context, &vault_storage_account.pubkey()).await;
+  assert!(vault_state.is_initialized);
+  assert_eq!(vault_state.governance, payer);
+  assert_eq!(vault_state.vault_token_account, vault_x_token_account);
+  assert_eq!(vault_state.llx_token_mint_id, llx_token_mint);
+  assert_eq!(vault_state.x_token_mint, x_token_mint);
+  assert_eq!(vault_state.lx_token_mint, x_token_mint);
+
+  // Deposits flow through the adopted accounts.
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[spl_token::instruction::mint_to(
+        &spl_token::id(),
+        &x_token_mint,
+        &client_x_token_account,
+        &payer,
+        &[],
+        1000,
+      )
+      .unwrap()],
+      &[],
+    )
+    .await,
+    Ok(())
+  );
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[VaultInstruction::deposit(
+        &::vault::id(),
+        &spl_token::id(),
+        &client_x_token_account,
+        &client_llx_token_account,
+        vec![
+          AccountMeta::new_readonly(payer, true),
+          AccountMeta::new(vault_storage_account.pubkey(), false),
+          AccountMeta::new_readonly(::vault::id(), false),
+          AccountMeta::new(llx_token_mint, false),
+          AccountMeta::new_readonly(pda, false),
+          AccountMeta::new(vault_x_token_account, false),
+        ],
+        100,
+        0, // signer_num
+        0, // min_shares
+      )
+      .unwrap()],
+      &[],
+    )
+    .await,
+    Ok(())
+  );
+  check_token_account(&mut program_test_context, &client_llx_token_account, &COption::None, 100).await;
+  check_token_account(&mut program_test_context, &vault_x_token_account, &COption::Some(pda), 100).await;
+
+  // The ERC-4626-style views read the adopted accounts too.
+  let tokenized_vault = TokenizedVault::new(
+    &::vault::id(),
+    &vault_storage_account.pubkey(),
+    get_vault_state(&mut program_test_context, &vault_storage_account.pubkey()).await,
+    vec![],
+  );
+  assert_eq!(
+    simulate_return_data::<VaultPreview>(
+      &mut program_test_context,
+      tokenized_vault.total_assets().unwrap(),
+    )
+    .await,
+    VaultPreview {
+      total_assets: 100,
+      llx_supply: 100,
+      price_per_share: PRICE_PER_SHARE_SCALE,
+      redeemable: None,
+    }
+  );
+  for (instruction, expected) in vec![
+    (tokenized_vault.convert_to_shares(10).unwrap(), 10),
+    (tokenized_vault.convert_to_assets(10).unwrap(), 10),
+    (tokenized_vault.max_deposit().unwrap(), u64::MAX),
+    (tokenized_vault.max_mint().unwrap(), u64::MAX),
+    (tokenized_vault.max_withdraw(&client_llx_token_account).unwrap(), 100),
+    (tokenized_vault.max_redeem(&client_llx_token_account).unwrap(), 100),
+    (tokenized_vault.preview_deposit(10).unwrap(), 10),
+    (tokenized_vault.preview_mint(10).unwrap(), 10),
+    (tokenized_vault.preview_withdraw(10).unwrap(), 10),
+    (tokenized_vault.preview_redeem(30).unwrap(), 30),
+  ] {
+    assert_eq!(simulate_return_data::<u64>(&mut program_test_context, instruction).await, expected);
+  }
+}
+
+/// Tests initializing a Vault from an existing token account & llX mint held by SPL Token
+/// multisigs.
+#[tokio::test]
+async fn test_initialize_vault_with_multisig_accounts() {
+  let mut program_test = ProgramTest::new(
+    "token_test",
+    spl_token::id(),
+    processor!(Processor::process),
+  );
+  program_test.add_program(
+    "vault_test",
+    ::vault::id(),
+    processor!(::vault::processor::Processor::process),
+  );
+  let mut program_test_context = program_test.start_with_context().await;
+
+  let mint_client_vault_accounts =
+    create_tokens_and_accounts(&mut program_test_context, 1, 0).await;
+  let x_token_mint = mint_client_vault_accounts[0][0].pubkey();
+  let payer = program_test_context.payer.pubkey();
+
+  // 2-of-3 multisig owning the token account & holding the llX mint authority.
+  let multisig = Keypair::new();
+  let signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[
+        system_instruction::create_account(
+          &payer,
+          &multisig.pubkey(),
+          Rent::default().minimum_balance(spl_token::state::Multisig::LEN),
+          spl_token::state::Multisig::LEN as u64,
+          &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_multisig(
+          &spl_token::id(),
+          &multisig.pubkey(),
+          &[&signers[0].pubkey(), &signers[1].pubkey(), &signers[2].pubkey()],
+          2,
+        )
+        .unwrap(),
+      ],
+      &[&multisig],
+    )
+    .await,
+    Ok(())
+  );
+  let vault_x_token_account =
+    create_token_account(&mut program_test_context, &x_token_mint, &multisig.pubkey()).await;
+  let llx_token_mint = create_mint(&mut program_test_context, &multisig.pubkey(), 6).await;
+
+  let vault_storage_account = Keypair::new();
+  let (pda, _bump_seed) =
+    VaultInstruction::find_vault_authority(&::vault::id(), &vault_storage_account.pubkey());
+  let initialize = |owner_signers: &[&Keypair], mint_signers: &[&Keypair]| {
+    let owner_signers: Vec<Pubkey> = owner_signers.iter().map(|signer| signer.pubkey()).collect();
+    let mint_signers: Vec<Pubkey> = mint_signers.iter().map(|signer| signer.pubkey()).collect();
+    vec![
+      system_instruction::create_account(
+        &payer,
+        &vault_storage_account.pubkey(),
+        Rent::default().minimum_balance(::vault::state::Vault::LEN),
+        ::vault::state::Vault::LEN as u64,
+        &::vault::id(),
+      ),
+      VaultInstruction::initialize_vault_with_accounts(
+        &::vault::id(),
+        &payer,
+        &vault_storage_account.pubkey(),
+        &vault_x_token_account,
+        &multisig.pubkey(),
+        &owner_signers.iter().collect::<Vec<_>>(),
+        &llx_token_mint,
+        &multisig.pubkey(),
+        &mint_signers.iter().collect::<Vec<_>>(),
+        &x_token_mint,
+        &spl_token::id(),
+        &::vault::id(), // Strategy program ID
+        true,           // hodl
+        99,             // unused deposit inst. ID
+        99,             // unused withdraw inst. ID
+        99,             // unused estimate value inst. ID
+        false,          // block deposits on loss
+      )
+      .unwrap(),
+    ]
+  };
+
+  // A single multisig signer isn't enough to hand over the mint authority.
+  assert!(process_instructions(
+    &mut program_test_context,
+    &initialize(&[&signers[0], &signers[1]], &[&signers[2]]),
+    &[&vault_storage_account, &signers[0], &signers[1], &signers[2]],
+  )
+  .await
+  .is_err());
+
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &initialize(&[&signers[0], &signers[1]], &[&signers[1], &signers[2]]),
+      &[&vault_storage_account, &signers[0], &signers[1], &signers[2]],
+    )
+    .await,
+    Ok(())
+  );
+  check_token_account(&mut program_test_context, &vault_x_token_account, &COption::Some(pda), 0).await;
+  check_mint(&mut program_test_context, &llx_token_mint, &COption::Some(pda), 6).await;
+  let vault_state = get_vault_state(&mut program_test_context, &vault_storage_account.pubkey()).await;
+  assert!(vault_state.is_initialized);
+  assert_eq!(vault_state.vault_token_account, vault_x_token_account);
+  assert_eq!(vault_state.llx_token_mint_id, llx_token_mint);
+}
+
+/// Tests governance setting a withdrawal fee & pausing deposits.
+#[tokio::test]
+async fn test_withdrawal_fee_and_pause() {
+  let mut program_test = ProgramTest::new(
+    "token_test",
+    spl_token::id(),
+    processor!(Processor::process),
+  );
+  program_test.add_program(
+    "vault_test",
+    ::vault::id(),
+    processor!(::vault::processor::Processor::process),
+  );
+  let mut program_test_context = program_test.start_with_context().await;
+
+  let mint_client_vault_accounts =
+    create_tokens_and_accounts(&mut program_test_context, 1, 1).await;
+  let x_token_mint = mint_client_vault_accounts[0][0].pubkey();
+  let client_x_token_account = mint_client_vault_accounts[0][1].pubkey();
+  let payer = program_test_context.payer.pubkey();
+
+  let vault_storage_account = Keypair::new();
+  let storage = vault_storage_account.pubkey();
+  let (pda, _bump_seed) = VaultInstruction::find_vault_authority(&::vault::id(), &storage);
+  let (vault_x_token_account, _) =
+    VaultInstruction::find_vault_token_account(&::vault::id(), &storage);
+  let (llx_token_mint, _) = VaultInstruction::find_llx_token_mint(&::vault::id(), &storage);
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[
+        system_instruction::create_account(
+          &payer,
+          &storage,
+          Rent::default().minimum_balance(::vault::state::Vault::LEN),
+          ::vault::state::Vault::LEN as u64,
+          &::vault::id(),
+        ),
+        VaultInstruction::initialize_vault(
+          &::vault::id(),
+          &payer,
+          &storage,
+          &x_token_mint,
+          &x_token_mint,
+          &spl_token::id(),
+          &::vault::id(), // Strategy program ID
+          true,           // hodl
+          99,             // unused deposit inst. ID
+          99,             // unused withdraw inst. ID
+          99,             // unused estimate value inst. ID
+          false,          // block deposits on loss
+        )
+        .unwrap(),
+        spl_token::instruction::mint_to(
+          &spl_token::id(),
+          &x_token_mint,
+          &client_x_token_account,
+          &payer,
+          &[],
+          1000,
+        )
+        .unwrap(),
+      ],
+      &[&vault_storage_account],
+    )
+    .await,
+    Ok(())
+  );
+  let client_llx_token_account =
+    create_token_account(&mut program_test_context, &llx_token_mint, &payer).await;
+  let transfer_metas = || {
+    vec![
+      AccountMeta::new_readonly(payer, true),
+      AccountMeta::new(storage, false),
+      AccountMeta::new_readonly(::vault::id(), false),
+      AccountMeta::new(llx_token_mint, false),
+      AccountMeta::new_readonly(pda, false),
+      AccountMeta::new(vault_x_token_account, false),
+    ]
+  };
+  let deposit = |amount| {
+    VaultInstruction::deposit(
+      &::vault::id(),
+      &spl_token::id(),
+      &client_x_token_account,
+      &client_llx_token_account,
+      transfer_metas(),
+      amount,
+      0, // signer_num
+      0, // min_shares
+    )
+    .unwrap()
+  };
+  let withdraw = |amount| {
+    VaultInstruction::withdraw(
+      &::vault::id(),
+      &spl_token::id(),
+      &client_llx_token_account,
+      &client_x_token_account,
+      transfer_metas(),
+      amount,
+      0, // signer_num
+    )
+    .unwrap()
+  };
+  assert_matches!(
+    process_instructions(&mut program_test_context, &[deposit(1000)], &[]).await,
+    Ok(())
+  );
+
+  // Only governance may set the fee, and only up to the maximum.
+  let stranger = Keypair::new();
+  assert!(process_instructions(
+    &mut program_test_context,
+    &[VaultInstruction::set_withdrawal_fee(&::vault::id(), &stranger.pubkey(), &storage, 100)
+      .unwrap()],
+    &[&stranger],
+  )
+  .await
+  .is_err());
+  assert!(process_instructions(
+    &mut program_test_context,
+    &[VaultInstruction::set_withdrawal_fee(
+      &::vault::id(),
+      &payer,
+      &storage,
+      state::MAX_WITHDRAWAL_FEE_BPS + 1,
+    )
+    .unwrap()],
+    &[],
+  )
+  .await
+  .is_err());
+  assert_eq!(
+    process_instructions_with_events(
+      &mut program_test_context,
+      &[VaultInstruction::set_withdrawal_fee(&::vault::id(), &payer, &storage, 100).unwrap()],
+      &[],
+    )
+    .await,
+    vec![VaultEvent::SetWithdrawalFee { vault: storage, governance: payer, fee_bps: 100 }]
+  );
+
+  // 1% of the payout stays in the Vault.
+  assert_eq!(
+    process_instructions_with_events(&mut program_test_context, &[withdraw(500)], &[]).await,
+    vec![VaultEvent::Withdraw {
+      vault: storage,
+      authority: payer,
+      recipient: payer,
+      amount: 495,
+      fee: 5,
+      shares: 500,
+      price_per_share: PRICE_PER_SHARE_SCALE,
+      in_kind: false,
+    }]
+  );
+  check_token_account(&mut program_test_context, &client_x_token_account, &COption::None, 495).await;
+  check_token_account(&mut program_test_context, &client_llx_token_account, &COption::None, 500).await;
+  check_token_account(&mut program_test_context, &vault_x_token_account, &COption::Some(pda), 505).await;
+
+  // Pausing stops deposits, but not withdrawals.
+  assert_eq!(
+    process_instructions_with_events(
+      &mut program_test_context,
+      &[VaultInstruction::set_paused(&::vault::id(), &payer, &storage, true).unwrap()],
+      &[],
+    )
+    .await,
+    vec![VaultEvent::SetPaused { vault: storage, governance: payer, paused: true }]
+  );
+  let vault_state = get_vault_state(&mut program_test_context, &storage).await;
+  assert!(vault_state.paused);
+  assert_matches!(
+    process_instructions(&mut program_test_context, &[deposit(100)], &[]).await,
+    Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(code)
+    ))) if code == ::vault::error::VaultError::Paused as u32
+  );
+  assert_eq!(
+    simulate_return_data::<u64>(
+      &mut program_test_context,
+      VaultInstruction::max_deposit(&::vault::id(), &storage, &vault_state).unwrap(),
+    )
+    .await,
+    0
+  );
+  assert_matches!(
+    process_instructions(&mut program_test_context, &[withdraw(100)], &[]).await,
+    Ok(())
+  );
+  // 100 llX are worth 101 X, of which 2 (rounded up) are kept.
+  check_token_account(&mut program_test_context, &client_x_token_account, &COption::None, 594).await;
+  check_token_account(&mut program_test_context, &vault_x_token_account, &COption::Some(pda), 406).await;
+
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[
+        VaultInstruction::set_paused(&::vault::id(), &payer, &storage, false).unwrap(),
+        deposit(100),
+      ],
+      &[],
+    )
+    .await,
+    Ok(())
+  );
+  check_token_account(&mut program_test_context, &client_llx_token_account, &COption::None, 498).await;
+}
+
+/// Tests that a donation inflating the price of llX can't make deposits round down to nothing.
+#[tokio::test]
+async fn test_deposit_after_donation() {
+  let mut program_test = ProgramTest::new(
+    "token_test",
+    spl_token::id(),
+    processor!(Processor::process),
+  );
+  program_test.add_program(
+    "vault_test",
+    ::vault::id(),
+    processor!(::vault::processor::Processor::process),
+  );
+  let mut program_test_context = program_test.start_with_context().await;
+
+  let mint_client_vault_accounts =
+    create_tokens_and_accounts(&mut program_test_context, 1, 1).await;
+  let x_token_mint = mint_client_vault_accounts[0][0].pubkey();
+  let attacker_x_token_account = mint_client_vault_accounts[0][1].pubkey();
+  let payer = program_test_context.payer.pubkey();
+  let victim_x_token_account =
+    create_token_account(&mut program_test_context, &x_token_mint, &payer).await;
+
+  let vault_storage_account = Keypair::new();
+  let storage = vault_storage_account.pubkey();
+  let (pda, _bump_seed) = VaultInstruction::find_vault_authority(&::vault::id(), &storage);
+  let (vault_x_token_account, _) =
+    VaultInstruction::find_vault_token_account(&::vault::id(), &storage);
+  let (llx_token_mint, _) = VaultInstruction::find_llx_token_mint(&::vault::id(), &storage);
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[
+        system_instruction::create_account(
+          &payer,
+          &storage,
+          Rent::default().minimum_balance(::vault::state::Vault::LEN),
+          ::vault::state::Vault::LEN as u64,
+          &::vault::id(),
+        ),
+        VaultInstruction::initialize_vault(
+          &::vault::id(),
+          &payer,
+          &storage,
+          &x_token_mint,
+          &x_token_mint,
+          &spl_token::id(),
+          &::vault::id(), // Strategy program ID
+          true,           // hodl
+          99,             // unused deposit inst. ID
+          99,             // unused withdraw inst. ID
+          99,             // unused estimate value inst. ID
+          false,          // block deposits on loss
+        )
+        .unwrap(),
+        spl_token::instruction::mint_to(
+          &spl_token::id(),
+          &x_token_mint,
+          &attacker_x_token_account,
+          &payer,
+          &[],
+          1000,
+        )
+        .unwrap(),
+        spl_token::instruction::mint_to(
+          &spl_token::id(),
+          &x_token_mint,
+          &victim_x_token_account,
+          &payer,
+          &[],
+          1000,
+        )
+        .unwrap(),
+      ],
+      &[&vault_storage_account],
+    )
+    .await,
+    Ok(())
+  );
+  let attacker_llx_token_account =
+    create_token_account(&mut program_test_context, &llx_token_mint, &payer).await;
+  let victim_llx_token_account =
+    create_token_account(&mut program_test_context, &llx_token_mint, &payer).await;
+  let deposit =
+    |client_x_token_account: &Pubkey, client_llx_token_account: &Pubkey, amount, min_shares| {
+      VaultInstruction::deposit(
+        &::vault::id(),
+        &spl_token::id(),
+        client_x_token_account,
+        client_llx_token_account,
+        vec![
+          AccountMeta::new_readonly(payer, true),
+          AccountMeta::new(storage, false),
+          AccountMeta::new_readonly(::vault::id(), false),
+          AccountMeta::new(llx_token_mint, false),
+          AccountMeta::new_readonly(pda, false),
+          AccountMeta::new(vault_x_token_account, false),
+        ],
+        amount,
+        0, // signer_num
+        min_shares,
+      )
+      .unwrap()
+    };
+
+  // The attacker takes the only llX token, then donates to the Vault so that it's worth 501 X.
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[
+        deposit(&attacker_x_token_account, &attacker_llx_token_account, 1, 0),
+        spl_token::instruction::transfer(
+          &spl_token::id(),
+          &attacker_x_token_account,
+          &vault_x_token_account,
+          &payer,
+          &[],
+          500,
+        )
+        .unwrap(),
+      ],
+      &[],
+    )
+    .await,
+    Ok(())
+  );
+  check_token_account(&mut program_test_context, &attacker_llx_token_account, &COption::None, 1)
+    .await;
+
+  // A deposit worth less than one llX token would otherwise mint nothing.
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[deposit(&victim_x_token_account, &victim_llx_token_account, 400, 0)],
+      &[],
+    )
+    .await,
+    Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(code)
+    ))) if code == ::vault::error::VaultError::ExceededSlippage as u32
+  );
+  // Nor does a deposit go through for fewer llX tokens than the depositor asked for.
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[deposit(&victim_x_token_account, &victim_llx_token_account, 600, 2)],
+      &[],
+    )
+    .await,
+    Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+      0,
+      InstructionError::Custom(code)
+    ))) if code == ::vault::error::VaultError::ExceededSlippage as u32
+  );
+  check_token_account(&mut program_test_context, &victim_x_token_account, &COption::None, 1000)
+    .await;
+  check_token_account(&mut program_test_context, &vault_x_token_account, &COption::Some(pda), 501)
+    .await;
+
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[deposit(&victim_x_token_account, &victim_llx_token_account, 600, 1)],
+      &[],
+    )
+    .await,
+    Ok(())
+  );
+  check_token_account(&mut program_test_context, &victim_llx_token_account, &COption::None, 1)
+    .await;
+}
+
+/// Tests swapping an unrelated token into X via a token-swap pool & depositing it in one go.
+#[tokio::test]
+async fn test_zap_deposit() {
+  let mut program_test = ProgramTest::new(
+    "token_test",
+    spl_token::id(),
+    processor!(Processor::process),
+  );
+  program_test.add_program(
+    "vault_test",
+    ::vault::id(),
+    processor!(::vault::processor::Processor::process),
+  );
+  program_test.add_program(
+    "token_swap_test",
+    spl_token_swap::id(),
+    processor!(spl_token_swap::processor::Processor::process),
+  );
+  let mut program_test_context = program_test.start_with_context().await;
+
+  // Row 0: X mint, client X account.
+  // Row 1: Y mint, client Y account.
+  let mint_client_vault_accounts =
+    create_tokens_and_accounts(&mut program_test_context, 2, 1).await;
+  let x_token_mint = mint_client_vault_accounts[0][0].pubkey();
+  let client_x_token_account = mint_client_vault_accounts[0][1].pubkey();
+  let y_token_mint = mint_client_vault_accounts[1][0].pubkey();
+  let client_y_token_account = mint_client_vault_accounts[1][1].pubkey();
+  let payer = program_test_context.payer.pubkey();
+
+  // Constant product Y/X pool holding 1000 of each.
+  let swap_account = Keypair::new();
+  let pool_mint = Keypair::new();
+  let (swap_authority, swap_nonce) =
+    Pubkey::find_program_address(&[&swap_account.pubkey().to_bytes()[..32]], &spl_token_swap::id());
+  let pool_y_token_account =
+    create_token_account(&mut program_test_context, &y_token_mint, &swap_authority).await;
+  let pool_x_token_account =
+    create_token_account(&mut program_test_context, &x_token_mint, &swap_authority).await;
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[
+        system_instruction::create_account(
+          &payer,
+          &pool_mint.pubkey(),
+          Rent::default().minimum_balance(spl_token::state::Mint::LEN),
+          spl_token::state::Mint::LEN as u64,
+          &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_mint(
+          &spl_token::id(),
+          &pool_mint.pubkey(),
+          &swap_authority,
+          None,
+          6,
+        )
+        .unwrap(),
+        spl_token::instruction::mint_to(&spl_token::id(), &y_token_mint, &pool_y_token_account, &payer, &[], 1000)
+          .unwrap(),
+        spl_token::instruction::mint_to(&spl_token::id(), &x_token_mint, &pool_x_token_account, &payer, &[], 1000)
+          .unwrap(),
+        spl_token::instruction::mint_to(&spl_token::id(), &y_token_mint, &client_y_token_account, &payer, &[], 100)
+          .unwrap(),
+      ],
+      &[&pool_mint],
+    )
+    .await,
+    Ok(())
+  );
+  let pool_fee_account =
+    create_token_account(&mut program_test_context, &pool_mint.pubkey(), &payer).await;
+  let pool_token_account =
+    create_token_account(&mut program_test_context, &pool_mint.pubkey(), &payer).await;
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[
+        system_instruction::create_account(
+          &payer,
+          &swap_account.pubkey(),
+          Rent::default().minimum_balance(spl_token_swap::state::SwapVersion::LATEST_LEN),
+          spl_token_swap::state::SwapVersion::LATEST_LEN as u64,
+          &spl_token_swap::id(),
+        ),
+        spl_token_swap::instruction::initialize(
+          &spl_token_swap::id(),
+          &spl_token::id(),
+          &swap_account.pubkey(),
+          &swap_authority,
+          &pool_y_token_account,
+          &pool_x_token_account,
+          &pool_mint.pubkey(),
+          &pool_fee_account,
+          &pool_token_account,
+          swap_nonce,
+          Fees::default(),
+          SwapCurve {
+            curve_type: CurveType::ConstantProduct,
+            calculator: Box::new(ConstantProductCurve {}),
+          },
+        )
+        .unwrap(),
+      ],
+      &[&swap_account],
+    )
+    .await,
+    Ok(())
+  );
+
+  let vault_storage_account = Keypair::new();
+  let (pda, _bump_seed) =
+    VaultInstruction::find_vault_authority(&::vault::id(), &vault_storage_account.pubkey());
+  let (vault_x_token_account, _) =
+    VaultInstruction::find_vault_token_account(&::vault::id(), &vault_storage_account.pubkey());
+  let (llx_token_mint, _) =
+    VaultInstruction::find_llx_token_mint(&::vault::id(), &vault_storage_account.pubkey());
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[
+        system_instruction::create_account(
+          &payer,
+          &vault_storage_account.pubkey(),
+          Rent::default().minimum_balance(::vault::state::Vault::LEN),
+          ::vault::state::Vault::LEN as u64,
+          &::vault::id(),
+        ),
+        VaultInstruction::initialize_vault(
+          &::vault::id(),
+          &payer,
+          &vault_storage_account.pubkey(),
+          &x_token_mint,
+          &x_token_mint,
+          &spl_token::id(),
+          &::vault::id(), // Strategy program ID
+          true,           // hodl
+          99,             // unused deposit inst. ID
+          99,             // unused withdraw inst. ID
+          99,             // unused estimate value inst. ID
+          false,          // block deposits on loss
+        )
+        .unwrap(),
+      ],
+      &[&vault_storage_account],
+    )
+    .await,
+    Ok(())
+  );
+  let client_llx_token_account =
+    create_token_account(&mut program_test_context, &llx_token_mint, &payer).await;
+
+  let zap_deposit = |minimum_x_out| {
+    VaultInstruction::zap_deposit(
+      &::vault::id(),
+      &spl_token::id(),
+      &client_y_token_account,
+      &client_x_token_account,
+      &client_llx_token_account,
+      zap_account_metas(
+        &swap_account.pubkey(),
+        &swap_authority,
+        &pool_y_token_account,
+        &pool_x_token_account,
+        &pool_mint.pubkey(),
+        &pool_fee_account,
+      ),
+      vec![
+        AccountMeta::new_readonly(payer, true), // source authority
+        AccountMeta::new(vault_storage_account.pubkey(), false),
+        AccountMeta::new_readonly(::vault::id(), false),
+        AccountMeta::new(llx_token_mint, false),
+        AccountMeta::new_readonly(pda, false),
+        AccountMeta::new(vault_x_token_account, false),
+      ],
+      100,
+      minimum_x_out,
+    )
+    .unwrap()
+  };
+
+  assert_eq!(::vault::swap::id(), spl_token_swap::id());
+  // Pools must belong to the SPL Token Swap program.
+  let mut bad_zap = zap_deposit(90);
+  bad_zap.accounts[1].pubkey = ::vault::id();
+  assert!(process_instructions(&mut program_test_context, &[bad_zap], &[]).await.is_err());
+  check_token_account(&mut program_test_context, &client_y_token_account, &COption::None, 100).await;
+
+  // 100 Y buys 90 X from the pool.
+  assert!(process_instructions(&mut program_test_context, &[zap_deposit(91)], &[]).await.is_err());
+  check_token_account(&mut program_test_context, &client_y_token_account, &COption::None, 100).await;
+  assert_matches!(process_instructions(&mut program_test_context, &[zap_deposit(90)], &[]).await, Ok(()));
+  check_token_account(&mut program_test_context, &client_y_token_account, &COption::None, 0).await;
+  check_token_account(&mut program_test_context, &pool_x_token_account, &COption::None, 910).await;
+  check_token_account(&mut program_test_context, &client_x_token_account, &COption::None, 0).await;
+  check_token_account(&mut program_test_context, &vault_x_token_account, &COption::Some(pda), 90).await;
+  check_token_account(&mut program_test_context, &client_llx_token_account, &COption::None, 90).await;
+}
+
+/// Tests a Vault investing in another Vault via the Vault's strategy instructions.
+#[tokio::test]
+async fn test_nested_vault() {
+  let mut program_test = ProgramTest::new(
+    "token_test",
+    spl_token::id(),
+    processor!(Processor::process),
+  );
+  program_test.add_program(
+    "vault_test",
+    ::vault::id(),
+    processor!(::vault::processor::Processor::process),
+  );
+  let mut program_test_context = program_test.start_with_context().await;
+
+  // Row 0: X mint, client X account.
+  let mint_client_vault_accounts =
+    create_tokens_and_accounts(&mut program_test_context, 1, 1).await;
+  let x_token_mint = mint_client_vault_accounts[0][0].pubkey();
+  let client_x_token_account = mint_client_vault_accounts[0][1].pubkey();
+  let payer = program_test_context.payer.pubkey();
+
+  // A HODL Vault, and an outer Vault investing in it.
+  let inner_storage_account = Keypair::new();
+  let (inner_llx_token_mint, _) =
+    VaultInstruction::find_llx_token_mint(&::vault::id(), &inner_storage_account.pubkey());
+  let outer_storage_account = Keypair::new();
+  let (inner_pda, _) =
+    VaultInstruction::find_vault_authority(&::vault::id(), &inner_storage_account.pubkey());
+  let (outer_pda, _) =
+    VaultInstruction::find_vault_authority(&::vault::id(), &outer_storage_account.pubkey());
+  let (outer_llx_token_mint, _) =
+    VaultInstruction::find_llx_token_mint(&::vault::id(), &outer_storage_account.pubkey());
+  let vault_rent = Rent::default().minimum_balance(::vault::state::Vault::LEN);
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[
+        system_instruction::create_account(
+          &payer,
+          &inner_storage_account.pubkey(),
+          vault_rent,
+          ::vault::state::Vault::LEN as u64,
+          &::vault::id(),
+        ),
+        VaultInstruction::initialize_vault(
+          &::vault::id(),
+          &payer,
+          &inner_storage_account.pubkey(),
+          &x_token_mint,
+          &x_token_mint,
+          &spl_token::id(),
+          &::vault::id(),
+          true, // hodl
+          0,
+          0,
+          0,
+          false,
+        )
+        .unwrap(),
+        system_instruction::create_account(
+          &payer,
+          &outer_storage_account.pubkey(),
+          vault_rent,
+          ::vault::state::Vault::LEN as u64,
+          &::vault::id(),
+        ),
+        VaultInstruction::initialize_nested_vault(
+          &::vault::id(),
+          &payer,
+          &outer_storage_account.pubkey(),
+          &x_token_mint,
+          &inner_llx_token_mint,
+          false,
+        )
+        .unwrap(),
+        spl_token::instruction::mint_to(&spl_token::id(), &x_token_mint, &client_x_token_account, &payer, &[], 1000)
+          .unwrap(),
+      ],
+      &[&inner_storage_account, &outer_storage_account],
+    )
+    .await,
+    Ok(())
+  );
+  let inner = get_vault_state(&mut program_test_context, &inner_storage_account.pubkey()).await;
+  let outer = get_vault_state(&mut program_test_context, &outer_storage_account.pubkey()).await;
+  assert_eq!(outer.strategy_program_id, ::vault::id());
+  assert_eq!(outer.lx_token_mint, inner_llx_token_mint);
+  assert_eq!(outer.strategy_program_estimate_instruction_id, STRATEGY_ESTIMATE_INSTRUCTION_ID);
+  let client_llx_token_account =
+    create_token_account(&mut program_test_context, &inner_llx_token_mint, &payer).await;
+  let client_lllx_token_account =
+    create_token_account(&mut program_test_context, &outer_llx_token_mint, &payer).await;
+
+  let inner_metas = vault_instruction::transfer_account_metas(
+    &::vault::id(),
+    &inner_storage_account.pubkey(),
+    &inner,
+    vec![],
+  );
+  let outer_metas = || {
+    let mut accounts = vec![AccountMeta::new_readonly(payer, true)];
+    accounts.extend(vault_instruction::transfer_account_metas(
+      &::vault::id(),
+      &outer_storage_account.pubkey(),
+      &outer,
+      inner_metas.clone(),
+    ));
+    accounts
+  };
+  let outer_estimate_metas = vault_instruction::estimate_value_account_metas(
+    &outer_storage_account.pubkey(),
+    &outer,
+    vault_instruction::estimate_position_value_account_metas(
+      &outer_storage_account.pubkey(),
+      &inner_storage_account.pubkey(),
+      &inner,
+      vec![],
+    ),
+  );
+
+  // Another client deposits directly in the inner Vault, then we deposit via the outer Vault.
+  let mut direct_metas = vec![AccountMeta::new_readonly(payer, true)];
+  direct_metas.extend(inner_metas.clone());
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[
+        VaultInstruction::deposit(
+          &::vault::id(),
+          &spl_token::id(),
+          &client_x_token_account,
+          &client_llx_token_account,
+          direct_metas,
+          100,
+          0, // signer_num
+          0, // min_shares
+        )
+        .unwrap(),
+        VaultInstruction::deposit(
+          &::vault::id(),
+          &spl_token::id(),
+          &client_x_token_account,
+          &client_lllx_token_account,
+          outer_metas(),
+          60,
+          0, // signer_num
+          0, // min_shares
+        )
+        .unwrap(),
+      ],
+      &[],
+    )
+    .await,
+    Ok(())
+  );
+  check_token_account(&mut program_test_context, &client_x_token_account, &COption::None, 840).await;
+  check_token_account(&mut program_test_context, &inner.vault_token_account, &COption::Some(inner_pda), 160).await;
+  check_token_account(&mut program_test_context, &outer.vault_token_account, &COption::Some(outer_pda), 60).await;
+  check_token_account(&mut program_test_context, &client_lllx_token_account, &COption::None, 60).await;
+  check_vault_value(&mut program_test_context, outer_estimate_metas.clone(), 60).await;
+
+  // The inner Vault earns 80 X: the outer Vault is valued by its share of it, not all of it.
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[spl_token::instruction::mint_to(
+        &spl_token::id(),
+        &x_token_mint,
+        &inner.vault_token_account,
+        &payer,
+        &[],
+        80,
+      )
+      .unwrap()],
+      &[],
+    )
+    .await,
+    Ok(())
+  );
+  check_vault_value(&mut program_test_context, outer_estimate_metas.clone(), 90).await;
+
+  // Nested valuations only value the outer Vault's own holding, not any llX account passed.
+  let temp_memory_account = Keypair::new();
+  let mut forged_metas = vec![AccountMeta::new_readonly(client_llx_token_account, false)];
+  forged_metas.extend(vault_instruction::estimate_position_value_account_metas(
+    &outer_storage_account.pubkey(),
+    &inner_storage_account.pubkey(),
+    &inner,
+    vec![],
+  ));
+  assert!(process_instructions(
+    &mut program_test_context,
+    &[
+      system_instruction::create_account(
+        &payer,
+        &temp_memory_account.pubkey(),
+        Rent::default().minimum_balance(8),
+        8,
+        &::vault::id(),
+      ),
+      VaultInstruction::estimate_position_value(
+        &::vault::id(),
+        &temp_memory_account.pubkey(),
+        forged_metas,
+      )
+      .unwrap(),
+    ],
+    &[&temp_memory_account],
+  )
+  .await
+  .is_err());
+  assert_eq!(
+    get_vault_state(&mut program_test_context, &outer_storage_account.pubkey())
+      .await
+      .last_estimated_value,
+    90
+  );
+
+  // Withdraw through both Vaults in two halves.
+  for remaining in [30, 0].iter() {
+    assert_matches!(
+      process_instructions(
+        &mut program_test_context,
+        &[VaultInstruction::withdraw(
+          &::vault::id(),
+          &spl_token::id(),
+          &client_lllx_token_account,
+          &client_x_token_account,
+          outer_metas(),
+          30,
+          0, // signer_num
+        )
+        .unwrap()],
+        &[],
+      )
+      .await,
+      Ok(())
+    );
+    check_token_account(&mut program_test_context, &client_lllx_token_account, &COption::None, *remaining).await;
+    check_token_account(&mut program_test_context, &outer.vault_token_account, &COption::Some(outer_pda), *remaining).await;
+  }
+  check_token_account(&mut program_test_context, &client_x_token_account, &COption::None, 930).await;
+  check_token_account(&mut program_test_context, &inner.vault_token_account, &COption::Some(inner_pda), 150).await;
+  check_vault_value(&mut program_test_context, outer_estimate_metas, 0).await;
+}
+
+/// Tests registering Vaults for their X mint.
+#[tokio::test]
+async fn test_vault_registry() {
+  let mut program_test = ProgramTest::new(
+    "token_test",
+    spl_token::id(),
+    processor!(Processor::process),
+  );
+  program_test.add_program(
+    "vault_test",
+    ::vault::id(),
+    processor!(::vault::processor::Processor::process),
+  );
+  let mut program_test_context = program_test.start_with_context().await;
+
+  let mint_client_vault_accounts =
+    create_tokens_and_accounts(&mut program_test_context, 1, 1).await;
+  let x_token_mint = mint_client_vault_accounts[0][0].pubkey();
+  let payer = program_test_context.payer.pubkey();
+
+  // Create & register two HODL Vaults, each in a single transaction.
+  let vault_storage_accounts = vec![Keypair::new(), Keypair::new()];
+  for vault_storage_account in vault_storage_accounts.iter() {
+    assert_matches!(
+      process_instructions(
+        &mut program_test_context,
+        &[
+          system_instruction::create_account(
+            &payer,
+            &vault_storage_account.pubkey(),
+            Rent::default().minimum_balance(::vault::state::Vault::LEN),
+            ::vault::state::Vault::LEN as u64,
+            &::vault::id(),
+          ),
+          VaultInstruction::initialize_vault(
+            &::vault::id(),
+            &payer,
+            &vault_storage_account.pubkey(),
+            &x_token_mint,
+            &x_token_mint,
+            &spl_token::id(),
+            &::vault::id(),
+            true, // hodl
+            0,
+            0,
+            0,
+            false,
+          )
+          .unwrap(),
+          VaultInstruction::register_vault(&::vault::id(), &payer, &vault_storage_account.pubkey(), &x_token_mint)
+            .unwrap(),
+        ],
+        &[vault_storage_account],
+      )
+      .await,
+      Ok(())
+    );
+  }
+  let first = vault_storage_accounts[0].pubkey();
+  let second = vault_storage_accounts[1].pubkey();
+  let registered = |vault_storage_account: Pubkey, status: VaultStatus| RegisteredVault {
+    x_token_mint,
+    vault_storage_account,
+    strategy_program_id: ::vault::id(),
+    hodl: true,
+    status,
+  };
+  assert_eq!(
+    get_registered_vault(&mut program_test_context, &x_token_mint, &first).await,
+    registered(first, VaultStatus::Active)
+  );
+  assert_eq!(
+    get_registered_vault(&mut program_test_context, &x_token_mint, &second).await,
+    registered(second, VaultStatus::Active)
+  );
+  assert_eq!(get_vault_registry(&mut program_test_context, &x_token_mint).await, vec![first, second]);
+
+  // Only governance can deregister, and only registered Vaults.
+  let impostor = Keypair::new();
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[VaultInstruction::deregister_vault(&::vault::id(), &impostor.pubkey(), &first, &x_token_mint).unwrap()],
+      &[&impostor],
+    )
+    .await,
+    Err(_)
+  );
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[VaultInstruction::register_vault(&::vault::id(), &payer, &first, &x_token_mint).unwrap()],
+      &[],
+    )
+    .await,
+    Err(_)
+  );
+  assert_eq!(
+    process_instructions_with_events(
+      &mut program_test_context,
+      &[VaultInstruction::deregister_vault(&::vault::id(), &payer, &first, &x_token_mint).unwrap()],
+      &[],
+    )
+    .await,
+    vec![VaultEvent::Deregister { vault: first, governance: payer, x_token_mint }]
+  );
+  assert_eq!(
+    get_registered_vault(&mut program_test_context, &x_token_mint, &first).await,
+    registered(first, VaultStatus::Retired)
+  );
+  assert_eq!(
+    get_registered_vault(&mut program_test_context, &x_token_mint, &second).await,
+    registered(second, VaultStatus::Active)
+  );
+  // The retired Vault is swap-removed from the registry, which shrinks & refunds its rent.
+  assert_eq!(get_vault_registry(&mut program_test_context, &x_token_mint).await, vec![second]);
+  assert_matches!(
+    process_instructions(
+      &mut program_test_context,
+      &[VaultInstruction::deregister_vault(&::vault::id(), &payer, &first, &x_token_mint).unwrap()],
+      &[],
+    )
+    .await,
+    Err(_)
+  );
+
+  // Re-registering reactivates the existing entry.
+  assert_eq!(
+    process_instructions_with_events(
+      &mut program_test_context,
+      &[VaultInstruction::register_vault(&::vault::id(), &payer, &first, &x_token_mint).unwrap()],
+      &[],
+    )
+    .await,
+    vec![VaultEvent::Register { vault: first, governance: payer, x_token_mint }]
+  );
+  assert_eq!(
+    get_registered_vault(&mut program_test_context, &x_token_mint, &first).await,
+    registered(first, VaultStatus::Active)
+  );
+  assert_eq!(get_vault_registry(&mut program_test_context, &x_token_mint).await, vec![second, first]);
+}
+
+/// Fetches & unpacks a Vault's storage account.
+async fn get_vault_state(
+  program_test_context: &mut ProgramTestContext,
+  vault_storage_account: &Pubkey,
+) -> state::Vault {
+  let account = program_test_context
+    .banks_client
+    .get_account(*vault_storage_account)
+    .await
+    .unwrap()
+    .expect("Account unretrievable");
+  state::Vault::unpack(&account.data).unwrap()
+}
+
+/// Fetches & unpacks a Vault's registry entry.
+async fn get_registered_vault(
+  program_test_context: &mut ProgramTestContext,
+  x_token_mint: &Pubkey,
+  vault_storage_account: &Pubkey,
+) -> RegisteredVault {
+  let (registry_account, _) =
+    VaultInstruction::find_registered_vault(&::vault::id(), x_token_mint, vault_storage_account);
+  let account = program_test_context
+    .banks_client
+    .get_account(registry_account)
+    .await
+    .unwrap()
+    .expect("Account unretrievable");
+  assert_eq!(account.owner, ::vault::id());
+  assert_eq!(account.data.len(), RegisteredVault::LEN);
+  RegisteredVault::unpack(&account.data).unwrap()
+}
+
+/// Fetches an X mint's Vault registry, checking that it holds no more than its rent, and returns
+/// the listed Vaults.
+async fn get_vault_registry(
+  program_test_context: &mut ProgramTestContext,
+  x_token_mint: &Pubkey,
+) -> Vec<Pubkey> {
+  let (registry_account, _) = VaultInstruction::find_vault_registry(&::vault::id(), x_token_mint);
+  let account = program_test_context
+    .banks_client
+    .get_account(registry_account)
+    .await
+    .unwrap()
+    .expect("Account unretrievable");
+  assert_eq!(account.owner, ::vault::id());
+  assert_eq!(account.lamports, Rent::default().minimum_balance(account.data.len()));
+  let registry = VaultRegistry::unpack(&account.data).unwrap();
+  assert_eq!(registry.x_token_mint, *x_token_mint);
+  registry.vaults
+}
+
+/// Signs & processes `instructions` with the payer and any additional `signers`.
+async fn process_instructions(
+  program_test_context: &mut ProgramTestContext,
+  instructions: &[Instruction],
+  signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+  let mut transaction =
+    Transaction::new_with_payer(instructions, Some(&program_test_context.payer.pubkey()));
+  let mut all_signers = vec![&program_test_context.payer];
+  all_signers.extend(signers);
+  transaction.sign(&all_signers, program_test_context.last_blockhash);
+  program_test_context
+    .banks_client
+    .process_transaction(transaction)
+    .await
+}
+
+/// Processes `instructions` like [process_instructions], returning the Vault events they logged.
+async fn process_instructions_with_events(
+  program_test_context: &mut ProgramTestContext,
+  instructions: &[Instruction],
+  signers: &[&Keypair],
+) -> Vec<VaultEvent> {
+  let mut transaction =
+    Transaction::new_with_payer(instructions, Some(&program_test_context.payer.pubkey()));
+  let mut all_signers = vec![&program_test_context.payer];
+  all_signers.extend(signers);
+  transaction.sign(&all_signers, program_test_context.last_blockhash);
+  let result = program_test_context
+    .banks_client
+    .process_transaction_with_metadata(transaction)
+    .await
+    .unwrap();
+  assert_matches!(result.result, Ok(()));
+  let logs = result.metadata.expect("Missing transaction metadata").log_messages;
+  VaultEvent::from_logs(&::vault::id(), &logs).unwrap()
+}
+
+/// Checks for expected values on a token account.
+async fn check_token_account(
+  program_test_context: &mut ProgramTestContext,
+  token_account_key: &Pubkey,
+  expected_owner: &COption<Pubkey>,
+  expected_amount: u64,
+) {
+  let token_account = program_test_context
+    .banks_client
+    .get_account(*token_account_key)
+    .await
+    .unwrap()
+    .expect("Account unretrievable");
+  println!("check_token_account");
+  assert_eq!(token_account.owner, spl_token::id());
+  let internal_account = spl_token::state::Account::unpack(&token_account.data).unwrap();
+  if expected_owner.is_some() {
+    println!("Error: token {} Got {} Expected {}", token_account_key, internal_account.owner, expected_owner.unwrap());
+    assert_eq!(internal_account.owner, expected_owner.unwrap());
+  }
+  println!("amounts: {} {}", internal_account.amount, expected_amount);
+  assert_eq!(internal_account.amount, expected_amount);
+}
+
+
+/// Checks for expected values on a mint.
+async fn check_mint(
+  program_test_context: &mut ProgramTestContext,
+  mint_key: &Pubkey,
+  expected_authority: &COption<Pubkey>,
+  expected_decimals: u8,
+) {
+  let mint_account = program_test_context
+    .banks_client
+    .get_account(*mint_key)
+    .await
+    .unwrap()
+    .expect("Account unretrievable");
+  assert_eq!(mint_account.owner, spl_token::id());
+  let mint = spl_token::state::Mint::unpack(&mint_account.data).unwrap();
+  assert_eq!(mint.mint_authority, *expected_authority);
+  assert_eq!(mint.decimals, expected_decimals);
+}
+
+/// Creates a mint with `decimals` whose mint authority is `authority`.
+async fn create_mint(
+  program_test_context: &mut ProgramTestContext,
+  authority: &Pubkey,
+  decimals: u8,
+) -> Pubkey {
+  let mint = Keypair::new();
+  let payer = program_test_context.payer.pubkey();
+  assert_matches!(
+    process_instructions(
+      program_test_context,
+      &[
+        system_instruction::create_account(
+          &payer,
+          &mint.pubkey(),
+          Rent::default().minimum_balance(spl_token::state::Mint::LEN),
+          spl_token::state::Mint::LEN as u64,
+          &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_mint(
+          &spl_token::id(),
+          &mint.pubkey(),
+          authority,
+          None, // Freeze authority
+          decimals,
+        )
+        .unwrap(),
+      ],
+      &[&mint],
+    )
+    .await,
+    Ok(())
+  );
+  mint.pubkey()
+}
+
+/// Creates a token account for `mint` owned by `owner`.
+async fn create_token_account(
+  program_test_context: &mut ProgramTestContext,
+  mint: &Pubkey,
+  owner: &Pubkey,
+) -> Pubkey {
+  let token_account = Keypair::new();
+  let payer = program_test_context.payer.pubkey();
+  assert_matches!(
+    process_instructions(
+      program_test_context,
+      &[
+        system_instruction::create_account(
+          &payer,
+          &token_account.pubkey(),
+          Rent::default().minimum_balance(spl_token::state::Account::LEN),
+          spl_token::state::Account::LEN as u64,
+          &spl_token::id(),
+        ),
+        spl_token::instruction::initialize_account(
+          &spl_token::id(),
+          &token_account.pubkey(),
+          mint,
+          owner,
+        )
+        .unwrap(),
+      ],
+      &[&token_account],
+    )
+    .await,
+    Ok(())
+  );
+  token_account.pubkey()
+}
+
+
+/// Checks the Vault's estimated value.
+async fn check_vault_value(
+  program_test_context: &mut ProgramTestContext,
+  additional_account_metas: Vec<AccountMeta>,
+  expected_amount: u64,
+) {
+  assert_eq!(
+    estimate_vault_value(program_test_context, additional_account_metas)
+      .await
+      .unwrap(),
+    expected_amount
+  );
+}
+
+/// Estimates the Vault's value via a temporary memory account.
+async fn estimate_vault_value(
+  program_test_context: &mut ProgramTestContext,
+  additional_account_metas: Vec<AccountMeta>,
+) -> Result<u64, BanksClientError> {
+  let temp_memory_account = Keypair::new();
+  let payer = program_test_context.payer.pubkey();
+  process_instructions(
+    program_test_context,
+    &[
+      // Create Vault storage acccount.
+      system_instruction::create_account(
+        &payer,
+        &temp_memory_account.pubkey(),
+        Rent::default().minimum_balance(8),
+        8, // sizeof(u64)
+        &::vault::id(),
+      ),
+      // Accounts: [vault_program, token_program, source_wallet, target_wallet, signers,
+      //           this_programs_accounts, child_strategy_accounts]
+      VaultInstruction::estimate_value(
+        &::vault::id(),
+        &::vault::id(),
+        &temp_memory_account.pubkey(),
+        additional_account_metas,
+      )
+      .unwrap(),
+    ],
+    &[&temp_memory_account],
+  )
+  .await?;
+
+  let temp_memory_account = program_test_context
+    .banks_client
+    .get_account(temp_memory_account.pubkey())
+    .await
+    .unwrap()
+    .expect("Account unretrievable");
+  Ok(u64::from_le_bytes(*s2a(&temp_memory_account.data[..8])))
+}
+/// Simulates a Preview instruction, decoding its return data as `T`.
+async fn simulate_return_data<T: BorshDeserialize>(
+  program_test_context: &mut ProgramTestContext,
+  instruction: Instruction,
+) -> T {
+  let mut transaction =
+    Transaction::new_with_payer(&[instruction], Some(&program_test_context.payer.pubkey()));
+  transaction.sign(&[&program_test_context.payer], program_test_context.last_blockhash);
+  let simulation = program_test_context
+    .banks_client
+    .simulate_transaction(transaction)
+    .await
+    .unwrap();
+  assert_matches!(simulation.result, Some(Ok(())));
+  let return_data = simulation
+    .simulation_details
+    .expect("Missing simulation details")
+    .return_data
+    .expect("Missing return data");
+  assert_eq!(return_data.program_id, ::vault::id());
+  T::try_from_slice(&return_data.data).unwrap()
+}
+
 fn s2a(slice: &[u8]) -> &[u8; 8] {
   slice.try_into().expect("slice with incorrect length")
 }